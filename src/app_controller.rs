@@ -1,37 +1,68 @@
+use std::collections::{BTreeMap, HashSet};
+#[cfg(feature = "interactive")]
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use rustc_hash::FxHashMap;
 use serde_json::Value;
+use thousands::Separable;
 
 use crate::{
     Code2PromptSession,
-    common::{cache::CacheManager, hash::HashMap},
+    common::{
+        cache::CacheManager,
+        format,
+        hash::HashMap,
+        path::{self, to_fwd_slash},
+    },
     engine::{
-        cache::{TemplateVariables, load_vars_from_file},
-        config::Code2PromptConfigBuilder,
+        cache::{TemplateVariables, expand_env_vars, load_vars_from_file},
+        chunk, claude_xml,
+        config::{Code2PromptConfigBuilder, OutputFormat, TokenFormat},
         config_file,
+        history::{History, HistoryEntry},
+        hooks::{HookMetadata, Hooks},
+        model::{RunSummary, TemplateContext, TokenBreakdown},
         token::count_tokens,
+        token_map::{DirChange, TokenMapExport, diff_by_dir},
+        traverse::{Phase, PhaseTimings},
     },
     ui::{
         cache,
-        cli::Cli,
+        cli::{
+            Cli, Command, ConfigCommand, HistoryCommand, RootMode, SelectionFormat, TemplateCommand,
+        },
         config::{
-            build_config_builder, build_exclude_patterns, build_include_patterns,
-            needs_interactive_tui, patterns_from_strings,
+            DEFAULT_EXCLUDES, build_config_builder, build_exclude_patterns, build_include_patterns,
+            config_problems, detected_ecosystems, effective_settings, needs_interactive_tui,
+            path_in_root, patterns_from_strings,
         },
-        output, template,
-        tree_arena::DirNode,
+        output,
+        template::{self, VarSpec, VarType},
+        tree_arena::{DirFlags, DirNode},
         tree_view::build_tree_view,
-        tui_select::{TuiAction, TuiSettings},
+        tui_select::{ExtStats, TuiAction, TuiSettings},
     },
 };
 
 // Gated imports for TUI features
 #[cfg(feature = "tui")]
 use {
-    crate::ui::{tree_arena::build_dir_arena, tui_select},
-    std::collections::HashSet,
+    crate::engine::{
+        model::ProcessedEntry,
+        tags::TagFile,
+        traverse::{ScanProgress, list_files},
+    },
+    crate::ui::{
+        arrange, simple_select,
+        tree_arena::{build_dir_arena, mark_skipped},
+        triage, tui_select, vars_review,
+    },
+    crossbeam_channel::Sender,
+    std::sync::Mutex,
 };
 
 // Gated imports for colors feature
@@ -39,22 +70,33 @@ use {
 use colored::{ColoredString, Colorize};
 
 /// The primary orchestration function for the application.
-pub fn run(args: Cli) -> Result<()> {
-    let (tpl_content, tpl_hash) = template::resolve_template(&args.path, &args.template)?;
+pub fn run(mut args: Cli) -> Result<()> {
+    init_logging(&args);
+    let _trace = init_tracing(&args)?;
+    if let Some(command) = &args.command {
+        return run_command(command);
+    }
+
+    args.path = resolve_root(&args)?;
+
+    if args.low_priority {
+        crate::common::process::lower_priority();
+    }
+
+    let (tpl_content, tpl_hash) =
+        template::resolve_template(&args.path, &args.template.first().cloned())?;
+    let extra_templates = extra_templates(&args)?;
 
     if args.list_templates {
         println!("Template Search Order:");
         println!("1. --template <path>");
         println!(
             "2. Project-local: {}",
-            args.path.join(".code2prompt/template.hbs").display()
+            template::project_template_path(&args.path).display()
         );
         println!(
             "3. User-global:  {}",
-            dirs::config_dir()
-                .unwrap_or_default()
-                .join("code2prompt/template.hbs")
-                .display()
+            template::global_template_path().display()
         );
         println!("4. Built-in Default");
         println!(
@@ -69,21 +111,26 @@ pub fn run(args: Cli) -> Result<()> {
     }
 
     let cache_manager = CacheManager::new(&args.path)?;
-    let cfg_file: config_file::ConfigFile =
-        confy::load("code2prompt", None).context("Failed to load config file")?;
+    let cfg_file = config_file::load_for(&args.path)?;
 
     // --- START: Variable Merging ---
     let mut vars_map = HashMap::<String, String>::default();
 
-    if !args.no_var_cache {
-        if let Some(cached) = cache_manager.load::<TemplateVariables>()? {
-            vars_map.extend(cached.0);
-        }
-    }
+    let cached_vars = if args.no_var_cache {
+        HashMap::default()
+    } else {
+        cache_manager
+            .load::<TemplateVariables>()?
+            .map(|cached| cached.0)
+            .unwrap_or_default()
+    };
+    vars_map.extend(cached_vars.clone());
 
     if let Some(defaults) = &cfg_file.template.defaults {
         for (k, v) in defaults {
-            vars_map.entry(k.clone()).or_insert_with(|| v.clone());
+            vars_map
+                .entry(k.clone())
+                .or_insert_with(|| expand_env_vars(v));
         }
     }
 
@@ -104,23 +151,132 @@ pub fn run(args: Cli) -> Result<()> {
 
     // --- END: Variable Merging ---
 
-    let placeholders = template::extract_placeholders(&tpl_content)?;
+    // Outputs without a template need none of its variables.
+    let renders_template = !args.no_template && args.output_format != OutputFormat::RagJsonl;
+    let tpl_for_vars = if renders_template { &tpl_content } else { "" };
+    let (front_matter, _) = template::split_front_matter(tpl_for_vars)?;
+    let mut placeholders = template::extract_placeholders(tpl_for_vars)?;
+    for (tpl, _) in extra_templates.iter().filter(|_| renders_template) {
+        for p in template::extract_placeholders(tpl)? {
+            if !placeholders.contains(&p) {
+                placeholders.push(p);
+            }
+        }
+    }
+    // Variables declared in the config apply to the templates using them;
+    // the template's own declarations take precedence.
+    let mut var_specs: BTreeMap<String, VarSpec> = cfg_file
+        .template
+        .vars
+        .iter()
+        .flatten()
+        .filter(|(name, _)| placeholders.contains(name))
+        .map(|(name, spec)| (name.clone(), spec.clone()))
+        .collect();
+    var_specs.extend(front_matter.vars);
+    for (tpl, _) in extra_templates.iter().filter(|_| renders_template) {
+        for (name, spec) in template::split_front_matter(tpl)?.0.vars {
+            var_specs.entry(name).or_insert(spec);
+        }
+    }
+
+    if args.review_vars {
+        // Only the cached values still in effect: flags, variable files and
+        // the environment override the cache.
+        let reviewable: Vec<(String, String)> = placeholders
+            .iter()
+            .filter_map(|p| {
+                let value = cached_vars.get(p)?;
+                (vars_map.get(p) == Some(value)).then(|| (p.clone(), value.clone()))
+            })
+            .collect();
+        if reviewable.is_empty() {
+            if !args.quiet {
+                eprintln!("[i] The template uses no cached variables to review.");
+            }
+        } else if let Some(changed) = review_vars(&args, reviewable, &var_specs)? {
+            vars_map.extend(changed);
+            cache_manager.save(&TemplateVariables(vars_map.clone()))?;
+        }
+    }
     let missing_vars: Vec<String> = placeholders
         .into_iter()
         .filter(|p| !vars_map.contains_key(p))
+        // With --ask-task, `task` is asked for after the file selection instead.
+        .filter(|p| !(args.ask_task && p == "task"))
         .collect();
 
     #[cfg(feature = "interactive")]
     if !missing_vars.is_empty() && !args.no_interactive {
         println!("{}", colour("[i] Your template requires some variables."));
-        let new_vars = template::prompt_for_variables(&missing_vars, &vars_map)?;
+        let new_vars = template::prompt_for_variables(&missing_vars, &vars_map, &var_specs)?;
         vars_map.extend(new_vars);
         if !args.no_var_cache {
             cache_manager.save(&TemplateVariables(vars_map.clone()))?;
         }
     }
 
-    let user_vars_data: Value = serde_json::to_value(vars_map)?;
+    if args.ask_task {
+        var_specs.remove("task");
+    }
+    template::apply_var_specs(&var_specs, &mut vars_map)?;
+
+    let hooks = cfg_file.hooks.clone().unwrap_or_default();
+    hooks.run_pre_scan(&args.path)?;
+
+    #[cfg(unix)]
+    if args.use_daemon && renders_template && extra_templates.is_empty() {
+        let unsupported = daemon_unsupported(&args, &cfg_file);
+        if !unsupported.is_empty() {
+            if !args.quiet {
+                eprintln!(
+                    "[!] The daemon does not support {}; scanning normally.",
+                    unsupported.join(", ")
+                );
+            }
+        } else {
+            match render_with_daemon(&args, &cfg_file, &vars_map, &hooks, &tpl_hash) {
+                Ok(true) => return Ok(()),
+                Ok(false) if !args.quiet => {
+                    eprintln!("[!] No daemon is running; scanning normally.")
+                }
+                Err(e) if !args.quiet => {
+                    eprintln!("[!] Daemon failed ({e:#}); scanning normally.")
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut user_vars_data = Value::Object(template::typed_vars(&var_specs, vars_map));
+
+    for output in args.output_file.iter().filter(|_| !args.quiet) {
+        if path_in_root(&args.path, Path::new(output)).is_some() {
+            eprintln!(
+                "[!] The output file {output} is inside the scanned directory; it is left out of the scan."
+            );
+        }
+    }
+
+    if args.split_by_dir
+        && matches!(
+            args.output_format,
+            OutputFormat::RagJsonl | OutputFormat::Messages | OutputFormat::ClaudeXml
+        )
+    {
+        anyhow::bail!("--split-by-dir writes markdown, json or xml prompts.");
+    }
+    if args.scaffold.is_some() && args.output_format == OutputFormat::RagJsonl {
+        anyhow::bail!("--scaffold goes with a prompt, not -F rag-jsonl records.");
+    }
+
+    #[cfg(not(feature = "tui"))]
+    if args.top_files.is_some() {
+        anyhow::bail!("--top-files requires the 'tui' feature.");
+    }
+    if args.print_selection.is_some() && !needs_interactive_tui(&args) {
+        anyhow::bail!("--print-selection prints what is confirmed in the interactive selector.");
+    }
 
     let mut session = if needs_interactive_tui(&args) {
         #[cfg(feature = "tui")]
@@ -136,21 +292,88 @@ pub fn run(args: Cli) -> Result<()> {
     } else {
         run_batch_flow(&args, &cfg_file)?
     };
+    #[cfg(feature = "tui")]
+    if let Some(n) = args.top_files {
+        triage_files(&args.path, &mut session, &cache_manager, n.into())?;
+    }
+
+    if args.no_template {
+        return write_concatenation(&args, &mut session);
+    }
+    if args.output_format == OutputFormat::RagJsonl {
+        return write_rag_jsonl(&args, &mut session);
+    }
+
+    if args.ask_task {
+        let task = template::prompt_for_task()?;
+        if let Some(obj) = user_vars_data.as_object_mut() {
+            obj.insert("task".into(), Value::String(task));
+        }
+    }
 
     let mut context = session.build_template_data(
         args.diff.then_some(""),
         parse_branch_pair(&args.git_diff_branch),
         parse_branch_pair(&args.git_log_branch),
     )?;
+    // After the template data, which samples and orders the files.
+    if let Some(scaffold) = &args.scaffold {
+        write_scaffold(scaffold, &session, args.quiet)?;
+    }
+
+    context.user_prelude = args
+        .prepend
+        .as_deref()
+        .map(template::load_text_arg)
+        .transpose()?;
+    context.user_epilogue = args
+        .append
+        .as_deref()
+        .map(template::load_text_arg)
+        .transpose()?;
+    let (prelude, epilogue) = (context.user_prelude.clone(), context.user_epilogue.clone());
+    context.issue = args
+        .issue
+        .as_deref()
+        .map(|spec| crate::engine::issue::load(spec, &args.path))
+        .transpose()?;
 
     // 2. Generate and inject the source tree string into the context
-    context.source_tree = build_tree_view(
+    let source_tree = build_tree_view(
         &session.config.path,
         &session.processed_entries,
         session.config.full_directory_tree,
+        session.config.file_ids,
     );
+    if !session.config.no_tree {
+        context.source_tree = source_tree.clone();
+    }
+
+    if args.split_by_dir {
+        return write_split_by_dir(
+            &args,
+            &session,
+            context,
+            &user_vars_data,
+            &tpl_content,
+            &tpl_hash,
+        );
+    }
+
+    // `-F messages` and `-F claude-xml` render the template without the
+    // files, which are laid out separately.
+    let detach_files = matches!(
+        args.output_format,
+        OutputFormat::Messages | OutputFormat::ClaudeXml
+    );
+    let detached_files = if detach_files {
+        context.file_groups.clear();
+        std::mem::take(&mut context.files)
+    } else {
+        Vec::new()
+    };
 
-    let mut template_value = serde_json::to_value(context)?;
+    let mut template_value = serde_json::to_value(&context)?;
     if let Some(obj) = template_value.as_object_mut() {
         if let Some(user_obj) = user_vars_data.as_object() {
             obj.extend(user_obj.clone());
@@ -162,27 +385,123 @@ pub fn run(args: Cli) -> Result<()> {
     } else {
         "custom"
     };
-    let hb = template::handlebars_setup(&tpl_content, tpl_render_name)?;
-    let rendered = hb
-        .render(tpl_render_name, &template_value)
-        .map(|s| s.trim().to_string())
-        .map_err(|e| anyhow::anyhow!("Failed to render template: {e}"))?;
+    let render = |tpl_content: &str, tpl_render_name: &str| -> Result<String> {
+        let hb = template::handlebars_setup(tpl_content, tpl_render_name)?;
+        let rendered = template::render_template(&hb, tpl_render_name, &template_value)?;
+        let rendered = template::wrap_with_user_blocks(
+            rendered,
+            tpl_content,
+            prelude.as_deref(),
+            epilogue.as_deref(),
+        );
+        Ok(match args.output_format {
+            OutputFormat::Messages => {
+                let limit = args
+                    .max_tokens
+                    .unwrap_or_else(|| session.config.tokenizer.context_window());
+                let messages = chunk::chat_messages(
+                    rendered,
+                    &detached_files,
+                    limit,
+                    session.config.tokenizer,
+                )?;
+                serde_json::to_string_pretty(&messages)?
+            }
+            // Long documents go before the instructions.
+            OutputFormat::ClaudeXml => {
+                format!(
+                    "{}\n\n{rendered}",
+                    claude_xml::documents_xml(&detached_files)
+                )
+            }
+            _ => rendered,
+        })
+    };
+    let rendered = render(&tpl_content, tpl_render_name)?;
+    let extra_rendered = extra_templates
+        .iter()
+        .map(|(tpl, output)| Ok((render(tpl, "custom")?, output)))
+        .collect::<Result<Vec<_>>>()?;
+    if detach_files {
+        context.files = detached_files;
+    }
 
     let token_count = count_tokens(&rendered, session.config.tokenizer)?;
 
-    let handler = output::OutputHandler::new(
+    let mut handler = output::OutputHandler::new(
         &rendered,
+        &source_tree,
         token_count,
         &session.processed_entries,
         &args,
         &session.config,
     );
-    handler.handle()?;
+    // Only worth counting when shown: with `--tokens breakdown` or in the
+    // summary, printed or in the JSON output.
+    let shows_breakdown = args.tokens == TokenFormat::Breakdown
+        || !args.quiet
+        || args.output_format == OutputFormat::Json;
+    let breakdown = shows_breakdown
+        .then(|| session.token_breakdown(&context, token_count))
+        .transpose()?;
+    if let Some(breakdown) = breakdown.filter(|_| args.tokens == TokenFormat::Breakdown) {
+        handler = handler.with_breakdown(breakdown);
+    }
+    let summary = run_summary(&session, token_count, breakdown);
+    let mut extra_outputs = Vec::with_capacity(extra_rendered.len());
+    for ((extra, pattern), (tpl, _)) in extra_rendered.into_iter().zip(&extra_templates) {
+        let tokens = count_tokens(&extra, session.config.tokenizer)?;
+        let path = output_path(pattern, &session, tokens, summary.files_included);
+        template::write_to_file(&path, &extra)?;
+        extra_outputs.push((extra, Some(path), tokens, template::hash_content(tpl)));
+    }
+    let output_path = handler.with_summary(summary.clone()).handle()?;
 
-    output::print_summary(
-        &session.config.path.to_string_lossy(),
-        session.processed_entries.len(),
-    );
+    // Every output gets the post-render hook and a history entry, the first
+    // one first.
+    let files = session
+        .processed_entries
+        .iter()
+        .filter(|e| e.is_file)
+        .count();
+    let records_history = args.history || cfg_file.history.unwrap_or(false);
+    let outputs = std::iter::once((rendered, output_path, token_count, tpl_hash));
+    for (prompt, output_path, token_count, template_hash) in outputs.chain(extra_outputs) {
+        hooks.run_post_render(
+            &prompt,
+            &HookMetadata {
+                repo: session.config.path.to_string_lossy().into_owned(),
+                output_path,
+                files,
+                token_count,
+                tokenizer: session.config.tokenizer,
+                template_hash: template_hash.clone(),
+            },
+        )?;
+        if records_history {
+            let entry = HistoryEntry::new(
+                prompt,
+                token_count,
+                session.processed_entries.len(),
+                session.config.tokenizer,
+                template_hash,
+            );
+            History::open(&session.config.path)?
+                .record(&entry)
+                .context("Failed to save prompt history")?;
+        }
+    }
+
+    if !args.quiet {
+        output::print_summary(
+            &session.config.path.to_string_lossy(),
+            &summary,
+            &detected_ecosystems(&args),
+        );
+    }
+    if args.show_errors {
+        output::print_scan_errors(&session.scan_errors);
+    }
 
     Ok(())
 }
@@ -193,14 +512,44 @@ pub fn run(args: Cli) -> Result<()> {
 fn run_batch_flow(args: &Cli, cfg_file: &config_file::ConfigFile) -> Result<Code2PromptSession> {
     let includes = build_include_patterns(args);
     let excludes = build_exclude_patterns(args, cfg_file, true);
-    create_and_process_session(
+    let mut session = create_and_process_session(
         args,
         cfg_file,
         &includes,
         &excludes,
-        args.token_map, // Pass through whether token map is enabled
-        None,           // No extra builder function for batch mode
-    )
+        args.token_map || args.token_map_json.is_some(),
+        None, // No extra builder function for batch mode
+    )?;
+
+    // A saved selection stands in for confirming the TUI.
+    if let Some(selection) = saved_selection(args)? {
+        let to_paths =
+            |paths: &[String]| -> Vec<PathBuf> { paths.iter().map(PathBuf::from).collect() };
+        filter_session_entries(
+            &mut session,
+            &selection.extensions,
+            &to_paths(&selection.directories),
+            &to_paths(&selection.pinned),
+        );
+    }
+    Ok(session)
+}
+
+/// The selection given by `--select-file` or `--use-last-selection`, if any.
+fn saved_selection(args: &Cli) -> Result<Option<cache::LastSelection>> {
+    if let Some(file) = &args.select_file {
+        return cache::LastSelection::from_file(file).map(Some);
+    }
+    if !args.use_last_selection {
+        return Ok(None);
+    }
+    match CacheManager::new(&args.path)?.load::<cache::LastSelection>()? {
+        Some(selection) => Ok(Some(selection)),
+        None => anyhow::bail!(
+            "No selection has been saved for {}; confirm one in the TUI first.",
+            args.path.display()
+        ),
+    }
 }
 
 // ──────────────────────────────────────────────────────────────
@@ -217,49 +566,106 @@ fn run_interactive_flow(
     let mut current_settings: Option<TuiSettings> = None;
 
     loop {
+        if !args.quiet {
+            eprintln!("Scanning files for interactive selection…");
+        }
         let (mut session, sorted_ext, dir_arena) =
-            prepare_interactive_data(args, cfg_file, current_settings.as_ref())?;
+            prepare_interactive_data(args, cfg_file, current_settings.as_ref(), None)?;
+
+        // Rescans after a settings change run inside the TUI; the latest one's
+        // session and settings replace ours once the TUI exits.
+        let rescanned: Mutex<Option<(Code2PromptSession, TuiSettings)>> = Mutex::new(None);
+        let rescan = |settings: &TuiSettings, progress: Sender<ScanProgress>| {
+            let _ = config_file::save_gui_settings(settings);
+
+            let (mut session, sorted_ext, dir_arena) =
+                prepare_interactive_data(args, cfg_file, Some(settings), Some(progress))?;
+            session.config.progress = None;
+            *rescanned.lock().unwrap() = Some((session, settings.clone()));
+            Ok((sorted_ext, dir_arena))
+        };
 
         // `session.config` now holds the right initial values.
         let last_sel_opt = cache_manager.load::<cache::LastSelection>()?;
-        let action = tui_select::select_filters_tui(
-            &args.path,
-            sorted_ext,
-            dir_arena,
-            last_sel_opt,
-            &session.config, // We pass the fully-formed config here
-        )?;
-        println!();
+        let action = if args.simple_ui {
+            simple_select::select_filters_simple(&sorted_ext, &dir_arena, last_sel_opt.as_ref())?
+        } else {
+            tui_select::select_filters_tui(
+                &args.path,
+                sorted_ext,
+                dir_arena,
+                last_sel_opt,
+                &session.config, // We pass the fully-formed config here
+                &rescan,
+            )?
+        };
+        eprintln!();
+
+        if let Some((latest, settings)) = rescanned.into_inner().unwrap() {
+            session = latest;
+            current_settings = Some(settings);
+        }
 
         // ---- 3. Process the action ----
         match action {
-            TuiAction::Confirm { exts, paths } => {
+            TuiAction::Confirm {
+                exts,
+                paths,
+                pinned,
+                skipped,
+                arrange,
+                triage,
+            } => {
+                let to_strings = |paths: &[PathBuf]| -> Vec<String> {
+                    paths.iter().map(|p| path::display(p)).collect()
+                };
                 let new_selection = cache::LastSelection {
                     extensions: exts.clone(),
-                    directories: paths
-                        .iter()
-                        .map(|p| p.to_string_lossy().into_owned())
-                        .collect(),
+                    directories: to_strings(&paths),
+                    pinned: to_strings(&pinned),
                 };
                 cache_manager.save(&new_selection)?;
 
-                if exts.is_empty() && paths.is_empty() {
+                if exts.is_empty() && paths.is_empty() && pinned.is_empty() && skipped.is_empty() {
                     println!("{}", colour("No selections made. Exiting."));
                     std::process::exit(0);
                 }
 
-                filter_session_entries(&mut session, &exts, &paths);
+                filter_session_entries(&mut session, &exts, &paths, &pinned);
+                session.include_files(&skipped)?;
+                if let Some(style) = args.print_selection {
+                    print_selection(style, &new_selection, &session)?;
+                    std::process::exit(0);
+                }
+                if triage {
+                    triage_files(
+                        &args.path,
+                        &mut session,
+                        cache_manager,
+                        triage::DEFAULT_TOP_FILES,
+                    )?;
+                }
+                if arrange {
+                    arrange_files(&args.path, &mut session)?;
+                }
                 return Ok(session);
             }
-            TuiAction::RescanWithConfig {
-                settings,
-                show_msg: _,
-            } => {
-                let new_settings = settings.clone();
-                let mut cfg_edit = cfg_file.clone();
-                cfg_edit.gui.settings = new_settings.clone();
-                let _ = confy::store("code2prompt", None, cfg_edit);
-                current_settings = Some(new_settings);
+            TuiAction::ExcludePath { path, is_dir } => {
+                let mut repo_cfg = cache_manager
+                    .load::<config_file::RepoConfig>()?
+                    .unwrap_or_default();
+                if repo_cfg.add_exclude(&to_fwd_slash(&path), is_dir) {
+                    cache_manager.save(&repo_cfg)?;
+                }
+                eprintln!("Excluded {} from future scans.", path.display());
+                continue;
+            }
+            TuiAction::TagPath { path, is_dir, tag } => {
+                let mut tags = TagFile::load(&args.path)?;
+                if tags.add(&tag, &to_fwd_slash(&path), is_dir) {
+                    tags.save(&args.path)?;
+                }
+                eprintln!("Tagged {} as '{tag}'.", path.display());
                 continue;
             }
             TuiAction::Cancel => {
@@ -270,19 +676,80 @@ fn run_interactive_flow(
     }
 }
 
+/// Shows the triage screen for the `n` largest files, leaves out of the
+/// prompt the ones dropped there and adds the ones excluded there to the
+/// repository's excludes.
+#[cfg(feature = "tui")]
+fn triage_files(
+    repo_path: &Path,
+    session: &mut Code2PromptSession,
+    cache_manager: &CacheManager,
+    n: usize,
+) -> Result<()> {
+    let files = session
+        .processed_entries
+        .iter()
+        .filter(|e| e.is_file)
+        .map(|e| (path::display(&e.relative_path), e.token_count.unwrap_or(0)))
+        .collect();
+    let largest = triage::Triage::largest(files, n);
+    let Some(triaged) = triage::triage_files_tui(&format::format_path_label(repo_path), largest)?
+    else {
+        return Ok(());
+    };
+
+    let dropped = triaged.dropped();
+    session
+        .processed_entries
+        .retain(|e| !dropped.contains(&path::display(&e.relative_path)));
+    let excluded = triaged.excluded();
+    if !excluded.is_empty() {
+        let mut repo_cfg = cache_manager
+            .load::<config_file::RepoConfig>()?
+            .unwrap_or_default();
+        let mut changed = false;
+        for path in &excluded {
+            changed |= repo_cfg.add_exclude(&to_fwd_slash(Path::new(path)), false);
+        }
+        if changed {
+            cache_manager.save(&repo_cfg)?;
+        }
+        eprintln!("Excluded {} files from future scans.", excluded.len());
+    }
+    Ok(())
+}
+
+/// Shows the arrange screen for the selected files, in the order they would
+/// be rendered, and renders them in the order chosen there.
+#[cfg(feature = "tui")]
+fn arrange_files(repo_path: &Path, session: &mut Code2PromptSession) -> Result<()> {
+    session.apply_priorities();
+    let files = session
+        .processed_entries
+        .iter()
+        .filter(|e| e.is_file)
+        .map(|e| (path::display(&e.relative_path), e.token_count.unwrap_or(0)))
+        .collect();
+    if let Some(order) = arrange::arrange_files_tui(&format::format_path_label(repo_path), files)? {
+        session.config.file_order = order;
+    }
+    Ok(())
+}
+
 #[cfg(feature = "tui")]
 fn prepare_interactive_data(
     args: &Cli,
     cfg_file: &config_file::ConfigFile,
     overrides: Option<&TuiSettings>,
-) -> Result<(Code2PromptSession, Vec<(String, usize)>, Vec<DirNode>)> {
-    println!("Scanning files for interactive selection…");
-
+    progress: Option<Sender<ScanProgress>>,
+) -> Result<(Code2PromptSession, Vec<ExtStats>, Vec<DirNode>)> {
     // Create a closure to apply settings overrides to the config builder.
     let builder_ext = |b: &mut Code2PromptConfigBuilder| {
+        b.progress(progress.clone());
         if let Some(o) = overrides {
             b.line_numbers(o.line_numbers)
                 .hidden(o.hidden)
+                .no_ignore(o.no_ignore)
                 .follow_symlinks(o.follow_symlinks)
                 .no_codeblock(o.no_codeblock)
                 .tokenizer(o.tokenizer);
@@ -292,7 +759,7 @@ fn prepare_interactive_data(
     let _include_patterns: &[String] = &[];
     let excludes = build_exclude_patterns(args, cfg_file, true);
 
-    let session = create_and_process_session(
+    let mut session = create_and_process_session(
         args,
         cfg_file,
         &[],       // include_patterns
@@ -301,39 +768,310 @@ fn prepare_interactive_data(
         Some(&builder_ext),
     )?;
 
-    // The rest of the logic remains the same.
-    let by_ext: HashMap<String, usize> = session
+    // Byte sizes are filled in by the selector, from the directory arena.
+    let by_ext: HashMap<String, ExtStats> = session
         .processed_entries
         .iter()
         .filter_map(|e| Some((e.extension.clone()?, e.token_count?)))
         .fold(HashMap::default(), |mut m, (ext, tok)| {
-            *m.entry(ext).or_default() += tok;
+            let stats = m.entry(ext.clone()).or_insert_with(|| ExtStats {
+                name: ext,
+                ..Default::default()
+            });
+            stats.tokens += tok;
+            stats.files += 1;
             m
         });
-    let mut sorted_ext: Vec<_> = by_ext.into_iter().collect();
-    sorted_ext.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut sorted_ext: Vec<_> = by_ext.into_values().collect();
+    sorted_ext.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.name.cmp(&b.name)));
 
     let mut ext_to_slot: FxHashMap<String, u16> = FxHashMap::default();
-    for (i, (ext, _)) in sorted_ext.iter().enumerate() {
-        ext_to_slot.insert(ext.clone(), (i + 1) as u16);
+    for (i, ext) in sorted_ext.iter().enumerate() {
+        ext_to_slot.insert(ext.name.clone(), (i + 1) as u16);
     }
 
-    let dir_arena = build_dir_arena(&session.processed_entries, &ext_to_slot);
+    // Files skipped as hidden or gitignored join the tree as placeholders, so
+    // the selector can include them without a rescan.
+    let skipped = skipped_files(&session)?;
+    let scanned = session.processed_entries.len();
+    session
+        .processed_entries
+        .extend(skipped.iter().map(|(rel, _)| {
+            ProcessedEntry {
+                path: session.config.path.join(rel),
+                relative_path: PathBuf::from(rel),
+                is_file: true,
+                extension: Path::new(rel)
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .map(str::to_owned),
+                ..Default::default()
+            }
+        }));
+    let mut dir_arena = build_dir_arena(&session.processed_entries, &ext_to_slot);
+    session.processed_entries.truncate(scanned);
+    mark_skipped(&mut dir_arena, &skipped.into_iter().collect());
 
     Ok((session, sorted_ext, dir_arena))
 }
 
-// Extracted filtering logic for clarity and testing
+/// The files the session's scan skipped only because they are hidden or
+/// gitignored, with the matching flags.
 #[cfg(feature = "tui")]
+fn skipped_files(session: &Code2PromptSession) -> Result<Vec<(String, DirFlags)>> {
+    let cfg = &session.config;
+    if cfg.hidden && cfg.no_ignore {
+        return Ok(Vec::new());
+    }
+    let scanned: HashSet<String> = session
+        .processed_entries
+        .iter()
+        .map(|e| to_fwd_slash(&e.relative_path))
+        .collect();
+
+    let mut unfiltered = cfg.clone();
+    unfiltered.hidden = true;
+    unfiltered.no_ignore = true;
+    let not_ignored: HashSet<String> = if cfg.no_ignore {
+        HashSet::new()
+    } else {
+        let mut with_hidden = cfg.clone();
+        with_hidden.hidden = true;
+        list_files(&cfg.path, &with_hidden)?.into_iter().collect()
+    };
+
+    let mut skipped = Vec::new();
+    for rel in list_files(&cfg.path, &unfiltered)? {
+        if scanned.contains(&rel) {
+            continue;
+        }
+        let mut flags = DirFlags::empty();
+        if !cfg.hidden && rel.split('/').any(|part| part.starts_with('.')) {
+            flags |= DirFlags::HIDDEN;
+        }
+        if !cfg.no_ignore && !not_ignored.contains(&rel) {
+            flags |= DirFlags::IGNORED;
+        }
+        // Anything else was skipped for its content (empty, binary, …).
+        if !flags.is_empty() {
+            skipped.push((rel, flags));
+        }
+    }
+    Ok(skipped)
+}
+
+/// Prints the confirmed selection for `--print-selection`.
+#[cfg(feature = "tui")]
+fn print_selection(
+    style: SelectionFormat,
+    selection: &cache::LastSelection,
+    session: &Code2PromptSession,
+) -> Result<()> {
+    match style {
+        SelectionFormat::Json => println!("{}", serde_json::to_string_pretty(selection)?),
+        SelectionFormat::Args => {
+            let args: Vec<String> = session
+                .processed_entries
+                .iter()
+                .map(|e| {
+                    let glob = glob::Pattern::escape(&to_fwd_slash(&e.relative_path));
+                    format!("-i {}", format::shell_quote(&glob))
+                })
+                .collect();
+            println!("{}", args.join(" "));
+        }
+    }
+    Ok(())
+}
+
+/// The templates after the first `-T`, read and each paired with its `-O`,
+/// to render from the same scan as the first.
+fn extra_templates(args: &Cli) -> Result<Vec<(String, String)>> {
+    if args.template.len() <= 1 {
+        if args.output_file.len() > 1 {
+            anyhow::bail!("Give several -O only with as many -T, one output per template.");
+        }
+        return Ok(Vec::new());
+    }
+    if args.output_file.len() != args.template.len() {
+        anyhow::bail!(
+            "Give one -O for each of the {} templates.",
+            args.template.len()
+        );
+    }
+    if args.split_by_dir {
+        anyhow::bail!("--split-by-dir renders a single template.");
+    }
+    args.template
+        .iter()
+        .zip(&args.output_file)
+        .skip(1)
+        .map(|(path, output)| {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read template file: {}", path.display()))?;
+            Ok((content, output.clone()))
+        })
+        .collect()
+}
+
+/// Renders one prompt per top-level directory for `--split-by-dir`, each
+/// with the shared preamble and tree but only the directory's files.
+fn write_split_by_dir(
+    args: &Cli,
+    session: &Code2PromptSession,
+    mut context: TemplateContext,
+    user_vars: &Value,
+    tpl_content: &str,
+    tpl_hash: &str,
+) -> Result<()> {
+    let name = if tpl_hash == "builtin" {
+        "default"
+    } else {
+        "custom"
+    };
+    let hb = template::handlebars_setup(tpl_content, name)?;
+    let pattern = output::split_output_pattern(args.output_file.first().map(String::as_str));
+    let groups = std::mem::take(&mut context.file_groups);
+    if groups.is_empty() && !args.quiet {
+        eprintln!("[i] No files were selected; no prompts were written.");
+    }
+    for group in groups {
+        let files = group.files.len();
+        let path = output::split_output_path(&pattern, &group.dir);
+        context.files = group.files.clone();
+        context.file_groups = vec![group];
+
+        let mut value = serde_json::to_value(&context)?;
+        if let (Some(obj), Some(user_obj)) = (value.as_object_mut(), user_vars.as_object()) {
+            obj.extend(user_obj.clone());
+        }
+        let rendered = template::render_template(&hb, name, &value)?;
+        let rendered = template::wrap_with_user_blocks(
+            rendered,
+            tpl_content,
+            context.user_prelude.as_deref(),
+            context.user_epilogue.as_deref(),
+        );
+        let tokens = count_tokens(&rendered, session.config.tokenizer)?;
+        let path = output_path(&path, session, tokens, files);
+        template::write_to_file(&path, &rendered)?;
+    }
+    Ok(())
+}
+
+/// Writes the `--scaffold` response skeleton: a section for each file the
+/// prompt includes, to be filled in by the model or a reviewer.
+fn write_scaffold(path: &Path, session: &Code2PromptSession, quiet: bool) -> Result<()> {
+    let scaffold = output::scaffold(&session.processed_entries)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, scaffold)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    if !quiet {
+        eprintln!("[i] Response scaffold written to {}", path.display());
+    }
+    Ok(())
+}
+
+/// Streams the files without a template to the output file, or to stdout
+/// without one, for `--no-template`.
+fn write_concatenation(args: &Cli, session: &mut Code2PromptSession) -> Result<()> {
+    use std::io::Write;
+
+    session.load_files()?;
+    let entries = &session.processed_entries;
+    let mut out: Box<dyn Write> = match args.output_file.first() {
+        Some(pattern) => {
+            let files = entries.iter().filter(|e| e.is_file && e.code.is_some());
+            let tokens = files.clone().filter_map(|e| e.token_count).sum();
+            let path = output_path(pattern, session, tokens, files.count());
+            let file =
+                std::fs::File::create(&path).with_context(|| format!("Failed to create {path}"))?;
+            Box::new(std::io::BufWriter::new(file))
+        }
+        None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+    };
+    output::write_concatenated(&mut out, entries, session.config.absolute_path)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the session's files as `-F rag-jsonl` records to the output file,
+/// or to stdout without one, instead of rendering a prompt.
+fn write_rag_jsonl(args: &Cli, session: &mut Code2PromptSession) -> Result<()> {
+    let records = session.rag_records()?;
+    let mut jsonl = String::new();
+    for record in &records {
+        jsonl.push_str(&serde_json::to_string(record)?);
+        jsonl.push('\n');
+    }
+    let Some(pattern) = args.output_file.first() else {
+        print!("{jsonl}");
+        return Ok(());
+    };
+    let tokens = records.iter().map(|r| r.tokens).sum();
+    let path = output_path(pattern, session, tokens, records.len());
+    template::write_to_file(&path, &jsonl)
+}
+
+/// Expands the `-O` pattern for outputs written without the output handler.
+fn output_path(pattern: &str, session: &Code2PromptSession, tokens: usize, files: usize) -> String {
+    let vars = output::OutputPathVars {
+        repo: format::format_path_label(&session.config.path),
+        tokens,
+        files,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    };
+    output::expand_output_path(pattern, &vars)
+}
+
+/// The session's scan counts with the prompt's tokens. Scanned files that
+/// no count explains were left out in the selector or by a saved selection.
+pub fn run_summary(
+    session: &Code2PromptSession,
+    tokens: usize,
+    sections: Option<TokenBreakdown>,
+) -> RunSummary {
+    let mut summary = session.config.stats.summary();
+    summary.files_included = session
+        .processed_entries
+        .iter()
+        .filter(|e| e.is_file)
+        .count();
+    summary.skipped.not_selected = summary
+        .files_scanned
+        .saturating_sub(summary.skipped.total() + summary.files_included);
+    summary.scan_errors = session.scan_errors.len();
+    summary.tokens = tokens;
+    summary.sections = sections;
+    summary
+}
+
+// Extracted filtering logic for clarity and testing
 pub fn filter_session_entries(
     session: &mut Code2PromptSession,
     sel_exts: &[String],
     sel_paths: &[PathBuf],
+    pinned: &[PathBuf],
 ) {
     // Correctly create a HashSet<String> for efficient and correct lookups.
     let ext_set: HashSet<String> = sel_exts.iter().cloned().collect();
+    // Pinned files are always kept; when nothing else is selected they are
+    // the only files kept.
+    let pinned_only = sel_exts.is_empty() && sel_paths.is_empty() && !pinned.is_empty();
 
     session.processed_entries.retain(|e| {
+        if pinned.contains(&e.relative_path) {
+            return true;
+        }
+        if pinned_only {
+            return false;
+        }
+
         let matches_extension = if ext_set.is_empty() {
             true
         } else {
@@ -395,6 +1133,891 @@ fn create_and_process_session(
     Ok(session)
 }
 
+/// Sends `log` records to stderr: errors only by default, warnings with
+/// `-v`, debug output from code2prompt with `-vv` and trace output with
+/// `-vvv`. Without these flags, `RUST_LOG` applies when set.
+#[cfg(feature = "logging")]
+fn init_logging(args: &Cli) {
+    use log::LevelFilter;
+
+    let mut builder = env_logger::Builder::new();
+    builder
+        .filter_level(LevelFilter::Error)
+        .format_timestamp(None)
+        .format_target(false)
+        .parse_default_env();
+    if args.quiet || args.verbose > 0 {
+        let level = match args.verbose {
+            _ if args.quiet => LevelFilter::Error,
+            1 => LevelFilter::Warn,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        };
+        // Dependencies such as the walker are chatty below warnings.
+        builder
+            .filter_level(level.min(LevelFilter::Warn))
+            .filter_module(env!("CARGO_CRATE_NAME"), level);
+    }
+    let _ = builder.try_init();
+}
+
+#[cfg(not(feature = "logging"))]
+fn init_logging(args: &Cli) {
+    if args.verbose > 0 {
+        eprintln!("[!] -v has no effect: the 'logging' feature was not included at compile time.");
+    }
+}
+
+/// With `--trace-out`, records spans to that file until the returned guard
+/// is dropped. Spans carry their thread, so the parallel walk shows up as
+/// one track per worker.
+#[cfg(feature = "tracing")]
+fn init_tracing(args: &Cli) -> Result<Option<tracing_chrome::FlushGuard>> {
+    use tracing_subscriber::prelude::*;
+
+    let Some(path) = &args.trace_out else {
+        return Ok(None);
+    };
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create trace file {}", path.display()))?;
+    let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+        .writer(file)
+        .include_args(true)
+        .build();
+    // Not `try_init`, which would also claim the `log` logger.
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))?;
+    Ok(Some(guard))
+}
+
+#[cfg(not(feature = "tracing"))]
+fn init_tracing(args: &Cli) -> Result<Option<()>> {
+    if args.trace_out.is_some() {
+        anyhow::bail!(
+            "--trace-out requires the 'tracing' feature, which was not included at compile time."
+        );
+    }
+    Ok(None)
+}
+
+/// The directory to scan: with `--root git` the root of the git repository
+/// containing the path, with `--root auto` the nearest repository root if
+/// the user accepts it, and otherwise the path as given.
+fn resolve_root(args: &Cli) -> Result<PathBuf> {
+    if args.root == RootMode::Cwd {
+        return Ok(args.path.clone());
+    }
+    let start = path::canonicalize(&args.path)
+        .with_context(|| format!("Failed to canonicalize {}", args.path.display()))?;
+    let markers: &[&str] = match args.root {
+        RootMode::Git => &[".git"],
+        _ => &[".git", ".code2prompt"],
+    };
+    let root = match path::find_ancestor_with(&start, markers) {
+        Some(root) if root != start => root,
+        Some(_) => return Ok(args.path.clone()),
+        None if args.root == RootMode::Git => {
+            anyhow::bail!("{} is not inside a git repository.", args.path.display())
+        }
+        None => return Ok(args.path.clone()),
+    };
+    if args.root == RootMode::Git || offer_root(args, &root, &start)? {
+        Ok(root)
+    } else {
+        Ok(args.path.clone())
+    }
+}
+
+/// Asks whether to scan from `root` instead of `start`, a directory below
+/// it. Without prompts, or when stdin or stderr is not a terminal, only says
+/// how to.
+fn offer_root(args: &Cli, root: &Path, start: &Path) -> Result<bool> {
+    let sub = path::display(start.strip_prefix(root).unwrap_or(start));
+    #[cfg(feature = "interactive")]
+    if !args.no_interactive && io::stdin().is_terminal() && io::stderr().is_terminal() {
+        return inquire::Confirm::new(&format!(
+            "{sub} is inside the repository at {}. Scan the whole repository?",
+            path::display(root)
+        ))
+        .with_help_message("--root git always does, --root cwd never asks")
+        .with_default(true)
+        .prompt()
+        .context("Failed to read the answer");
+    }
+    if !args.quiet {
+        eprintln!(
+            "[i] Scanning {sub} inside the repository at {}; --root git scans the whole repository.",
+            path::display(root)
+        );
+    }
+    Ok(false)
+}
+
+/// Shows cached variable values for `--review-vars`: in the TUI when the
+/// selector will be shown, otherwise as prompts. Returns the reviewed
+/// values, or `None` to keep the cached ones.
+fn review_vars(
+    args: &Cli,
+    vars: Vec<(String, String)>,
+    specs: &BTreeMap<String, VarSpec>,
+) -> Result<Option<HashMap<String, String>>> {
+    #[cfg(feature = "tui")]
+    if needs_interactive_tui(args) {
+        let reviewed = vars_review::review_vars_tui(&format::format_path_label(&args.path), vars)?;
+        return Ok(reviewed.map(|vars| vars.into_iter().collect()));
+    }
+    #[cfg(feature = "interactive")]
+    {
+        let _ = args;
+        template::review_cached_variables(&vars, specs).map(Some)
+    }
+    #[cfg(not(feature = "interactive"))]
+    {
+        let _ = (args, vars, specs);
+        anyhow::bail!(
+            "--review-vars without the selector requires the 'interactive' feature, which was not included at compile time."
+        )
+    }
+}
+
+/// Runs a subcommand instead of a scan.
+fn run_command(command: &Command) -> Result<()> {
+    match command {
+        Command::Template(TemplateCommand::Check { file }) => run_template_check(file),
+        Command::Config(ConfigCommand::Check { scan }) => run_config_check(scan),
+        Command::Config(ConfigCommand::Init {
+            project,
+            path,
+            force,
+        }) => run_config_init(*project, path, *force),
+        Command::History { action, repo } => run_history(action, repo),
+        Command::Serve { roots, http } => run_serve(http, roots),
+        Command::Commit {
+            repo,
+            with_files,
+            send,
+        } => run_commit(repo, *with_files, *send),
+        Command::Review { repo, base, head } => run_review(repo, base, head.as_deref()),
+        Command::MapDiff {
+            old,
+            new,
+            depth,
+            max_growth,
+        } => run_map_diff(old, new, *depth, *max_growth),
+        Command::Bench { path } => run_bench(path),
+        Command::WarmCache { path } => run_warm_cache(path),
+        Command::Daemon { roots, socket } => run_daemon(roots, socket.as_deref()),
+    }
+}
+
+/// Renders the built-in commit-message template over the staged diff, and
+/// prints the prompt or, with `--send`, the model's reply.
+#[cfg(feature = "git")]
+fn run_commit(repo: &Path, with_files: bool, send: bool) -> Result<()> {
+    let cfg_file = config_file::load_for(repo)?;
+    let (diff, changed) = crate::engine::git::get_staged_diff(repo)?;
+    if diff.is_empty() {
+        anyhow::bail!("Nothing is staged in {}.", repo.display());
+    }
+
+    let includes = if with_files {
+        let globs: Vec<String> = changed.iter().map(|p| glob::Pattern::escape(p)).collect();
+        patterns_from_strings(&globs)?
+    } else {
+        Vec::new()
+    };
+    let config = Code2PromptConfigBuilder::default()
+        .path(repo.to_path_buf())
+        .include_patterns(includes)
+        .no_ignore(true)
+        .tokenizer(cfg_file.tokenizer.unwrap_or_default())
+        .build()?;
+    let mut session = Code2PromptSession::new(config)?;
+    // Without include patterns a scan would take in the whole repository.
+    if with_files && !changed.is_empty() {
+        session.process_codebase()?;
+    }
+    let mut context = session.build_template_data(None, None, None)?;
+    context.git_diff = Some(diff);
+
+    let hb = template::handlebars_setup(include_str!("../commit_template.hbs"), "commit")?;
+    let prompt = hb
+        .render("commit", &serde_json::to_value(&context)?)
+        .map_err(|e| anyhow::anyhow!("Failed to render template: {e}"))?;
+    if send {
+        let settings = cfg_file.llm.unwrap_or_default();
+        let reply = crate::engine::llm::complete(&settings, prompt.trim())?;
+        println!("{reply}");
+    } else {
+        println!("{}", prompt.trim());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "git"))]
+fn run_commit(_repo: &Path, _with_files: bool, _send: bool) -> Result<()> {
+    anyhow::bail!("The commit command requires the 'git' feature.")
+}
+
+/// Renders the built-in review template over the changes of `head` since
+/// `base`, with the changed files in full, and prints the prompt.
+#[cfg(feature = "git")]
+fn run_review(repo: &Path, base: &str, head: Option<&str>) -> Result<()> {
+    use crate::engine::git;
+
+    let head = match head {
+        Some(head) => head.to_owned(),
+        None => git::current_branch(repo)?,
+    };
+    let changed = git::get_changed_files_between_branches(repo, base, &head)?;
+    if changed.is_empty() {
+        anyhow::bail!("{head} has no changes against {base}.");
+    }
+
+    let cfg_file = config_file::load_for(repo)?;
+    let globs: Vec<String> = changed.iter().map(|p| glob::Pattern::escape(p)).collect();
+    let root = path::canonicalize(repo)?;
+    let config = Code2PromptConfigBuilder::default()
+        .path(root.clone())
+        .include_patterns(patterns_from_strings(&globs)?)
+        .no_ignore(true)
+        .tokenizer(cfg_file.tokenizer.unwrap_or_default())
+        .build()?;
+    let mut session = Code2PromptSession::new(config)?;
+    session.process_codebase()?;
+    let mut context =
+        session.build_template_data(None, Some((base, &head)), Some((base, &head)))?;
+    context.source_tree = build_tree_view(&root, &session.processed_entries, true, false);
+
+    let hb = template::handlebars_setup(include_str!("../review_template.hbs"), "review")?;
+    let prompt = hb
+        .render("review", &serde_json::to_value(&context)?)
+        .map_err(|e| anyhow::anyhow!("Failed to render template: {e}"))?;
+    println!("{}", prompt.trim());
+    Ok(())
+}
+
+#[cfg(not(feature = "git"))]
+fn run_review(_repo: &Path, _base: &str, _head: Option<&str>) -> Result<()> {
+    anyhow::bail!("The review command requires the 'git' feature.")
+}
+
+/// The timings of one `bench` scan and render.
+struct BenchRun {
+    timings: Arc<PhaseTimings>,
+    render: Duration,
+    total: Duration,
+    files: usize,
+    tokens: usize,
+}
+
+/// Scans and renders `path` with the default template, timing each phase.
+fn bench_run(path: &Path, cfg_file: &config_file::ConfigFile, cache: bool) -> Result<BenchRun> {
+    let mut excludes = cfg_file.exclude.clone().unwrap_or_default();
+    if !cfg_file.no_default_excludes.unwrap_or(false) {
+        excludes.extend(DEFAULT_EXCLUDES.iter().map(|s| s.to_string()));
+    }
+    let timings = Arc::new(PhaseTimings::default());
+    let config = Code2PromptConfigBuilder::default()
+        .path(path.to_path_buf())
+        .exclude_patterns(patterns_from_strings(&excludes)?)
+        .tokenizer(cfg_file.tokenizer.unwrap_or_default())
+        .token_map_enabled(true)
+        .cache(cache)
+        .timings(Some(timings.clone()))
+        .build()?;
+
+    let start = Instant::now();
+    let mut session = Code2PromptSession::new(config)?;
+    session.process_codebase()?;
+    let render_start = Instant::now();
+    let mut context = session.build_template_data(None, None, None)?;
+    context.source_tree = build_tree_view(
+        &session.config.path,
+        &session.processed_entries,
+        session.config.full_directory_tree,
+        session.config.file_ids,
+    );
+    let hb = template::handlebars_setup(include_str!("../default_template.hbs"), "default")?;
+    hb.render("default", &serde_json::to_value(&context)?)
+        .map_err(|e| anyhow::anyhow!("Failed to render template: {e}"))?;
+    let render = render_start.elapsed();
+
+    let files: Vec<_> = session
+        .processed_entries
+        .iter()
+        .filter(|e| e.is_file)
+        .collect();
+    Ok(BenchRun {
+        timings,
+        render,
+        total: start.elapsed(),
+        files: files.len(),
+        tokens: files.iter().filter_map(|e| e.token_count).sum(),
+    })
+}
+
+/// Scans `path` the way a run with `--cache` would, with every file's tokens
+/// counted, so that the scan cache holds the counts and contents of all of
+/// them afterwards.
+fn run_warm_cache(path: &Path) -> Result<()> {
+    use clap::Parser;
+
+    if !cfg!(feature = "cache") {
+        anyhow::bail!("warm-cache requires the 'cache' feature.");
+    }
+    let mut args = Cli::try_parse_from(["code2prompt".as_ref(), path.as_os_str()])?;
+    args.path = resolve_root(&args)?;
+    args.cache = true;
+    let cfg_file = config_file::load_for(&args.path)?;
+    let excludes = build_exclude_patterns(&args, &cfg_file, true);
+
+    let start = Instant::now();
+    let session = create_and_process_session(&args, &cfg_file, &[], &excludes, true, None)?;
+    let summary = session.config.stats.summary();
+    let tokens: usize = session
+        .processed_entries
+        .iter()
+        .filter_map(|e| e.token_count)
+        .sum();
+    println!(
+        "Cached {} files, {} tokens, in {} ({} were already cached).",
+        summary.cache_hits + summary.cache_misses,
+        format::format_tokens(tokens, format::TokenFormatStyle::Compact),
+        format_duration(start.elapsed()),
+        summary.cache_hits
+    );
+    if !session.scan_errors.is_empty() {
+        output::print_scan_errors(&session.scan_errors);
+    }
+    Ok(())
+}
+
+/// Prints the directories whose tokens changed between two `--token-map-json`
+/// exports, and fails if the total grew by more than `max_growth` percent.
+fn run_map_diff(old: &Path, new: &Path, depth: usize, max_growth: Option<f64>) -> Result<()> {
+    let old_map = TokenMapExport::load(old)?;
+    let new_map = TokenMapExport::load(new)?;
+    if old_map.tokenizer != new_map.tokenizer {
+        eprintln!(
+            "[!] The token maps were counted with different tokenizers ({:?} and {:?}).",
+            old_map.tokenizer, new_map.tokenizer
+        );
+    }
+
+    let changes = diff_by_dir(&old_map, &new_map, depth);
+    let total = DirChange {
+        dir: "Total".to_string(),
+        old: old_map.total_tokens,
+        new: new_map.total_tokens,
+    };
+    let width = changes
+        .iter()
+        .map(|c| c.dir.len())
+        .chain(["Directory".len(), total.dir.len()])
+        .max()
+        .unwrap_or(0);
+    let row = |c: &DirChange| {
+        println!(
+            "{:<width$}  {:>12}  {:>12}  {:>12}  {}",
+            c.dir,
+            c.old.separate_with_commas(),
+            c.new.separate_with_commas(),
+            format!("{:+}", c.delta()).separate_with_commas(),
+            growth_percent(c).map_or_else(|| "new".to_string(), |p| format!("{p:+.1}%"))
+        );
+    };
+    println!(
+        "{:<width$}  {:>12}  {:>12}  {:>12}",
+        "Directory", "Old", "New", "Change"
+    );
+    if changes.is_empty() {
+        println!("(no directory changed)");
+    }
+    changes.iter().for_each(row);
+    row(&total);
+
+    if let Some(limit) = max_growth
+        && let Some(growth) = growth_percent(&total)
+        && growth > limit
+    {
+        anyhow::bail!("The prompt grew by {growth:.1}%, over the limit of {limit}%.");
+    }
+    Ok(())
+}
+
+/// The change as a percentage of the old tokens; `None` if there were none.
+fn growth_percent(change: &DirChange) -> Option<f64> {
+    (change.old > 0).then(|| change.delta() as f64 * 100.0 / change.old as f64)
+}
+
+/// Times scans and renders of `path` without and with the scan cache.
+fn run_bench(path: &Path) -> Result<()> {
+    let cfg_file = config_file::load_for(path)?;
+
+    eprintln!("Benchmarking {}…", path.display());
+    let mut runs = vec![("no cache", bench_run(path, &cfg_file, false)?)];
+    if cfg!(feature = "cache") {
+        // The first cached run fills the cache; only the warm one is shown.
+        bench_run(path, &cfg_file, true)?;
+        runs.push(("warm cache", bench_run(path, &cfg_file, true)?));
+    }
+
+    let first = &runs[0].1;
+    println!(
+        "{} files, {} tokens",
+        first.files,
+        format::format_tokens(first.tokens, format::TokenFormatStyle::Compact)
+    );
+    println!();
+    print!("{:<10}", "phase");
+    for (name, _) in &runs {
+        print!("{name:>12}");
+    }
+    println!();
+    let rows: [(&str, fn(&BenchRun) -> Duration); 6] = [
+        ("walk", |r| r.timings.get(Phase::Walk)),
+        ("read", |r| r.timings.get(Phase::Read)),
+        ("tokenize", |r| r.timings.get(Phase::Tokenize)),
+        ("cache", |r| r.timings.get(Phase::Cache)),
+        ("render", |r| r.render),
+        ("total", |r| r.total),
+    ];
+    for (label, duration) in rows {
+        print!("{label:<10}");
+        for (_, run) in &runs {
+            print!("{:>12}", format_duration(duration(run)));
+        }
+        println!();
+    }
+    println!();
+    println!("Read, tokenize and cache times are summed over the walker's threads.");
+    if let Some(peak) = peak_memory() {
+        println!("Peak memory: {}", format::format_size(peak));
+    }
+    Ok(())
+}
+
+fn format_duration(d: Duration) -> String {
+    if d.as_secs() >= 10 {
+        format!("{:.1} s", d.as_secs_f64())
+    } else if d.as_millis() >= 10 {
+        format!("{} ms", d.as_millis())
+    } else {
+        format!("{:.1} ms", d.as_secs_f64() * 1000.0)
+    }
+}
+
+/// The process's peak resident memory, where the platform reports it.
+fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|l| l.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(unix)]
+fn run_daemon(roots: &[PathBuf], socket: Option<&Path>) -> Result<()> {
+    use crate::ui::daemon;
+
+    let socket = socket.map_or_else(daemon::default_socket_path, Path::to_path_buf);
+    let cfg_file = config_file::load()?;
+    let listener = daemon::bind(&socket)?;
+    eprintln!("[i] Daemon listening on {}", socket.display());
+    daemon::run(listener, roots, cfg_file)
+}
+
+#[cfg(not(unix))]
+fn run_daemon(_roots: &[PathBuf], _socket: Option<&Path>) -> Result<()> {
+    anyhow::bail!("The daemon is only supported on Unix.")
+}
+
+/// The arguments a `--use-daemon` run may give: those its [`RenderRequest`]
+/// carries and those applied here to the prompt the daemon renders.
+///
+/// [`RenderRequest`]: crate::ui::serve::RenderRequest
+#[cfg(unix)]
+const DAEMON_ARGS: &[&str] = &[
+    // Sent in the request.
+    "path",
+    "root",
+    "include",
+    "extensions",
+    "exclude",
+    "preset",
+    "no_default_excludes",
+    "no_auto_detect",
+    "template",
+    "vars",
+    "vars_file",
+    "no_var_cache",
+    "review_vars",
+    "tokenizer",
+    "line_numbers",
+    "relative_paths",
+    "no_codeblock",
+    "hidden",
+    "no_ignore",
+    // Applied to the reply.
+    "output_file",
+    "output_format",
+    "tokens",
+    "no_clipboard",
+    "clipboard_content",
+    "history",
+    "no_interactive",
+    "quiet",
+    "verbose",
+    "low_priority",
+    "use_daemon",
+];
+
+/// The arguments and settings in effect that the daemon cannot apply, so
+/// that `--use-daemon` scans normally instead of silently ignoring them.
+#[cfg(unix)]
+fn daemon_unsupported(args: &Cli, cfg_file: &config_file::ConfigFile) -> Vec<String> {
+    let cmd = <Cli as clap::CommandFactory>::command();
+    let mut unsupported: Vec<String> = args
+        .given
+        .iter()
+        .filter(|id| !DAEMON_ARGS.contains(&id.as_str()))
+        .map(|id| {
+            let arg = cmd.get_arguments().find(|arg| arg.get_id() == id.as_str());
+            match arg.and_then(|arg| arg.get_long()) {
+                Some(long) => format!("--{long}"),
+                None => id.clone(),
+            }
+        })
+        .collect();
+    [
+        ("the interactive selector", needs_interactive_tui(args)),
+        (
+            "--output-format",
+            !matches!(
+                args.output_format,
+                OutputFormat::Markdown | OutputFormat::Json
+            ),
+        ),
+        ("--tokens breakdown", args.tokens == TokenFormat::Breakdown),
+        ("[priority]", cfg_file.priority.is_some()),
+        ("[languages]", cfg_file.languages.is_some()),
+    ]
+    .into_iter()
+    .filter(|&(_, set)| set)
+    .for_each(|(name, _)| unsupported.push(name.to_string()));
+    unsupported
+}
+
+/// Renders through the daemon. Returns `Ok(false)` if none is listening, so
+/// the caller can scan normally instead.
+#[cfg(unix)]
+fn render_with_daemon(
+    args: &Cli,
+    cfg_file: &config_file::ConfigFile,
+    vars: &HashMap<String, String>,
+    hooks: &Hooks,
+    tpl_hash: &str,
+) -> Result<bool> {
+    use crate::ui::{config::ci_context, daemon, serve::RenderRequest};
+
+    // The excludes are sent in full, as the daemon knows neither the
+    // presets nor the repository config; the settings as in effect here.
+    let req = RenderRequest {
+        path: path::canonicalize(&args.path).unwrap_or_else(|_| args.path.clone()),
+        include: build_include_patterns(args),
+        exclude: build_exclude_patterns(args, cfg_file, true),
+        no_default_excludes: true,
+        template: args.template.first().cloned(),
+        vars: vars.clone(),
+        tokenizer: args.tokenizer.or(cfg_file.tokenizer),
+        line_numbers: args.line_numbers || cfg_file.line_numbers.unwrap_or(false),
+        absolute_paths: !args.relative_paths,
+        no_codeblock: args.no_codeblock || cfg_file.no_codeblock.unwrap_or(false),
+        // The includes of ci-context keep other hidden files out.
+        hidden: args.hidden || ci_context(args),
+        no_ignore: args.no_ignore,
+    };
+    let socket = daemon::default_socket_path();
+    if !socket.exists() {
+        return Ok(false);
+    }
+    let resp = daemon::request(&socket, &req)?;
+
+    let config = build_config_builder(args, cfg_file, |_| {})
+        .tokenizer(resp.tokenizer)
+        .build()
+        .context("Failed to build configuration")?;
+    let entries: Vec<_> = resp
+        .files
+        .iter()
+        .map(|f| crate::ProcessedEntry {
+            path: config.path.join(f),
+            relative_path: PathBuf::from(f),
+            is_file: true,
+            extension: Path::new(f)
+                .extension()
+                .map(|e| e.to_string_lossy().into_owned()),
+            ..Default::default()
+        })
+        .collect();
+    let output_path =
+        output::OutputHandler::new(&resp.prompt, "", resp.token_count, &entries, args, &config)
+            .handle()?;
+
+    hooks.run_post_render(
+        &resp.prompt,
+        &HookMetadata {
+            repo: config.path.to_string_lossy().into_owned(),
+            output_path,
+            files: entries.len(),
+            token_count: resp.token_count,
+            tokenizer: resp.tokenizer,
+            template_hash: tpl_hash.to_string(),
+        },
+    )?;
+    if args.history || cfg_file.history.unwrap_or(false) {
+        let entry = HistoryEntry::new(
+            resp.prompt.clone(),
+            resp.token_count,
+            entries.len(),
+            resp.tokenizer,
+            tpl_hash.to_string(),
+        );
+        History::open(&config.path)?
+            .record(&entry)
+            .context("Failed to save prompt history")?;
+    }
+
+    // The daemon did the scan; only the prompt's side is known here.
+    let summary = RunSummary {
+        files_scanned: entries.len(),
+        files_included: entries.len(),
+        tokens: resp.token_count,
+        ..Default::default()
+    };
+    if !args.quiet {
+        output::print_summary(
+            &config.path.to_string_lossy(),
+            &summary,
+            &detected_ecosystems(args),
+        );
+    }
+    Ok(true)
+}
+
+fn run_serve(_addr: &str, _roots: &[PathBuf]) -> Result<()> {
+    #[cfg(feature = "serve")]
+    {
+        let cfg_file = config_file::load()?;
+        crate::ui::serve::serve(_addr, _roots, cfg_file)
+    }
+    #[cfg(not(feature = "serve"))]
+    {
+        anyhow::bail!(
+            "`serve` requires the 'serve' feature, which was not included at compile time."
+        )
+    }
+}
+
+fn run_history(action: &HistoryCommand, repo: &Path) -> Result<()> {
+    let history = History::open(repo)?;
+    match action {
+        HistoryCommand::List => {
+            let entries = history.entries()?;
+            if entries.is_empty() {
+                println!("No prompts recorded. Run with --history to start saving them.");
+                return Ok(());
+            }
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            for (i, e) in entries.iter().enumerate() {
+                println!(
+                    "{:>3}  {:<9} {:>7} tokens {:>5} files  {}",
+                    i + 1,
+                    format::format_age(now.saturating_sub(e.timestamp)),
+                    format::format_tokens(e.token_count, format::TokenFormatStyle::Compact),
+                    e.files,
+                    e.args.join(" ")
+                );
+            }
+        }
+        HistoryCommand::Show { n } => println!("{}", history.get(*n)?.prompt),
+        HistoryCommand::Copy { n } => {
+            let entry = history.get(*n)?;
+            #[cfg(feature = "clipboard")]
+            {
+                crate::ui::clipboard::copy_to_clipboard(&entry.prompt)?;
+                println!("[✓] Copied prompt #{n} to clipboard.");
+            }
+            #[cfg(not(feature = "clipboard"))]
+            {
+                let _ = entry;
+                anyhow::bail!("history copy requires the 'clipboard' feature.");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_template_check(file: &Path) -> Result<()> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read template file: {}", file.display()))?;
+    let check = template::check_template(&content)
+        .with_context(|| format!("{} does not compile", file.display()))?;
+
+    let list = |names: &[String]| {
+        if names.is_empty() {
+            "(none)".to_string()
+        } else {
+            names.join(", ")
+        }
+    };
+    println!("Template: {}", file.display());
+    println!("  Built-in variables: {}", list(&check.builtins));
+    println!("  User variables:     {}", list(&check.variables));
+    for (name, spec) in &check.declared {
+        let mut notes = Vec::new();
+        match spec.kind {
+            VarType::String => {}
+            VarType::Enum => notes.push(format!("one of: {}", spec.options.join(", "))),
+            kind => notes.push(format!("{kind:?}").to_lowercase()),
+        }
+        if let Some(default) = &spec.default {
+            notes.push(format!("default: {default}"));
+        } else if spec.required {
+            notes.push("required".to_string());
+        }
+        if !check.variables.contains(name) {
+            notes.push("not used".to_string());
+        }
+        let notes = if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", notes.join(", "))
+        };
+        println!(
+            "    {name}: {}{notes}",
+            spec.description.as_deref().unwrap_or("no description")
+        );
+    }
+    if !check.unknown_helpers.is_empty() {
+        println!(
+            "{}",
+            colour(format!(
+                "  Unknown helpers:    {}",
+                list(&check.unknown_helpers)
+            ))
+        );
+    }
+    if let Some(err) = &check.render_error {
+        println!("{}", colour(format!("  Render error:       {err}")));
+    }
+
+    if !check.is_ok() {
+        anyhow::bail!("Template check failed for {}", file.display());
+    }
+    println!("[✓] Template OK");
+    Ok(())
+}
+
+/// Prints the effective settings of a scan with the `scan` arguments, and
+/// fails if any of them is invalid.
+fn run_config_check(scan: &[String]) -> Result<()> {
+    use clap::Parser;
+
+    let path = config_file::path()?;
+    let global = config_file::load()?;
+    let mut argv = vec!["code2prompt".to_string()];
+    argv.extend(scan.iter().cloned());
+    if scan.is_empty() {
+        argv.push(".".to_string());
+    }
+    let args = Cli::try_parse_from(argv).unwrap_or_else(|e| e.exit());
+    let project = config_file::load_project_for(&global, &args.path)?;
+    let repo_config = CacheManager::new(&args.path)?.path_of::<config_file::RepoConfig>()?;
+
+    println!("Config file:       {}", path.display());
+    if project.is_some() {
+        let project_path = config_file::project_path(&args.path);
+        println!("Project config:    {}", project_path.display());
+    }
+    if repo_config.exists() {
+        println!("Repository config: {}", repo_config.display());
+    }
+    println!();
+    let lines: Vec<_> = effective_settings(&args, &global, project.as_ref())?
+        .into_iter()
+        .map(|s| (format!("{} = {}", s.key, s.value), s.source))
+        .collect();
+    // Long tables are left out of the alignment, rather than pushing the
+    // sources of every other line far to the right.
+    let width = lines
+        .iter()
+        .map(|(line, _)| line.len())
+        .filter(|&len| len <= 48)
+        .max()
+        .unwrap_or(0);
+    for (line, source) in lines {
+        println!("{line:<width$}  # {source}");
+    }
+
+    let cfg_file = match project {
+        Some(project) => global.overlaid_with(project),
+        None => global,
+    };
+    let problems = config_problems(&args, &cfg_file);
+    if !problems.is_empty() {
+        println!();
+        for problem in &problems {
+            println!("{}", colour(format!("  {problem}")));
+        }
+        anyhow::bail!("Config check failed");
+    }
+    println!("[✓] Config OK");
+    Ok(())
+}
+
+/// Writes the starter config and template, to the user's config directory
+/// or with `project` to the `.code2prompt/` directory of `path`.
+fn run_config_init(project: bool, path: &Path, force: bool) -> Result<()> {
+    let (config, template) = if project {
+        (
+            config_file::project_path(path),
+            template::project_template_path(path),
+        )
+    } else {
+        (config_file::path()?, template::global_template_path())
+    };
+    let files = [
+        (config, config_file::STARTER_CONFIG.to_string()),
+        (template, template::starter_template()),
+    ];
+    for (file, content) in files {
+        if file.exists() && !force {
+            eprintln!(
+                "[i] Kept {}, which already exists (--force overwrites it)",
+                file.display()
+            );
+            continue;
+        }
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        std::fs::write(&file, content)
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+        println!("[✓] Wrote {}", file.display());
+    }
+    Ok(())
+}
+
 #[cfg(feature = "colors")]
 fn colour<S: AsRef<str>>(s: S) -> ColoredString {
     s.as_ref().yellow()
@@ -415,7 +2038,6 @@ fn parse_branch_pair(branches: &Option<Vec<String>>) -> Option<(&str, &str)> {
     })
 }
 
-#[cfg(feature = "tui")]
 fn paths_match_case_insensitive(full_path: &Path, prefix: &Path) -> bool {
     let mut full_components = full_path.components();
     let mut prefix_components = prefix.components();
@@ -40,13 +40,22 @@ impl CacheManager {
         self.repo_path_handler.get_cache_file_path(key, extension)
     }
 
+    /// Gets the path of the file a `Cacheable` type is stored in.
+    pub fn path_of<T: Cacheable>(&self) -> Result<PathBuf> {
+        let ext = match T::FORMAT {
+            CacheFormat::Json => "json",
+            CacheFormat::Toml => "toml",
+        };
+        self.get_path_for(T::KEY, ext)
+    }
+
     /// Saves a `Cacheable` item to its corresponding file.
     pub fn save<T: Cacheable>(&self, item: &T) -> Result<()> {
-        let (ext, content) = match T::FORMAT {
-            CacheFormat::Json => ("json", serde_json::to_string_pretty(item)?),
-            CacheFormat::Toml => ("toml", toml::to_string_pretty(item)?),
+        let content = match T::FORMAT {
+            CacheFormat::Json => serde_json::to_string_pretty(item)?,
+            CacheFormat::Toml => toml::to_string_pretty(item)?,
         };
-        let path = self.get_path_for(T::KEY, ext)?;
+        let path = self.path_of::<T>()?;
         std::fs::create_dir_all(
             path.parent()
                 .context("Cache path has no parent directory")?,
@@ -58,11 +67,7 @@ impl CacheManager {
 
     /// Loads a `Cacheable` item from its file, if it exists.
     pub fn load<T: Cacheable>(&self) -> Result<Option<T>> {
-        let ext = match T::FORMAT {
-            CacheFormat::Json => "json",
-            CacheFormat::Toml => "toml",
-        };
-        let path = self.get_path_for(T::KEY, ext)?;
+        let path = self.path_of::<T>()?;
 
         if !path.exists() {
             return Ok(None);
@@ -71,9 +76,10 @@ impl CacheManager {
         let content = std::fs::read_to_string(&path)
             .with_context(|| format!("Failed to read cache file from {}", path.display()))?;
 
+        let invalid = || format!("Invalid cache file {}", path.display());
         let item = match T::FORMAT {
-            CacheFormat::Json => serde_json::from_str(&content)?,
-            CacheFormat::Toml => toml::from_str(&content)?,
+            CacheFormat::Json => serde_json::from_str(&content).with_context(invalid)?,
+            CacheFormat::Toml => toml::from_str(&content).with_context(invalid)?,
         };
 
         Ok(Some(item))
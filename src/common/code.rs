@@ -1,15 +1,164 @@
-/// Wraps code in a markdown block, optionally with language extension and line numbers.
-pub fn wrap(code: &str, ext: &str, line_numbers: bool, no_block: bool) -> String {
-    if no_block {
-        return code.to_owned();
+use std::borrow::Cow;
+
+/// Formatting of line numbers prefixed to each line of code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineNumberFormat {
+    /// Text placed between the number and the line.
+    pub separator: String,
+    /// Minimum width of the number column. `None` sizes it to the largest number.
+    pub width: Option<usize>,
+    /// The number given to the first line, for excerpts of larger files.
+    pub start: usize,
+}
+
+impl Default for LineNumberFormat {
+    fn default() -> Self {
+        Self {
+            separator: " | ".into(),
+            width: None,
+            start: 1,
+        }
     }
-    let mut body = String::new();
-    if line_numbers {
+}
+
+impl LineNumberFormat {
+    /// Prefixes each line of `code` with its number. Single-line snippets are
+    /// returned unnumbered, since the number adds nothing there.
+    pub fn apply(&self, code: &str) -> String {
+        let line_count = code.lines().count();
+        if line_count <= 1 {
+            return code.to_owned();
+        }
+        let last = self.start + line_count - 1;
+        let width = self.width.unwrap_or_else(|| last.to_string().len());
+        let mut body = String::with_capacity(code.len() + line_count * (width + 4));
         for (i, line) in code.lines().enumerate() {
-            body.push_str(&format!("{:4} | {}\n", i + 1, line));
+            body.push_str(&format!(
+                "{:>width$}{}{}\n",
+                self.start + i,
+                self.separator,
+                line
+            ));
         }
-    } else {
-        body.push_str(code);
+        body
+    }
+}
+
+/// Maps common file extensions to the language tag expected after a code fence.
+/// Extensions not listed here are emitted as-is.
+const FENCE_LANGUAGES: &[(&str, &str)] = &[
+    ("bash", "bash"),
+    ("c", "c"),
+    ("cc", "cpp"),
+    ("cjs", "javascript"),
+    ("clj", "clojure"),
+    ("cpp", "cpp"),
+    ("cs", "csharp"),
+    ("css", "css"),
+    ("cxx", "cpp"),
+    ("dart", "dart"),
+    ("ex", "elixir"),
+    ("exs", "elixir"),
+    ("fs", "fsharp"),
+    ("go", "go"),
+    ("gradle", "groovy"),
+    ("h", "c"),
+    ("hbs", "handlebars"),
+    ("hpp", "cpp"),
+    ("hs", "haskell"),
+    ("htm", "html"),
+    ("html", "html"),
+    ("ipynb", "json"),
+    ("java", "java"),
+    ("jl", "julia"),
+    ("js", "javascript"),
+    ("json", "json"),
+    ("jsx", "jsx"),
+    ("kt", "kotlin"),
+    ("kts", "kotlin"),
+    ("lua", "lua"),
+    ("m", "objectivec"),
+    ("md", "markdown"),
+    ("mjs", "javascript"),
+    ("ml", "ocaml"),
+    ("php", "php"),
+    ("pl", "perl"),
+    ("proto", "protobuf"),
+    ("ps1", "powershell"),
+    ("py", "python"),
+    ("r", "r"),
+    ("rb", "ruby"),
+    ("rs", "rust"),
+    ("scala", "scala"),
+    ("scss", "scss"),
+    ("sh", "bash"),
+    ("sql", "sql"),
+    ("swift", "swift"),
+    ("tf", "hcl"),
+    ("toml", "toml"),
+    ("ts", "typescript"),
+    ("tsx", "tsx"),
+    ("vue", "vue"),
+    ("xml", "xml"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("zig", "zig"),
+    ("zsh", "bash"),
+];
+
+/// Returns the fence language for an extension from the built-in table,
+/// falling back to the extension itself.
+pub fn fence_language(ext: &str) -> &str {
+    FENCE_LANGUAGES
+        .iter()
+        .find(|(e, _)| e.eq_ignore_ascii_case(ext))
+        .map_or(ext, |(_, lang)| lang)
+}
+
+/// Returns the length of the longest run of consecutive backticks in `code`.
+fn longest_backtick_run(code: &str) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in code.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// Wraps code in a markdown block, optionally with a language tag and line numbers.
+///
+/// The fence is made longer than any backtick run inside the code, so files
+/// that themselves contain fenced blocks (e.g. markdown) don't break out of it.
+pub fn wrap(
+    code: &str,
+    lang: &str,
+    line_numbers: Option<&LineNumberFormat>,
+    no_block: bool,
+) -> String {
+    if no_block {
+        return code.to_owned();
+    }
+    let mut body = match line_numbers {
+        Some(fmt) => fmt.apply(code),
+        None => code.to_owned(),
+    };
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+    let fence = "`".repeat(longest_backtick_run(&body).max(2) + 1);
+    format!("{fence}{lang}\n{body}{fence}")
+}
+
+/// Strips a leading UTF-8 BOM and converts CRLF (and lone CR) line endings to LF.
+pub fn normalize_newlines(code: &str) -> Cow<'_, str> {
+    let code = code.strip_prefix('\u{feff}').unwrap_or(code);
+    if !code.contains('\r') {
+        return Cow::Borrowed(code);
     }
-    format!("```{ext}\n{body}```")
+    Cow::Owned(code.replace("\r\n", "\n").replace('\r', "\n"))
 }
@@ -34,12 +34,93 @@ pub fn format_tokens(n: usize, style: TokenFormatStyle) -> String {
     }
 }
 
+/// Formats an age in seconds as a short relative label (e.g., "5m ago", "3d ago").
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => "just now".into(),
+        60..=3_599 => format!("{}m ago", secs / 60),
+        3_600..=86_399 => format!("{}h ago", secs / 3_600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+/// Formats a byte count with a binary unit for narrow columns (e.g., "820B", "4.1K", "12M").
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
+}
+
+/// Quotes `s` as a single POSIX shell word.
+pub fn shell_quote(s: &str) -> String {
+    let is_plain = !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_./=:@%+,".contains(&b));
+    if is_plain {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+/// Cuts `s` to at most `max` terminal columns, ending it with '…' if
+/// anything was cut. Never splits a grapheme cluster, such as an emoji with
+/// a skin tone.
+#[cfg(any(feature = "tui", feature = "token_map"))]
+pub fn truncate_to_width(s: &str, max: usize) -> String {
+    use unicode_segmentation::UnicodeSegmentation;
+    use unicode_width::UnicodeWidthStr;
+
+    if s.width() <= max {
+        return s.to_string();
+    }
+    let mut out = String::new();
+    let mut width = 0;
+    for g in s.graphemes(true) {
+        let w = g.width();
+        // Keep a column for the ellipsis.
+        if width + w + 1 > max {
+            break;
+        }
+        out.push_str(g);
+        width += w;
+    }
+    if max > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// Pads `s` with spaces to `width` terminal columns, like `{:<width$}` does
+/// for characters.
+#[cfg(any(feature = "tui", feature = "token_map"))]
+pub fn pad_to_width(s: &str, width: usize) -> String {
+    use unicode_width::UnicodeWidthStr;
+
+    format!("{s}{}", " ".repeat(width.saturating_sub(s.width())))
+}
+
 /// Creates a user-friendly label for a path.
 /// Used in UI headers and tree roots. Takes the file_name, or if that's
-/// missing, the last component of the current directory.
+/// missing, the root itself or the last component of the current directory.
 pub fn format_path_label<P: AsRef<Path>>(p: P) -> String {
     let path = p.as_ref();
-    if path.file_name().is_none() {
+    if path.file_name().is_none() && path.has_root() {
+        // A drive or share root such as `C:\` or `\\server\share`.
+        super::path::simplify(path).to_string_lossy().into_owned()
+    } else if path.file_name().is_none() {
         std::env::current_dir()
             .ok()
             .and_then(|d| d.file_name().and_then(|n| n.to_str().map(str::to_owned)))
@@ -51,3 +132,59 @@ pub fn format_path_label<P: AsRef<Path>>(p: P) -> String {
             .to_owned()
     }
 }
+
+/// Converts days since 1970-01-01 into a (year, month, day) date.
+/// See Howard Hinnant's `civil_from_days` algorithm.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+/// Converts a (year, month, day) date into days since 1970-01-01; the
+/// inverse of [`civil_from_days`].
+pub fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = i64::from((m + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(d) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Parses a `YYYY-MM-DD` date into seconds since the Unix epoch, at
+/// midnight UTC.
+pub fn parse_date(s: &str) -> Option<i64> {
+    let mut parts = s.trim().splitn(3, '-');
+    let y: i64 = parts.next()?.parse().ok()?;
+    let m: u32 = parts.next()?.parse().ok()?;
+    let d: u32 = parts.next()?.parse().ok()?;
+    let days = days_from_civil(y, m, d);
+    // Rejects days past the end of the month, such as 2024-02-30.
+    (civil_from_days(days) == (y, m, d)).then_some(days * 86_400)
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 UTC timestamp
+/// (e.g., "2024-05-01T09:30:00Z").
+pub fn format_utc_timestamp(secs: u64) -> String {
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    let secs = secs % 86_400;
+    let (hh, mm, ss) = (secs / 3_600, secs % 3_600 / 60, secs % 60);
+    format!("{y:04}-{m:02}-{d:02}T{hh:02}:{mm:02}:{ss:02}Z")
+}
+
+/// Escapes `&`, `<`, `>` and `"` for HTML and XML text and attributes.
+pub fn escape_markup(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
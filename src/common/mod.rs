@@ -5,3 +5,4 @@ pub mod format;
 pub mod glob;
 pub mod hash;
 pub mod path;
+pub mod process;
@@ -1,6 +1,75 @@
-use std::path::Path;
+//! Paths in anything code2prompt outputs (prompts, trees, JSON, token maps,
+//! file lists) go through [`display`]: relative paths always use `/`, like
+//! cache keys and filter globs, so outputs are the same on every platform.
+//! Absolute paths keep the platform's separators.
+
+use std::io;
+use std::path::{Path, PathBuf};
 
 /// "foo\\bar" -> "foo/bar"
 pub fn to_fwd_slash(p: &Path) -> String {
     p.to_string_lossy().replace('\\', "/")
 }
+
+/// A path as written to outputs: forward slashes if relative, otherwise the
+/// [`simplify`]d native form.
+pub fn display(p: &Path) -> String {
+    if p.is_absolute() {
+        simplify(p).to_string_lossy().into_owned()
+    } else {
+        to_fwd_slash(p)
+    }
+}
+
+/// The nearest of `start` and its ancestors that contains one of `markers`,
+/// such as `.git`.
+pub fn find_ancestor_with(start: &Path, markers: &[&str]) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| markers.iter().any(|m| dir.join(m).exists()))
+        .map(Path::to_path_buf)
+}
+
+/// `Path::canonicalize`, without the verbatim prefix Windows adds to the result.
+///
+/// Use this instead of `canonicalize` for any path that is displayed,
+/// stripped of a prefix or matched against globs.
+pub fn canonicalize(p: &Path) -> io::Result<PathBuf> {
+    p.canonicalize().map(|p| simplify(&p))
+}
+
+/// Turns a verbatim path into its ordinary form:
+/// `\\?\C:\repo` -> `C:\repo` and `\\?\UNC\server\share` -> `\\server\share`.
+///
+/// The path is kept as is when the ordinary form would mean something else,
+/// e.g. if a component ends with a dot or a space, which Windows trims.
+/// The standard library re-adds the prefix to long paths when opening them.
+pub fn simplify(p: &Path) -> PathBuf {
+    let Some(s) = p.to_str() else {
+        return p.to_path_buf();
+    };
+    let plain = if let Some(rest) = s.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{rest}")
+    } else if let Some(rest) = s.strip_prefix(r"\\?\")
+        && is_drive_path(rest)
+    {
+        rest.to_string()
+    } else {
+        return p.to_path_buf();
+    };
+    let trimmed_by_windows = plain
+        .split('\\')
+        .skip(1)
+        .any(|c| c.ends_with('.') || c.ends_with(' '));
+    if trimmed_by_windows {
+        p.to_path_buf()
+    } else {
+        PathBuf::from(plain)
+    }
+}
+
+/// `C:` or `C:\...`
+fn is_drive_path(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':' && (b.len() == 2 || b[2] == b'\\')
+}
@@ -0,0 +1,25 @@
+//! Process-wide scheduling settings.
+
+/// Gives the process the lowest CPU priority and, on Linux, the idle IO
+/// class, like `nice -n 19 ionice -c 3`. Threads spawned afterwards inherit
+/// both. Does nothing on other platforms.
+pub fn lower_priority() {
+    #[cfg(unix)]
+    // SAFETY: both calls only change the scheduling of the calling process
+    // and take no pointers.
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, 19);
+        #[cfg(target_os = "linux")]
+        {
+            const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+            const CURRENT_PROCESS: libc::c_long = 0;
+            const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                CURRENT_PROCESS,
+                IOPRIO_CLASS_IDLE << 13,
+            );
+        }
+    }
+}
@@ -1,29 +1,171 @@
 #[cfg(any(feature = "cache", feature = "tui"))]
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
 use std::time::SystemTime;
 
 use anyhow::{Context, Result, anyhow};
 #[cfg(any(feature = "cache", feature = "tui"))]
+use chacha20poly1305::{
+    XChaCha20Poly1305, XNonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use crossbeam_channel::{Sender, bounded};
+#[cfg(any(feature = "cache", feature = "tui"))]
 use flate2::{Compression, read::GzDecoder, write::GzEncoder};
-use rusqlite::{Connection, OptionalExtension, params};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior, params};
 use serde::{Deserialize, Serialize};
 
 use crate::common::cache::{CacheFormat, Cacheable};
 use crate::common::hash::HashMap;
-use crate::engine::utils::RepoCachePath;
+use crate::engine::token::TokenizerChoice;
+use crate::engine::utils::{RepoCachePath, cache_dir};
+
+/// The version of what rows hold; rows of other versions are never read.
+const CACHE_VERSION: u32 = 2;
+
+/// The version of the `file_cache` table's layout, kept in the database's
+/// `user_version`. Changing the layout takes a new version and a step in
+/// [`MIGRATIONS`]; caches that cannot be migrated are rebuilt.
+const SCHEMA_VERSION: i64 = 3;
+
+/// The steps from each schema version to the next: the version a step
+/// upgrades from, and its SQL.
+const MIGRATIONS: &[(i64, &str)] = &[
+    (
+        1,
+        "ALTER TABLE file_cache ADD COLUMN last_accessed INTEGER NOT NULL DEFAULT 0;",
+    ),
+    // Counts cached before are not known to be of the content as prepared
+    // now, so they are left unread.
+    (2, "ALTER TABLE file_cache ADD COLUMN prepare_hash INTEGER;"),
+];
+
+const CREATE_TABLE: &str = "CREATE TABLE file_cache (
+    path TEXT NOT NULL,
+    tokenizer TEXT NOT NULL,
+    mtime_nanos INTEGER NOT NULL,
+    size_bytes  INTEGER NOT NULL,
+    sha256  BLOB NOT NULL,
+    token_count INTEGER NOT NULL,
+    content BLOB,
+    cache_version INTEGER NOT NULL,
+    last_accessed INTEGER NOT NULL DEFAULT 0,
+    prepare_hash INTEGER,
+    PRIMARY KEY (path, tokenizer)
+);";
+
+/// How stale a row's `last_accessed` gets before a lookup refreshes it, so
+/// that warm runs do not write to the cache for every file.
+const TOUCH_INTERVAL_SECS: i64 = 86_400;
+
+/// The bytes a row is counted for besides its path and content.
+const ROW_OVERHEAD_BYTES: i64 = 128;
+
+/// Size limits of the scan caches, in bytes: of the cache of the repository
+/// being scanned, and of the caches of all repositories together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheLimits {
+    pub repo_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+}
+
+impl CacheLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.repo_bytes.is_none() && self.total_bytes.is_none()
+    }
+}
+
+/// Most writes a [`CacheWriter`] applies in one transaction.
+const WRITE_BATCH_SIZE: usize = 512;
+
+/// Writes queued for a [`CacheWriter`] before senders wait for it.
+const WRITE_QUEUE_CAPACITY: usize = 1024;
+
+/// Starts the contents encrypted with a [`CacheKey`], which gzip data never
+/// starts with.
+const ENCRYPTED_MAGIC: &[u8] = b"C2PE";
+
+/// The bytes of the nonce stored after [`ENCRYPTED_MAGIC`].
+#[cfg(any(feature = "cache", feature = "tui"))]
+const NONCE_BYTES: usize = 24;
 
-const CACHE_VERSION: u32 = 1;
+/// The key file contents are encrypted with in the scan cache: 32 bytes,
+/// written as 64 hexadecimal digits.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CacheKey([u8; 32]);
+
+impl CacheKey {
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = hex::decode(hex.trim()).context("The cache key is not hexadecimal")?;
+        let key = bytes
+            .try_into()
+            .map_err(|b: Vec<u8>| anyhow!("The cache key has {} bytes, not 32", b.len()))?;
+        Ok(Self(key))
+    }
+
+    /// Reads the key from the environment variable `var`.
+    pub fn from_env(var: &str) -> Result<Self> {
+        let hex = std::env::var(var)
+            .with_context(|| format!("The cache key variable {var} is not set"))?;
+        Self::from_hex(&hex).with_context(|| format!("Invalid cache key in {var}"))
+    }
+
+    #[cfg(any(feature = "cache", feature = "tui"))]
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&self.0.into())
+    }
+}
+
+impl std::fmt::Debug for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CacheKey(..)")
+    }
+}
 
 #[derive(Debug)]
 pub struct ScanCache {
     conn: Connection,
+    key: Option<CacheKey>,
+    /// The hash of the options contents are prepared with before they are
+    /// counted, see [`ScanCache::with_prepare_hash`].
+    prepare_hash: i64,
 }
 
 #[derive(Debug, Clone)]
 pub struct CachedMeta {
     pub token_count: usize,
     pub sha256: [u8; 32], // Sha256 produces a 32-byte hash
+    /// Whether the row was last accessed too long ago, to be refreshed with
+    /// [`CacheWrite::Touch`].
+    pub stale: bool,
+}
+
+/// A file's row in the scan cache, with its content compressed and
+/// encrypted, made by [`ScanCache::row`].
+#[derive(Debug)]
+pub struct CacheRow {
+    rel_path: String,
+    tokenizer: TokenizerChoice,
+    mtime_nanos: i64,
+    size: i64,
+    sha256: [u8; 32],
+    tokens: i64,
+    content: Option<Vec<u8>>,
+    prepare_hash: i64,
+}
+
+/// A write to the scan cache, see [`ScanCache::apply`].
+#[derive(Debug)]
+pub enum CacheWrite {
+    Insert(CacheRow),
+    /// Marks a file's row for a tokenizer as just accessed.
+    Touch {
+        rel_path: String,
+        tokenizer: TokenizerChoice,
+    },
 }
 
 impl ScanCache {
@@ -36,41 +178,88 @@ impl ScanCache {
         let conn = Connection::open(&cache_path).with_context(|| {
             format!("Failed to open cache database at {}", cache_path.display())
         })?;
+        Self::init(conn, &cache_path)
+    }
+
+    /// Opens a cache database at an explicit file path.
+    pub fn open_at(db_path: &Path) -> Result<Self> {
+        let conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open cache database at {}", db_path.display()))?;
+        Self::init(conn, db_path)
+    }
 
+    fn init(mut conn: Connection, db_path: &Path) -> Result<Self> {
         // Enable Write-Ahead Logging for better concurrency and performance.
         conn.pragma_update(None, "journal_mode", "WAL")?;
         conn.pragma_update(None, "synchronous", "NORMAL")?;
 
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS file_cache (
-                 path TEXT PRIMARY KEY,
-                 mtime_nanos INTEGER NOT NULL,
-                 size_bytes  INTEGER NOT NULL,
-                 sha256  BLOB NOT NULL,
-                 token_count INTEGER NOT NULL,
-                 content BLOB,
-                 cache_version INTEGER NOT NULL
-             );",
-        )?;
+        // Up-to-date caches are only read here, so that the walker's threads
+        // do not wait on each other to open them.
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            upgrade(&mut conn)
+                .with_context(|| format!("Failed to set up the cache at {}", db_path.display()))?;
+        }
+
+        Ok(Self {
+            conn,
+            key: None,
+            prepare_hash: 0,
+        })
+    }
+
+    /// Encrypts the contents written from now on with `key`, and reads only
+    /// contents encrypted with it.
+    pub fn with_key(mut self, key: Option<CacheKey>) -> Self {
+        self.key = key;
+        self
+    }
 
-        Ok(Self { conn })
+    /// Reads and writes the token counts of contents prepared with the
+    /// options hashed to `hash`: counts made with other options are not
+    /// reused, as they are of different text. Contents are cached as read,
+    /// so they are shared.
+    pub fn with_prepare_hash(mut self, hash: i64) -> Self {
+        self.prepare_hash = hash;
+        self
     }
 
-    /// Looks up a file in the cache using its path, modification time, and size.
+    /// Removes the contents that are not encrypted, such as those cached
+    /// before a key was set, keeping their token counts. Returns how many
+    /// were removed.
+    pub fn drop_plaintext_contents(&self) -> Result<usize> {
+        Ok(self.conn.execute(
+            "UPDATE file_cache SET content = NULL
+             WHERE content IS NOT NULL AND substr(content, 1, ?1) != ?2",
+            params![ENCRYPTED_MAGIC.len() as i64, ENCRYPTED_MAGIC],
+        )?)
+    }
+
+    /// Looks up a file's token count for a tokenizer, using its path,
+    /// modification time, size and the options it is prepared with.
     pub fn lookup(
         &self,
         rel_path: &str,
         mtime: SystemTime,
         size: u64,
+        tokenizer: TokenizerChoice,
     ) -> Result<Option<CachedMeta>> {
         let mtime_nanos = mtime.duration_since(SystemTime::UNIX_EPOCH)?.as_nanos() as i64;
 
         let res = self
             .conn
             .query_row(
-                "SELECT token_count, sha256 FROM file_cache
-                 WHERE path = ?1 AND mtime_nanos = ?2 AND size_bytes = ?3",
-                params![rel_path, mtime_nanos, size as i64],
+                "SELECT token_count, sha256, last_accessed FROM file_cache
+                 WHERE path = ?1 AND mtime_nanos = ?2 AND size_bytes = ?3 AND tokenizer = ?4
+                   AND cache_version = ?5 AND prepare_hash = ?6",
+                params![
+                    rel_path,
+                    mtime_nanos,
+                    size as i64,
+                    tokenizer.to_string(),
+                    CACHE_VERSION,
+                    self.prepare_hash
+                ],
                 |row| {
                     let sha_vec: Vec<u8> = row.get(1)?;
                     let sha_array: [u8; 32] = match sha_vec.try_into() {
@@ -92,10 +281,12 @@ impl ScanCache {
                             ));
                         }
                     };
+                    let last_accessed: i64 = row.get(2)?;
 
                     Ok(CachedMeta {
                         token_count: row.get(0)?,
                         sha256: sha_array,
+                        stale: last_accessed < unix_now() - TOUCH_INTERVAL_SECS,
                     })
                 }
             )
@@ -104,39 +295,109 @@ impl ScanCache {
         Ok(res)
     }
 
-    /// Inserts or updates a file's metadata in the cache.
+    /// Inserts or updates a file's metadata and token count for a tokenizer.
+    /// Rows for other tokenizers are kept only if the file is unchanged.
+    #[allow(clippy::too_many_arguments)]
     pub fn insert(
         &self,
         rel_path: &str,
         mtime: SystemTime,
         size: u64,
         sha256: [u8; 32],
+        tokenizer: TokenizerChoice,
         tokens: usize,
         content: Option<&str>,
     ) -> Result<()> {
+        let row = self.row(rel_path, mtime, size, sha256, tokenizer, tokens, content)?;
+        self.apply(&[CacheWrite::Insert(row)])
+    }
+
+    /// Makes the row [`CacheWrite::Insert`] writes, compressing and
+    /// encrypting the content on the calling thread.
+    #[allow(clippy::too_many_arguments)]
+    pub fn row(
+        &self,
+        rel_path: &str,
+        mtime: SystemTime,
+        size: u64,
+        sha256: [u8; 32],
+        tokenizer: TokenizerChoice,
+        tokens: usize,
+        content: Option<&str>,
+    ) -> Result<CacheRow> {
         let mtime_nanos = mtime.duration_since(SystemTime::UNIX_EPOCH)?.as_nanos() as i64;
 
         let compressed_content = content
             .map(|s| {
                 let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
                 encoder.write_all(s.as_bytes())?;
-                encoder.finish()
+                let compressed = encoder.finish()?;
+                match &self.key {
+                    #[cfg(any(feature = "cache", feature = "tui"))]
+                    Some(key) => encrypt(key, rel_path, &compressed),
+                    _ => Ok(compressed),
+                }
             })
             .transpose()?;
 
-        self.conn.execute(
-            "INSERT OR REPLACE INTO file_cache (path, mtime_nanos, size_bytes, sha256, token_count, content, cache_version)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                rel_path,
-                mtime_nanos,
-                size as i64,
-                sha256.as_ref(),
-                tokens as i64,
-                compressed_content,
-                CACHE_VERSION,
-            ],
-        )?;
+        Ok(CacheRow {
+            rel_path: rel_path.to_string(),
+            tokenizer,
+            mtime_nanos,
+            size: size as i64,
+            sha256,
+            tokens: tokens as i64,
+            content: compressed_content,
+            prepare_hash: self.prepare_hash,
+        })
+    }
+
+    /// Applies writes in a single transaction. Inserting a row replaces the
+    /// file's rows for other tokenizers unless the file is unchanged.
+    pub fn apply(&self, writes: &[CacheWrite]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let now = unix_now();
+        for write in writes {
+            match write {
+                CacheWrite::Insert(row) => {
+                    tx.prepare_cached(
+                        "DELETE FROM file_cache
+                         WHERE path = ?1 AND (mtime_nanos != ?2 OR size_bytes != ?3)",
+                    )?
+                    .execute(params![
+                        row.rel_path,
+                        row.mtime_nanos,
+                        row.size
+                    ])?;
+                    tx.prepare_cached(
+                        "INSERT OR REPLACE INTO file_cache (path, tokenizer, mtime_nanos, size_bytes, sha256, token_count, content, cache_version, last_accessed, prepare_hash)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    )?
+                    .execute(params![
+                        row.rel_path,
+                        row.tokenizer.to_string(),
+                        row.mtime_nanos,
+                        row.size,
+                        row.sha256.as_ref(),
+                        row.tokens,
+                        row.content,
+                        CACHE_VERSION,
+                        now,
+                        row.prepare_hash,
+                    ])?;
+                }
+                CacheWrite::Touch {
+                    rel_path,
+                    tokenizer,
+                } => {
+                    tx.prepare_cached(
+                        "UPDATE file_cache SET last_accessed = ?1 WHERE path = ?2 AND tokenizer = ?3",
+                    )?
+                    .execute(params![now, rel_path, tokenizer.to_string()])?;
+                }
+            }
+        }
+        tx.commit()?;
         Ok(())
     }
 
@@ -160,6 +421,11 @@ impl ScanCache {
             let path: String = row.get(0)?;
             let compressed_bytes: Option<Vec<u8>> = row.get(1)?;
 
+            let compressed_bytes = match (compressed_bytes, &self.key) {
+                #[cfg(any(feature = "cache", feature = "tui"))]
+                (Some(bytes), Some(key)) => decrypt(key, &path, &bytes),
+                (bytes, _) => bytes,
+            };
             if let Some(bytes) = compressed_bytes {
                 let mut decoder = GzDecoder::new(&bytes[..]);
                 let mut decompressed_content = String::new();
@@ -171,6 +437,306 @@ impl ScanCache {
 
         Ok(results)
     }
+
+    /// The bytes the cache's rows take: their compressed contents and paths,
+    /// plus a fixed overhead per row.
+    pub fn size(&self) -> Result<u64> {
+        let size: i64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(LENGTH(content) + LENGTH(path) + ?1), 0) FROM file_cache",
+            params![ROW_OVERHEAD_BYTES],
+            |row| row.get(0),
+        )?;
+        Ok(size as u64)
+    }
+
+    /// Evicts the least recently used rows until the cache takes at most
+    /// `max_bytes`, and compacts the database if any were. Returns how many
+    /// rows were evicted.
+    pub fn prune(&self, max_bytes: u64) -> Result<usize> {
+        let mut size = self.size()?;
+        if size <= max_bytes {
+            return Ok(0);
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT rowid, COALESCE(LENGTH(content), 0) + LENGTH(path) + ?1 FROM file_cache
+             ORDER BY last_accessed, rowid",
+        )?;
+        let mut rows = stmt.query(params![ROW_OVERHEAD_BYTES])?;
+        let mut evicted = Vec::new();
+        while size > max_bytes
+            && let Some(row) = rows.next()?
+        {
+            evicted.push(row.get::<_, i64>(0)?);
+            size = size.saturating_sub(row.get::<_, i64>(1)? as u64);
+        }
+        drop(rows);
+        drop(stmt);
+
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut delete = tx.prepare("DELETE FROM file_cache WHERE rowid = ?1")?;
+            for rowid in &evicted {
+                delete.execute(params![rowid])?;
+            }
+        }
+        tx.commit()?;
+        self.conn.execute_batch("VACUUM;")?;
+        Ok(evicted.len())
+    }
+}
+
+/// Applies the writes of all walker threads on a single connection, in
+/// batched transactions, so that they never contend for the database lock.
+/// The threads keep their own connections for lookups.
+pub struct CacheWriter {
+    tx: Sender<CacheWrite>,
+    handle: JoinHandle<usize>,
+}
+
+impl CacheWriter {
+    /// Starts the writer thread on `cache`.
+    pub fn spawn(cache: ScanCache) -> Self {
+        let (tx, rx) = bounded::<CacheWrite>(WRITE_QUEUE_CAPACITY);
+        let handle = std::thread::spawn(move || {
+            let mut failed = 0;
+            while let Ok(first) = rx.recv() {
+                let batch: Vec<_> = std::iter::once(first)
+                    .chain(rx.try_iter().take(WRITE_BATCH_SIZE - 1))
+                    .collect();
+                if let Err(_e) = cache.apply(&batch) {
+                    #[cfg(feature = "logging")]
+                    log::warn!("Failed to write to the scan cache: {_e:#}");
+                    failed += batch.len();
+                }
+            }
+            failed
+        });
+        Self { tx, handle }
+    }
+
+    /// A handle for a thread to queue writes with.
+    pub fn sender(&self) -> Sender<CacheWrite> {
+        self.tx.clone()
+    }
+
+    /// Waits until the writes queued by every sender are applied, once the
+    /// senders are dropped. Returns how many writes failed.
+    pub fn finish(self) -> Result<usize> {
+        drop(self.tx);
+        self.handle
+            .join()
+            .map_err(|_| anyhow!("The scan cache writer panicked"))
+    }
+}
+
+/// The schema version of a cache database, or `None` if it has no table
+/// yet. Caches from before `user_version` was set are told apart by their
+/// columns: version 1 keyed rows by tokenizer, version 2 added
+/// `last_accessed`, and older ones count as version 0.
+fn schema_version(conn: &Connection) -> Result<Option<i64>> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let has_column = |name: &str| -> Result<bool> {
+        Ok(conn
+            .prepare("SELECT 1 FROM pragma_table_info('file_cache') WHERE name = ?1")?
+            .exists(params![name])?)
+    };
+    let has_table = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'file_cache'")?
+        .exists([])?;
+    if !has_table {
+        return Ok(None);
+    }
+    if version > 0 {
+        return Ok(Some(version));
+    }
+    Ok(Some(
+        match (has_column("tokenizer")?, has_column("last_accessed")?) {
+            (true, true) => 2,
+            (true, false) => 1,
+            _ => 0,
+        },
+    ))
+}
+
+/// Brings a cache database to [`SCHEMA_VERSION`]: creates its table,
+/// migrates it, or rebuilds it if it cannot be migrated.
+fn upgrade(conn: &mut Connection) -> Result<()> {
+    // Another process may be opening the same cache: the one that gets the
+    // lock first upgrades it.
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    match schema_version(&tx)? {
+        None => tx.execute_batch(CREATE_TABLE)?,
+        Some(SCHEMA_VERSION) => {}
+        Some(version) => {
+            let migrated = if version > 0 && version < SCHEMA_VERSION {
+                migrate(&tx, version)
+            } else {
+                Err(anyhow!("schema version {version} is not supported"))
+            };
+            match migrated {
+                Ok(()) => {
+                    #[cfg(feature = "logging")]
+                    log::info!(
+                        "Migrated the scan cache at {} from schema version {version} to {SCHEMA_VERSION}",
+                        tx.path().unwrap_or_default()
+                    );
+                }
+                Err(_e) => {
+                    #[cfg(feature = "logging")]
+                    log::warn!(
+                        "Rebuilding the scan cache at {}: {_e:#}",
+                        tx.path().unwrap_or_default()
+                    );
+                    tx.execute_batch("DROP TABLE file_cache;")?;
+                    tx.execute_batch(CREATE_TABLE)?;
+                }
+            }
+            tx.execute(
+                "DELETE FROM file_cache WHERE cache_version != ?1",
+                params![CACHE_VERSION],
+            )?;
+        }
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Runs the migrations from schema version `from` to [`SCHEMA_VERSION`].
+fn migrate(conn: &Connection, from: i64) -> Result<()> {
+    for (_, sql) in MIGRATIONS.iter().filter(|(step, _)| *step >= from) {
+        conn.execute_batch(sql)?;
+    }
+    Ok(())
+}
+
+/// Encrypts a file's compressed content, bound to its path so that it
+/// cannot be passed off as another file's.
+#[cfg(any(feature = "cache", feature = "tui"))]
+fn encrypt(key: &CacheKey, rel_path: &str, compressed: &[u8]) -> Result<Vec<u8>> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let payload = Payload {
+        msg: compressed,
+        aad: rel_path.as_bytes(),
+    };
+    let ciphertext = key
+        .cipher()
+        .encrypt(&nonce, payload)
+        .map_err(|_| anyhow!("Failed to encrypt the cached content of {rel_path}"))?;
+    Ok([ENCRYPTED_MAGIC, nonce.as_slice(), &ciphertext].concat())
+}
+
+/// The compressed content of an encrypted row, or `None` if it is not
+/// encrypted or not with `key`.
+#[cfg(any(feature = "cache", feature = "tui"))]
+fn decrypt(key: &CacheKey, rel_path: &str, bytes: &[u8]) -> Option<Vec<u8>> {
+    let rest = bytes.strip_prefix(ENCRYPTED_MAGIC)?;
+    if rest.len() < NONCE_BYTES {
+        return None;
+    }
+    let (nonce, ciphertext) = rest.split_at(NONCE_BYTES);
+    let payload = Payload {
+        msg: ciphertext,
+        aad: rel_path.as_bytes(),
+    };
+    key.cipher()
+        .decrypt(XNonce::from_slice(nonce), payload)
+        .ok()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Keeps the scan caches within `limits` before a scan of `repo_root`.
+/// See [`prune_caches_in`].
+pub fn prune_caches(repo_root: &Path, limits: CacheLimits) -> Result<()> {
+    if limits.is_unlimited() {
+        return Ok(());
+    }
+    let own = RepoCachePath::new(repo_root)?.get_cache_file_path("scan_cache", "sqlite")?;
+    prune_caches_in(&cache_dir(), &own, limits)
+}
+
+/// Keeps the scan caches in `dir` within `limits`: the cache at `own`
+/// within the per-repository limit, and all of them together within the
+/// total limit. The caches of other repositories are removed whole, least
+/// recently written first, before rows of `own` are evicted to make room.
+pub fn prune_caches_in(dir: &Path, own: &Path, limits: CacheLimits) -> Result<()> {
+    let cache = ScanCache::open_at(own)?;
+    let mut max_bytes = limits.repo_bytes.unwrap_or(u64::MAX);
+    if let Some(total) = limits.total_bytes {
+        let mut others = other_caches(dir, own)?;
+        let mut others_bytes: u64 = others.iter().map(|c| c.bytes).sum();
+        let own_bytes = cache.size()?.min(max_bytes);
+        let mut oldest = others.drain(..);
+        while own_bytes + others_bytes > total
+            && let Some(other) = oldest.next()
+        {
+            for file in other.files {
+                match std::fs::remove_file(&file) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(e).with_context(|| {
+                            format!("Failed to remove cache file {}", file.display())
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            others_bytes -= other.bytes;
+        }
+        max_bytes = max_bytes.min(total.saturating_sub(others_bytes));
+    }
+    let _evicted = cache.prune(max_bytes)?;
+    #[cfg(feature = "logging")]
+    if _evicted > 0 {
+        log::info!("Evicted {_evicted} files from the scan cache");
+    }
+    Ok(())
+}
+
+/// The scan cache of another repository, with its write-ahead log.
+struct OtherCache {
+    files: Vec<PathBuf>,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// The scan caches in `dir` other than `own`, least recently written first.
+fn other_caches(dir: &Path, own: &Path) -> Result<Vec<OtherCache>> {
+    let mut caches = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to list cache directory {}", dir.display()))?
+    {
+        let db = entry?.path();
+        let is_cache = db
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("scan_cache_") && n.ends_with(".sqlite"));
+        if !is_cache || db == own {
+            continue;
+        }
+        let mut cache = OtherCache {
+            files: Vec::new(),
+            bytes: 0,
+            modified: SystemTime::UNIX_EPOCH,
+        };
+        for suffix in ["", "-wal", "-shm"] {
+            let mut name = db.clone().into_os_string();
+            name.push(suffix);
+            let file = PathBuf::from(name);
+            if let Ok(md) = std::fs::metadata(&file) {
+                cache.bytes += md.len();
+                cache.modified = cache.modified.max(md.modified()?);
+                cache.files.push(file);
+            }
+        }
+        caches.push(cache);
+    }
+    caches.sort_by_key(|c| c.modified);
+    Ok(caches)
 }
 
 /// A wrapper for template variables to make them `Cacheable`.
@@ -211,5 +777,25 @@ pub fn load_vars_from_file(path: &Path) -> Result<HashMap<String, String>> {
         }
     };
 
-    Ok(map)
+    Ok(map
+        .into_iter()
+        .map(|(k, v)| (k, expand_env_vars(&v)))
+        .collect())
+}
+
+static ENV_VAR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}").unwrap());
+
+/// Expands `${VAR}` and `${VAR:-default}` references from the environment.
+/// Unset variables without a default are left untouched, so they stay visible.
+pub fn expand_env_vars(value: &str) -> String {
+    ENV_VAR_RE
+        .replace_all(value, |caps: &regex::Captures| {
+            match (std::env::var(&caps[1]), caps.get(2)) {
+                (Ok(v), _) => v,
+                (Err(_), Some(default)) => default.as_str().to_owned(),
+                (Err(_), None) => caps[0].to_owned(),
+            }
+        })
+        .into_owned()
 }
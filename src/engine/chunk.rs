@@ -0,0 +1,110 @@
+//! Splits a prompt's files into chunks that each fit a token limit, for
+//! `-F messages`: the template without the files becomes the system
+//! message, and each chunk a user message.
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+
+use crate::engine::model::FileContext;
+use crate::engine::token::{TokenizerChoice, count_tokens};
+
+/// A chat-completions message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChatMessage {
+    pub role: &'static str,
+    pub content: String,
+}
+
+/// The system message followed by one user message per chunk of `files`,
+/// so that the system message and any one chunk fit in `limit` tokens.
+pub fn chat_messages(
+    system: String,
+    files: &[FileContext],
+    limit: usize,
+    tokenizer: TokenizerChoice,
+) -> Result<Vec<ChatMessage>> {
+    let system_tokens = count_tokens(&system, tokenizer)?;
+    if system_tokens >= limit {
+        bail!("The system message alone has {system_tokens} tokens, over the limit of {limit}.");
+    }
+    let mut messages = vec![ChatMessage {
+        role: "system",
+        content: system,
+    }];
+    for chunk in chunk_files(files, limit - system_tokens, tokenizer)? {
+        messages.push(ChatMessage {
+            role: "user",
+            content: chunk,
+        });
+    }
+    Ok(messages)
+}
+
+/// Packs the files, in order, into chunks of at most `limit` tokens. A file
+/// too large for a chunk of its own is split between lines into parts; a
+/// single line over the limit still makes a part of its own.
+pub fn chunk_files(
+    files: &[FileContext],
+    limit: usize,
+    tokenizer: TokenizerChoice,
+) -> Result<Vec<String>> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    let mut flush = |current: &mut String, current_tokens: &mut usize| {
+        if !current.is_empty() {
+            chunks.push(current.trim_end().to_string());
+            current.clear();
+            *current_tokens = 0;
+        }
+    };
+
+    for file in files.iter().filter(|f| !f.code.is_empty()) {
+        let block = format!("`{}`:\n\n{}\n\n", file.path, file.code);
+        let tokens = count_tokens(&block, tokenizer)?;
+        if tokens > limit {
+            flush(&mut current, &mut current_tokens);
+            for part in split_file(file, limit, tokenizer)? {
+                current.push_str(&part);
+                flush(&mut current, &mut current_tokens);
+            }
+            continue;
+        }
+        if current_tokens + tokens > limit {
+            flush(&mut current, &mut current_tokens);
+        }
+        current.push_str(&block);
+        current_tokens += tokens;
+    }
+    flush(&mut current, &mut current_tokens);
+    Ok(chunks)
+}
+
+/// Cuts a file's code between lines into parts headed `path (part i of n)`.
+fn split_file(file: &FileContext, limit: usize, tokenizer: TokenizerChoice) -> Result<Vec<String>> {
+    let header = |i: usize, n: usize| format!("`{}` (part {i} of {n}):\n\n", file.path);
+    let room = limit.saturating_sub(count_tokens(&header(999, 999), tokenizer)?);
+
+    let mut bodies: Vec<String> = Vec::new();
+    let mut body = String::new();
+    let mut body_tokens = 0;
+    for line in file.code.split_inclusive('\n') {
+        let tokens = count_tokens(line, tokenizer)?;
+        if body_tokens + tokens > room && !body.is_empty() {
+            bodies.push(std::mem::take(&mut body));
+            body_tokens = 0;
+        }
+        body.push_str(line);
+        body_tokens += tokens;
+    }
+    if !body.is_empty() {
+        bodies.push(body);
+    }
+
+    let n = bodies.len();
+    Ok(bodies
+        .into_iter()
+        .enumerate()
+        .map(|(i, body)| format!("{}{body}", header(i + 1, n)))
+        .collect())
+}
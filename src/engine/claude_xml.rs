@@ -0,0 +1,28 @@
+//! `-F claude-xml`: the files as `<documents>`, the layout Anthropic's prompt
+//! guidelines recommend for long documents, followed by the rest of the
+//! prompt.
+
+use crate::common::format::escape_markup;
+use crate::engine::model::FileContext;
+
+/// An index of the files' paths, then each file as a numbered
+/// `<document>` with its escaped path and contents.
+pub fn documents_xml(files: &[FileContext]) -> String {
+    let files: Vec<&FileContext> = files.iter().filter(|f| !f.code.is_empty()).collect();
+    let mut out = String::from("<document_index>\n");
+    for (i, file) in files.iter().enumerate() {
+        out.push_str(&format!("{}. {}\n", i + 1, escape_markup(&file.path)));
+    }
+    out.push_str("</document_index>\n\n<documents>\n");
+    for (i, file) in files.iter().enumerate() {
+        let contents = escape_markup(&file.code);
+        let newline = if contents.ends_with('\n') { "" } else { "\n" };
+        out.push_str(&format!(
+            "<document index=\"{}\">\n<source>{}</source>\n<document_contents>\n{contents}{newline}</document_contents>\n</document>\n",
+            i + 1,
+            escape_markup(&file.path),
+        ));
+    }
+    out.push_str("</documents>");
+    out
+}
@@ -0,0 +1,112 @@
+//! The owners of files, from the repository's `CODEOWNERS` file.
+//!
+//! As on GitHub, the file is looked for in `.github/`, the root and `docs/`,
+//! patterns follow `.gitignore` rules and the last pattern matching a file
+//! gives its owners. Templates see them as `owners`, and `--owner` keeps only
+//! the files owned by one of the given teams or users.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+
+/// Where a repository's `CODEOWNERS` file may be, in order of precedence.
+pub const LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The rules of a `CODEOWNERS` file, in the file's order.
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<OwnerRule>,
+}
+
+#[derive(Debug, Clone)]
+struct OwnerRule {
+    matchers: Vec<GlobMatcher>,
+    owners: Vec<String>,
+}
+
+impl CodeOwners {
+    /// Loads the `CODEOWNERS` file of the repository at `repo`; no rules if
+    /// it has none.
+    pub fn load(repo: &Path) -> Result<Self> {
+        let Some(path) = LOCATIONS.iter().map(|l| repo.join(l)).find(|p| p.is_file()) else {
+            return Ok(Self::default());
+        };
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Self::parse(&text))
+    }
+
+    /// Parses the lines of a `CODEOWNERS` file, skipping the invalid ones
+    /// as GitHub does.
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .filter_map(|line| {
+                let line = line.split_once('#').map_or(line, |(rule, _)| rule);
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                match matchers(pattern) {
+                    Ok(matchers) => Some(OwnerRule {
+                        matchers,
+                        owners: fields.map(str::to_string).collect(),
+                    }),
+                    Err(_e) => {
+                        #[cfg(feature = "logging")]
+                        log::warn!("Ignoring CODEOWNERS pattern '{pattern}': {_e}");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The owners of a relative path: those of the last matching rule.
+    pub fn owners_of(&self, rel_path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| r.matchers.iter().any(|m| m.is_match(rel_path)))
+            .map_or(&[], |r| &r.owners)
+    }
+
+    /// Whether a relative path is owned by one of `wanted`, compared without
+    /// case and with or without the leading `@`.
+    pub fn is_owned_by(&self, rel_path: &str, wanted: &[String]) -> bool {
+        self.owners_of(rel_path)
+            .iter()
+            .any(|owner| wanted.iter().any(|w| same_owner(owner, w)))
+    }
+}
+
+fn same_owner(a: &str, b: &str) -> bool {
+    a.trim_start_matches('@')
+        .eq_ignore_ascii_case(b.trim_start_matches('@'))
+}
+
+/// The globs of a `.gitignore`-style pattern: anchored to the root when it
+/// has a slash other than at the end, matching what is inside a directory,
+/// and only directories when it ends with a slash.
+fn matchers(pattern: &str) -> Result<Vec<GlobMatcher>> {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let base = if trimmed.contains('/') {
+        trimmed.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{trimmed}")
+    };
+    let mut globs = vec![format!("{base}/**")];
+    if !dir_only {
+        globs.push(base);
+    }
+    globs
+        .iter()
+        .map(|g| {
+            Ok(GlobBuilder::new(g)
+                .literal_separator(true)
+                .build()?
+                .compile_matcher())
+        })
+        .collect()
+}
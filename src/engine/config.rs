@@ -1,10 +1,24 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use clap::ValueEnum;
+use crossbeam_channel::Sender;
 use derive_builder::Builder;
 use glob::Pattern;
 
+use crate::common::code::{self, LineNumberFormat};
+use crate::common::hash::HashMap;
+use crate::engine::cache::{CacheKey, CacheLimits};
+use crate::engine::embeddings::{EmbeddingSettings, SemanticQuery};
+use crate::engine::grep::GrepFilter;
+use crate::engine::notebook::{self, NotebookOptions};
+use crate::engine::priority::PriorityRule;
+use crate::engine::sample::SampleStrategy;
+use crate::engine::symbols::SymbolSource;
 use crate::engine::token::TokenizerChoice;
+use crate::engine::transform::TransformPipeline;
+use crate::engine::traverse::{DEFAULT_SCAN_BATCH_SIZE, PhaseTimings, ScanProgress, ScanStats};
+use crate::engine::{codeowners::CodeOwners, tags::TagRule};
 use crate::ui::cli::FileSortMethod;
 
 #[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Default)]
@@ -13,6 +27,14 @@ pub enum OutputFormat {
     Markdown,
     Json,
     Xml,
+    /// One JSON record per file, without a template, for RAG ingestion.
+    RagJsonl,
+    /// A JSON array of chat messages: the template without the files as the
+    /// system message, then the files in user messages that fit the model.
+    Messages,
+    /// The files as numbered XML `<document>`s after an index, followed by
+    /// the template without the files.
+    ClaudeXml,
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -21,6 +43,9 @@ impl std::fmt::Display for OutputFormat {
             OutputFormat::Markdown => write!(f, "markdown"),
             OutputFormat::Json => write!(f, "json"),
             OutputFormat::Xml => write!(f, "xml"),
+            OutputFormat::RagJsonl => write!(f, "rag-jsonl"),
+            OutputFormat::Messages => write!(f, "messages"),
+            OutputFormat::ClaudeXml => write!(f, "claude-xml"),
         }
     }
 }
@@ -30,6 +55,11 @@ pub enum TokenFormat {
     #[default]
     Format,
     Raw,
+    /// Like `format`, but per-file counts are estimated instead of encoded.
+    Approximate,
+    /// Like `format`, plus how the total splits between files, source tree,
+    /// git output and template.
+    Breakdown,
 }
 
 impl std::fmt::Display for TokenFormat {
@@ -37,11 +67,13 @@ impl std::fmt::Display for TokenFormat {
         match self {
             TokenFormat::Format => write!(f, "format"),
             TokenFormat::Raw => write!(f, "raw"),
+            TokenFormat::Approximate => write!(f, "approximate"),
+            TokenFormat::Breakdown => write!(f, "breakdown"),
         }
     }
 }
 
-#[derive(Debug, Clone, Builder)]
+#[derive(Debug, Clone, Default, Builder)]
 #[builder(setter(into), build_fn(name = "build_internal"))]
 pub struct Code2PromptConfig {
     #[builder(default = "PathBuf::from(\".\")")]
@@ -55,25 +87,194 @@ pub struct Code2PromptConfig {
     #[builder(default)]
     pub line_numbers: bool,
     #[builder(default)]
+    pub line_number_format: LineNumberFormat,
+    #[builder(default)]
     pub absolute_path: bool,
     #[builder(default)]
     pub full_directory_tree: bool,
+    /// Show each file's ID in the source tree (`--file-ids`).
+    #[builder(default)]
+    pub file_ids: bool,
+    /// Leave the source tree out of the prompt (`--no-tree`).
+    #[builder(default)]
+    pub no_tree: bool,
+    /// Leave the files' contents out of the prompt (`--tree-only`).
+    #[builder(default)]
+    pub tree_only: bool,
     #[builder(default)]
     pub no_codeblock: bool,
+    /// User overrides of the extension → fence language table.
+    #[builder(default)]
+    pub fence_languages: HashMap<String, String>,
     #[builder(default = "TokenizerChoice::Cl100k")]
     pub tokenizer: TokenizerChoice,
     #[builder(default)]
     pub token_map_enabled: bool,
+    /// Estimate per-file token counts instead of encoding each file.
+    #[builder(default)]
+    pub approximate_tokens: bool,
     #[builder(default)]
     pub no_ignore: bool,
     #[builder(default)]
     pub hidden: bool,
+    /// Include hidden files outside hidden directories (`--hidden-files`).
+    #[builder(default)]
+    pub hidden_files: bool,
+    /// Hidden directories the walk descends into (`--hidden-dirs`).
+    #[builder(default)]
+    pub hidden_dirs: Vec<String>,
     #[builder(default)]
     pub follow_symlinks: bool,
     #[builder(default)]
     pub sort: Option<FileSortMethod>,
     #[builder(default)]
     pub cache: bool,
+    /// Size limits the scan cache is pruned to before a scan.
+    #[builder(default)]
+    pub cache_limits: CacheLimits,
+    /// Store only token counts in the scan cache, not file contents.
+    #[builder(default)]
+    pub no_content_cache: bool,
+    /// The environment variable holding the key file contents are encrypted
+    /// with in the scan cache.
+    #[builder(default)]
+    pub cache_key_env: Option<String>,
+    /// Hash every file read from disk, for the `--provenance` report.
+    #[builder(default)]
+    pub provenance: bool,
+    /// Threads for the scan and for reading cached files; `None` lets the
+    /// walker pick.
+    #[builder(default)]
+    pub threads: Option<usize>,
+    /// Files each scan thread collects before handing them over.
+    #[builder(default = "DEFAULT_SCAN_BATCH_SIZE")]
+    pub scan_batch_size: usize,
+    #[builder(default)]
+    pub notebook: NotebookOptions,
+    #[builder(default)]
+    pub normalize_newlines: bool,
+    /// Glob weights for ordering files and trimming to `token_budget`.
+    #[builder(default)]
+    pub priorities: Vec<PriorityRule>,
+    /// Don't place READMEs, manifests and docs overviews first (`--no-priority-docs`).
+    #[builder(default)]
+    pub no_priority_docs: bool,
+    /// Relative paths of the files placed first, in this order (`--order-file`).
+    #[builder(default)]
+    pub file_order: Vec<String>,
+    /// When set, only a sample of this many files is kept (`--max-files`).
+    #[builder(default)]
+    pub max_files: Option<usize>,
+    /// How the `max_files` sample is picked.
+    #[builder(default)]
+    pub sample_strategy: SampleStrategy,
+    /// When set, the lowest-priority files are dropped to fit this many tokens.
+    #[builder(default)]
+    pub token_budget: Option<usize>,
+    /// Files longer than this many tokens are cut down to their head and tail.
+    #[builder(default)]
+    pub max_file_tokens: Option<usize>,
+    /// Git diffs longer than this many tokens lose their context lines, then
+    /// whole files.
+    #[builder(default)]
+    pub max_diff_tokens: Option<usize>,
+    /// Per-glob content transformers (comment stripping, redaction, ...).
+    #[builder(default)]
+    pub transforms: TransformPipeline,
+    /// Build the `symbol_index` template variable from this source.
+    #[builder(default)]
+    pub symbols: Option<SymbolSource>,
+    /// Seed files whose transitive imports are added to the selection.
+    #[builder(default)]
+    pub with_deps: Vec<PathBuf>,
+    /// Seed files whose (transitive) importers are added to the selection.
+    #[builder(default)]
+    pub with_dependents: Vec<PathBuf>,
+    /// Add the tests of the included files to the selection.
+    #[builder(default)]
+    pub with_tests: bool,
+    /// How many levels of imports `with_deps` and `with_dependents` follow
+    /// (unlimited if unset).
+    #[builder(default)]
+    pub dep_depth: Option<usize>,
+    /// Select the files best matching this query by embedding similarity.
+    #[builder(default)]
+    pub query: Option<SemanticQuery>,
+    /// How `query` reaches the embedding model.
+    #[builder(default)]
+    pub embeddings: EmbeddingSettings,
+    /// Keep only files matching this filter (`--grep`).
+    #[builder(default)]
+    pub grep: Option<GrepFilter>,
+    /// The repository's file tags, from `.code2prompt/tags.toml`.
+    #[builder(default)]
+    pub tags: Vec<TagRule>,
+    /// Keep only files carrying one of these tags (`--tag`).
+    #[builder(default)]
+    pub tag_filter: Vec<String>,
+    /// The owners of the repository's files, from its `CODEOWNERS` file.
+    #[builder(default)]
+    pub codeowners: CodeOwners,
+    /// Keep only files owned by one of these teams or users (`--owner`).
+    #[builder(default)]
+    pub owner_filter: Vec<String>,
+    /// Keep only files changed by commits since this time, in seconds since
+    /// the Unix epoch (`--changed-since`).
+    #[builder(default)]
+    pub changed_since: Option<i64>,
+    /// Keep only files changed by commits of this author (`--author`).
+    #[builder(default)]
+    pub author: Option<String>,
+    /// Receives the scan's progress, e.g. for the TUI's rescan overlay.
+    #[builder(default)]
+    pub progress: Option<Sender<ScanProgress>>,
+    /// Collects the time spent in each phase of the scan (`bench`).
+    #[builder(default)]
+    pub timings: Option<Arc<PhaseTimings>>,
+    /// Counts files, bytes and cache hits across the scans (run summary).
+    #[builder(default)]
+    pub stats: Arc<ScanStats>,
+}
+
+impl Code2PromptConfig {
+    /// Wraps a file's (prepared) content according to the output settings.
+    pub fn wrap_code(&self, content: &str, ext: Option<&str>) -> String {
+        code::wrap(
+            content,
+            self.content_language(ext.unwrap_or(""), content),
+            self.line_numbers.then_some(&self.line_number_format),
+            self.no_codeblock,
+        )
+    }
+
+    /// Resolves the code fence language for an extension, preferring user overrides.
+    pub fn fence_language<'a>(&'a self, ext: &'a str) -> &'a str {
+        self.fence_languages
+            .get(&ext.to_ascii_lowercase())
+            .map_or_else(|| code::fence_language(ext), String::as_str)
+    }
+
+    /// The code fence language of `content`, a file's prepared content: that
+    /// of its extension, except for notebooks converted to Python cells.
+    pub fn content_language<'a>(&'a self, ext: &'a str, content: &str) -> &'a str {
+        let overridden = self.fence_languages.contains_key(&ext.to_ascii_lowercase());
+        if !overridden
+            && self.notebook.is_enabled()
+            && notebook::is_notebook(Some(ext))
+            && notebook::is_converted(content)
+        {
+            return "python";
+        }
+        self.fence_language(ext)
+    }
+
+    /// Reads the scan cache's encryption key from `cache_key_env`, if set.
+    pub fn cache_key(&self) -> anyhow::Result<Option<CacheKey>> {
+        self.cache_key_env
+            .as_deref()
+            .map(CacheKey::from_env)
+            .transpose()
+    }
 }
 
 impl Code2PromptConfigBuilder {
@@ -1,15 +1,66 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use glob::Pattern;
 use serde::{Deserialize, Serialize};
 
+use crate::common::cache::{CacheFormat, Cacheable};
 use crate::common::hash::HashMap;
+use crate::engine::cache::CacheLimits;
+use crate::engine::embeddings::EmbeddingSettings;
+use crate::engine::hooks::Hooks;
+use crate::engine::llm::LlmSettings;
 use crate::engine::token::TokenizerChoice;
+use crate::engine::transform::{TransformSpec, TransformStep};
+use crate::ui::template::VarSpec;
 use crate::ui::tui_select::TuiSettings;
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct TemplateConfig {
     pub defaults: Option<HashMap<String, String>>,
+    /// Declarations of the variables templates use, as in front matter.
+    pub vars: Option<BTreeMap<String, VarSpec>>,
+}
+
+/// The `[output]` table.
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSection {
+    /// Leave generated prompts (`*.c2p.md`, `prompt*.md`) out of the scan,
+    /// for repositories that commit them.
+    pub auto_exclude_artifacts: Option<bool>,
+}
+
+/// The `[cache]` table, for the scan cache of `--cache`.
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CacheSection {
+    /// Most megabytes the cache of one repository may take.
+    pub max_repo_mb: Option<u64>,
+    /// Most megabytes the caches of all repositories may take together.
+    pub max_total_mb: Option<u64>,
+    /// Store file contents along with token counts (`--no-content-cache`
+    /// turns it off).
+    pub content: Option<bool>,
+    /// The environment variable holding the key contents are encrypted
+    /// with, as 64 hexadecimal digits.
+    pub key_env: Option<String>,
+}
+
+impl CacheSection {
+    pub fn limits(&self) -> CacheLimits {
+        let bytes = |mb: Option<u64>| mb.map(|mb| mb.saturating_mul(1024 * 1024));
+        CacheLimits {
+            repo_bytes: bytes(self.max_repo_mb),
+            total_bytes: bytes(self.max_total_mb),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct GuiSection {
     #[serde(default)]
     pub settings: TuiSettings,
@@ -17,16 +68,267 @@ pub struct GuiSection {
 
 /// Represents the structure of the `config.toml` file.
 /// All fields are optional, so users only need to specify what they want to override.
+/// Unknown keys are rejected, so that typos do not go unnoticed.
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct ConfigFile {
     pub exclude: Option<Vec<String>>,
     pub tokenizer: Option<TokenizerChoice>,
     pub no_codeblock: Option<bool>,
     pub line_numbers: Option<bool>,
+    pub normalize_newlines: Option<bool>,
     pub no_default_excludes: Option<bool>,
+    /// Save every rendered prompt to the per-repository history.
+    pub history: Option<bool>,
+    /// Glob → weight table, e.g. `"src/core/**" = 10`.
+    pub priority: Option<HashMap<String, i32>>,
+    /// Glob → content transform steps, applied in lexical glob order.
+    pub transform: Option<BTreeMap<String, TransformSpec>>,
+    /// Commands run before scanning and after rendering.
+    pub hooks: Option<Hooks>,
+    /// Embedding endpoint and model used by `--query`.
+    pub embeddings: Option<EmbeddingSettings>,
+    /// Chat endpoint and model used by `commit --send`.
+    pub llm: Option<LlmSettings>,
+    /// Extension → code fence language overrides, e.g. `tpl = "handlebars"`.
+    pub languages: Option<HashMap<String, String>>,
+    /// Name → arguments that `@name` on the command line stands for.
+    pub aliases: Option<BTreeMap<String, String>>,
+    /// Repositories whose project config may run commands and choose API
+    /// endpoints and key variables. Only read from the global config.
+    pub trusted_projects: Option<Vec<PathBuf>>,
     #[serde(default)]
     // Ensures that if the `template` key is missing, it uses `TemplateConfig::default()`
     pub template: TemplateConfig,
     #[serde(default)]
+    pub output: OutputSection,
+    #[serde(default)]
+    pub cache: CacheSection,
+    #[serde(default)]
     pub gui: GuiSection,
 }
+
+/// The location of `config.toml`.
+pub fn path() -> Result<PathBuf> {
+    confy::get_configuration_file_path("code2prompt", None)
+        .context("Failed to locate the config file")
+}
+
+/// Loads `config.toml`, creating it with the defaults if it does not exist.
+pub fn load() -> Result<ConfigFile> {
+    let path = path()?;
+    confy::load_path(&path)
+        .with_context(|| format!("Failed to load config file {}", path.display()))
+}
+
+/// Saves the TUI's settings to `config.toml`, leaving the rest of it as is.
+pub fn save_gui_settings(settings: &TuiSettings) -> Result<()> {
+    let mut global = load()?;
+    global.gui.settings = settings.clone();
+    confy::store_path(path()?, global).context("Failed to save config file")
+}
+
+/// The commented starter config written by `config init`.
+pub const STARTER_CONFIG: &str = include_str!("../../starter_config.toml");
+
+/// The location of the project config of the repository at `repo`.
+pub fn project_path(repo: &Path) -> PathBuf {
+    repo.join(".code2prompt/config.toml")
+}
+
+/// Loads the project config of the repository at `repo`, if it has one.
+pub fn load_project(repo: &Path) -> Result<Option<ConfigFile>> {
+    let path = project_path(repo);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    toml::from_str(&content)
+        .map(Some)
+        .with_context(|| format!("Failed to load config file {}", path.display()))
+}
+
+/// Loads the project config of the repository at `repo` as [`load_project`]
+/// does, without the settings [`ConfigFile::untrusted_removed`] drops unless
+/// `global` trusts the repository. Dropped settings are reported on stderr.
+pub fn load_project_for(global: &ConfigFile, repo: &Path) -> Result<Option<ConfigFile>> {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+
+    let Some(project) = load_project(repo)? else {
+        return Ok(None);
+    };
+    if global.trusts(repo) {
+        return Ok(Some(project));
+    }
+    let (project, dropped) = project.untrusted_removed(global);
+    if !dropped.is_empty() {
+        WARNED.call_once(|| {
+            eprintln!(
+                "[!] Ignoring {} from {}: add the repository to `trusted_projects` in {} to allow them.",
+                dropped.join(", "),
+                project_path(repo).display(),
+                path().map_or_else(|_| "config.toml".to_string(), |p| p.display().to_string()),
+            )
+        });
+    }
+    Ok(Some(project))
+}
+
+/// Loads `config.toml` with the project config of the repository at `repo`,
+/// if any, laid over it.
+pub fn load_for(repo: &Path) -> Result<ConfigFile> {
+    let global = load()?;
+    Ok(match load_project_for(&global, repo)? {
+        Some(project) => global.overlaid_with(project),
+        None => global,
+    })
+}
+
+impl ConfigFile {
+    /// Whether `trusted_projects` lists the repository at `repo`.
+    pub fn trusts(&self, repo: &Path) -> bool {
+        let Ok(repo) = repo.canonicalize() else {
+            return false;
+        };
+        self.trusted_projects
+            .iter()
+            .flatten()
+            .any(|trusted| trusted.canonicalize().is_ok_and(|t| t == repo))
+    }
+
+    /// This project config without the settings a repository must be trusted
+    /// to set: hooks, transform commands, the LLM and embedding endpoints and
+    /// key variables (which keep their values from `global`), the cache key
+    /// variable and `trusted_projects` itself. Also returns the names of the
+    /// settings that were set and dropped.
+    pub fn untrusted_removed(mut self, global: &ConfigFile) -> (ConfigFile, Vec<&'static str>) {
+        let mut dropped = Vec::new();
+        if self.hooks.take().is_some() {
+            dropped.push("hooks");
+        }
+        let mut has_cmd = false;
+        for spec in self.transform.iter_mut().flat_map(|t| t.values_mut()) {
+            has_cmd |= spec.cmd.take().is_some();
+            let steps = spec.steps.len();
+            spec.steps
+                .retain(|step| !matches!(step, TransformStep::Cmd(_)));
+            has_cmd |= spec.steps.len() != steps;
+        }
+        if has_cmd {
+            dropped.push("transform commands");
+        }
+        if let Some(llm) = &mut self.llm {
+            let base = global.llm.clone().unwrap_or_default();
+            if llm.endpoint != base.endpoint || llm.api_key_env != base.api_key_env {
+                dropped.push("llm.endpoint and llm.api_key_env");
+            }
+            llm.endpoint = base.endpoint;
+            llm.api_key_env = base.api_key_env;
+        }
+        if let Some(embeddings) = &mut self.embeddings {
+            let base = global.embeddings.clone().unwrap_or_default();
+            if embeddings.endpoint != base.endpoint || embeddings.api_key_env != base.api_key_env {
+                dropped.push("embeddings.endpoint and embeddings.api_key_env");
+            }
+            embeddings.endpoint = base.endpoint;
+            embeddings.api_key_env = base.api_key_env;
+        }
+        if self.cache.key_env.take().is_some() {
+            dropped.push("cache.key_env");
+        }
+        // The limits prune the caches of other repositories too.
+        let max_repo_mb = self.cache.max_repo_mb.take();
+        if self.cache.max_total_mb.take().is_some() || max_repo_mb.is_some() {
+            dropped.push("cache.max_repo_mb and cache.max_total_mb");
+        }
+        if self.aliases.take().is_some() {
+            dropped.push("aliases");
+        }
+        if self.trusted_projects.take().is_some() {
+            dropped.push("trusted_projects");
+        }
+        (self, dropped)
+    }
+
+    /// These settings with those of `project` taking precedence: values it
+    /// sets replace these, tables are merged key by key and excludes add up.
+    /// The TUI's saved settings stay global.
+    pub fn overlaid_with(self, project: ConfigFile) -> ConfigFile {
+        fn merged<M>(base: Option<M>, over: Option<M>) -> Option<M>
+        where
+            M: IntoIterator + FromIterator<M::Item>,
+        {
+            match (base, over) {
+                (Some(base), Some(over)) => Some(base.into_iter().chain(over).collect()),
+                (base, over) => over.or(base),
+            }
+        }
+        ConfigFile {
+            exclude: merged(self.exclude, project.exclude),
+            tokenizer: project.tokenizer.or(self.tokenizer),
+            no_codeblock: project.no_codeblock.or(self.no_codeblock),
+            line_numbers: project.line_numbers.or(self.line_numbers),
+            normalize_newlines: project.normalize_newlines.or(self.normalize_newlines),
+            no_default_excludes: project.no_default_excludes.or(self.no_default_excludes),
+            history: project.history.or(self.history),
+            priority: merged(self.priority, project.priority),
+            transform: merged(self.transform, project.transform),
+            hooks: project.hooks.or(self.hooks),
+            embeddings: project.embeddings.or(self.embeddings),
+            llm: project.llm.or(self.llm),
+            languages: merged(self.languages, project.languages),
+            aliases: merged(self.aliases, project.aliases),
+            trusted_projects: self.trusted_projects,
+            template: TemplateConfig {
+                defaults: merged(self.template.defaults, project.template.defaults),
+                vars: merged(self.template.vars, project.template.vars),
+            },
+            output: OutputSection {
+                auto_exclude_artifacts: project
+                    .output
+                    .auto_exclude_artifacts
+                    .or(self.output.auto_exclude_artifacts),
+            },
+            cache: CacheSection {
+                max_repo_mb: project.cache.max_repo_mb.or(self.cache.max_repo_mb),
+                max_total_mb: project.cache.max_total_mb.or(self.cache.max_total_mb),
+                content: project.cache.content.or(self.cache.content),
+                key_env: project.cache.key_env.or(self.cache.key_env),
+            },
+            gui: self.gui,
+        }
+    }
+}
+
+/// Per-repository settings, stored next to the repository's other caches.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RepoConfig {
+    /// Exclude globs added with the TUI's `x` action, applied to every scan.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl Cacheable for RepoConfig {
+    const KEY: &'static str = "config";
+    const FORMAT: CacheFormat = CacheFormat::Toml;
+}
+
+impl RepoConfig {
+    /// Excludes the file or directory at `path`, relative to the repository
+    /// root. Returns `false` if it was already excluded.
+    pub fn add_exclude(&mut self, path: &str, is_dir: bool) -> bool {
+        let escaped = Pattern::escape(path);
+        let glob = if is_dir {
+            format!("{escaped}/**")
+        } else {
+            escaped
+        };
+        if self.exclude.contains(&glob) {
+            return false;
+        }
+        self.exclude.push(glob);
+        true
+    }
+}
@@ -0,0 +1,527 @@
+//! Import graphs, for selecting files together with what they depend on
+//! (`--with-deps`) or with what depends on them (`--with-dependents`).
+//!
+//! Imports are found with per-language patterns and resolved to files of the
+//! repository; imports of external packages are ignored. Supported: Rust
+//! (`mod`, `use crate/self/super`), Python, JavaScript/TypeScript (relative
+//! specifiers), Go (packages of the module in `go.mod`) and C/C++ (quoted
+//! `#include`s).
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::common::{hash::HashMap, path};
+use crate::engine::{config::Code2PromptConfig, encoding, traverse::list_files};
+
+const JS_EXTS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+const C_EXTS: &[&str] = &["c", "h", "cc", "cpp", "cxx", "hpp", "hh"];
+
+/// Which files each file of the repository imports, and is imported by.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    imports: HashMap<String, Vec<String>>,
+    /// The imports that are Rust submodules declared with `mod`.
+    submodules: HashMap<String, Vec<String>>,
+    importers: HashMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    /// Reads and resolves the imports of `files` (relative to `root`).
+    pub fn build(root: &Path, files: &[String]) -> Self {
+        let sources = files.iter().filter_map(|f| {
+            if !is_supported(f) {
+                return None;
+            }
+            let src = encoding::read_source(&root.join(f)).ok()?;
+            Some((f.clone(), src.text))
+        });
+        let go_module = std::fs::read_to_string(root.join("go.mod"))
+            .ok()
+            .and_then(|m| go_module_name(&m));
+        Self::from_sources(sources, files, go_module.as_deref())
+    }
+
+    /// Builds the graph from in-memory `(path, source)` pairs; `files` are all
+    /// the files imports may resolve to.
+    pub fn from_sources(
+        sources: impl IntoIterator<Item = (String, String)>,
+        files: &[String],
+        go_module: Option<&str>,
+    ) -> Self {
+        let resolver = Resolver::new(files, go_module);
+        let mut imports: HashMap<String, Vec<String>> = HashMap::default();
+        let mut submodules: HashMap<String, Vec<String>> = HashMap::default();
+        for (path, src) in sources {
+            let (mut deps, mut mods) = resolver.resolve_imports(&path, &src);
+            mods.retain(|m| *m != path);
+            mods.sort();
+            mods.dedup();
+            deps.extend(mods.iter().cloned());
+            deps.retain(|d| *d != path);
+            deps.sort();
+            deps.dedup();
+            if !mods.is_empty() {
+                submodules.insert(path.clone(), mods);
+            }
+            imports.insert(path, deps);
+        }
+
+        let mut importers: HashMap<String, Vec<String>> = HashMap::default();
+        for (file, deps) in &imports {
+            for dep in deps {
+                importers.entry(dep.clone()).or_default().push(file.clone());
+            }
+        }
+        for files in importers.values_mut() {
+            files.sort();
+        }
+        Self {
+            imports,
+            submodules,
+            importers,
+        }
+    }
+
+    /// The files `file` imports directly.
+    pub fn imports_of(&self, file: &str) -> &[String] {
+        self.imports.get(file).map_or(&[], Vec::as_slice)
+    }
+
+    /// The seeds and the files they (transitively) import, following at most
+    /// `depth` levels of imports when given. The submodules of a Rust module
+    /// are only followed when the module is a seed or itself reached through
+    /// `mod`: a module reached through `use` brings only what it uses, or any
+    /// `use` of a `mod.rs` would pull in the whole crate.
+    pub fn dependencies(&self, seeds: &[String], depth: Option<usize>) -> BTreeSet<String> {
+        walk(seeds, depth, |file, with_submodules| {
+            let submodules = self.submodules.get(file).map_or(&[][..], Vec::as_slice);
+            self.imports_of(file)
+                .iter()
+                .map(|dep| (dep.as_str(), submodules.contains(dep)))
+                .filter(|&(_, is_submodule)| with_submodules || !is_submodule)
+                .collect()
+        })
+    }
+
+    /// The files importing `file` directly.
+    pub fn importers_of(&self, file: &str) -> &[String] {
+        self.importers.get(file).map_or(&[], Vec::as_slice)
+    }
+
+    /// The seeds and the files (transitively) importing them, following at
+    /// most `depth` levels when given.
+    pub fn dependents(&self, seeds: &[String], depth: Option<usize>) -> BTreeSet<String> {
+        walk(seeds, depth, |file, _| {
+            self.importers_of(file)
+                .iter()
+                .map(|f| (f.as_str(), true))
+                .collect()
+        })
+    }
+}
+
+/// Breadth-first closure of `seeds` under `next`, which gets whether a file
+/// was reached as a seed or through a `true` edge, and returns the next files
+/// with the flag of the edge to each.
+fn walk<'a>(
+    seeds: &'a [String],
+    depth: Option<usize>,
+    next: impl Fn(&str, bool) -> Vec<(&'a str, bool)>,
+) -> BTreeSet<String> {
+    let mut seen: HashMap<&str, bool> = seeds.iter().map(|s| (s.as_str(), true)).collect();
+    let mut queue: VecDeque<(&str, bool, usize)> =
+        seeds.iter().map(|s| (s.as_str(), true, 0)).collect();
+    while let Some((file, flag, level)) = queue.pop_front() {
+        if depth.is_some_and(|d| level >= d) {
+            continue;
+        }
+        for (dep, dep_flag) in next(file, flag) {
+            // Reached again through a `true` edge, a file has more to follow.
+            if seen
+                .get(dep)
+                .is_some_and(|&seen_flag| seen_flag || !dep_flag)
+            {
+                continue;
+            }
+            seen.insert(dep, dep_flag);
+            queue.push_back((dep, dep_flag, level + 1));
+        }
+    }
+    seen.into_keys().map(str::to_owned).collect()
+}
+
+/// Resolves the `--with-deps` and `--with-dependents` seeds of `cfg` to the
+/// set of files to include.
+pub fn related_files(cfg: &Code2PromptConfig, root: &Path) -> Result<BTreeSet<String>> {
+    let files = list_files(root, cfg)?;
+    let seeds = |flag, paths: &[PathBuf]| {
+        paths
+            .iter()
+            .map(|s| seed_path(root, s, &files, flag))
+            .collect::<Result<Vec<_>>>()
+    };
+    let deps_seeds = seeds("--with-deps", &cfg.with_deps)?;
+    let dependents_seeds = seeds("--with-dependents", &cfg.with_dependents)?;
+
+    let graph = DependencyGraph::build(root, &files);
+    let mut selected = graph.dependencies(&deps_seeds, cfg.dep_depth);
+    selected.extend(graph.dependents(&dependents_seeds, cfg.dep_depth));
+    Ok(selected)
+}
+
+/// Turns a seed given relative to the repository or the working directory
+/// into a repository-relative path.
+fn seed_path(root: &Path, seed: &Path, files: &[String], flag: &str) -> Result<String> {
+    let candidates = [
+        Some(path::to_fwd_slash(seed)),
+        path::canonicalize(seed)
+            .ok()
+            .and_then(|p| p.strip_prefix(root).ok().map(path::to_fwd_slash)),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .map(|c| c.trim_start_matches("./").to_owned())
+        .find(|c| files.contains(c))
+        .map_or_else(
+            || bail!("{flag}: {} is not a file of the repository", seed.display()),
+            Ok,
+        )
+}
+
+fn is_supported(path: &str) -> bool {
+    let ext = extension(path);
+    ext == "rs" || ext == "py" || ext == "go" || JS_EXTS.contains(&ext) || C_EXTS.contains(&ext)
+}
+
+fn extension(path: &str) -> &str {
+    path.rsplit_once('.')
+        .map(|(_, e)| e)
+        .filter(|e| !e.contains('/'))
+        .unwrap_or("")
+}
+
+/// Parses the module path out of a `go.mod` file.
+pub fn go_module_name(go_mod: &str) -> Option<String> {
+    go_mod
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("module "))
+        .map(|m| m.trim().trim_matches('"').to_owned())
+}
+
+// ────────────────────────────────────────────────────────────
+//  Path helpers
+// ────────────────────────────────────────────────────────────
+
+/// The directory part of a relative path (`""` for top-level files).
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// Joins `rel` onto `dir`, resolving `.` and `..`. Returns `None` when the
+/// result would leave the repository.
+fn join(dir: &str, rel: &str) -> Option<String> {
+    let mut parts: Vec<&str> = dir.split('/').filter(|p| !p.is_empty()).collect();
+    for part in rel.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            p => parts.push(p),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// The paths a Rust `use` tree names, each starting with `crate`, `self` or
+/// `super`: `crate::a::{b, c::{self, D as E}}` names `crate::a::b`,
+/// `crate::a::c` and `crate::a::c::D`.
+fn use_paths(tree: &str) -> Vec<String> {
+    fn expand(prefix: &str, tree: &str, paths: &mut Vec<String>) {
+        let join = |path: &str| match (prefix, path) {
+            (_, "") => prefix.to_owned(),
+            ("", _) => path.to_owned(),
+            _ => format!("{prefix}::{path}"),
+        };
+        let tree = tree.trim();
+        let Some((head, group)) = tree.split_once('{') else {
+            // `Item as Alias`
+            match tree.split_whitespace().next() {
+                None => {}
+                Some("self" | "*") => paths.push(prefix.to_owned()),
+                Some(leaf) => paths.push(join(leaf)),
+            }
+            return;
+        };
+        let prefix = join(head.trim().trim_end_matches("::"));
+        let group = group.trim_end().strip_suffix('}').unwrap_or(group);
+        let (mut depth, mut start) = (0, 0);
+        for (i, c) in group.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    expand(&prefix, &group[start..i], paths);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        expand(&prefix, &group[start..], paths);
+    }
+    let mut paths = Vec::new();
+    expand("", tree, &mut paths);
+    paths
+}
+
+// ────────────────────────────────────────────────────────────
+//  Resolution
+// ────────────────────────────────────────────────────────────
+
+static JS_IMPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?:\bfrom|\bimport|\brequire)\s*\(?\s*['"]([^'"\n]+)['"]"#).unwrap()
+});
+static PY_FROM: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^[ \t]*from[ \t]+(\.*)([\w.]*)[ \t]+import[ \t]+\(?([\w, \t*]+)").unwrap()
+});
+static PY_IMPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^[ \t]*import[ \t]+([\w.]+(?:[ \t]+as[ \t]+\w+)?(?:[ \t]*,[ \t]*[\w.]+(?:[ \t]+as[ \t]+\w+)?)*)").unwrap()
+});
+static RS_MOD: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^[ \t]*(?:pub(?:\([^)]*\))?[ \t]+)?mod[ \t]+(\w+)[ \t]*;").unwrap()
+});
+static RS_USE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^[ \t]*(?:pub(?:\([^)]*\))?[ \t]+)?use[ \t]+((?:crate|self|super)\b[^;]*);")
+        .unwrap()
+});
+static RS_LINE_COMMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"//[^\n]*").unwrap());
+static GO_IMPORT_BLOCK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?ms)^import[ \t]*\((.*?)\)").unwrap());
+static GO_IMPORT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?m)^import[ \t]+(?:[\w.]+[ \t]+)?"([^"]+)""#).unwrap());
+static QUOTED: Lazy<Regex> = Lazy::new(|| Regex::new(r#""([^"\n]+)""#).unwrap());
+static C_INCLUDE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?m)^[ \t]*#[ \t]*include[ \t]*"([^"]+)""#).unwrap());
+
+struct Resolver<'a> {
+    files: HashSet<&'a str>,
+    go_module: Option<&'a str>,
+    /// Non-test Go files per directory.
+    go_packages: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(files: &'a [String], go_module: Option<&'a str>) -> Self {
+        let mut go_packages: HashMap<&str, Vec<&str>> = HashMap::default();
+        for f in files {
+            if f.ends_with(".go") && !f.ends_with("_test.go") {
+                go_packages.entry(parent(f)).or_default().push(f);
+            }
+        }
+        Self {
+            files: files.iter().map(String::as_str).collect(),
+            go_module,
+            go_packages,
+        }
+    }
+
+    /// Returns the first candidate that is a file of the repository.
+    fn first(&self, candidates: impl IntoIterator<Item = String>) -> Option<String> {
+        candidates
+            .into_iter()
+            .find(|c| self.files.contains(c.as_str()))
+    }
+
+    /// The files `path` imports, and the Rust submodules it declares.
+    fn resolve_imports(&self, path: &str, src: &str) -> (Vec<String>, Vec<String>) {
+        let imports = match extension(path) {
+            "rs" => return self.rust(path, src),
+            "py" => self.python(path, src),
+            "go" => self.go(path, src),
+            ext if JS_EXTS.contains(&ext) => self.js(path, src),
+            ext if C_EXTS.contains(&ext) => self.c(path, src),
+            _ => Vec::new(),
+        };
+        (imports, Vec::new())
+    }
+
+    fn js(&self, path: &str, src: &str) -> Vec<String> {
+        JS_IMPORT
+            .captures_iter(src)
+            .filter(|c| c[1].starts_with('.'))
+            .filter_map(|c| {
+                let base = join(parent(path), &c[1])?;
+                let stem = base
+                    .strip_suffix(".js")
+                    .or_else(|| base.strip_suffix(".jsx"))
+                    .unwrap_or(&base)
+                    .to_owned();
+                let with_ext = JS_EXTS.iter().map(|e| format!("{stem}.{e}"));
+                let index = JS_EXTS.iter().map(|e| format!("{base}/index.{e}"));
+                self.first(std::iter::once(base.clone()).chain(with_ext).chain(index))
+            })
+            .collect()
+    }
+
+    fn python_module(&self, base: &str, module: &str) -> Option<String> {
+        let path = join(base, &module.replace('.', "/"))?;
+        self.first([format!("{path}.py"), format!("{path}/__init__.py")])
+    }
+
+    fn python(&self, path: &str, src: &str) -> Vec<String> {
+        let mut deps = Vec::new();
+        // Absolute imports are looked up from the root and a `src/` layout.
+        let roots = ["", "src"];
+        for c in PY_IMPORT.captures_iter(src) {
+            for module in c[1].split(',') {
+                let module = module.split_whitespace().next().unwrap_or_default();
+                deps.extend(roots.iter().find_map(|r| self.python_module(r, module)));
+            }
+        }
+        for c in PY_FROM.captures_iter(src) {
+            let (dots, module) = (c[1].len(), &c[2]);
+            let bases: Vec<String> = if dots > 0 {
+                let up = "../".repeat(dots - 1);
+                join(parent(path), &up).into_iter().collect()
+            } else {
+                roots.iter().map(|r| r.to_string()).collect()
+            };
+            for base in &bases {
+                deps.extend(self.python_module(base, module));
+                // `from pkg import submodule`
+                let pkg = join(base, &module.replace('.', "/")).unwrap_or_default();
+                for name in c[3].split(',').filter_map(|n| n.split_whitespace().next()) {
+                    deps.extend(self.python_module(&pkg, name));
+                }
+            }
+        }
+        deps
+    }
+
+    /// The directory holding the submodules of a Rust source file.
+    fn rust_module_dir(path: &str) -> String {
+        let file = path.rsplit('/').next().unwrap_or(path);
+        if matches!(file, "mod.rs" | "lib.rs" | "main.rs") {
+            parent(path).to_owned()
+        } else {
+            path.trim_end_matches(".rs").to_owned()
+        }
+    }
+
+    /// The nearest ancestor directory holding a `lib.rs` or `main.rs`.
+    fn rust_crate_root(&self, path: &str) -> String {
+        let mut dir = parent(path);
+        loop {
+            let prefix = if dir.is_empty() {
+                String::new()
+            } else {
+                format!("{dir}/")
+            };
+            if ["lib.rs", "main.rs"]
+                .iter()
+                .any(|f| self.files.contains(format!("{prefix}{f}").as_str()))
+            {
+                return dir.to_owned();
+            }
+            if dir.is_empty() {
+                return "src".to_owned();
+            }
+            dir = parent(dir);
+        }
+    }
+
+    /// The file of the module `segments` names below `base`. `use a::b::Item`
+    /// may name a module or an item in it: the longest module path is tried
+    /// first, down to `min` segments.
+    fn rust_module(&self, base: &str, segments: &[&str], min: usize) -> Option<String> {
+        (min..=segments.len()).rev().find_map(|n| {
+            let path = join(base, &segments[..n].join("/"))?;
+            let roots = ["mod.rs", "lib.rs", "main.rs"];
+            let candidates = std::iter::once(format!("{path}.rs"))
+                .chain(roots.iter().map(|f| format!("{path}/{f}")))
+                .map(|c| c.trim_start_matches('/').to_owned());
+            self.first(candidates)
+        })
+    }
+
+    /// The uses of `path` and the submodules it declares.
+    fn rust(&self, path: &str, src: &str) -> (Vec<String>, Vec<String>) {
+        let module_dir = Self::rust_module_dir(path);
+        let mods = RS_MOD
+            .captures_iter(src)
+            .filter_map(|c| self.rust_module(&module_dir, &[&c[1]], 1))
+            .collect();
+        let mut uses = Vec::new();
+        let code = RS_LINE_COMMENT.replace_all(src, "");
+        for use_path in RS_USE.captures_iter(&code).flat_map(|c| use_paths(&c[1])) {
+            let mut segments: Vec<&str> = use_path.split("::").collect();
+            let (base, min) = match segments[0] {
+                // An item of the crate root is not worth the whole crate root.
+                "crate" => (self.rust_crate_root(path), 1),
+                "self" => (module_dir.clone(), 0),
+                _ => {
+                    let supers = segments.iter().take_while(|s| **s == "super").count();
+                    let mut base = module_dir.as_str();
+                    for _ in 0..supers {
+                        base = parent(base);
+                    }
+                    segments.drain(..supers - 1);
+                    (base.to_owned(), 0)
+                }
+            };
+            uses.extend(self.rust_module(&base, &segments[1..], min));
+        }
+        (uses, mods)
+    }
+
+    fn go(&self, path: &str, src: &str) -> Vec<String> {
+        // Files of the same package see each other without imports.
+        let mut deps: Vec<String> = self
+            .go_packages
+            .get(parent(path))
+            .into_iter()
+            .flatten()
+            .map(|f| f.to_string())
+            .collect();
+        let Some(module) = self.go_module else {
+            return deps;
+        };
+        let block_imports = GO_IMPORT_BLOCK.captures_iter(src).flat_map(|b| {
+            QUOTED
+                .captures_iter(b.get(1).map_or("", |m| m.as_str()))
+                .map(|c| c[1].to_owned())
+                .collect::<Vec<_>>()
+        });
+        let single_imports = GO_IMPORT.captures_iter(src).map(|c| c[1].to_owned());
+        for import in block_imports.chain(single_imports) {
+            let dir = if import == module {
+                ""
+            } else if let Some(dir) = import.strip_prefix(&format!("{module}/")) {
+                dir
+            } else {
+                continue;
+            };
+            if let Some(files) = self.go_packages.get(dir) {
+                deps.extend(files.iter().map(|f| f.to_string()));
+            }
+        }
+        deps
+    }
+
+    fn c(&self, path: &str, src: &str) -> Vec<String> {
+        C_INCLUDE
+            .captures_iter(src)
+            .filter_map(|c| {
+                let candidates = [parent(path), "", "include"]
+                    .into_iter()
+                    .filter_map(|dir| join(dir, &c[1]));
+                self.first(candidates)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+//! Keeps git diffs within a token budget (`--max-diff-tokens`).
+//!
+//! Context lines go first, since the added and removed lines carry the
+//! change; if that is not enough, whole files are replaced by a one-line
+//! marker, largest first.
+
+use crate::engine::token::{TokenizerChoice, count_tokens, estimate_tokens};
+
+/// A run of diff lines: one file's diff, or the text between files (section
+/// headers and the like).
+struct Block<'a> {
+    /// The file's path, for file diffs.
+    path: Option<&'a str>,
+    lines: Vec<&'a str>,
+    tokens: usize,
+    /// Stands in for the lines once the file is dropped.
+    marker: Option<String>,
+}
+
+/// Trims `diff` to about `max_tokens` tokens. Returns `None` when it already
+/// fits.
+///
+/// Without the `token_map` feature, or with `approximate`, tokens are
+/// estimated from bytes.
+pub fn trim_diff(
+    diff: &str,
+    max_tokens: usize,
+    tokenizer_name: TokenizerChoice,
+    approximate: bool,
+) -> Option<String> {
+    let count = |text: &str| {
+        if approximate || !cfg!(feature = "token_map") {
+            estimate_tokens(text, tokenizer_name)
+        } else {
+            count_tokens(text, tokenizer_name)
+                .unwrap_or_else(|_| estimate_tokens(text, tokenizer_name))
+        }
+    };
+    if count(diff) <= max_tokens {
+        return None;
+    }
+
+    let mut blocks = split_files(diff);
+    for block in &mut blocks {
+        if block.path.is_some() {
+            drop_context_lines(&mut block.lines);
+        }
+        block.tokens = count(&block.lines.concat());
+    }
+
+    let mut total: usize = blocks.iter().map(|b| b.tokens).sum();
+    let mut by_size: Vec<usize> = (0..blocks.len())
+        .filter(|&i| blocks[i].path.is_some())
+        .collect();
+    by_size.sort_by_key(|&i| std::cmp::Reverse(blocks[i].tokens));
+    for i in by_size {
+        if total <= max_tokens {
+            break;
+        }
+        let block = &mut blocks[i];
+        let marker = format!(
+            "... [diff of {} omitted: {} tokens]\n",
+            block.path.unwrap_or_default(),
+            block.tokens
+        );
+        total = total - block.tokens + count(&marker);
+        block.marker = Some(marker);
+    }
+
+    let mut out = String::new();
+    for block in &blocks {
+        match &block.marker {
+            Some(marker) => out.push_str(marker),
+            None => block.lines.iter().for_each(|line| out.push_str(line)),
+        }
+    }
+    Some(out)
+}
+
+fn split_files(diff: &str) -> Vec<Block<'_>> {
+    let mut blocks: Vec<Block> = Vec::new();
+    for line in diff.split_inclusive('\n') {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            let path = header
+                .trim_end()
+                .rsplit_once(" b/")
+                .map_or(header.trim_end(), |(_, b)| b);
+            blocks.push(Block {
+                path: Some(path),
+                lines: vec![line],
+                tokens: 0,
+                marker: None,
+            });
+            continue;
+        }
+        // Text after a file's hunks that is not part of them, such as the
+        // next section's header, starts a block of its own.
+        let in_text = blocks.last().is_some_and(|b| b.path.is_none());
+        if !in_text && (blocks.is_empty() || is_section_header(line)) {
+            blocks.push(Block {
+                path: None,
+                lines: Vec::new(),
+                tokens: 0,
+                marker: None,
+            });
+        }
+        blocks.last_mut().unwrap().lines.push(line);
+    }
+    blocks
+}
+
+/// The headers `get_git_diff` puts above its staged and unstaged parts.
+fn is_section_header(line: &str) -> bool {
+    matches!(
+        line.trim_end(),
+        "--- Staged Changes ---" | "--- Unstaged Changes ---"
+    )
+}
+
+/// Drops the unchanged lines around each hunk's changes.
+fn drop_context_lines(lines: &mut Vec<&str>) {
+    let mut in_hunk = false;
+    lines.retain(|line| {
+        if line.starts_with("@@") {
+            in_hunk = true;
+        }
+        !(in_hunk && line.starts_with(' '))
+    });
+}
@@ -0,0 +1,307 @@
+//! Semantic file selection (`--query`).
+//!
+//! Files are split into chunks of lines and embedded through an
+//! OpenAI-compatible `/embeddings` endpoint — OpenAI itself, or a local model
+//! served by e.g. Ollama or llama.cpp. Files are ranked by the cosine
+//! similarity of their best chunk to the query. The index lives in the cache
+//! directory and only files whose content changed are embedded again.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::engine::{config::Code2PromptConfig, encoding};
+
+/// Chunks are cut to this many characters, to stay within the input limits
+/// of common embedding models.
+const MAX_CHUNK_CHARS: usize = 6000;
+
+/// The `[embeddings]` section of the config file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingSettings {
+    /// Base URL of an OpenAI-compatible API, e.g. `http://localhost:11434/v1`.
+    pub endpoint: String,
+    pub model: String,
+    /// Environment variable holding the API key, if the endpoint needs one.
+    pub api_key_env: Option<String>,
+    /// Lines per chunk.
+    pub chunk_lines: usize,
+    /// Chunks sent per request.
+    pub batch_size: usize,
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: "https://api.openai.com/v1".to_string(),
+            model: "text-embedding-3-small".to_string(),
+            api_key_env: Some("OPENAI_API_KEY".to_string()),
+            chunk_lines: 60,
+            batch_size: 64,
+        }
+    }
+}
+
+/// A `--query` and how many files it selects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticQuery {
+    pub text: String,
+    pub top_k: usize,
+}
+
+/// Turns texts into vectors.
+pub trait Embedder {
+    /// Identifies the model; indexes built with another model are discarded.
+    fn model(&self) -> &str;
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct IndexedFile {
+    /// SHA-256 of the content the chunks were embedded from.
+    hash: String,
+    chunks: Vec<Vec<f32>>,
+}
+
+/// Chunk embeddings of the files of one repository.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EmbeddingIndex {
+    model: String,
+    files: BTreeMap<String, IndexedFile>,
+}
+
+impl EmbeddingIndex {
+    /// Loads an index, starting afresh when it is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?)
+            .with_context(|| format!("Failed to write embedding index {}", path.display()))
+    }
+
+    /// Number of indexed files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Brings the index in line with `files` (relative to `root`): drops
+    /// files that are gone and embeds new or changed ones. Returns how many
+    /// files were embedded.
+    pub fn update(
+        &mut self,
+        root: &Path,
+        files: &[String],
+        embedder: &dyn Embedder,
+        chunk_lines: usize,
+    ) -> Result<usize> {
+        if self.model != embedder.model() {
+            self.model = embedder.model().to_string();
+            self.files.clear();
+        }
+        let present: HashSet<&String> = files.iter().collect();
+        self.files.retain(|path, _| present.contains(path));
+
+        let mut stale: Vec<(&String, String, Vec<String>)> = Vec::new();
+        for path in files {
+            let Ok(src) = encoding::read_source(&root.join(path)) else {
+                continue;
+            };
+            let hash = hex::encode(Sha256::digest(src.text.as_bytes()));
+            if self.files.get(path).is_some_and(|f| f.hash == hash) {
+                continue;
+            }
+            stale.push((path, hash, chunk_text(path, &src.text, chunk_lines)));
+        }
+
+        if stale.is_empty() {
+            return Ok(0);
+        }
+        let texts: Vec<String> = stale.iter().flat_map(|(.., c)| c.clone()).collect();
+        let mut vectors = embedder.embed(&texts)?.into_iter();
+        for (path, hash, chunks) in &stale {
+            let chunks = vectors.by_ref().take(chunks.len()).collect();
+            self.files.insert(
+                path.to_string(),
+                IndexedFile {
+                    hash: hash.clone(),
+                    chunks,
+                },
+            );
+        }
+        Ok(stale.len())
+    }
+
+    /// The `top_k` files most similar to `query`, best first.
+    pub fn search(&self, query: &[f32], top_k: usize) -> Vec<(String, f32)> {
+        let mut scored: Vec<(String, f32)> = self
+            .files
+            .iter()
+            .filter_map(|(path, file)| {
+                let best = file
+                    .chunks
+                    .iter()
+                    .map(|c| cosine(query, c))
+                    .max_by(f32::total_cmp)?;
+                Some((path.clone(), best))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored.truncate(top_k);
+        scored
+    }
+}
+
+/// Splits a file into chunks of `lines` lines, each headed by its path so the
+/// location contributes to the embedding.
+pub fn chunk_text(path: &str, text: &str, lines: usize) -> Vec<String> {
+    let all: Vec<&str> = text.lines().collect();
+    all.chunks(lines.max(1))
+        .filter(|c| c.iter().any(|l| !l.trim().is_empty()))
+        .map(|c| {
+            let chunk = format!("{path}\n{}", c.join("\n"));
+            match chunk.char_indices().nth(MAX_CHUNK_CHARS) {
+                Some((cut, _)) => chunk[..cut].to_string(),
+                None => chunk,
+            }
+        })
+        .collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom == 0.0 { 0.0 } else { dot / denom }
+}
+
+/// Updates `index` and returns the `query.top_k` files best matching
+/// `query.text`.
+pub fn rank_files(
+    index: &mut EmbeddingIndex,
+    root: &Path,
+    files: &[String],
+    embedder: &dyn Embedder,
+    chunk_lines: usize,
+    query: &SemanticQuery,
+) -> Result<Vec<String>> {
+    index.update(root, files, embedder, chunk_lines)?;
+    let query_vec = embedder
+        .embed(std::slice::from_ref(&query.text))?
+        .pop()
+        .context("Embedding endpoint returned no vector for the query")?;
+    Ok(index
+        .search(&query_vec, query.top_k)
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect())
+}
+
+/// Resolves the `--query` of `cfg` to the files to include, using the
+/// repository's cached index.
+#[cfg(feature = "embeddings")]
+pub fn select_files(cfg: &Code2PromptConfig, root: &Path) -> Result<Vec<String>> {
+    use crate::engine::{traverse::list_files, utils::RepoCachePath};
+
+    let Some(query) = &cfg.query else {
+        return Ok(Vec::new());
+    };
+    let files = list_files(root, cfg)?;
+    let embedder = HttpEmbedder::new(cfg.embeddings.clone());
+    let index_path = RepoCachePath::new(root)?.get_cache_file_path("embeddings", "json")?;
+    let mut index = EmbeddingIndex::load(&index_path);
+    let ranked = rank_files(
+        &mut index,
+        root,
+        &files,
+        &embedder,
+        cfg.embeddings.chunk_lines,
+        query,
+    )?;
+    index.save(&index_path)?;
+    Ok(ranked)
+}
+
+#[cfg(not(feature = "embeddings"))]
+pub fn select_files(_cfg: &Code2PromptConfig, _root: &Path) -> Result<Vec<String>> {
+    anyhow::bail!("--query requires code2prompt to be built with the `embeddings` feature")
+}
+
+/// An embedder calling `POST {endpoint}/embeddings`.
+#[cfg(feature = "embeddings")]
+pub struct HttpEmbedder {
+    settings: EmbeddingSettings,
+    api_key: Option<String>,
+}
+
+#[cfg(feature = "embeddings")]
+impl HttpEmbedder {
+    pub fn new(settings: EmbeddingSettings) -> Self {
+        let api_key = settings
+            .api_key_env
+            .as_ref()
+            .and_then(|var| std::env::var(var).ok());
+        Self { settings, api_key }
+    }
+}
+
+#[cfg(feature = "embeddings")]
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[cfg(feature = "embeddings")]
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[cfg(feature = "embeddings")]
+impl Embedder for HttpEmbedder {
+    fn model(&self) -> &str {
+        &self.settings.model
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = format!(
+            "{}/embeddings",
+            self.settings.endpoint.trim_end_matches('/')
+        );
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.settings.batch_size.max(1)) {
+            let mut request = ureq::post(&url);
+            if let Some(key) = &self.api_key {
+                request = request.set("Authorization", &format!("Bearer {key}"));
+            }
+            let mut response: EmbeddingsResponse = request
+                .send_json(serde_json::json!({ "model": self.settings.model, "input": batch }))
+                .with_context(|| format!("Embeddings request to {url} failed"))?
+                .into_json()
+                .context("Invalid embeddings response")?;
+            if response.data.len() != batch.len() {
+                anyhow::bail!(
+                    "Embeddings endpoint returned {} vectors for {} inputs",
+                    response.data.len(),
+                    batch.len()
+                );
+            }
+            response.data.sort_by_key(|d| d.index);
+            vectors.extend(response.data.into_iter().map(|d| d.embedding));
+        }
+        Ok(vectors)
+    }
+}
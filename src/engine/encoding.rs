@@ -0,0 +1,168 @@
+//! Reading of source files that are not valid UTF-8.
+//!
+//! Legacy codebases contain Latin-1, Shift-JIS or UTF-16 files. With the
+//! `encoding` feature these are detected and transcoded to UTF-8 instead of
+//! being skipped; without it, only valid UTF-8 files are read.
+//!
+//! Files of at least [`ENDS_THRESHOLD`] bytes that are only cut down to
+//! size have just their first and last bytes read, by [`read_source_ends`].
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// The text of a source file along with the encoding it was transcoded from.
+#[derive(Debug, Clone)]
+pub struct DecodedSource {
+    pub text: String,
+    /// `None` if the file was already valid UTF-8.
+    pub transcoded_from: Option<&'static str>,
+}
+
+/// Reads a file as text, transcoding it to UTF-8 if necessary.
+///
+/// Returns an `InvalidData` error for files that look binary.
+pub fn read_source(path: &Path) -> io::Result<DecodedSource> {
+    let bytes = std::fs::read(path)?;
+    decode_bytes(bytes).ok_or_else(invalid_data)
+}
+
+fn invalid_data() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+/// Files at least this large have just their ends read by
+/// [`read_source_ends`].
+pub const ENDS_THRESHOLD: u64 = 256 * 1024;
+
+/// The first and last bytes of a large file, decoded.
+#[derive(Debug, Clone)]
+pub struct SourceEnds {
+    pub head: String,
+    pub tail: String,
+    /// The length of the whole file in bytes.
+    pub total_bytes: usize,
+}
+
+/// Reads only the first `head` and last `tail` bytes of a file, dropping
+/// characters cut in two at the inner edges. The bytes in between are
+/// neither read nor validated.
+///
+/// Returns `None` for files below [`ENDS_THRESHOLD`], files no longer than
+/// `head + tail`, and ends that are not plain UTF-8 text; these need a
+/// full [`read_source`].
+pub fn read_source_ends(path: &Path, head: usize, tail: usize) -> io::Result<Option<SourceEnds>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < ENDS_THRESHOLD || head.saturating_add(tail) as u64 >= len {
+        return Ok(None);
+    }
+    let mut head_bytes = vec![0; head];
+    file.read_exact(&mut head_bytes)?;
+    let mut tail_bytes = vec![0; tail];
+    file.seek(SeekFrom::Start(len - tail as u64))?;
+    file.read_exact(&mut tail_bytes)?;
+
+    let head_text = match std::str::from_utf8(&head_bytes) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&head_bytes[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return Ok(None),
+    };
+    let continuation = tail_bytes
+        .iter()
+        .take(3)
+        .take_while(|&&b| b & 0xC0 == 0x80)
+        .count();
+    let Ok(tail_text) = std::str::from_utf8(&tail_bytes[continuation..]) else {
+        return Ok(None);
+    };
+    // NUL bytes may mean UTF-16, which only a full read can tell.
+    if head_text.contains('\0') || tail_text.contains('\0') {
+        return Ok(None);
+    }
+    Ok(Some(SourceEnds {
+        head: head_text.to_string(),
+        tail: tail_text.to_string(),
+        total_bytes: len as usize,
+    }))
+}
+
+/// Decodes raw bytes into UTF-8 text.
+///
+/// Returns `None` if the bytes cannot be decoded (or look binary).
+pub fn decode_bytes(bytes: Vec<u8>) -> Option<DecodedSource> {
+    match String::from_utf8(bytes) {
+        // Valid UTF-8 with NUL bytes may still be BOM-less UTF-16 of ASCII text.
+        #[cfg(feature = "encoding")]
+        Ok(text) if text.contains('\0') => transcode(text.as_bytes()).or(Some(DecodedSource {
+            text,
+            transcoded_from: None,
+        })),
+        Ok(text) => Some(DecodedSource {
+            text,
+            transcoded_from: None,
+        }),
+        #[cfg(feature = "encoding")]
+        Err(e) => transcode(e.as_bytes()),
+        #[cfg(not(feature = "encoding"))]
+        Err(_) => None,
+    }
+}
+
+#[cfg(feature = "encoding")]
+fn transcode(bytes: &[u8]) -> Option<DecodedSource> {
+    use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+
+    let (encoding, body) = if let Some((enc, bom_len)) = Encoding::for_bom(bytes) {
+        (enc, &bytes[bom_len..])
+    } else if bytes.contains(&0) {
+        // chardetng does not handle UTF-16; NUL bytes are otherwise a binary marker.
+        (guess_utf16(bytes)?, bytes)
+    } else {
+        let mut detector = chardetng::EncodingDetector::new();
+        detector.feed(bytes, true);
+        (detector.guess(None, true), bytes)
+    };
+
+    let (text, _) = encoding.decode_without_bom_handling(body);
+    // UTF-16 detection above is only a guess; reject it if it produced control noise.
+    if (encoding == UTF_16LE || encoding == UTF_16BE) && text.contains('\0') {
+        return None;
+    }
+    Some(DecodedSource {
+        text: text.into_owned(),
+        transcoded_from: Some(encoding.name()),
+    })
+}
+
+/// Guesses a BOM-less UTF-16 encoding from the position of NUL bytes.
+/// Mostly-ASCII UTF-16 text has a NUL in every other byte.
+#[cfg(feature = "encoding")]
+fn guess_utf16(bytes: &[u8]) -> Option<&'static encoding_rs::Encoding> {
+    let sample = &bytes[..bytes.len().min(4096)];
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let (mut even, mut odd) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        even += usize::from(pair[0] == 0);
+        odd += usize::from(pair[1] == 0);
+    }
+    let mostly = |n: usize| n * 10 >= pairs * 4;
+    let rarely = |n: usize| n * 20 <= pairs;
+    if mostly(odd) && rarely(even) {
+        Some(encoding_rs::UTF_16LE)
+    } else if mostly(even) && rarely(odd) {
+        Some(encoding_rs::UTF_16BE)
+    } else {
+        None
+    }
+}
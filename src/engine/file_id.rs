@@ -0,0 +1,58 @@
+//! Short IDs for the files of a prompt, such as `F1a2b3c`, derived from their
+//! relative paths so that a file keeps its ID from one run to the next.
+//! Templates can ask the model to cite files by ID, and tooling can map the
+//! IDs in its answers back to paths.
+
+use std::path::PathBuf;
+
+use sha2::{Digest, Sha256};
+
+use crate::common::{hash::HashMap, path::to_fwd_slash};
+use crate::engine::model::ProcessedEntry;
+
+/// The fewest hex digits of an ID.
+const MIN_DIGITS: usize = 6;
+
+/// The IDs of `paths` (relative, with forward slashes), in the same order:
+/// `F` and the first six hex digits of the path's SHA-256. Only the IDs of
+/// paths whose digests share those digits get longer, just enough to tell
+/// them apart, so adding a file leaves the IDs of the others as they are.
+pub fn file_ids<S: AsRef<str>>(paths: &[S]) -> Vec<String> {
+    let digests: Vec<String> = paths
+        .iter()
+        .map(|p| hex::encode(Sha256::digest(p.as_ref().as_bytes())))
+        .collect();
+    let mut sorted: Vec<&str> = digests.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let common = |a: &str, b: &str| a.bytes().zip(b.bytes()).take_while(|(x, y)| x == y).count();
+    digests
+        .iter()
+        .map(|d| {
+            // The digests sharing the most digits with `d` are next to it.
+            let i = sorted.binary_search(&d.as_str()).unwrap_or_default();
+            let shared = [i.checked_sub(1), Some(i + 1)]
+                .into_iter()
+                .flatten()
+                .filter_map(|j| sorted.get(j))
+                .map(|other| common(d, other))
+                .max()
+                .unwrap_or(0);
+            format!("F{}", &d[..(shared + 1).clamp(MIN_DIGITS, d.len())])
+        })
+        .collect()
+}
+
+/// The IDs of the files among `entries`, by relative path.
+pub fn entry_ids(entries: &[ProcessedEntry]) -> HashMap<PathBuf, String> {
+    let files: Vec<&ProcessedEntry> = entries.iter().filter(|e| e.is_file).collect();
+    let paths: Vec<String> = files
+        .iter()
+        .map(|e| to_fwd_slash(&e.relative_path))
+        .collect();
+    files
+        .iter()
+        .map(|e| e.relative_path.clone())
+        .zip(file_ids(&paths))
+        .collect()
+}
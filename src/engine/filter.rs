@@ -1,4 +1,5 @@
 //! This module contains the logic for filtering files based on include and exclude patterns.
+use std::ffi::OsStr;
 use std::path::Path;
 
 use colored::Colorize;
@@ -6,6 +7,8 @@ use globset::GlobSet;
 #[cfg(feature = "logging")]
 use log::debug;
 
+use crate::common::path::to_fwd_slash;
+
 // An explicit struct to make the matching logic clear and testable.
 #[derive(Debug, Default)]
 struct MatchResult {
@@ -53,7 +56,7 @@ pub fn should_include_file(
     }
 
     let relative_path = path.strip_prefix(root_path).unwrap_or(path);
-    let path_str = relative_path.to_string_lossy().replace('\\', "/");
+    let path_str = to_fwd_slash(relative_path);
 
     let matches = get_match_result(&path_str, include_set, exclude_set);
 
@@ -91,3 +94,61 @@ pub fn should_include_file(
     );
     result
 }
+
+/// The commits whose files `--changed-since` and `--author` keep: those
+/// committed at or after `since` (seconds since the Unix epoch) and by an
+/// author whose name or email contains `author`, ignoring case.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeFilter {
+    pub since: Option<i64>,
+    pub author: Option<String>,
+}
+
+impl ChangeFilter {
+    /// Whether the filter restricts the files at all.
+    pub fn is_active(&self) -> bool {
+        self.since.is_some() || self.author.is_some()
+    }
+
+    /// Whether a commit made at `time` by `name <email>` is selected.
+    pub fn selects(&self, time: i64, name: &str, email: &str) -> bool {
+        if self.since.is_some_and(|since| time < since) {
+            return false;
+        }
+        self.author.as_deref().is_none_or(|author| {
+            let author = author.to_lowercase();
+            format!("{name} <{email}>").to_lowercase().contains(&author)
+        })
+    }
+}
+
+/// The hidden entries a walk keeps when `--hidden` is not given: dotfiles
+/// with `--hidden-files`, and the hidden directories named by `--hidden-dirs`.
+#[derive(Debug, Clone, Default)]
+pub struct HiddenFilter {
+    pub files: bool,
+    pub dirs: Vec<String>,
+}
+
+impl HiddenFilter {
+    /// Whether any hidden entry is kept, so the walker's blanket hidden
+    /// filter has to make way for `allows`.
+    pub fn is_active(&self) -> bool {
+        self.files || !self.dirs.is_empty()
+    }
+
+    /// Whether the walk keeps the entry called `name`: entries that are not
+    /// hidden, dotfiles if `files` is set and the hidden directories in `dirs`.
+    /// A directory that is not kept is not descended into.
+    pub fn allows(&self, name: &OsStr, is_dir: bool) -> bool {
+        let name = name.to_string_lossy();
+        if !name.starts_with('.') {
+            return true;
+        }
+        if is_dir {
+            self.dirs.iter().any(|d| d.trim_end_matches('/') == name)
+        } else {
+            self.files
+        }
+    }
+}
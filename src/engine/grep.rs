@@ -0,0 +1,104 @@
+//! Content-based selection (`--grep`): keeps only the files matching a
+//! regular expression, optionally trimmed to the matching lines and the
+//! `--grep-context` lines around them.
+
+use std::ops::Range;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::common::code::LineNumberFormat;
+
+#[derive(Debug, Clone)]
+pub struct GrepFilter {
+    pub regex: Regex,
+    /// Lines kept around each match; `None` keeps whole files.
+    pub context: Option<usize>,
+}
+
+impl GrepFilter {
+    pub fn new(pattern: &str, context: Option<usize>) -> Result<Self> {
+        Ok(Self {
+            regex: parse_pattern(pattern)?,
+            context,
+        })
+    }
+
+    pub fn is_match(&self, content: &str) -> bool {
+        self.regex.is_match(content)
+    }
+
+    /// The line ranges (0-based) to keep: every matching line widened by
+    /// the context, with overlapping or adjacent ranges merged.
+    pub fn regions(&self, content: &str) -> Vec<Range<usize>> {
+        let context = self.context.unwrap_or(0);
+        let total = content.lines().count();
+        let mut regions: Vec<Range<usize>> = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if !self.regex.is_match(line) {
+                continue;
+            }
+            let range = i.saturating_sub(context)..(i + context + 1).min(total);
+            match regions.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => regions.push(range),
+            }
+        }
+        regions
+    }
+
+    /// Trims `content` to its matching regions, or returns `None` when it
+    /// has no match. Without a context the content is returned whole.
+    pub fn apply(&self, content: &str, line_numbers: Option<&LineNumberFormat>) -> Option<String> {
+        if !self.is_match(content) {
+            return None;
+        }
+        if self.context.is_none() {
+            return Some(content.to_owned());
+        }
+        Some(excerpt(content, &self.regions(content), line_numbers))
+    }
+}
+
+/// Compiles a `--grep` pattern.
+pub fn parse_pattern(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).with_context(|| format!("Invalid --grep pattern {pattern:?}"))
+}
+
+/// Joins the given line ranges of `content`, marking the omitted lines in
+/// between. Line numbers, when requested, are those of the whole file.
+pub fn excerpt(
+    content: &str,
+    regions: &[Range<usize>],
+    line_numbers: Option<&LineNumberFormat>,
+) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let width = line_numbers.map(|fmt| {
+        let last = fmt.start + lines.len().saturating_sub(1);
+        fmt.width.unwrap_or_else(|| last.to_string().len())
+    });
+
+    let mut out = String::new();
+    let mut next = 0;
+    for range in regions {
+        if range.start > next {
+            out.push_str(&omitted(range.start - next));
+        }
+        for i in range.clone() {
+            if let (Some(fmt), Some(width)) = (line_numbers, width) {
+                out.push_str(&format!("{:>width$}{}", fmt.start + i, fmt.separator));
+            }
+            out.push_str(lines[i]);
+            out.push('\n');
+        }
+        next = range.end;
+    }
+    if next < lines.len() {
+        out.push_str(&omitted(lines.len() - next));
+    }
+    out
+}
+
+fn omitted(n: usize) -> String {
+    format!("... [{n} line{} omitted]\n", if n == 1 { "" } else { "s" })
+}
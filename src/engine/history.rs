@@ -0,0 +1,124 @@
+//! An opt-in archive of rendered prompts.
+//!
+//! Each repository gets a JSON-lines file under the cache directory. Every run
+//! with history enabled appends the rendered prompt together with a snapshot of
+//! the command line and settings that produced it, so that a previous prompt
+//! can be recalled without scanning the codebase again.
+
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{token::TokenizerChoice, utils::RepoCachePath};
+
+/// One archived prompt.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The command-line arguments of the run, excluding the binary name.
+    pub args: Vec<String>,
+    pub tokenizer: TokenizerChoice,
+    /// Hash of the template content, or `"builtin"`.
+    pub template_hash: String,
+    pub files: usize,
+    pub token_count: usize,
+    pub prompt: String,
+}
+
+impl HistoryEntry {
+    /// Creates an entry stamped with the current time and command line.
+    pub fn new(
+        prompt: String,
+        token_count: usize,
+        files: usize,
+        tokenizer: TokenizerChoice,
+        template_hash: String,
+    ) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            args: std::env::args().skip(1).collect(),
+            tokenizer,
+            template_hash,
+            files,
+            token_count,
+            prompt,
+        }
+    }
+}
+
+/// The prompt history of one repository.
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    /// The number of prompts kept per repository; older ones are dropped.
+    pub const MAX_ENTRIES: usize = 50;
+
+    /// Opens the history for a repository.
+    pub fn open(repo_path: &Path) -> Result<Self> {
+        let path = RepoCachePath::new(repo_path)?.get_cache_file_path("history", "jsonl")?;
+        Ok(Self { path })
+    }
+
+    /// Opens a history stored at an explicit file path.
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Appends an entry, pruning the oldest ones beyond [`Self::MAX_ENTRIES`].
+    pub fn record(&self, entry: &HistoryEntry) -> Result<()> {
+        let line = serde_json::to_string(entry)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open history file {}", self.path.display()))?;
+        writeln!(file, "{line}")?;
+        drop(file);
+
+        let content = std::fs::read_to_string(&self.path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        if lines.len() > Self::MAX_ENTRIES {
+            let kept = lines[lines.len() - Self::MAX_ENTRIES..].join("\n");
+            std::fs::write(&self.path, kept + "\n")?;
+        }
+        Ok(())
+    }
+
+    /// Returns all entries, most recent first. Unreadable lines are skipped.
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read history file {}", self.path.display()))?;
+        Ok(content
+            .lines()
+            .rev()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect())
+    }
+
+    /// Returns the `n`-th most recent entry, counting from 1.
+    pub fn get(&self, n: usize) -> Result<HistoryEntry> {
+        let mut entries = self.entries()?;
+        let total = entries.len();
+        if n == 0 || n > total {
+            return Err(anyhow!(
+                "No history entry #{n} ({total} prompt(s) recorded)"
+            ));
+        }
+        Ok(entries.swap_remove(n - 1))
+    }
+}
@@ -0,0 +1,92 @@
+//! User commands run around a code2prompt invocation.
+//!
+//! ```toml
+//! [hooks]
+//! pre_scan = "git diff --quiet || echo 'warning: uncommitted changes' >&2"
+//! post_render = "curl -sf -X POST --data-binary @- https://prompts.internal/upload"
+//! ```
+//!
+//! Hooks run through the shell with their output sent to stderr, so they never
+//! mix with a prompt printed on stdout. Both receive `$CODE2PROMPT_REPO`; the
+//! `post_render` hook also gets the prompt on stdin, the written file in
+//! `$CODE2PROMPT_OUTPUT` (when `-O` is used) and a JSON summary of the run in
+//! `$CODE2PROMPT_METADATA`. A hook that fails aborts the run.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{token::TokenizerChoice, utils::shell_command};
+
+/// The `[hooks]` table of the config file.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    /// Run before the codebase is scanned.
+    pub pre_scan: Option<String>,
+    /// Run after the prompt has been rendered and written out.
+    pub post_render: Option<String>,
+}
+
+/// Run metadata passed to `post_render` as `$CODE2PROMPT_METADATA`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HookMetadata {
+    pub repo: String,
+    pub output_path: Option<String>,
+    pub files: usize,
+    pub token_count: usize,
+    pub tokenizer: TokenizerChoice,
+    /// Hash of the template content, or `"builtin"`.
+    pub template_hash: String,
+}
+
+impl Hooks {
+    pub fn run_pre_scan(&self, repo: &Path) -> Result<()> {
+        let Some(cmd) = &self.pre_scan else {
+            return Ok(());
+        };
+        let mut command = shell_command(cmd);
+        command.env("CODE2PROMPT_REPO", repo);
+        run_hook("pre_scan", cmd, command, None)
+    }
+
+    pub fn run_post_render(&self, prompt: &str, meta: &HookMetadata) -> Result<()> {
+        let Some(cmd) = &self.post_render else {
+            return Ok(());
+        };
+        let mut command = shell_command(cmd);
+        command
+            .env("CODE2PROMPT_REPO", &meta.repo)
+            .env("CODE2PROMPT_METADATA", serde_json::to_string(meta)?);
+        if let Some(path) = &meta.output_path {
+            command.env("CODE2PROMPT_OUTPUT", path);
+        }
+        run_hook("post_render", cmd, command, Some(prompt))
+    }
+}
+
+fn run_hook(name: &str, cmd: &str, mut command: Command, stdin: Option<&str>) -> Result<()> {
+    let mut child = command
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(std::io::stderr())
+        .spawn()
+        .with_context(|| format!("Failed to run {name} hook '{cmd}'"))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        // A hook that ignores its input may close the pipe early; that's fine.
+        let _ = pipe.write_all(input.as_bytes());
+    }
+    let status = child
+        .wait()
+        .with_context(|| format!("Failed to wait for {name} hook"))?;
+    if !status.success() {
+        bail!("The {name} hook '{cmd}' failed ({status})");
+    }
+    Ok(())
+}
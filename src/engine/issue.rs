@@ -0,0 +1,245 @@
+//! Fetches a GitHub or GitLab issue (`--issue`) through the host's REST API,
+//! so its title and description can be rendered next to the code.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::engine::model::Issue;
+
+/// Where an issue lives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueRef {
+    GitHub {
+        host: String,
+        owner: String,
+        repo: String,
+        number: u64,
+    },
+    /// `project` is the full path of the project, subgroups included.
+    GitLab {
+        host: String,
+        project: String,
+        number: u64,
+    },
+}
+
+impl IssueRef {
+    /// Parses an issue URL, or an issue number (`123` or `#123`) of the
+    /// repository whose clone URL is `remote`.
+    pub fn parse(spec: &str, remote: Option<&str>) -> Result<Self> {
+        let spec = spec.trim();
+        if let Ok(number) = spec.trim_start_matches('#').parse::<u64>() {
+            let remote = remote.with_context(|| {
+                format!("Issue {spec} needs a URL, as the repository has no `origin` remote")
+            })?;
+            let (host, path) = split_remote(remote)
+                .with_context(|| format!("Unrecognised remote URL {remote}"))?;
+            return if host.contains("gitlab") {
+                Ok(Self::GitLab {
+                    host: host.to_string(),
+                    project: path.to_string(),
+                    number,
+                })
+            } else {
+                let (owner, repo) = path
+                    .split_once('/')
+                    .filter(|(_, repo)| !repo.contains('/'))
+                    .with_context(|| format!("Unrecognised remote URL {remote}"))?;
+                Ok(Self::GitHub {
+                    host: host.to_string(),
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    number,
+                })
+            };
+        }
+
+        let invalid = || format!("Invalid issue {spec}: expected a number or an issue URL");
+        let rest = spec
+            .strip_prefix("https://")
+            .or_else(|| spec.strip_prefix("http://"))
+            .with_context(invalid)?;
+        let (host, path) = rest.split_once('/').with_context(invalid)?;
+        let path = path.trim_end_matches('/');
+        if let Some((project, number)) = path.split_once("/-/issues/") {
+            return Ok(Self::GitLab {
+                host: host.to_string(),
+                project: project.to_string(),
+                number: number.parse().ok().with_context(invalid)?,
+            });
+        }
+        match path.split('/').collect::<Vec<_>>()[..] {
+            [owner, repo, "issues" | "pull", number] => Ok(Self::GitHub {
+                host: host.to_string(),
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+                number: number.parse().ok().with_context(invalid)?,
+            }),
+            _ => Err(anyhow::anyhow!(invalid())),
+        }
+    }
+
+    /// The header authenticating the request, with a token read from the
+    /// environment. `GITHUB_TOKEN` (or `GH_TOKEN`) and `GITLAB_TOKEN` are only
+    /// sent to github.com and gitlab.com; other hosts get
+    /// `GH_ENTERPRISE_TOKEN` or `GITLAB_TOKEN` only when `GH_HOST` or
+    /// `GITLAB_HOST` names them, so a repository cannot point `origin`
+    /// elsewhere to collect the user's token.
+    pub fn auth_header(&self) -> Option<(&'static str, String)> {
+        self.auth_header_from(|var| std::env::var(var).ok())
+    }
+
+    /// [`Self::auth_header`], reading variables through `env`.
+    pub fn auth_header_from(
+        &self,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<(&'static str, String)> {
+        let token = |vars: &[&str]| vars.iter().find_map(|var| env(var));
+        let names_host =
+            |var: &str, host: &str| env(var).is_some_and(|h| h.trim().eq_ignore_ascii_case(host));
+        match self {
+            Self::GitHub { host, .. } => {
+                let token = if host == "github.com" {
+                    token(&["GITHUB_TOKEN", "GH_TOKEN"])
+                } else if names_host("GH_HOST", host) {
+                    token(&["GH_ENTERPRISE_TOKEN"])
+                } else {
+                    None
+                };
+                token.map(|t| ("Authorization", format!("Bearer {t}")))
+            }
+            Self::GitLab { host, .. } => {
+                if host == "gitlab.com" || names_host("GITLAB_HOST", host) {
+                    token(&["GITLAB_TOKEN"]).map(|t| ("PRIVATE-TOKEN", t))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// The REST endpoint returning the issue.
+    pub fn api_url(&self) -> String {
+        match self {
+            Self::GitHub {
+                host,
+                owner,
+                repo,
+                number,
+            } => {
+                let api = if host == "github.com" {
+                    "https://api.github.com".to_string()
+                } else {
+                    format!("https://{host}/api/v3")
+                };
+                format!("{api}/repos/{owner}/{repo}/issues/{number}")
+            }
+            Self::GitLab {
+                host,
+                project,
+                number,
+            } => format!(
+                "https://{host}/api/v4/projects/{}/issues/{number}",
+                project.replace('/', "%2F")
+            ),
+        }
+    }
+}
+
+/// Splits a clone URL (`https://host/path.git`, `ssh://git@host/path.git`
+/// or `git@host:path.git`) into its host and project path.
+fn split_remote(remote: &str) -> Option<(&str, &str)> {
+    let (host, path) = match remote.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        None => remote.split_once(':')?,
+    };
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    (!host.is_empty() && !path.is_empty()).then_some((host, path))
+}
+
+/// Resolves `spec` (see [`IssueRef::parse`]) against the `origin` remote of
+/// the repository at `repo`, and fetches the issue.
+pub fn load(spec: &str, repo: &Path) -> Result<Issue> {
+    #[cfg(feature = "git")]
+    let remote = crate::engine::git::remote_url(repo, "origin").ok();
+    #[cfg(not(feature = "git"))]
+    let remote: Option<String> = {
+        let _ = repo;
+        None
+    };
+    fetch(&IssueRef::parse(spec, remote.as_deref())?)
+}
+
+#[cfg(feature = "issues")]
+#[derive(serde::Deserialize)]
+struct GitHubIssue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    html_url: String,
+    labels: Vec<GitHubLabel>,
+}
+
+#[cfg(feature = "issues")]
+#[derive(serde::Deserialize)]
+struct GitHubLabel {
+    name: String,
+}
+
+#[cfg(feature = "issues")]
+#[derive(serde::Deserialize)]
+struct GitLabIssue {
+    iid: u64,
+    title: String,
+    description: Option<String>,
+    web_url: String,
+    labels: Vec<String>,
+}
+
+/// Fetches the issue, authenticating with [`IssueRef::auth_header`].
+#[cfg(feature = "issues")]
+pub fn fetch(issue: &IssueRef) -> Result<Issue> {
+    let url = issue.api_url();
+    let mut request = ureq::get(&url).set("User-Agent", "code2prompt");
+    if let IssueRef::GitHub { .. } = issue {
+        request = request.set("Accept", "application/vnd.github+json");
+    }
+    if let Some((name, value)) = issue.auth_header() {
+        request = request.set(name, &value);
+    }
+    let response = request
+        .call()
+        .with_context(|| format!("Request to {url} failed"))?;
+
+    Ok(match issue {
+        IssueRef::GitHub { .. } => {
+            let issue: GitHubIssue = response.into_json().context("Invalid issue response")?;
+            Issue {
+                number: issue.number,
+                title: issue.title,
+                body: issue.body.unwrap_or_default(),
+                url: issue.html_url,
+                labels: issue.labels.into_iter().map(|l| l.name).collect(),
+            }
+        }
+        IssueRef::GitLab { .. } => {
+            let issue: GitLabIssue = response.into_json().context("Invalid issue response")?;
+            Issue {
+                number: issue.iid,
+                title: issue.title,
+                body: issue.description.unwrap_or_default(),
+                url: issue.web_url,
+                labels: issue.labels,
+            }
+        }
+    })
+}
+
+#[cfg(not(feature = "issues"))]
+pub fn fetch(_issue: &IssueRef) -> Result<Issue> {
+    anyhow::bail!("--issue requires code2prompt to be built with the `issues` feature")
+}
@@ -0,0 +1,83 @@
+//! Sends prompts to an OpenAI-compatible `/chat/completions` endpoint
+//! (`commit --send`), such as OpenAI itself or a local model served by
+//! Ollama or llama.cpp.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// The `[llm]` section of the config file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmSettings {
+    /// Base URL of an OpenAI-compatible API, e.g. `http://localhost:11434/v1`.
+    pub endpoint: String,
+    pub model: String,
+    /// Environment variable holding the API key, if the endpoint needs one.
+    pub api_key_env: Option<String>,
+}
+
+impl Default for LlmSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: "https://api.openai.com/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key_env: Some("OPENAI_API_KEY".to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "llm")]
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[cfg(feature = "llm")]
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[cfg(feature = "llm")]
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+/// Sends `prompt` as a single user message and returns the reply.
+#[cfg(feature = "llm")]
+pub fn complete(settings: &LlmSettings, prompt: &str) -> Result<String> {
+    use anyhow::Context;
+
+    let url = format!(
+        "{}/chat/completions",
+        settings.endpoint.trim_end_matches('/')
+    );
+    let mut request = ureq::post(&url);
+    if let Some(key) = settings
+        .api_key_env
+        .as_ref()
+        .and_then(|var| std::env::var(var).ok())
+    {
+        request = request.set("Authorization", &format!("Bearer {key}"));
+    }
+    let response: ChatResponse = request
+        .send_json(serde_json::json!({
+            "model": settings.model,
+            "messages": [{ "role": "user", "content": prompt }],
+        }))
+        .with_context(|| format!("Chat request to {url} failed"))?
+        .into_json()
+        .context("Invalid chat completion response")?;
+    response
+        .choices
+        .into_iter()
+        .next()
+        .map(|choice| choice.message.content.trim().to_string())
+        .context("The chat completion had no choices")
+}
+
+#[cfg(not(feature = "llm"))]
+pub fn complete(_settings: &LlmSettings, _prompt: &str) -> Result<String> {
+    anyhow::bail!("--send requires code2prompt to be built with the `llm` feature")
+}
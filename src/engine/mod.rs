@@ -1,11 +1,32 @@
 pub mod cache;
+pub mod chunk;
+pub mod claude_xml;
+pub mod codeowners;
 pub mod config;
 pub mod config_file;
+pub mod deps;
+pub mod diff_trim;
+pub mod embeddings;
+pub mod encoding;
+pub mod file_id;
 pub mod filter;
 pub mod git;
+pub mod grep;
+pub mod history;
+pub mod hooks;
+pub mod issue;
+pub mod llm;
 pub mod model;
+pub mod notebook;
+pub mod presets;
+pub mod priority;
+pub mod sample;
 pub mod session;
+pub mod symbols;
+pub mod tags;
+pub mod test_files;
 pub mod token;
 pub mod token_map;
+pub mod transform;
 pub mod traverse;
 pub mod utils;
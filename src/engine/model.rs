@@ -4,6 +4,7 @@ use std::{collections::BTreeMap, path::PathBuf, time::SystemTime};
 
 use serde::{Deserialize, Serialize};
 
+use crate::engine::symbols::FileSymbols;
 use crate::ui::tree_arena::PathInfo;
 
 /// The complete, serializable context passed to the template engine.
@@ -11,22 +12,219 @@ use crate::ui::tree_arena::PathInfo;
 pub struct TemplateContext {
     pub absolute_code_path: String,
     pub files: Vec<FileContext>,
+    /// The same files, grouped by their top-level directory.
+    pub file_groups: Vec<FileGroup>,
     pub source_tree: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub git_diff: Option<String>,
+    /// The same changes as `git_diff`, one entry per changed file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_diff_files: Option<Vec<DiffFile>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub git_diff_branch: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub git_log_branch: Option<String>,
+    /// Extra instructions placed before the templated body (`--prepend`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_prelude: Option<String>,
+    /// Extra instructions placed after the templated body (`--append`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_epilogue: Option<String>,
+    /// Public symbols of every file in the repository (`--symbols`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol_index: Option<Vec<FileSymbols>>,
+    /// The issue passed with `--issue`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue: Option<Issue>,
+    /// The checked-out branch, unless HEAD is detached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// The full SHA of the HEAD commit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit_sha: Option<String>,
+    /// The URL of the `origin` remote, without credentials.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_remote_url: Option<String>,
+    /// The repository's name on `origin`, or its directory's name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo_name: Option<String>,
+    /// When the prompt was built, as an RFC 3339 UTC timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
 }
 
-/// Represents a single file within the template context.
+/// One file's changes in the working tree, staged or not.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffFile {
+    /// Relative to the repository root.
+    pub path: String,
+    /// added, deleted, modified, renamed, copied or typechange.
+    pub status: String,
+    /// Each hunk's header and lines, as in a patch.
+    pub hunks: Vec<String>,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// A GitHub or GitLab issue fetched for `--issue`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    /// Empty if the issue has no description.
+    pub body: String,
+    pub url: String,
+    pub labels: Vec<String>,
+}
+
+/// How a rendered prompt's tokens split between its parts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TokenBreakdown {
+    /// The files' contents, fences included.
+    pub files: usize,
+    pub source_tree: usize,
+    /// The working tree diff and the diff between branches.
+    pub git_diff: usize,
+    pub git_log: usize,
+    /// Everything else: the template's own text, file headers, the symbol
+    /// index, the issue and any prepended or appended instructions.
+    pub template: usize,
+}
+
+impl TokenBreakdown {
+    pub fn total(&self) -> usize {
+        self.files + self.source_tree + self.git_diff + self.git_log + self.template
+    }
+}
+
+/// A file as written by `-F rag-jsonl`, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RagRecord {
+    pub path: String,
+    /// The code fence language of the file's extension.
+    pub language: String,
+    pub tokens: usize,
+    pub content: String,
+    /// Hex SHA-256 of `content`.
+    pub sha256: String,
+}
+
+/// What a run scanned and put in the prompt, printed after it and included
+/// in the JSON output as `summary`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RunSummary {
+    /// Files the walk visited, after ignore files and hidden-file rules.
+    pub files_scanned: usize,
+    pub files_included: usize,
+    pub skipped: SkippedFiles,
+    /// Bytes of file content read from disk; cache hits read none.
+    pub bytes_read: u64,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    pub scan_duration_ms: u64,
+    /// Paths the scan could not read, see `Code2PromptSession::scan_errors`.
+    pub scan_errors: usize,
+    pub tokens: usize,
+    /// The tokens of each part of the prompt, when they were counted.
+    pub sections: Option<TokenBreakdown>,
+}
+
+impl RunSummary {
+    /// The share of cache lookups that hit, if the cache was used.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let lookups = self.cache_hits + self.cache_misses;
+        (lookups > 0).then(|| self.cache_hits as f64 / lookups as f64)
+    }
+}
+
+/// A path the scan could not read, so the prompt is missing it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScanError {
+    /// Relative to the scanned directory, when the error names a path.
+    pub path: Option<String>,
+    pub kind: ScanErrorKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    /// A followed symlink whose target does not exist.
+    BrokenSymlink,
+    /// Deleted or moved while the scan was running.
+    Vanished,
+    /// A followed symlink pointing to one of its own ancestors.
+    SymlinkLoop,
+    Other,
+}
+
+impl std::fmt::Display for ScanErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::PermissionDenied => "permission denied",
+            Self::BrokenSymlink => "broken symlink",
+            Self::Vanished => "vanished",
+            Self::SymlinkLoop => "symlink loop",
+            Self::Other => "error",
+        })
+    }
+}
+
+/// Scanned files left out of the prompt, by reason.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SkippedFiles {
+    /// Not matching the include patterns, or matching an exclude pattern.
+    pub filtered: usize,
+    pub empty: usize,
+    /// Larger than 1 MiB.
+    pub too_large: usize,
+    pub unreadable: usize,
+    pub no_grep_match: usize,
+    pub over_budget: usize,
+    /// Not in the `--max-files` sample.
+    pub not_sampled: usize,
+    /// Left out in the selector or by a saved selection.
+    pub not_selected: usize,
+}
+
+impl SkippedFiles {
+    pub fn total(&self) -> usize {
+        self.filtered
+            + self.empty
+            + self.too_large
+            + self.unreadable
+            + self.no_grep_match
+            + self.over_budget
+            + self.not_sampled
+            + self.not_selected
+    }
+}
+
+/// The files under one top-level directory of the repository, in prompt order.
 #[derive(Debug, Serialize)]
+pub struct FileGroup {
+    /// The directory, relative to the repository root; `.` for the files
+    /// at the root.
+    pub dir: String,
+    pub files: Vec<FileContext>,
+}
+
+/// Represents a single file within the template context.
+#[derive(Debug, Clone, Serialize)]
 pub struct FileContext {
     pub path: String,
+    /// A short ID derived from the relative path, e.g. `F1a2b3c`.
+    pub id: String,
     pub extension: String,
     pub code: String,
     pub token_count: Option<usize>,
+    /// The file's tags, from `.code2prompt/tags.toml`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The file's owners, from the repository's `CODEOWNERS` file.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
@@ -65,8 +263,26 @@ pub struct TokenMapEntry {
     pub metadata: EntryMetadata,
 }
 
+/// Where a file's content in the prompt was read from (`--provenance`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentSource {
+    /// The scan cache (`--cache`).
+    Cache,
+    Disk,
+}
+
+impl std::fmt::Display for ContentSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContentSource::Cache => write!(f, "cache"),
+            ContentSource::Disk => write!(f, "disk"),
+        }
+    }
+}
+
 /// Holds all relevant information about a processed file.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ProcessedEntry {
     pub path: PathBuf,
     pub relative_path: PathBuf,
@@ -75,6 +291,11 @@ pub struct ProcessedEntry {
     pub extension: Option<String>,
     pub token_count: Option<usize>,
     pub mtime: Option<SystemTime>,
+    /// Where `code` was read from, once it is loaded.
+    pub source: Option<ContentSource>,
+    /// The SHA-256 of the file's text, when it was read from disk with the
+    /// scan cache or `--provenance` on, or found in the cache.
+    pub sha256: Option<[u8; 32]>,
 }
 
 #[cfg(feature = "tui")]
@@ -95,4 +316,7 @@ impl PathInfo for ProcessedEntry {
     fn token_count(&self) -> Option<usize> {
         self.token_count
     }
+    fn byte_size(&self) -> Option<u64> {
+        std::fs::metadata(&self.path).ok().map(|m| m.len())
+    }
 }
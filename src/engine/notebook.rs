@@ -0,0 +1,166 @@
+//! Cleaning of Jupyter notebooks (`.ipynb`) into a compact, markdown-like form.
+//!
+//! Raw notebook JSON is dominated by output payloads, base64 images and
+//! metadata. This module keeps the cell sources (and optionally the outputs)
+//! using the "percent" cell markers understood by jupytext and most editors.
+
+use serde_json::Value;
+
+/// Options controlling how notebooks are cleaned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotebookOptions {
+    /// Convert notebooks to the markdown-like cell representation.
+    pub convert: bool,
+    /// Drop all output cells.
+    pub drop_outputs: bool,
+    /// Drop base64-encoded images from outputs.
+    pub drop_images: bool,
+    /// Drop `In[n]` / `Out[n]` execution counts.
+    pub drop_execution_counts: bool,
+}
+
+impl NotebookOptions {
+    /// Returns `true` if notebooks should be converted at all.
+    /// Any of the drop options implies conversion.
+    pub fn is_enabled(&self) -> bool {
+        self.convert || self.drop_outputs || self.drop_images || self.drop_execution_counts
+    }
+}
+
+/// Returns `true` if the given extension denotes a Jupyter notebook.
+pub fn is_notebook(ext: Option<&str>) -> bool {
+    ext.is_some_and(|e| e.eq_ignore_ascii_case("ipynb"))
+}
+
+/// Whether `text`, a notebook's content as prepared for the prompt, is in the
+/// converted form rather than the raw JSON (conversion off or failed). A
+/// notebook is a JSON object, while the converted form opens with a cell
+/// header or is empty.
+pub fn is_converted(text: &str) -> bool {
+    !text
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with('{')
+}
+
+/// Converts raw notebook JSON into the markdown-like representation.
+///
+/// Returns `None` if the content is not a valid notebook, in which case the
+/// caller should fall back to the raw content.
+pub fn clean_notebook(raw: &str, opts: &NotebookOptions) -> Option<String> {
+    let nb: Value = serde_json::from_str(raw).ok()?;
+    let cells = nb.get("cells")?.as_array()?;
+
+    let mut out = String::new();
+    for cell in cells {
+        let source = join_text(cell.get("source"));
+        match cell.get("cell_type").and_then(Value::as_str) {
+            Some("markdown") => {
+                push_header(&mut out, "# %% [markdown]");
+                push_block(&mut out, &source);
+            }
+            Some("code") => {
+                let count = execution_count(cell, opts);
+                match count {
+                    Some(n) => push_header(&mut out, &format!("# %% In[{n}]")),
+                    None => push_header(&mut out, "# %%"),
+                }
+                push_block(&mut out, &source);
+
+                if !opts.drop_outputs {
+                    push_outputs(&mut out, cell, count, opts);
+                }
+            }
+            Some(other) => {
+                push_header(&mut out, &format!("# %% [{other}]"));
+                push_block(&mut out, &source);
+            }
+            None => {}
+        }
+    }
+    Some(out)
+}
+
+fn execution_count(cell: &Value, opts: &NotebookOptions) -> Option<u64> {
+    if opts.drop_execution_counts {
+        return None;
+    }
+    cell.get("execution_count").and_then(Value::as_u64)
+}
+
+fn push_outputs(out: &mut String, cell: &Value, count: Option<u64>, opts: &NotebookOptions) {
+    let Some(outputs) = cell.get("outputs").and_then(Value::as_array) else {
+        return;
+    };
+    for output in outputs {
+        let text = match output.get("output_type").and_then(Value::as_str) {
+            Some("stream") => join_text(output.get("text")),
+            Some("execute_result") | Some("display_data") => {
+                render_mime_bundle(output.get("data"), opts)
+            }
+            Some("error") => {
+                let name = output
+                    .get("ename")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Error");
+                let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+                format!("{name}: {value}")
+            }
+            _ => continue,
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+        match count {
+            Some(n) => push_header(out, &format!("# Out[{n}]:")),
+            None => push_header(out, "# Out:"),
+        }
+        push_block(out, &text);
+    }
+}
+
+fn render_mime_bundle(data: Option<&Value>, opts: &NotebookOptions) -> String {
+    let Some(data) = data.and_then(Value::as_object) else {
+        return String::new();
+    };
+    let mut parts = Vec::new();
+    if let Some(text) = data.get("text/plain") {
+        parts.push(join_text(Some(text)));
+    }
+    for (mime, payload) in data {
+        if !mime.starts_with("image/") {
+            continue;
+        }
+        if opts.drop_images {
+            parts.push(format!("[{mime} omitted]"));
+        } else {
+            parts.push(format!(
+                "data:{mime};base64,{}",
+                join_text(Some(payload)).trim()
+            ));
+        }
+    }
+    parts.join("\n")
+}
+
+/// Notebook text fields are either a single string or a list of lines.
+fn join_text(v: Option<&Value>) -> String {
+    match v {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+fn push_header(out: &mut String, header: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(header);
+    out.push('\n');
+}
+
+fn push_block(out: &mut String, text: &str) {
+    out.push_str(text.trim_end());
+    out.push('\n');
+}
@@ -0,0 +1,358 @@
+//! Curated exclude lists (`--preset`) for the code most prompts can do
+//! without: tests and fixtures, vendored dependencies, and everything that is
+//! not source code. The `ci-context` preset works the other way around: it
+//! includes only the files that decide how CI builds the code.
+//!
+//! Globs are chosen per ecosystem, detected from the manifests at the
+//! repository root; when none is recognised, the globs of all ecosystems
+//! apply. Detected ecosystems also contribute default excludes and code fence
+//! languages to every scan, unless `--no-auto-detect` is given.
+
+use std::path::Path;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum Preset {
+    /// Tests, fixtures, snapshots and test data.
+    NoTests,
+    /// Vendored and installed dependencies, and minified bundles.
+    NoVendor,
+    /// Source code only: no tests, vendored code, docs, examples, benches,
+    /// lockfiles or build output.
+    SrcOnly,
+    /// Only CI workflows, Dockerfiles, Makefiles and build scripts, hidden
+    /// or excluded as they may be.
+    CiContext,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ecosystem {
+    Rust,
+    JavaScript,
+    Python,
+    Go,
+}
+
+impl std::fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Ecosystem::Rust => "Rust",
+            Ecosystem::JavaScript => "JavaScript",
+            Ecosystem::Python => "Python",
+            Ecosystem::Go => "Go",
+        })
+    }
+}
+
+const ALL_ECOSYSTEMS: &[Ecosystem] = &[
+    Ecosystem::Rust,
+    Ecosystem::JavaScript,
+    Ecosystem::Python,
+    Ecosystem::Go,
+];
+
+/// Manifests marking each ecosystem.
+const MANIFESTS: &[(Ecosystem, &[&str])] = &[
+    (Ecosystem::Rust, &["Cargo.toml"]),
+    (Ecosystem::JavaScript, &["package.json", "deno.json"]),
+    (
+        Ecosystem::Python,
+        &[
+            "pyproject.toml",
+            "setup.py",
+            "setup.cfg",
+            "requirements.txt",
+            "Pipfile",
+        ],
+    ),
+    (Ecosystem::Go, &["go.mod"]),
+];
+
+const COMMON_TESTS: &[&str] = &[
+    "**/tests/**",
+    "**/test/**",
+    "**/fixtures/**",
+    "**/__snapshots__/**",
+];
+
+const COMMON_VENDOR: &[&str] = &["**/vendor/**", "**/third_party/**", "**/third-party/**"];
+
+const COMMON_NON_SOURCE: &[&str] = &[
+    "**/docs/**",
+    "**/doc/**",
+    "**/examples/**",
+    "**/benches/**",
+    "**/.github/**",
+    "**/*.md",
+    "**/dist/**",
+    "**/build/**",
+];
+
+/// Per-ecosystem additions to the common globs.
+const ECOSYSTEM_GLOBS: &[(Preset, Ecosystem, &[&str])] = &[
+    (
+        Preset::NoTests,
+        Ecosystem::Rust,
+        &["**/*_tests.rs", "**/tests.rs"],
+    ),
+    (
+        Preset::NoTests,
+        Ecosystem::JavaScript,
+        &[
+            "**/*.test.*",
+            "**/*.spec.*",
+            "**/__tests__/**",
+            "**/__mocks__/**",
+            "**/__fixtures__/**",
+            "**/cypress/**",
+            "**/e2e/**",
+        ],
+    ),
+    (
+        Preset::NoTests,
+        Ecosystem::Python,
+        &["**/test_*.py", "**/*_test.py", "**/conftest.py"],
+    ),
+    (
+        Preset::NoTests,
+        Ecosystem::Go,
+        &["**/*_test.go", "**/testdata/**"],
+    ),
+    (Preset::NoVendor, Ecosystem::Rust, &["**/target/**"]),
+    (
+        Preset::NoVendor,
+        Ecosystem::JavaScript,
+        &[
+            "**/node_modules/**",
+            "**/bower_components/**",
+            "**/*.min.js",
+            "**/*.min.css",
+        ],
+    ),
+    (
+        Preset::NoVendor,
+        Ecosystem::Python,
+        &[
+            "**/.venv/**",
+            "**/venv/**",
+            "**/site-packages/**",
+            "**/__pycache__/**",
+            "**/*.egg-info/**",
+        ],
+    ),
+    (Preset::SrcOnly, Ecosystem::Rust, &["**/Cargo.lock"]),
+    (
+        Preset::SrcOnly,
+        Ecosystem::JavaScript,
+        &[
+            "**/package-lock.json",
+            "**/yarn.lock",
+            "**/pnpm-lock.yaml",
+            "**/coverage/**",
+            "**/storybook-static/**",
+        ],
+    ),
+    (
+        Preset::SrcOnly,
+        Ecosystem::Python,
+        &[
+            "**/poetry.lock",
+            "**/uv.lock",
+            "**/Pipfile.lock",
+            "**/htmlcov/**",
+        ],
+    ),
+    (Preset::SrcOnly, Ecosystem::Go, &["**/go.sum"]),
+];
+
+/// The ecosystems whose manifests are present in `root`.
+pub fn detect_ecosystems(root: &Path) -> Vec<Ecosystem> {
+    MANIFESTS
+        .iter()
+        .filter(|(_, files)| files.iter().any(|f| root.join(f).is_file()))
+        .map(|(eco, _)| *eco)
+        .collect()
+}
+
+/// Whether `file_name` is the manifest of one of the known ecosystems.
+pub fn is_manifest(file_name: &str) -> bool {
+    MANIFESTS
+        .iter()
+        .any(|(_, files)| files.contains(&file_name))
+}
+
+/// The exclude globs of `preset` for the given ecosystems (all of them when
+/// `ecosystems` is empty).
+pub fn preset_globs(preset: Preset, ecosystems: &[Ecosystem]) -> Vec<&'static str> {
+    let ecosystems = if ecosystems.is_empty() {
+        ALL_ECOSYSTEMS
+    } else {
+        ecosystems
+    };
+    // `src-only` builds on the other two presets.
+    let layers: &[Preset] = match preset {
+        Preset::NoTests => &[Preset::NoTests],
+        Preset::NoVendor => &[Preset::NoVendor],
+        Preset::SrcOnly => &[Preset::NoTests, Preset::NoVendor, Preset::SrcOnly],
+        // Includes rather than excludes, see `CI_CONTEXT_GLOBS`.
+        Preset::CiContext => &[],
+    };
+
+    let mut globs: Vec<&'static str> = Vec::new();
+    for layer in layers {
+        globs.extend_from_slice(match layer {
+            Preset::NoTests => COMMON_TESTS,
+            Preset::NoVendor => COMMON_VENDOR,
+            Preset::SrcOnly => COMMON_NON_SOURCE,
+            Preset::CiContext => &[],
+        });
+        for (p, eco, extra) in ECOSYSTEM_GLOBS {
+            if p == layer && ecosystems.contains(eco) {
+                globs.extend_from_slice(extra);
+            }
+        }
+    }
+    globs
+}
+
+/// Expands `presets` into exclude globs for the repository at `root`.
+pub fn expand_presets(presets: &[Preset], root: &Path) -> Vec<String> {
+    if presets.is_empty() {
+        return Vec::new();
+    }
+    let ecosystems = detect_ecosystems(root);
+    let mut globs: Vec<String> = Vec::new();
+    for preset in presets {
+        for glob in preset_globs(*preset, &ecosystems) {
+            if !globs.iter().any(|g| g == glob) {
+                globs.push(glob.to_string());
+            }
+        }
+    }
+    globs
+}
+
+/// The files `ci-context` includes.
+pub const CI_CONTEXT_GLOBS: &[&str] = &[
+    // CI pipelines
+    ".github/workflows/**",
+    ".github/actions/**",
+    ".gitlab-ci.yml",
+    ".gitlab/ci/**",
+    ".circleci/**",
+    ".buildkite/**",
+    ".travis.yml",
+    "azure-pipelines.yml",
+    "**/Jenkinsfile",
+    // Containers
+    "**/Dockerfile",
+    "**/Dockerfile.*",
+    "**/*.dockerfile",
+    "**/.dockerignore",
+    "**/docker-compose*.yml",
+    "**/docker-compose*.yaml",
+    "**/compose.yml",
+    "**/compose.yaml",
+    // Make and task runners
+    "**/Makefile",
+    "**/GNUmakefile",
+    "**/*.mk",
+    "**/justfile",
+    "**/Justfile",
+    "**/Taskfile.yml",
+    // Build scripts
+    "**/build.rs",
+    "**/build.sh",
+    "**/build.gradle",
+    "**/build.gradle.kts",
+    "**/CMakeLists.txt",
+    "**/scripts/ci*",
+    "**/scripts/build*",
+];
+
+/// The hidden directories `ci-context` descends into for its files.
+pub const CI_CONTEXT_HIDDEN_DIRS: &[&str] = &[
+    ".github",
+    ".gitlab",
+    ".circleci",
+    ".buildkite",
+    ".devcontainer",
+];
+
+// ────────────────────────────────────────────────────────────
+//  Auto-detected defaults
+// ────────────────────────────────────────────────────────────
+
+/// Build output, installed dependencies and lockfiles, excluded by default
+/// in repositories of each ecosystem.
+const AUTO_EXCLUDES: &[(Ecosystem, &[&str])] = &[
+    (Ecosystem::Rust, &["**/target/**", "**/Cargo.lock"]),
+    (
+        Ecosystem::JavaScript,
+        &[
+            "**/node_modules/**",
+            "**/dist/**",
+            "**/.next/**",
+            "**/coverage/**",
+            "**/package-lock.json",
+            "**/yarn.lock",
+            "**/pnpm-lock.yaml",
+        ],
+    ),
+    (
+        Ecosystem::Python,
+        &[
+            "**/__pycache__/**",
+            "**/.venv/**",
+            "**/venv/**",
+            "**/*.egg-info/**",
+            "**/.pytest_cache/**",
+            "**/.mypy_cache/**",
+            "**/poetry.lock",
+            "**/uv.lock",
+        ],
+    ),
+    (Ecosystem::Go, &["**/vendor/**", "**/go.sum"]),
+];
+
+/// Extension → fence language mappings the built-in table lacks.
+const AUTO_FENCE_LANGUAGES: &[(Ecosystem, &[(&str, &str)])] = &[
+    (Ecosystem::Rust, &[("lock", "toml")]),
+    (
+        Ecosystem::JavaScript,
+        &[
+            ("cjs", "javascript"),
+            ("mts", "typescript"),
+            ("cts", "typescript"),
+            ("svelte", "svelte"),
+        ],
+    ),
+    (
+        Ecosystem::Python,
+        &[("pyi", "python"), ("pyx", "cython"), ("pxd", "cython")],
+    ),
+    (Ecosystem::Go, &[("mod", "go"), ("sum", "text")]),
+];
+
+/// The default exclude globs for the detected ecosystems.
+pub fn auto_excludes(ecosystems: &[Ecosystem]) -> Vec<String> {
+    AUTO_EXCLUDES
+        .iter()
+        .filter(|(eco, _)| ecosystems.contains(eco))
+        .flat_map(|(_, globs)| globs.iter().map(|g| g.to_string()))
+        .collect()
+}
+
+/// The fence language mappings for the detected ecosystems.
+pub fn auto_fence_languages(ecosystems: &[Ecosystem]) -> Vec<(String, String)> {
+    AUTO_FENCE_LANGUAGES
+        .iter()
+        .filter(|(eco, _)| ecosystems.contains(eco))
+        .flat_map(|(_, langs)| {
+            langs
+                .iter()
+                .map(|(ext, lang)| (ext.to_string(), lang.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,201 @@
+//! Per-glob priority weights.
+//!
+//! Files matching higher-weighted globs are placed first in the prompt and are
+//! the last to be dropped when trimming to a token budget. Files matching no
+//! rule have a weight of 0; a file matching several rules takes the highest.
+//!
+//! READMEs, manifests and docs overviews are placed before other files of
+//! their weight, unless `--no-priority-docs` is given. An `--order-file`
+//! overrides all of this: the files it lists come first, in its order.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use glob::Pattern;
+
+use crate::common::{hash::HashMap, path::to_fwd_slash};
+use crate::engine::{
+    model::ProcessedEntry,
+    presets::is_manifest,
+    token::{TokenizerChoice, estimate_tokens},
+};
+
+/// A glob and the weight given to the files it matches.
+#[derive(Debug, Clone)]
+pub struct PriorityRule {
+    pub pattern: Pattern,
+    pub weight: i32,
+}
+
+impl PriorityRule {
+    pub fn new(glob: &str, weight: i32) -> Result<Self> {
+        Ok(Self {
+            pattern: Pattern::new(glob)
+                .with_context(|| format!("Invalid glob pattern: '{glob}'"))?,
+            weight,
+        })
+    }
+}
+
+/// Parses a `GLOB=WEIGHT` argument, e.g. `src/core/**=10`.
+pub fn parse_priority(s: &str) -> Result<(String, i32)> {
+    let (glob, weight) = s
+        .rsplit_once('=')
+        .ok_or_else(|| anyhow!("Priority must be in GLOB=WEIGHT format"))?;
+    let weight = weight
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid priority weight: '{weight}'"))?;
+    Ok((glob.trim().to_owned(), weight))
+}
+
+/// Returns the weight of a relative path: the highest matching rule, or 0.
+pub fn weight_of(rules: &[PriorityRule], rel_path: &str) -> i32 {
+    rules
+        .iter()
+        .filter(|r| r.pattern.matches(rel_path))
+        .map(|r| r.weight)
+        .max()
+        .unwrap_or(0)
+}
+
+fn entry_weight(rules: &[PriorityRule], entry: &ProcessedEntry) -> i32 {
+    weight_of(rules, &to_fwd_slash(&entry.relative_path))
+}
+
+/// Stably orders entries by descending weight, so the existing order (e.g.
+/// from `--sort`) is kept among files of equal weight.
+pub fn order_by_priority(entries: &mut [ProcessedEntry], rules: &[PriorityRule]) {
+    if rules.is_empty() {
+        return;
+    }
+    entries.sort_by_cached_key(|e| std::cmp::Reverse(entry_weight(rules, e)));
+}
+
+/// A file's token count, estimated from its code if it was not counted.
+pub(crate) fn file_tokens(entry: &ProcessedEntry, tokenizer: TokenizerChoice) -> usize {
+    entry
+        .token_count
+        .or_else(|| entry.code.as_deref().map(|c| estimate_tokens(c, tokenizer)))
+        .unwrap_or(0)
+}
+
+/// Drops the lowest-priority files until the files' tokens fit in `budget`.
+/// Among files of equal weight, those placed last are dropped first. Files
+/// without a token count are estimated from their code.
+///
+/// Returns the number of files dropped.
+pub fn trim_to_budget(
+    entries: &mut Vec<ProcessedEntry>,
+    rules: &[PriorityRule],
+    budget: usize,
+    tokenizer: TokenizerChoice,
+) -> usize {
+    let tokens = |e: &ProcessedEntry| file_tokens(e, tokenizer);
+    let mut total: usize = entries.iter().filter(|e| e.is_file).map(tokens).sum();
+    if total <= budget {
+        return 0;
+    }
+
+    // Visit files from the lowest weight, and from the end of the list within a weight.
+    let mut order: Vec<usize> = (0..entries.len()).filter(|&i| entries[i].is_file).collect();
+    order.sort_by_key(|&i| (entry_weight(rules, &entries[i]), std::cmp::Reverse(i)));
+
+    let mut drop = vec![false; entries.len()];
+    let mut dropped = 0;
+    for i in order {
+        if total <= budget {
+            break;
+        }
+        total -= tokens(&entries[i]);
+        drop[i] = true;
+        dropped += 1;
+    }
+
+    let mut idx = 0;
+    entries.retain(|_| {
+        let keep = !drop[idx];
+        idx += 1;
+        keep
+    });
+    dropped
+}
+
+/// The relative paths listed in an `--order-file`, one per line. Blank lines
+/// and lines starting with `#` are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileOrder(pub Vec<String>);
+
+impl FileOrder {
+    pub fn parse(text: &str) -> Self {
+        Self(
+            text.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(|l| {
+                    let l = l.replace('\\', "/");
+                    l.strip_prefix("./").map(str::to_owned).unwrap_or(l)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read order file: {}", path.display()))?;
+        Ok(Self::parse(&text))
+    }
+}
+
+/// Stably moves the files listed in `order` to the front, in that order.
+/// Paths that match no file are ignored.
+pub fn order_by_list(entries: &mut [ProcessedEntry], order: &[String]) {
+    if order.is_empty() {
+        return;
+    }
+    let mut rank = HashMap::<&str, usize>::default();
+    for (i, path) in order.iter().enumerate() {
+        rank.entry(path.as_str()).or_insert(i);
+    }
+    entries.sort_by_cached_key(|e| {
+        rank.get(to_fwd_slash(&e.relative_path).as_str())
+            .copied()
+            .unwrap_or(usize::MAX)
+    });
+}
+
+/// Where an orientation file goes among the others: READMEs, then manifests
+/// (`Cargo.toml`, `package.json`, ...), then overview pages under `docs/`,
+/// each from the shallowest. `None` for any other file.
+pub fn orientation_rank(rel_path: &str) -> Option<(u8, usize)> {
+    let name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+    let stem = name.split('.').next().unwrap_or(name).to_ascii_lowercase();
+    let kind = if stem.starts_with("readme") {
+        0
+    } else if is_manifest(name) {
+        1
+    } else if rel_path.starts_with("docs/")
+        && matches!(
+            stem.as_str(),
+            "index" | "overview" | "intro" | "introduction"
+        )
+    {
+        2
+    } else {
+        return None;
+    };
+    Some((kind, rel_path.matches('/').count()))
+}
+
+/// Stably moves orientation files (see [`orientation_rank`]) before the
+/// other files of the same weight.
+pub fn order_docs_first(entries: &mut [ProcessedEntry], rules: &[PriorityRule]) {
+    entries.sort_by_cached_key(|e| {
+        let rank = orientation_rank(&to_fwd_slash(&e.relative_path));
+        (
+            std::cmp::Reverse(entry_weight(rules, e)),
+            rank.is_none(),
+            rank,
+        )
+    });
+}
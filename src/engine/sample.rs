@@ -0,0 +1,119 @@
+//! `--max-files`: keeps a sample of the files when there are more than
+//! wanted, for a representative slice of a large repository.
+
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+
+use crate::common::path::to_fwd_slash;
+use crate::engine::model::ProcessedEntry;
+use crate::engine::priority::file_tokens;
+use crate::engine::token::TokenizerChoice;
+
+/// Which files `--max-files` keeps.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Default)]
+pub enum SampleStrategy {
+    /// Files from every top-level directory, in proportion to its file
+    /// count, spread evenly over its paths
+    #[default]
+    Stratified,
+    /// The files with the most tokens
+    LargestFirst,
+    /// The files with the fewest tokens
+    SmallestFirst,
+}
+
+/// Drops files until at most `max` remain, keeping the order of the rest.
+///
+/// Returns the number of files dropped.
+pub fn sample_files(
+    entries: &mut Vec<ProcessedEntry>,
+    max: usize,
+    strategy: SampleStrategy,
+    tokenizer: TokenizerChoice,
+) -> usize {
+    let files: Vec<usize> = (0..entries.len()).filter(|&i| entries[i].is_file).collect();
+    if files.len() <= max {
+        return 0;
+    }
+
+    let keep: Vec<usize> = match strategy {
+        SampleStrategy::LargestFirst | SampleStrategy::SmallestFirst => {
+            let mut by_size: Vec<(usize, usize)> = files
+                .iter()
+                .map(|&i| (file_tokens(&entries[i], tokenizer), i))
+                .collect();
+            if strategy == SampleStrategy::LargestFirst {
+                by_size.sort_by_key(|&(tokens, i)| (std::cmp::Reverse(tokens), i));
+            } else {
+                by_size.sort();
+            }
+            by_size.into_iter().take(max).map(|(_, i)| i).collect()
+        }
+        SampleStrategy::Stratified => stratified(entries, &files, max),
+    };
+
+    let dropped = files.len() - keep.len();
+    let mut kept = vec![false; entries.len()];
+    for i in keep {
+        kept[i] = true;
+    }
+    let mut idx = 0;
+    entries.retain(|e| {
+        let keep = !e.is_file || kept[idx];
+        idx += 1;
+        keep
+    });
+    dropped
+}
+
+/// Picks `max` of `files` (indices into `entries`): at least one from each
+/// top-level directory while `max` allows, the rest shared in proportion to
+/// the directories' file counts.
+fn stratified(entries: &[ProcessedEntry], files: &[usize], max: usize) -> Vec<usize> {
+    let mut groups: BTreeMap<String, Vec<(String, usize)>> = BTreeMap::new();
+    for &i in files {
+        let rel = to_fwd_slash(&entries[i].relative_path);
+        let dir = match rel.split_once('/') {
+            Some((top, _)) => top.to_string(),
+            None => ".".to_string(),
+        };
+        groups.entry(dir).or_default().push((rel, i));
+    }
+    let mut groups: Vec<Vec<(String, usize)>> = groups.into_values().collect();
+    for group in &mut groups {
+        group.sort();
+    }
+
+    let quotas: Vec<usize> = if max < groups.len() {
+        // Too few files for every directory: one from each of the largest.
+        let mut order: Vec<usize> = (0..groups.len()).collect();
+        order.sort_by_key(|&g| std::cmp::Reverse(groups[g].len()));
+        let mut quotas = vec![0; groups.len()];
+        for &g in &order[..max] {
+            quotas[g] = 1;
+        }
+        quotas
+    } else {
+        // One each, then the rest by largest remainder.
+        let extra = max - groups.len();
+        let weights: Vec<usize> = groups.iter().map(|g| g.len() - 1).collect();
+        let total: usize = weights.iter().sum::<usize>().max(1);
+        let mut quotas: Vec<usize> = weights.iter().map(|w| 1 + extra * w / total).collect();
+        let mut by_remainder: Vec<usize> = (0..groups.len()).collect();
+        by_remainder.sort_by_key(|&g| std::cmp::Reverse(extra * weights[g] % total));
+        let assigned: usize = quotas.iter().sum();
+        for &g in by_remainder.iter().take(max - assigned) {
+            quotas[g] += 1;
+        }
+        quotas
+    };
+
+    let mut keep = Vec::with_capacity(max);
+    for (group, quota) in groups.iter().zip(quotas) {
+        let quota = quota.min(group.len());
+        // Evenly spaced over the sorted paths, to reach every subdirectory.
+        keep.extend((0..quota).map(|k| group[k * group.len() / quota].1));
+    }
+    keep
+}
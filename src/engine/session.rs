@@ -1,19 +1,42 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
+use glob::Pattern;
 use handlebars::Handlebars;
 #[cfg(any(feature = "cache", feature = "tui"))]
 use rayon::prelude::*;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 
 #[cfg(feature = "git")]
-use crate::engine::git::{get_git_diff, get_git_diff_between_branches, get_git_log};
+use crate::engine::{
+    diff_trim::trim_diff,
+    git::{
+        get_git_diff, get_git_diff_between_branches, get_git_diff_files, get_git_log, repo_state,
+    },
+};
 use crate::{
     Code2PromptConfigBuilder,
-    common::{code, format, hash::HashMap},
+    common::{
+        code, format,
+        hash::HashMap,
+        path::{self, to_fwd_slash},
+    },
     engine::{
-        cache::ScanCache,
+        cache::{ScanCache, prune_caches},
         config::Code2PromptConfig,
-        model::{FileContext, ProcessedEntry, TemplateContext},
-        traverse::{ProcessingMode, process_codebase},
+        encoding, file_id,
+        model::{
+            ContentSource, FileContext, FileGroup, ProcessedEntry, RagRecord, ScanError,
+            TemplateContext, TokenBreakdown,
+        },
+        priority, sample,
+        symbols::build_symbol_index,
+        tags,
+        token::{
+            count_tokens, estimate_tokens, estimated_end_bytes, truncate_ends, truncate_middle,
+        },
+        traverse::{ProcessingMode, SkipReason, prepare_content, prepares_as_is, process_codebase},
     },
     ui::template::handlebars_setup,
 };
@@ -25,6 +48,9 @@ pub struct Code2PromptSession {
     pub processed_entries: Vec<ProcessedEntry>,
     pub all_extensions: HashMap<String, usize>,
     pub all_directories: HashMap<String, usize>,
+    /// Paths the last scan could not read (permission denied, broken
+    /// symlinks, files that vanished), sorted by path.
+    pub scan_errors: Vec<ScanError>,
     #[cfg(any(feature = "cache", feature = "tui"))]
     scan_cache: Option<ScanCache>,
 }
@@ -36,7 +62,20 @@ impl Code2PromptSession {
     pub fn new(config: Code2PromptConfig) -> Result<Self> {
         #[cfg(any(feature = "cache", feature = "tui"))]
         let scan_cache = if config.cache {
-            ScanCache::open(&config.path).ok()
+            if let Err(_e) = prune_caches(&config.path, config.cache_limits) {
+                #[cfg(feature = "logging")]
+                log::warn!("Failed to prune the scan cache: {_e:#}");
+            }
+            let key = config.cache_key()?;
+            ScanCache::open(&config.path).ok().map(|cache| {
+                if key.is_some()
+                    && let Err(_e) = cache.drop_plaintext_contents()
+                {
+                    #[cfg(feature = "logging")]
+                    log::warn!("Failed to drop unencrypted contents from the scan cache: {_e:#}");
+                }
+                cache.with_key(key)
+            })
         } else {
             None
         };
@@ -45,6 +84,7 @@ impl Code2PromptSession {
             processed_entries: Vec::new(),
             all_extensions: HashMap::default(),
             all_directories: HashMap::default(),
+            scan_errors: Vec::new(),
             #[cfg(any(feature = "cache", feature = "tui"))]
             scan_cache,
         })
@@ -58,20 +98,125 @@ impl Code2PromptSession {
     // Scanning / processing
     // ──────────────────────────────────────────────────────────
     pub fn scan_extensions(&mut self) -> Result<()> {
-        let (_, ext, dirs) = process_codebase(&self.config, ProcessingMode::ExtensionCollection)?;
+        let (_, ext, dirs, _) =
+            process_codebase(&self.config, ProcessingMode::ExtensionCollection)?;
         self.all_extensions = ext;
         self.all_directories = dirs;
         Ok(())
     }
 
     pub fn process_codebase(&mut self) -> Result<()> {
-        let (entries, ext, dirs) = process_codebase(&self.config, ProcessingMode::FullProcess)?;
+        let (entries, ext, dirs, errors) =
+            process_codebase(&self.config, ProcessingMode::FullProcess)?;
         self.processed_entries = entries;
+        self.scan_errors = errors;
         self.all_extensions = ext;
         self.all_directories = dirs;
+        self.refine_contents();
+        Ok(())
+    }
+
+    /// Processes the given files (relative paths) and appends their entries,
+    /// even when the scan skipped them as hidden or gitignored.
+    pub fn include_files(&mut self, files: &[PathBuf]) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        let mut config = self.config.clone();
+        config.hidden = true;
+        config.no_ignore = true;
+        config.include_patterns = files
+            .iter()
+            .map(|f| Pattern::new(&Pattern::escape(&to_fwd_slash(f))))
+            .collect::<Result<_, _>>()?;
+        let mut extra = Self::new(config)?;
+        extra.process_codebase()?;
+        self.processed_entries.extend(extra.processed_entries);
+        self.scan_errors.extend(extra.scan_errors);
         Ok(())
     }
 
+    /// Post-traversal content stages: drops the files not matching `--grep`,
+    /// trims the rest to their matching regions with a grep context, and cuts
+    /// files above `max_file_tokens` down to their head and tail.
+    fn refine_contents(&mut self) {
+        let cfg = &self.config;
+        if cfg.grep.is_none() && cfg.max_file_tokens.is_none() {
+            return;
+        }
+        let line_numbers = cfg.line_numbers.then_some(&cfg.line_number_format);
+        self.processed_entries.retain_mut(|entry| {
+            if !entry.is_file {
+                return true;
+            }
+            let needs_content = cfg.grep.is_some()
+                || cfg
+                    .max_file_tokens
+                    .is_some_and(|max| entry.token_count.is_none_or(|t| t > max));
+            if !needs_content {
+                return true;
+            }
+
+            // A large file that is only cut down to size needs no more than
+            // its ends, when cuts are estimated from bytes.
+            if let Some(max) = cfg.max_file_tokens
+                && cfg.grep.is_none()
+                && line_numbers.is_none()
+                && (cfg.approximate_tokens || !cfg!(feature = "token_map"))
+                && prepares_as_is(&entry.relative_path, cfg)
+            {
+                let (head, tail) = estimated_end_bytes(max, cfg.tokenizer);
+                if let Ok(Some(ends)) = encoding::read_source_ends(&entry.path, head, tail)
+                    && let Some(text) =
+                        truncate_ends(&ends.head, &ends.tail, ends.total_bytes, max, cfg.tokenizer)
+                {
+                    set_edited_code(entry, &text, cfg);
+                    return true;
+                }
+            }
+
+            let Ok(src) = encoding::read_source(&entry.path) else {
+                if cfg.grep.is_some() {
+                    cfg.stats.skip(SkipReason::Unreadable, 1);
+                }
+                return cfg.grep.is_none();
+            };
+            let content = prepare_content(&src.text, &entry.relative_path, cfg);
+
+            // Edited contents carry the file's own line numbers, so they are
+            // wrapped without numbering.
+            let mut edited = None;
+            if let Some(grep) = &cfg.grep {
+                let Some(excerpt) = grep.apply(&content, line_numbers) else {
+                    cfg.stats.skip(SkipReason::NoGrepMatch, 1);
+                    return false;
+                };
+                if grep.context.is_some() {
+                    edited = Some(excerpt);
+                }
+            }
+            if let Some(max) = cfg.max_file_tokens {
+                let text = edited.clone().unwrap_or_else(|| match line_numbers {
+                    Some(fmt) => fmt.apply(&content),
+                    None => content.to_string(),
+                });
+                if let Some(truncated) =
+                    truncate_middle(&text, max, cfg.tokenizer, cfg.approximate_tokens)
+                {
+                    edited = Some(truncated);
+                }
+            }
+
+            if let Some(text) = edited {
+                set_edited_code(entry, &text, cfg);
+                if cfg.provenance {
+                    entry.sha256 = Some(Sha256::digest(src.text.as_bytes()).into());
+                }
+            }
+            true
+        });
+    }
+
     // ──────────────────────────────────────────────────────────
     // Sorting
     // ──────────────────────────────────────────────────────────
@@ -81,7 +226,45 @@ impl Code2PromptSession {
         }
     }
 
+    /// Keeps the `--max-files` sample, orders files by priority weight, with
+    /// orientation files first within a weight, then puts the files of the
+    /// order file first. With a token budget, drops the lowest-priority files
+    /// that do not fit.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn apply_priorities(&mut self) {
+        if let Some(max) = self.config.max_files {
+            let dropped = sample::sample_files(
+                &mut self.processed_entries,
+                max,
+                self.config.sample_strategy,
+                self.config.tokenizer,
+            );
+            self.config.stats.skip(SkipReason::NotSampled, dropped);
+        }
+        let rules = &self.config.priorities;
+        if self.config.no_priority_docs {
+            priority::order_by_priority(&mut self.processed_entries, rules);
+        } else {
+            priority::order_docs_first(&mut self.processed_entries, rules);
+        }
+        priority::order_by_list(&mut self.processed_entries, &self.config.file_order);
+        if let Some(budget) = self.config.token_budget {
+            let dropped = priority::trim_to_budget(
+                &mut self.processed_entries,
+                rules,
+                budget,
+                self.config.tokenizer,
+            );
+            self.config.stats.skip(SkipReason::OverBudget, dropped);
+            #[cfg(feature = "logging")]
+            if dropped > 0 {
+                log::warn!("Dropped {dropped} low-priority file(s) to fit {budget} tokens");
+            }
+        }
+    }
+
     #[cfg(any(feature = "cache", feature = "tui"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     fn populate_code_jit(&mut self) -> Result<()> {
         let Some(cache) = &self.scan_cache else {
             return Ok(()); // Nothing to do if cache is disabled
@@ -99,54 +282,63 @@ impl Code2PromptSession {
         }
 
         // 2. Fetch all available content from the cache in a single batch query.
-        let paths_to_query: Vec<&str> = entries_to_load
+        let paths_to_query: Vec<String> = entries_to_load
             .iter()
-            .map(|e| e.relative_path.to_str().unwrap_or_default())
+            .map(|e| to_fwd_slash(&e.relative_path))
             .collect();
+        let paths_to_query: Vec<&str> = paths_to_query.iter().map(String::as_str).collect();
         let cached_contents = cache.get_cached_contents(&paths_to_query)?;
 
         // 3. Partition entries into those found in the cache and those requiring a disk read.
-        let (cached_entries, disk_read_entries): (Vec<_>, Vec<_>) =
-            entries_to_load.into_iter().partition(|e| {
-                let path_str = e.relative_path.to_string_lossy();
-                cached_contents.contains_key(path_str.as_ref())
-            });
+        let (cached_entries, disk_read_entries): (Vec<_>, Vec<_>) = entries_to_load
+            .into_iter()
+            .partition(|e| cached_contents.contains_key(&to_fwd_slash(&e.relative_path)));
 
         // 4. Populate entries with cached content.
         for entry in cached_entries {
-            let path_str = entry.relative_path.to_string_lossy();
-            if let Some(content) = cached_contents.get(path_str.as_ref()) {
-                entry.code = Some(code::wrap(
-                    content,
-                    entry.extension.as_deref().unwrap_or(""),
-                    self.config.line_numbers,
-                    self.config.no_codeblock,
-                ));
+            if let Some(raw) = cached_contents.get(&to_fwd_slash(&entry.relative_path)) {
+                let content = prepare_content(raw, &entry.relative_path, &self.config);
+                entry.code = Some(self.config.wrap_code(&content, entry.extension.as_deref()));
+                entry.source = Some(ContentSource::Cache);
             }
         }
 
         // 5. Read the remaining files from disk in parallel.
-        let results: Vec<_> = disk_read_entries
-            .into_par_iter()
-            .filter_map(|entry| {
-                std::fs::read_to_string(&entry.path).ok().map(|content| {
-                    let wrapped_code = code::wrap(
-                        &content,
-                        entry.extension.as_deref().unwrap_or(""),
-                        self.config.line_numbers,
-                        self.config.no_codeblock,
-                    );
-                    (entry.path.clone(), wrapped_code)
+        let read_all = || -> Vec<_> {
+            disk_read_entries
+                .into_par_iter()
+                .filter_map(|entry| {
+                    encoding::read_source(&entry.path).ok().map(|src| {
+                        let raw = src.text;
+                        let content = prepare_content(&raw, &entry.relative_path, &self.config);
+                        let wrapped_code =
+                            self.config.wrap_code(&content, entry.extension.as_deref());
+                        // The file may have changed since it was cached.
+                        let sha256 = self
+                            .config
+                            .provenance
+                            .then(|| Sha256::digest(raw.as_bytes()).into());
+                        (entry.path.clone(), (wrapped_code, sha256))
+                    })
                 })
-            })
-            .collect();
+                .collect()
+        };
+        let results = match self.config.threads {
+            Some(n) => rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build()?
+                .install(read_all),
+            None => read_all(),
+        };
 
         // Create a map for quick lookups and update the original entries.
         let disk_content_map: HashMap<_, _> = results.into_iter().collect();
         for entry in &mut self.processed_entries {
             if entry.code.is_none() {
-                if let Some(wrapped_code) = disk_content_map.get(&entry.path) {
+                if let Some((wrapped_code, sha256)) = disk_content_map.get(&entry.path) {
                     entry.code = Some(wrapped_code.clone());
+                    entry.source = Some(ContentSource::Disk);
+                    entry.sha256 = sha256.or(entry.sha256);
                 }
             }
         }
@@ -157,6 +349,7 @@ impl Code2PromptSession {
     // ──────────────────────────────────────────────────────────
     // Template-data builder
     // ──────────────────────────────────────────────────────────
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub fn build_template_data(
         &mut self,
         git_diff: Option<&str>,
@@ -165,49 +358,144 @@ impl Code2PromptSession {
     ) -> Result<TemplateContext> {
         // --- JIT Loading Step ---
         #[cfg(any(feature = "cache", feature = "tui"))]
-        self.populate_code_jit()?;
+        if !self.config.tree_only {
+            self.populate_code_jit()?;
+        }
+        self.apply_priorities();
 
-        let files_context: Vec<FileContext> = self
+        let tree_only = self.config.tree_only;
+        let ids = file_id::entry_ids(&self.processed_entries);
+        let files: Vec<(&ProcessedEntry, FileContext)> = self
             .processed_entries
             .iter()
-            .filter(|e| e.is_file && e.code.is_some())
+            .filter(|e| e.is_file && (tree_only || e.code.is_some()))
             .map(|e| {
                 let path_val = if self.config.absolute_path {
-                    e.path.to_string_lossy().into_owned()
+                    path::display(&e.path)
                 } else {
-                    e.relative_path.to_string_lossy().into_owned()
+                    path::display(&e.relative_path)
                 };
-                FileContext {
+                let file = FileContext {
                     path: path_val,
+                    id: ids.get(&e.relative_path).cloned().unwrap_or_default(),
                     extension: e.extension.as_deref().unwrap_or("").to_string(),
-                    code: e.code.as_deref().unwrap_or("").to_string(), // .unwrap() is safe due to filter
+                    code: e
+                        .code
+                        .as_deref()
+                        .filter(|_| !tree_only)
+                        .unwrap_or("")
+                        .to_string(),
                     token_count: e.token_count,
-                }
+                    tags: tags::tags_of(&self.config.tags, &to_fwd_slash(&e.relative_path)),
+                    owners: self
+                        .config
+                        .codeowners
+                        .owners_of(&to_fwd_slash(&e.relative_path))
+                        .to_vec(),
+                };
+                (e, file)
             })
             .collect();
+        let file_groups = group_by_top_dir(&files);
+        let files_context = files.into_iter().map(|(_, file)| file).collect();
 
         let mut context = TemplateContext {
             absolute_code_path: format::format_path_label(&self.config.path),
             files: files_context,
+            file_groups,
             source_tree: String::new(), // Populated later in main.rs
             git_diff: None,
+            git_diff_files: None,
             git_diff_branch: None,
             git_log_branch: None,
+            user_prelude: None,
+            user_epilogue: None,
+            symbol_index: None,
+            issue: None,
+            git_branch: None,
+            git_commit_sha: None,
+            git_remote_url: None,
+            repo_name: None,
+            generated_at: None,
         };
+        if let Some(source) = self.config.symbols {
+            context.symbol_index = Some(build_symbol_index(&self.config, source)?);
+        }
         // Git extras (kept behind feature gate)
         #[cfg(feature = "git")]
         {
             context.git_diff =
                 git_diff.map(|_| get_git_diff(&self.config.path).unwrap_or_default());
+            context.git_diff_files =
+                git_diff.map(|_| get_git_diff_files(&self.config.path).unwrap_or_default());
             context.git_diff_branch = git_diff_branch.map(|(a, b)| {
                 get_git_diff_between_branches(&self.config.path, a, b).unwrap_or_default()
             });
             context.git_log_branch = git_log_branch
                 .map(|(a, b)| get_git_log(&self.config.path, a, b).unwrap_or_default());
+            let state = repo_state(&self.config.path).unwrap_or_default();
+            context.git_branch = state.branch;
+            context.git_commit_sha = state.commit_sha;
+            context.git_remote_url = state.remote_url;
+            context.repo_name = state.name;
+            context.generated_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()
+                .map(|d| format::format_utc_timestamp(d.as_secs()));
+            if let Some(max) = self.config.max_diff_tokens {
+                for diff in [&mut context.git_diff, &mut context.git_diff_branch]
+                    .into_iter()
+                    .flatten()
+                {
+                    let cfg = &self.config;
+                    if let Some(trimmed) =
+                        trim_diff(diff, max, cfg.tokenizer, cfg.approximate_tokens)
+                    {
+                        *diff = trimmed;
+                    }
+                }
+            }
         }
         Ok(context)
     }
 
+    /// Loads the contents the scan cache held back and puts the files in
+    /// prompt order, for outputs that are not rendered from a template.
+    pub fn load_files(&mut self) -> Result<()> {
+        #[cfg(any(feature = "cache", feature = "tui"))]
+        self.populate_code_jit()?;
+        self.apply_priorities();
+        Ok(())
+    }
+
+    /// The files as `-F rag-jsonl` records, in prompt order. Their contents
+    /// are only wrapped in code blocks if the config asks for it.
+    pub fn rag_records(&mut self) -> Result<Vec<RagRecord>> {
+        self.load_files()?;
+        self.processed_entries
+            .iter()
+            .filter(|e| e.is_file)
+            .filter_map(|e| Some((e, e.code.as_deref()?)))
+            .map(|(e, content)| {
+                let path = if self.config.absolute_path {
+                    path::display(&e.path)
+                } else {
+                    path::display(&e.relative_path)
+                };
+                Ok(RagRecord {
+                    path,
+                    language: self
+                        .config
+                        .content_language(e.extension.as_deref().unwrap_or(""), content)
+                        .to_string(),
+                    tokens: count_tokens(content, self.config.tokenizer)?,
+                    content: content.to_string(),
+                    sha256: hex::encode(Sha256::digest(content.as_bytes())),
+                })
+            })
+            .collect()
+    }
+
     pub fn render_prompt_and_count_tokens(
         &mut self,
         template_content: &str,
@@ -245,12 +533,78 @@ impl Code2PromptSession {
         Ok((rendered, token_count, template_value))
     }
 
+    /// Splits `total`, the tokens of the prompt rendered from `context`,
+    /// between files, source tree, git output and template scaffolding. Files
+    /// whose tokens were counted during the scan are not counted again.
+    pub fn token_breakdown(
+        &self,
+        context: &TemplateContext,
+        total: usize,
+    ) -> Result<TokenBreakdown> {
+        let count = |text: &str| count_tokens(text, self.config.tokenizer);
+        let count_opt = |text: &Option<String>| text.as_deref().map_or(Ok(0), count);
+
+        let mut breakdown = TokenBreakdown {
+            source_tree: count(&context.source_tree)?,
+            git_diff: count_opt(&context.git_diff)? + count_opt(&context.git_diff_branch)?,
+            git_log: count_opt(&context.git_log_branch)?,
+            ..Default::default()
+        };
+        for file in &context.files {
+            breakdown.files += match file.token_count {
+                Some(tokens) => tokens,
+                None => count(&file.code)?,
+            };
+        }
+        // Tokens can merge across part boundaries, so the remainder is an
+        // estimate of the scaffolding.
+        breakdown.template = total.saturating_sub(breakdown.total());
+        Ok(breakdown)
+    }
+
     // ──────────────────────────────────────────────────────────
     // Template rendering
     // ──────────────────────────────────────────────────────────
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, hbs, data)))]
     fn render_template(&self, hbs: &Handlebars, tpl_name: &str, data: &Value) -> Result<String> {
         hbs.render(tpl_name, data)
             .map(|s| s.trim().to_owned())
             .map_err(|e| anyhow::anyhow!("Failed to render template: {e}"))
     }
 }
+
+/// Replaces the code of `entry` with `text`, an edited version of the file
+/// as read from disk, and recounts its tokens if they were counted.
+fn set_edited_code(entry: &mut ProcessedEntry, text: &str, cfg: &Code2PromptConfig) {
+    let lang = cfg.fence_language(entry.extension.as_deref().unwrap_or(""));
+    entry.code = Some(code::wrap(text, lang, None, cfg.no_codeblock));
+    entry.source = Some(ContentSource::Disk);
+    if entry.token_count.is_some() {
+        entry.token_count = if cfg.approximate_tokens {
+            Some(estimate_tokens(text, cfg.tokenizer))
+        } else {
+            count_tokens(text, cfg.tokenizer).ok()
+        };
+    }
+}
+
+/// Groups files by the first component of their relative path, in order of
+/// each group's first file; files at the root go in the `.` group.
+fn group_by_top_dir(files: &[(&ProcessedEntry, FileContext)]) -> Vec<FileGroup> {
+    let mut groups: Vec<FileGroup> = Vec::new();
+    for (entry, file) in files {
+        let mut components = entry.relative_path.components();
+        let dir = match (components.next(), components.next()) {
+            (Some(first), Some(_)) => first.as_os_str().to_string_lossy().into_owned(),
+            _ => ".".to_string(),
+        };
+        match groups.iter_mut().find(|g| g.dir == dir) {
+            Some(group) => group.files.push(file.clone()),
+            None => groups.push(FileGroup {
+                dir,
+                files: vec![file.clone()],
+            }),
+        }
+    }
+    groups
+}
@@ -0,0 +1,256 @@
+//! A compact index of the public symbols of every file in the repository,
+//! exposed to templates as `symbol_index`.
+//!
+//! Symbols come from a small built-in set of per-language patterns, or from
+//! Universal Ctags when it is installed. Unlike the file contents, the index
+//! ignores include filters, so a prompt can pair a full-repo API map with the
+//! code of only a few selected files.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{hash::HashMap, path};
+use crate::engine::{config::Code2PromptConfig, encoding, traverse::list_files};
+
+/// Where symbols are extracted from.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Default)]
+pub enum SymbolSource {
+    /// Built-in patterns for Rust, Python, JS/TS, Go, Java/Kotlin/C# and Ruby.
+    #[default]
+    Builtin,
+    /// Universal Ctags (`ctags` on the PATH), for every language it knows.
+    Ctags,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+}
+
+/// The symbols of one file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileSymbols {
+    pub path: String,
+    pub symbols: Vec<Symbol>,
+}
+
+/// Builds the index for the files under `cfg.path`, honouring ignore files
+/// and exclude patterns. Files without symbols are left out.
+pub fn build_symbol_index(
+    cfg: &Code2PromptConfig,
+    source: SymbolSource,
+) -> Result<Vec<FileSymbols>> {
+    let root = path::canonicalize(&cfg.path)
+        .with_context(|| format!("Failed to canonicalize {}", cfg.path.display()))?;
+    let files = list_files(&root, cfg)?;
+
+    let mut index: Vec<FileSymbols> = match source {
+        SymbolSource::Builtin => files
+            .iter()
+            .filter_map(|rel| {
+                let ext = Path::new(rel).extension()?.to_str()?;
+                let src = encoding::read_source(&root.join(rel)).ok()?;
+                let symbols = extract_symbols(&src.text, ext);
+                (!symbols.is_empty()).then(|| FileSymbols {
+                    path: rel.clone(),
+                    symbols,
+                })
+            })
+            .collect(),
+        SymbolSource::Ctags => run_ctags(&root, &files)?
+            .into_iter()
+            .map(|(path, symbols)| FileSymbols { path, symbols })
+            .collect(),
+    };
+    index.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(index)
+}
+
+// ────────────────────────────────────────────────────────────
+//  Built-in extraction
+// ────────────────────────────────────────────────────────────
+
+struct LanguagePattern {
+    exts: &'static [&'static str],
+    /// Must capture `name`, and `kind` unless `kind` is given.
+    regex: Regex,
+    kind: Option<&'static str>,
+}
+
+static PATTERNS: Lazy<Vec<LanguagePattern>> = Lazy::new(|| {
+    let pattern = |exts, re: &str, kind| LanguagePattern {
+        exts,
+        regex: Regex::new(re).expect("valid symbol regex"),
+        kind,
+    };
+    const JS: &[&str] = &["js", "jsx", "mjs", "cjs", "ts", "tsx"];
+    vec![
+        pattern(
+            &["rs"],
+            r#"(?m)^[ \t]*pub[ \t]+(?:(?:async|const|unsafe|extern[ \t]+"[^"]*")[ \t]+)*(?P<kind>fn|struct|enum|trait|type|const|static|mod|union|macro)[ \t]+(?P<name>[A-Za-z_][A-Za-z0-9_]*)"#,
+            None,
+        ),
+        pattern(
+            &["rs"],
+            r"(?m)^macro_rules![ \t]*(?P<name>[A-Za-z_][A-Za-z0-9_]*)",
+            Some("macro"),
+        ),
+        pattern(
+            &["py", "pyi"],
+            r"(?m)^(?:async[ \t]+)?(?P<kind>def|class)[ \t]+(?P<name>[A-Za-z][A-Za-z0-9_]*)",
+            None,
+        ),
+        pattern(
+            JS,
+            r"(?m)^export[ \t]+(?:default[ \t]+)?(?:declare[ \t]+)?(?:abstract[ \t]+)?(?:async[ \t]+)?(?P<kind>function|class|interface|type|enum|const|let|var|namespace)\*?[ \t]+(?P<name>[A-Za-z_$][A-Za-z0-9_$]*)",
+            None,
+        ),
+        pattern(
+            &["go"],
+            r"(?m)^func[ \t]+(?:\([^)]*\)[ \t]*)?(?P<name>[A-Z][A-Za-z0-9_]*)",
+            Some("func"),
+        ),
+        pattern(
+            &["go"],
+            r"(?m)^type[ \t]+(?P<name>[A-Z][A-Za-z0-9_]*)",
+            Some("type"),
+        ),
+        pattern(
+            &["java", "kt", "kts", "cs", "scala"],
+            r"(?m)^[ \t]*public[ \t]+(?:(?:static|final|abstract|sealed|partial|data)[ \t]+)*(?P<kind>class|interface|enum|record|object|struct)[ \t]+(?P<name>[A-Za-z_][A-Za-z0-9_]*)",
+            None,
+        ),
+        pattern(
+            &["rb"],
+            r"(?m)^[ \t]*(?P<kind>class|module|def)[ \t]+(?P<name>[A-Za-z_][A-Za-z0-9_.:?!]*)",
+            None,
+        ),
+    ]
+});
+
+/// Extracts the public top-level symbols of a file with the built-in
+/// patterns, in source order. Unknown extensions yield no symbols.
+pub fn extract_symbols(source: &str, ext: &str) -> Vec<Symbol> {
+    let ext = ext.to_ascii_lowercase();
+    let mut matches: Vec<(usize, &str, &str)> = PATTERNS
+        .iter()
+        .filter(|p| p.exts.contains(&ext.as_str()))
+        .flat_map(|p| {
+            p.regex.captures_iter(source).filter_map(move |caps| {
+                let kind = p.kind.or_else(|| caps.name("kind").map(|m| m.as_str()))?;
+                Some((caps.get(0)?.start(), kind, caps.name("name")?.as_str()))
+            })
+        })
+        .collect();
+    matches.sort_by_key(|(start, ..)| *start);
+
+    let (mut line, mut counted) = (1, 0);
+    matches
+        .into_iter()
+        .map(|(start, kind, name)| {
+            line += source[counted..start].matches('\n').count();
+            counted = start;
+            Symbol {
+                name: name.to_owned(),
+                kind: kind.to_owned(),
+                line,
+            }
+        })
+        .collect()
+}
+
+// ────────────────────────────────────────────────────────────
+//  Ctags
+// ────────────────────────────────────────────────────────────
+
+/// Ctags kinds kept in the index; locals, fields, variables and the like are
+/// too noisy for an API map.
+const CTAGS_KINDS: &[&str] = &[
+    "function",
+    "func",
+    "method",
+    "class",
+    "struct",
+    "enum",
+    "interface",
+    "trait",
+    "type",
+    "typedef",
+    "module",
+    "namespace",
+    "macro",
+    "union",
+];
+
+fn run_ctags(root: &Path, files: &[String]) -> Result<HashMap<String, Vec<Symbol>>> {
+    let mut child = Command::new("ctags")
+        .args(["--output-format=json", "--fields=+n", "-f", "-", "-L", "-"])
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run ctags (is Universal Ctags installed?)")?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let list = files.join("\n");
+    let writer = std::thread::spawn(move || {
+        let _ = stdin.write_all(list.as_bytes());
+    });
+    let output = child
+        .wait_with_output()
+        .context("Failed to wait for ctags")?;
+    let _ = writer.join();
+    if !output.status.success() {
+        bail!("ctags exited with {}", output.status);
+    }
+    Ok(parse_ctags_json(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[derive(Deserialize)]
+struct CtagsTag {
+    #[serde(rename = "_type")]
+    kind_of_line: String,
+    name: String,
+    path: String,
+    #[serde(default)]
+    line: usize,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    access: Option<String>,
+}
+
+/// Groups `ctags --output-format=json` tags by file, keeping non-private
+/// tags of the kinds in [`CTAGS_KINDS`].
+pub fn parse_ctags_json(output: &str) -> HashMap<String, Vec<Symbol>> {
+    let mut by_file: HashMap<String, Vec<Symbol>> = HashMap::default();
+    for tag in output
+        .lines()
+        .filter_map(|l| serde_json::from_str::<CtagsTag>(l).ok())
+        .filter(|t| t.kind_of_line == "tag" && CTAGS_KINDS.contains(&t.kind.as_str()))
+        .filter(|t| t.access.as_deref() != Some("private"))
+    {
+        by_file
+            .entry(tag.path.replace('\\', "/"))
+            .or_default()
+            .push(Symbol {
+                name: tag.name,
+                kind: tag.kind,
+                line: tag.line,
+            });
+    }
+    for symbols in by_file.values_mut() {
+        symbols.sort_by_key(|s| s.line);
+    }
+    by_file
+}
@@ -0,0 +1,106 @@
+//! File tags, kept in the repository's `.code2prompt/tags.toml`.
+//!
+//! Each tag lists the globs of the files it labels, e.g.
+//! `core = ["src/engine/**"]`. A file carries every tag with a matching glob;
+//! templates see them as `tags`, and `--tag` keeps only the files carrying
+//! one of the given tags.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+
+/// The tags of a repository, each with the globs of the files it labels.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TagFile(pub BTreeMap<String, Vec<String>>);
+
+/// A glob and the tag given to the files it matches.
+#[derive(Debug, Clone)]
+pub struct TagRule {
+    pub tag: String,
+    pub pattern: Pattern,
+}
+
+impl TagFile {
+    /// The location of the tags of the repository at `repo`.
+    pub fn path(repo: &Path) -> PathBuf {
+        repo.join(".code2prompt/tags.toml")
+    }
+
+    /// Loads the tags of the repository at `repo`; none if it has no tags file.
+    pub fn load(repo: &Path) -> Result<Self> {
+        let path = Self::path(repo);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read tags file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to load tags file {}", path.display()))
+    }
+
+    pub fn save(&self, repo: &Path) -> Result<()> {
+        let path = Self::path(repo);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write tags file {}", path.display()))
+    }
+
+    /// Tags the file or directory at `path`, relative to the repository
+    /// root. Returns `false` if it already had the tag.
+    pub fn add(&mut self, tag: &str, path: &str, is_dir: bool) -> bool {
+        let escaped = Pattern::escape(path);
+        let glob = if is_dir {
+            format!("{escaped}/**")
+        } else {
+            escaped
+        };
+        let globs = self.0.entry(tag.to_string()).or_default();
+        if globs.contains(&glob) {
+            return false;
+        }
+        globs.push(glob);
+        true
+    }
+
+    /// The tag rules, failing on the first invalid glob.
+    pub fn rules(&self) -> Result<Vec<TagRule>> {
+        self.0
+            .iter()
+            .flat_map(|(tag, globs)| globs.iter().map(move |glob| (tag, glob)))
+            .map(|(tag, glob)| {
+                Ok(TagRule {
+                    tag: tag.clone(),
+                    pattern: Pattern::new(glob).with_context(|| {
+                        format!("Invalid glob pattern for tag '{tag}': '{glob}'")
+                    })?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Returns the tags of a relative path, sorted and without duplicates.
+pub fn tags_of(rules: &[TagRule], rel_path: &str) -> Vec<String> {
+    let mut tags: Vec<String> = rules
+        .iter()
+        .filter(|r| r.pattern.matches(rel_path))
+        .map(|r| r.tag.clone())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Whether a relative path carries one of the `wanted` tags.
+pub fn has_any_tag(rules: &[TagRule], rel_path: &str, wanted: &[String]) -> bool {
+    rules
+        .iter()
+        .any(|r| wanted.contains(&r.tag) && r.pattern.matches(rel_path))
+}
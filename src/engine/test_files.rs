@@ -0,0 +1,94 @@
+//! Test files for source files, for selecting files together with their
+//! tests (`--with-tests`).
+//!
+//! Tests are matched by the naming conventions of each ecosystem:
+//! `foo_test.go`, `test_foo.py` and `foo_test.py`, `foo.test.ts` and
+//! `foo.spec.ts` (or anything in `__tests__/`), `tests/foo.rs` and
+//! `foo_tests.rs`, `foo_spec.rb`, and `FooTest.java` and the like. A test
+//! covers the files with the same name in the same language, next to it or
+//! anywhere when it lives in a test directory.
+
+use std::collections::BTreeSet;
+
+const JS_EXTS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+
+/// Directories whose files test code elsewhere in the repository.
+const TEST_DIRS: &[&str] = &["tests", "test", "__tests__", "spec", "specs"];
+
+/// Names too common to tell which file a test is for.
+const AMBIGUOUS_NAMES: &[&str] = &["mod", "index", "__init__", "main", "lib"];
+
+/// A file's directory, name without extension and language.
+struct FileName<'a> {
+    dir: &'a str,
+    stem: &'a str,
+    language: &'a str,
+}
+
+fn split(path: &str) -> Option<FileName<'_>> {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let (stem, ext) = name.rsplit_once('.')?;
+    let language = if JS_EXTS.contains(&ext) { "js" } else { ext };
+    Some(FileName {
+        dir,
+        stem,
+        language,
+    })
+}
+
+fn in_test_dir(dir: &str) -> bool {
+    dir.split('/').any(|part| TEST_DIRS.contains(&part))
+}
+
+/// The name of the file that `file` tests, if it is named like a test.
+fn subject<'a>(file: &FileName<'a>) -> Option<&'a str> {
+    let stem = file.stem;
+    let suffixes: &[&str] = match file.language {
+        "rs" => &["_tests", "_test"],
+        "js" => &[".test", ".spec"],
+        "rb" => &["_spec", "_test"],
+        "java" | "kt" | "scala" | "cs" | "swift" => &["Tests", "Test", "Spec"],
+        _ => &["_test"],
+    };
+    if let Some(subject) = suffixes.iter().find_map(|s| stem.strip_suffix(s)) {
+        return Some(subject);
+    }
+    if !matches!(file.language, "go" | "js" | "rs")
+        && let Some(subject) = stem.strip_prefix("test_")
+    {
+        return Some(subject);
+    }
+    // Integration tests and `__tests__/` take the name of what they test.
+    let last_dir = file.dir.rsplit('/').next().unwrap_or("");
+    match (file.language, last_dir) {
+        ("rs", "tests") | ("js", "__tests__") => Some(stem),
+        _ => None,
+    }
+}
+
+/// The files among `files` (relative paths with forward slashes) that test
+/// one of `sources`, other than the sources themselves.
+pub fn tests_for(sources: &[&str], files: &[String]) -> BTreeSet<String> {
+    let names: Vec<FileName> = sources.iter().filter_map(|s| split(s)).collect();
+    files
+        .iter()
+        .filter(|f| !sources.contains(&f.as_str()))
+        .filter(|f| {
+            let Some(test) = split(f) else {
+                return false;
+            };
+            let Some(subject) = subject(&test).filter(|s| !s.is_empty()) else {
+                return false;
+            };
+            if AMBIGUOUS_NAMES.contains(&subject) {
+                return false;
+            }
+            names.iter().any(|src| {
+                src.stem == subject
+                    && src.language == test.language
+                    && (src.dir == test.dir || in_test_dir(test.dir))
+            })
+        })
+        .cloned()
+        .collect()
+}
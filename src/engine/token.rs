@@ -44,6 +44,15 @@ impl TokenizerChoice {
         variants[next_pos]
     }
 
+    /// The context window, in tokens, of the largest models using this tokenizer.
+    pub fn context_window(&self) -> usize {
+        match self {
+            TokenizerChoice::O200kBase | TokenizerChoice::Cl100k => 128_000,
+            TokenizerChoice::P50kBase | TokenizerChoice::P50kEdit => 4_097,
+            TokenizerChoice::R50kBase => 2_049,
+        }
+    }
+
     pub fn previous(&self) -> Self {
         let variants = Self::value_variants();
         let current_pos = variants.iter().position(|v| v == self).unwrap_or(0);
@@ -126,6 +135,140 @@ pub fn get_model_info(tokenizer_name: TokenizerChoice) -> &'static str {
     }
 }
 
+/// Estimates the number of tokens in a text without encoding it.
+///
+/// The byte length is divided by a bytes-per-token ratio measured on source
+/// code for each tokenizer, so the estimate is cheap even for huge files.
+pub fn estimate_tokens(text: &str, tokenizer_name: TokenizerChoice) -> usize {
+    (text.len() as f64 / bytes_per_token(tokenizer_name)).ceil() as usize
+}
+
+fn bytes_per_token(tokenizer_name: TokenizerChoice) -> f64 {
+    match tokenizer_name {
+        TokenizerChoice::O200kBase | TokenizerChoice::Cl100k => 4.6,
+        TokenizerChoice::P50kBase | TokenizerChoice::P50kEdit => 3.5,
+        TokenizerChoice::R50kBase => 2.2,
+    }
+}
+
+/// Shortens `text` to about `max_tokens` tokens, keeping its head and tail
+/// halves around an elision marker. Returns `None` when it already fits.
+///
+/// Cuts are moved to line boundaries where possible. Without the `token_map`
+/// feature, or with `approximate`, token positions are estimated from bytes.
+pub fn truncate_middle(
+    text: &str,
+    max_tokens: usize,
+    tokenizer_name: TokenizerChoice,
+    approximate: bool,
+) -> Option<String> {
+    let (total, head_bytes, tail_bytes) = if approximate || !cfg!(feature = "token_map") {
+        let (head_bytes, tail_bytes) = estimated_end_bytes(max_tokens, tokenizer_name);
+        (
+            estimate_tokens(text, tokenizer_name),
+            head_bytes,
+            tail_bytes,
+        )
+    } else {
+        token_byte_spans(text, max_tokens, tokenizer_name)?
+    };
+    if total <= max_tokens {
+        return None;
+    }
+
+    let head = head_part(&text[..floor_char_boundary(text, head_bytes.min(text.len()))]);
+    let mut tail_start = text.len() - tail_bytes.min(text.len());
+    while !text.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    let tail_start = (text.len() - tail_part(&text[tail_start..]).len()).max(head.len());
+    Some(elide(head, &text[tail_start..], total - max_tokens))
+}
+
+/// The estimated byte lengths of the head and tail [`truncate_middle`]
+/// keeps with `approximate`, i.e. how much of each end of a file it needs.
+pub fn estimated_end_bytes(max_tokens: usize, tokenizer_name: TokenizerChoice) -> (usize, usize) {
+    let bytes = |tokens: usize| (tokens as f64 * bytes_per_token(tokenizer_name)) as usize;
+    (bytes(max_tokens / 2), bytes(max_tokens - max_tokens / 2))
+}
+
+/// [`truncate_middle`] with `approximate`, for a text of `total_bytes`
+/// bytes of which only the [`estimated_end_bytes`] at each end were read.
+pub fn truncate_ends(
+    head: &str,
+    tail: &str,
+    total_bytes: usize,
+    max_tokens: usize,
+    tokenizer_name: TokenizerChoice,
+) -> Option<String> {
+    let total = (total_bytes as f64 / bytes_per_token(tokenizer_name)).ceil() as usize;
+    if total <= max_tokens {
+        return None;
+    }
+    Some(elide(head_part(head), tail_part(tail), total - max_tokens))
+}
+
+/// Moves the end of a head cut back to a line boundary.
+fn head_part(head: &str) -> &str {
+    match head.rfind('\n') {
+        Some(nl) => &head[..nl + 1],
+        None => head,
+    }
+}
+
+/// Moves the start of a tail cut forward to a line boundary, unless that
+/// leaves nothing.
+fn tail_part(tail: &str) -> &str {
+    match tail.find('\n') {
+        Some(nl) if nl + 1 < tail.len() => &tail[nl + 1..],
+        _ => tail,
+    }
+}
+
+fn elide(head: &str, tail: &str, elided: usize) -> String {
+    let sep = if head.is_empty() || head.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    format!("{head}{sep}... [{elided} tokens elided] ...\n{tail}")
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// The token count of `text` and the byte lengths of its first `max / 2` and
+/// last `max - max / 2` tokens.
+#[cfg(feature = "token_map")]
+fn token_byte_spans(
+    text: &str,
+    max_tokens: usize,
+    tokenizer_name: TokenizerChoice,
+) -> Option<(usize, usize, usize)> {
+    let bpe = get_tokenizer(tokenizer_name).ok()?;
+    let tokens = bpe.encode_with_special_tokens(text);
+    let total = tokens.len();
+    if total <= max_tokens {
+        return Some((total, 0, 0));
+    }
+    let lens: Vec<usize> = bpe
+        ._decode_native_and_split(tokens)
+        .map(|bytes| bytes.len())
+        .collect();
+    let head = lens[..max_tokens / 2].iter().sum();
+    let tail = lens[total - (max_tokens - max_tokens / 2)..].iter().sum();
+    Some((total, head, tail))
+}
+
+#[cfg(not(feature = "token_map"))]
+fn token_byte_spans(_: &str, _: usize, _: TokenizerChoice) -> Option<(usize, usize, usize)> {
+    None
+}
+
 /// Counts the tokens in the rendered text using the specified encoding.
 ///
 /// # Arguments
@@ -138,6 +281,7 @@ pub fn get_model_info(tokenizer_name: TokenizerChoice) -> &'static str {
 /// * `usize` - The number of tokens in the text.
 // --- Real count_tokens ---
 #[cfg(feature = "token_map")]
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(bytes = text.len())))]
 pub fn count_tokens(text: &str, tokenizer_name: TokenizerChoice) -> Result<usize> {
     let bpe = get_tokenizer(tokenizer_name)?;
     Ok(bpe.encode_with_special_tokens(text).len())
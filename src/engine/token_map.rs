@@ -1,6 +1,47 @@
+use crate::common::path;
 use crate::engine::model::{EntryMetadata, ProcessedEntry, TokenMapEntry, TreeNode};
+use crate::engine::token::{TokenizerChoice, estimate_tokens};
+use anyhow::Context;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+/// How the token map aggregates file token counts.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Default)]
+pub enum TokenMapGroupBy {
+    /// Directory hierarchy
+    #[default]
+    Dir,
+    /// File extension
+    Ext,
+    /// Last author of each line, from git blame
+    Author,
+}
+
+impl TokenMapGroupBy {
+    /// The label of the row the entries that do not fit the map are folded
+    /// into.
+    pub fn other_label(self) -> &'static str {
+        match self {
+            Self::Dir => "(other files)",
+            Self::Ext => "(other extensions)",
+            Self::Author => "(other authors)",
+        }
+    }
+}
+
+/// Whether `entry` is the row the entries that do not fit the map are
+/// folded into.
+pub fn is_other(entry: &TokenMapEntry) -> bool {
+    [
+        TokenMapGroupBy::Dir,
+        TokenMapGroupBy::Ext,
+        TokenMapGroupBy::Author,
+    ]
+    .iter()
+    .any(|g| entry.depth == 0 && entry.name == g.other_label())
+}
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct NodePriority {
@@ -40,7 +81,7 @@ pub fn generate_token_map_with_limit(
                 continue;
             }
 
-            let path_str = entry.relative_path.to_string_lossy();
+            let path_str = path::display(&entry.relative_path);
             // The insert_path function expects path components.
             let components: Vec<&str> = path_str.split('/').collect();
 
@@ -78,9 +119,10 @@ pub fn generate_token_map_with_limit(
 
     let hidden_tokens = calculate_file_tokens(&root).saturating_sub(displayed_tokens);
     if hidden_tokens > 0 && total_tokens > 0 {
+        let label = TokenMapGroupBy::Dir.other_label();
         entries.push(TokenMapEntry {
-            path: "(other files)".to_string(),
-            name: "(other files)".to_string(),
+            path: label.to_string(),
+            name: label.to_string(),
             tokens: hidden_tokens,
             percentage: (hidden_tokens as f64 / total_tokens as f64) * 100.0,
             depth: 0,
@@ -95,6 +137,192 @@ pub fn generate_token_map_with_limit(
     entries
 }
 
+/// Labels (paths, extensions, authors) paired with their token totals.
+pub type TokenTotals = Vec<(String, usize)>;
+
+/// Ordering of sibling entries in the token map.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Default)]
+pub enum TokenMapSort {
+    /// Largest share first
+    #[default]
+    Percent,
+    /// Alphabetical by name
+    Name,
+}
+
+/// Reorders token map entries, keeping each directory's subtree attached to
+/// it. The row of the entries that did not fit always stays last.
+pub fn sort_token_map(entries: Vec<TokenMapEntry>, sort: TokenMapSort) -> Vec<TokenMapEntry> {
+    let (other, mut entries): (Vec<_>, Vec<_>) = entries.into_iter().partition(is_other);
+    sort_siblings(&mut entries, sort);
+    entries.extend(other);
+    entries
+}
+
+fn sort_siblings(entries: &mut Vec<TokenMapEntry>, sort: TokenMapSort) {
+    let Some(depth) = entries.iter().map(|e| e.depth).min() else {
+        return;
+    };
+
+    // Split into blocks, each a sibling followed by its descendants.
+    let mut blocks: Vec<Vec<TokenMapEntry>> = Vec::new();
+    for entry in entries.drain(..) {
+        match blocks.last_mut() {
+            Some(block) if entry.depth > depth => block.push(entry),
+            _ => blocks.push(vec![entry]),
+        }
+    }
+    match sort {
+        TokenMapSort::Percent => blocks.sort_by(|a, b| b[0].tokens.cmp(&a[0].tokens)),
+        TokenMapSort::Name => blocks.sort_by(|a, b| a[0].name.cmp(&b[0].name)),
+    }
+
+    let count = blocks.len();
+    for (i, mut block) in blocks.into_iter().enumerate() {
+        let mut head = block.remove(0);
+        head.is_last = i + 1 == count;
+        entries.push(head);
+        sort_siblings(&mut block, sort);
+        entries.extend(block);
+    }
+}
+
+/// Returns the `n` largest files and the `n` largest directories by token
+/// count, largest first. Files without a token count are estimated from
+/// their (wrapped) code.
+pub fn largest_contributors(
+    entries: &[ProcessedEntry],
+    tokenizer: TokenizerChoice,
+    n: usize,
+) -> (TokenTotals, TokenTotals) {
+    let mut files = Vec::new();
+    let mut dirs: HashMap<String, usize> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.is_file) {
+        let tokens = entry
+            .token_count
+            .or_else(|| entry.code.as_deref().map(|c| estimate_tokens(c, tokenizer)))
+            .unwrap_or(0);
+        if tokens == 0 {
+            continue;
+        }
+        let path = path::display(&entry.relative_path);
+        for (i, _) in path.match_indices('/') {
+            *dirs.entry(path[..i].to_string()).or_insert(0) += tokens;
+        }
+        files.push((path, tokens));
+    }
+
+    let top = |mut v: TokenTotals| {
+        v.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        v.truncate(n);
+        v
+    };
+    (top(files), top(dirs.into_iter().collect()))
+}
+
+/// Sums file tokens per extension. Files without one are grouped as "(none)".
+pub fn group_by_extension(entries: &[ProcessedEntry]) -> TokenTotals {
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.is_file) {
+        let tokens = entry.token_count.unwrap_or(0);
+        let label = match entry.extension.as_deref() {
+            Some(ext) if !ext.is_empty() => format!(".{ext}"),
+            _ => "(none)".to_string(),
+        };
+        *groups.entry(label).or_insert(0) += tokens;
+    }
+    groups.into_iter().collect()
+}
+
+/// Splits each file's tokens between authors in proportion to the lines
+/// `git blame` attributes to them. Unblamed files count as "(untracked)".
+#[cfg(feature = "git")]
+pub fn group_by_author(
+    repo_path: &std::path::Path,
+    entries: &[ProcessedEntry],
+) -> anyhow::Result<TokenTotals> {
+    let files: Vec<&ProcessedEntry> = entries
+        .iter()
+        .filter(|e| e.is_file && e.token_count.is_some_and(|t| t > 0))
+        .collect();
+    let paths: Vec<&std::path::Path> = files.iter().map(|e| e.path.as_path()).collect();
+    let blames = crate::engine::git::blame_line_counts(repo_path, &paths)?;
+
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    for (entry, lines_by_author) in files.iter().zip(blames) {
+        let tokens = entry.token_count.unwrap_or(0);
+        let total_lines: usize = lines_by_author.values().sum();
+        if total_lines == 0 {
+            *groups.entry("(untracked)".to_string()).or_insert(0) += tokens;
+            continue;
+        }
+        for (author, lines) in lines_by_author {
+            *groups.entry(author).or_insert(0) += tokens * lines / total_lines;
+        }
+    }
+    Ok(groups.into_iter().collect())
+}
+
+/// Builds a flat token map from pre-aggregated groups, largest first. Groups
+/// below `min_percent` or beyond `max_lines` are folded into one row labelled
+/// after `group_by`, e.g. "(other extensions)".
+pub fn generate_grouped_token_map(
+    groups: TokenTotals,
+    group_by: TokenMapGroupBy,
+    max_lines: Option<usize>,
+    min_percent: Option<f64>,
+) -> Vec<TokenMapEntry> {
+    let max_lines = max_lines.unwrap_or(20);
+    let min_percent = min_percent.unwrap_or(0.1);
+    let total_tokens: usize = groups.iter().map(|(_, t)| t).sum();
+    if total_tokens == 0 {
+        return Vec::new();
+    }
+    let min_tokens = (total_tokens as f64 * min_percent / 100.0).ceil() as usize;
+
+    let mut groups: Vec<_> = groups.into_iter().filter(|(_, t)| *t > 0).collect();
+    groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let percentage = |tokens: usize| (tokens as f64 / total_tokens as f64) * 100.0;
+    let mut entries: Vec<TokenMapEntry> = groups
+        .iter()
+        .take(max_lines.saturating_sub(1))
+        .filter(|(_, tokens)| *tokens >= min_tokens)
+        .map(|(label, tokens)| TokenMapEntry {
+            path: label.clone(),
+            name: label.clone(),
+            tokens: *tokens,
+            percentage: percentage(*tokens),
+            depth: 1,
+            is_last: false,
+            metadata: EntryMetadata {
+                is_dir: false,
+                is_symlink: false,
+            },
+        })
+        .collect();
+
+    let hidden_tokens = total_tokens - entries.iter().map(|e| e.tokens).sum::<usize>();
+    if hidden_tokens > 0 {
+        let label = group_by.other_label();
+        entries.push(TokenMapEntry {
+            path: label.to_string(),
+            name: label.to_string(),
+            tokens: hidden_tokens,
+            percentage: percentage(hidden_tokens),
+            depth: 0,
+            is_last: true,
+            metadata: EntryMetadata {
+                is_dir: false,
+                is_symlink: false,
+            },
+        });
+    } else if let Some(last) = entries.last_mut() {
+        last.is_last = true;
+    }
+    entries
+}
+
 fn calculate_file_tokens(node: &TreeNode) -> usize {
     let mut current_node_tokens = 0;
     if node.metadata.is_some_and(|m| !m.is_dir) {
@@ -265,3 +493,84 @@ fn rebuild_filtered_tree(
         );
     }
 }
+
+/// Every file's token count, as written by `--token-map-json` and compared
+/// by `code2prompt map-diff`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenMapExport {
+    pub tokenizer: TokenizerChoice,
+    pub total_tokens: usize,
+    /// Token counts by relative path.
+    pub files: BTreeMap<String, usize>,
+}
+
+impl TokenMapExport {
+    pub fn from_entries(entries: &[ProcessedEntry], tokenizer: TokenizerChoice) -> Self {
+        let files: BTreeMap<String, usize> = entries
+            .iter()
+            .filter(|e| e.is_file)
+            .filter_map(|e| Some((path::display(&e.relative_path), e.token_count?)))
+            .collect();
+        Self {
+            tokenizer,
+            total_tokens: files.values().sum(),
+            files,
+        }
+    }
+
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read token map {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| {
+            format!(
+                "{} is not a token map from --token-map-json",
+                path.display()
+            )
+        })
+    }
+}
+
+/// A directory's tokens in two token maps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirChange {
+    pub dir: String,
+    pub old: usize,
+    pub new: usize,
+}
+
+impl DirChange {
+    pub fn delta(&self) -> i64 {
+        self.new as i64 - self.old as i64
+    }
+}
+
+/// The directory of `path` cut to its first `depth` components; `.` for
+/// files at the root.
+pub fn dir_at_depth(path: &str, depth: usize) -> String {
+    let parts: Vec<&str> = path.split('/').collect();
+    let dirs = &parts[..parts.len() - 1];
+    if dirs.is_empty() || depth == 0 {
+        return ".".to_string();
+    }
+    dirs[..depth.min(dirs.len())].join("/")
+}
+
+/// Sums both maps per directory (see [`dir_at_depth`]) and returns the
+/// directories whose tokens changed, largest change first.
+pub fn diff_by_dir(old: &TokenMapExport, new: &TokenMapExport, depth: usize) -> Vec<DirChange> {
+    let mut dirs: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for (path, tokens) in &old.files {
+        dirs.entry(dir_at_depth(path, depth)).or_default().0 += tokens;
+    }
+    for (path, tokens) in &new.files {
+        dirs.entry(dir_at_depth(path, depth)).or_default().1 += tokens;
+    }
+    let mut changes: Vec<DirChange> = dirs
+        .into_iter()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(dir, (old, new))| DirChange { dir, old, new })
+        .collect();
+    changes.sort_by_key(|c| std::cmp::Reverse(c.delta().unsigned_abs()));
+    changes
+}
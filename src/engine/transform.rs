@@ -0,0 +1,372 @@
+//! A pipeline of content transformers applied to files before wrapping.
+//!
+//! Transformers are configured per glob in the config file, e.g.
+//!
+//! ```toml
+//! [transform."**/*.rs"]
+//! steps = ["strip_comments", { truncate = 300 }]
+//!
+//! [transform."config/**"]
+//! steps = ["redact", { replace = { pattern = "https?://internal\\.\\S+", with = "<url>" } }]
+//!
+//! [transform."*.proto"]
+//! cmd = "protoc-summarize --stdin"
+//! ```
+//!
+//! A `cmd` runs through the shell with the file content on stdin, and its
+//! stdout replaces the content; it runs before the listed `steps`, and can
+//! also be given as a `{ cmd = "..." }` step. Steps run in the order they are
+//! listed. When several globs match a file, their steps are applied in the
+//! lexical order of the globs.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::Stdio;
+
+use anyhow::{Context, Result, bail};
+use glob::Pattern;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::utils::shell_command;
+
+/// One step of a transform, as written in the config file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformStep {
+    /// Remove comments, using the comment syntax of the file's extension.
+    StripComments,
+    /// Mask things that look like secrets (keys, tokens, passwords).
+    Redact,
+    /// Keep only the first N lines.
+    Truncate(usize),
+    /// Replace every match of a regular expression.
+    Replace { pattern: String, with: String },
+    /// Pipe the content through a shell command and use its output.
+    Cmd(String),
+}
+
+/// The transform configured for one glob.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransformSpec {
+    /// External command run before `steps`.
+    pub cmd: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<TransformStep>,
+}
+
+#[derive(Debug, Clone)]
+enum Transformer {
+    StripComments,
+    Redact,
+    Truncate(usize),
+    Replace(Regex, String),
+    Command(String),
+}
+
+impl Transformer {
+    fn compile(step: &TransformStep) -> Result<Self> {
+        Ok(match step {
+            TransformStep::StripComments => Self::StripComments,
+            TransformStep::Redact => Self::Redact,
+            TransformStep::Truncate(n) => Self::Truncate(*n),
+            TransformStep::Replace { pattern, with } => Self::Replace(
+                Regex::new(pattern).with_context(|| format!("Invalid regex: '{pattern}'"))?,
+                with.clone(),
+            ),
+            TransformStep::Cmd(cmd) => Self::Command(cmd.clone()),
+        })
+    }
+
+    fn apply<'a>(&self, content: Cow<'a, str>, rel_path: &str) -> Cow<'a, str> {
+        match self {
+            Self::StripComments => match comment_syntax(extension(rel_path)) {
+                Some(syntax) => Cow::Owned(strip_comments(&content, &syntax)),
+                None => content,
+            },
+            Self::Redact => match redact(&content) {
+                Cow::Owned(s) => Cow::Owned(s),
+                Cow::Borrowed(_) => content,
+            },
+            Self::Truncate(n) => match truncate_lines(&content, *n) {
+                Some(s) => Cow::Owned(s),
+                None => content,
+            },
+            Self::Replace(re, with) => match re.replace_all(&content, with.as_str()) {
+                Cow::Owned(s) => Cow::Owned(s),
+                Cow::Borrowed(_) => content,
+            },
+            Self::Command(cmd) => match run_command(cmd, &content, rel_path) {
+                Ok(out) => Cow::Owned(out),
+                Err(_e) => {
+                    #[cfg(feature = "logging")]
+                    log::warn!("Transform command failed for {rel_path}: {_e:#}");
+                    content
+                }
+            },
+        }
+    }
+}
+
+fn extension(rel_path: &str) -> &str {
+    rel_path
+        .rsplit_once('.')
+        .map(|(_, e)| e)
+        .filter(|e| !e.contains('/'))
+        .unwrap_or("")
+}
+
+/// The compiled transform rules, in application order.
+#[derive(Debug, Clone, Default)]
+pub struct TransformPipeline {
+    rules: Vec<(Pattern, Vec<Transformer>)>,
+    /// The glob and spec each rule was compiled from.
+    specs: Vec<(String, TransformSpec)>,
+}
+
+impl TransformPipeline {
+    /// Compiles the `[transform."<glob>"]` tables of the config file.
+    pub fn from_specs(specs: &BTreeMap<String, TransformSpec>) -> Result<Self> {
+        let mut pipeline = Self::default();
+        for (glob, spec) in specs {
+            pipeline.add(glob, spec)?;
+        }
+        Ok(pipeline)
+    }
+
+    /// Appends the steps of one glob, after the rules already added.
+    pub fn add(&mut self, glob: &str, spec: &TransformSpec) -> Result<()> {
+        let pattern =
+            Pattern::new(glob).with_context(|| format!("Invalid glob pattern: '{glob}'"))?;
+        let cmd = spec.cmd.clone().map(TransformStep::Cmd);
+        let steps = cmd
+            .iter()
+            .chain(&spec.steps)
+            .map(Transformer::compile)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Invalid transform for '{glob}'"))?;
+        self.rules.push((pattern, steps));
+        self.specs.push((glob.to_string(), spec.clone()));
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The glob and spec of each rule, in application order.
+    pub fn specs(&self) -> &[(String, TransformSpec)] {
+        &self.specs
+    }
+
+    /// Applies every rule whose glob matches the (forward-slash) relative path.
+    pub fn apply<'a>(&self, content: Cow<'a, str>, rel_path: &str) -> Cow<'a, str> {
+        self.rules
+            .iter()
+            .filter(|(pattern, _)| pattern.matches(rel_path))
+            .flat_map(|(_, steps)| steps)
+            .fold(content, |acc, t| t.apply(acc, rel_path))
+    }
+}
+
+// ────────────────────────────────────────────────────────────
+//  External commands
+// ────────────────────────────────────────────────────────────
+
+/// Runs `cmd` through the shell with `content` on stdin and returns its
+/// stdout. The file's relative path is available as `$CODE2PROMPT_FILE`.
+/// Fails if the command cannot be started, exits unsuccessfully or prints
+/// invalid UTF-8.
+pub fn run_command(cmd: &str, content: &str, rel_path: &str) -> Result<String> {
+    let mut child = shell_command(cmd)
+        .env("CODE2PROMPT_FILE", rel_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run '{cmd}'"))?;
+
+    // Feed stdin from another thread so a command that writes before it has
+    // read everything cannot deadlock on a full pipe.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = content.to_owned();
+    let writer = std::thread::spawn(move || {
+        // A command that ignores its input may close the pipe early; that's fine.
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to wait for '{cmd}'"))?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        bail!(
+            "'{cmd}' exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).with_context(|| format!("'{cmd}' printed invalid UTF-8"))
+}
+
+// ────────────────────────────────────────────────────────────
+//  Comment stripping
+// ────────────────────────────────────────────────────────────
+
+/// How comments and strings are written in a language.
+pub struct CommentSyntax {
+    pub line: &'static [&'static str],
+    pub block: Option<(&'static str, &'static str)>,
+    /// Characters that delimit single-line string literals.
+    pub quotes: &'static [char],
+}
+
+/// Returns the comment syntax for a file extension, if known.
+pub fn comment_syntax(ext: &str) -> Option<CommentSyntax> {
+    const C_LIKE: CommentSyntax = CommentSyntax {
+        line: &["//"],
+        block: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+    };
+    let syntax = match ext.to_ascii_lowercase().as_str() {
+        // `'` starts lifetimes in Rust, so only `"` delimits strings.
+        "rs" => CommentSyntax {
+            quotes: &['"'],
+            ..C_LIKE
+        },
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "java" | "js" | "jsx" | "mjs" | "cjs"
+        | "ts" | "tsx" | "go" | "cs" | "swift" | "kt" | "kts" | "scala" | "dart" | "php"
+        | "scss" | "less" | "proto" | "zig" | "groovy" | "gradle" => C_LIKE,
+        "css" => CommentSyntax {
+            line: &[],
+            ..C_LIKE
+        },
+        "py" | "rb" | "sh" | "bash" | "zsh" | "fish" | "pl" | "r" | "yaml" | "yml" | "toml"
+        | "nix" | "ps1" | "cmake" | "mk" | "tf" | "ex" | "exs" | "jl" => CommentSyntax {
+            line: &["#"],
+            block: None,
+            quotes: &['"', '\''],
+        },
+        "sql" | "lua" | "hs" | "elm" => CommentSyntax {
+            line: &["--"],
+            block: None,
+            quotes: &['"', '\''],
+        },
+        "html" | "htm" | "xml" | "svg" | "vue" | "svelte" | "md" => CommentSyntax {
+            line: &[],
+            block: Some(("<!--", "-->")),
+            quotes: &[],
+        },
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+/// Removes comments, dropping lines that only contained a comment and
+/// trailing whitespace left behind by end-of-line comments.
+pub fn strip_comments(code: &str, syntax: &CommentSyntax) -> String {
+    let mut stripped = String::with_capacity(code.len());
+    let mut rest = code;
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(q) = quote {
+            let len = if c == '\\' {
+                // Keep the escaped character too.
+                rest.chars().take(2).map(char::len_utf8).sum()
+            } else {
+                c.len_utf8()
+            };
+            if c == q || c == '\n' {
+                quote = None;
+            }
+            stripped.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+
+        if syntax.quotes.contains(&c) {
+            quote = Some(c);
+        } else if syntax.line.iter().any(|p| rest.starts_with(p)) {
+            rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+            continue;
+        } else if let Some((open, close)) = syntax.block
+            && rest.starts_with(open)
+        {
+            let body = &rest[open.len()..];
+            let end = body.find(close).map_or(body.len(), |i| i + close.len());
+            // Keep the newlines so lines stay aligned with the original.
+            stripped.extend(body[..end].chars().filter(|&c| c == '\n'));
+            rest = &body[end..];
+            continue;
+        }
+        stripped.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    stripped
+        .split('\n')
+        .zip(code.split('\n'))
+        .filter(|(new, old)| !new.trim().is_empty() || old.trim().is_empty())
+        .map(|(new, old)| if new == old { new } else { new.trim_end() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ────────────────────────────────────────────────────────────
+//  Redaction and truncation
+// ────────────────────────────────────────────────────────────
+
+static REDACTIONS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
+    [
+        (
+            r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+            "[REDACTED PRIVATE KEY]",
+        ),
+        (r"\bAKIA[0-9A-Z]{16}\b", "[REDACTED]"),
+        (r"\bgh[pousr]_[A-Za-z0-9]{36,}\b", "[REDACTED]"),
+        (r"\bxox[abprs]-[A-Za-z0-9-]{10,}", "[REDACTED]"),
+        (r"(?i-u)(bearer\s+)[A-Za-z0-9._~+/-]{16,}=*", "${1}[REDACTED]"),
+        (
+            r#"((?i-u:api[_-]?key|secret|token|passw(?:or)?d|access[_-]?key)[A-Za-z0-9_]*["']?\s*[:=]\s*["']?)[^\s"',;]{6,}"#,
+            "${1}[REDACTED]",
+        ),
+    ]
+    .into_iter()
+    .map(|(re, with)| (Regex::new(re).expect("valid redaction regex"), with))
+    .collect()
+});
+
+/// Masks values that look like credentials.
+pub fn redact(content: &str) -> Cow<'_, str> {
+    REDACTIONS
+        .iter()
+        .fold(Cow::Borrowed(content), |acc, (re, with)| {
+            match re.replace_all(&acc, *with) {
+                Cow::Owned(s) => Cow::Owned(s),
+                Cow::Borrowed(_) => acc,
+            }
+        })
+}
+
+/// Keeps the first `max_lines` lines, noting how many were cut.
+/// Returns `None` if the content is already short enough.
+pub fn truncate_lines(content: &str, max_lines: usize) -> Option<String> {
+    let total = content.lines().count();
+    if total <= max_lines {
+        return None;
+    }
+    let mut out: String = content
+        .lines()
+        .take(max_lines)
+        .flat_map(|l| [l, "\n"])
+        .collect();
+    out.push_str(&format!(
+        "... [{} more lines truncated]\n",
+        total - max_lines
+    ));
+    Some(out)
+}
@@ -1,7 +1,19 @@
-use std::{cell::RefCell, fs, path::Path, sync::Arc, time::SystemTime};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::BTreeSet,
+    fs,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
-use anyhow::{Context, Result};
-use crossbeam_channel::{Sender, unbounded};
+use anyhow::{Context, Result, bail};
+use crossbeam_channel::{Sender, bounded};
+use glob::Pattern;
 use globset::GlobSet;
 use ignore::{DirEntry, WalkBuilder, WalkState};
 #[cfg(feature = "logging")]
@@ -15,12 +27,31 @@ use crate::common::{
     path::{self},
 };
 use crate::engine::{
-    cache::ScanCache, config::Code2PromptConfig, filter::should_include_file,
-    model::ProcessedEntry, token::count_tokens,
+    cache::{CacheWrite, CacheWriter, ScanCache},
+    config::Code2PromptConfig,
+    deps, embeddings, encoding,
+    filter::{ChangeFilter, HiddenFilter, should_include_file},
+    model::{ContentSource, ProcessedEntry, RunSummary, ScanError, ScanErrorKind, SkippedFiles},
+    notebook,
+    tags::has_any_tag,
+    test_files,
+    token::{count_tokens, estimate_tokens},
 };
 
 const MAX_FILE_SIZE_BYTES: u64 = 1_048_576; // 1 MiB
 
+/// The largest notebook read when notebooks are cleaned: outputs and images
+/// make up most of a notebook, so the size limit applies to the cleaned text.
+const MAX_NOTEBOOK_SIZE_BYTES: u64 = 64 * MAX_FILE_SIZE_BYTES;
+
+/// Files a worker collects before handing them to the aggregator
+/// (`Code2PromptConfig::scan_batch_size`).
+pub const DEFAULT_SCAN_BATCH_SIZE: usize = 256;
+/// Longest a worker holds on to a partial batch.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+/// Batches in flight; workers wait when the aggregator falls behind.
+const CHANNEL_CAPACITY: usize = 64;
+
 // ────────────────────────────────────────────────────────────
 // Public enum (unchanged)
 // ────────────────────────────────────────────────────────────
@@ -30,59 +61,211 @@ pub enum ProcessingMode {
     ExtensionCollection,
 }
 
+/// Progress of a running scan: one message per processed file, with that
+/// file's counts, sent to `Code2PromptConfig::progress`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanProgress {
+    pub files: usize,
+    pub tokens: usize,
+}
+
+/// A timed part of a scan, see [`PhaseTimings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// The whole walk, wall-clock.
+    Walk,
+    /// Reading and preparing file contents.
+    Read,
+    Tokenize,
+    /// Looking up and storing token counts in the scan cache.
+    Cache,
+}
+
+/// Time spent in each phase of a scan, collected when set as
+/// `Code2PromptConfig::timings` (`code2prompt bench`). All phases but
+/// `Walk` are summed over the walker's threads.
+#[derive(Debug, Default)]
+pub struct PhaseTimings {
+    nanos: [AtomicU64; 4],
+}
+
+impl PhaseTimings {
+    pub fn add(&self, phase: Phase, elapsed: Duration) {
+        self.nanos[phase as usize].fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, phase: Phase) -> Duration {
+        Duration::from_nanos(self.nanos[phase as usize].load(Ordering::Relaxed))
+    }
+}
+
+/// Why a file the walk visited is not in the prompt, see [`ScanStats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Not matching the include patterns, or matching an exclude pattern.
+    Filtered,
+    Empty,
+    /// Larger than 1 MiB.
+    TooLarge,
+    /// Not readable as text.
+    Unreadable,
+    /// No line matching `--grep`.
+    NoGrepMatch,
+    /// Dropped to fit the token budget.
+    OverBudget,
+    /// Not in the `--max-files` sample.
+    NotSampled,
+}
+
+/// Counts of the full scans run with a config, shown in the run summary.
+/// Clones of the config share them, so files added with
+/// `Code2PromptSession::include_files` are counted too.
+#[derive(Debug, Default)]
+pub struct ScanStats {
+    visited: AtomicUsize,
+    skipped: [AtomicUsize; 7],
+    bytes_read: AtomicU64,
+    cache_hits: AtomicUsize,
+    cache_misses: AtomicUsize,
+    walk_nanos: AtomicU64,
+}
+
+impl ScanStats {
+    pub fn skip(&self, reason: SkipReason, files: usize) {
+        self.skipped[reason as usize].fetch_add(files, Ordering::Relaxed);
+    }
+
+    pub fn skipped(&self, reason: SkipReason) -> usize {
+        self.skipped[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// The counts, with the prompt-related fields left to the caller.
+    pub fn summary(&self) -> RunSummary {
+        let get = |n: &AtomicUsize| n.load(Ordering::Relaxed);
+        RunSummary {
+            files_scanned: get(&self.visited),
+            skipped: SkippedFiles {
+                filtered: self.skipped(SkipReason::Filtered),
+                empty: self.skipped(SkipReason::Empty),
+                too_large: self.skipped(SkipReason::TooLarge),
+                unreadable: self.skipped(SkipReason::Unreadable),
+                no_grep_match: self.skipped(SkipReason::NoGrepMatch),
+                over_budget: self.skipped(SkipReason::OverBudget),
+                not_sampled: self.skipped(SkipReason::NotSampled),
+                not_selected: 0,
+            },
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            cache_hits: get(&self.cache_hits),
+            cache_misses: get(&self.cache_misses),
+            scan_duration_ms: self.walk_nanos.load(Ordering::Relaxed) / 1_000_000,
+            ..Default::default()
+        }
+    }
+}
+
 // ────────────────────────────────────────────────────────────
-// Private payloads sent once per worker thread
+// Private payloads sent by the worker threads
 // ────────────────────────────────────────────────────────────
 enum Batch {
     Entries(Vec<ProcessedEntry>),
+    Errors(Vec<ScanError>),
     Ext(HashMap<String, usize>),
     Dir(HashMap<String, usize>),
 }
 
 // ────────────────────────────────────────────────────────────
-// One Worker per thread – aggregates locally, emits in Drop
+// One Worker per thread – aggregates locally, emits in batches
 // ────────────────────────────────────────────────────────────
 struct Worker {
     mode: ProcessingMode,
     cfg: Arc<Code2PromptConfig>,
     tx: Sender<Batch>,
+    /// Where scan cache writes go, see [`CacheWriter`].
+    cache_writes: Option<Sender<CacheWrite>>,
+    last_flush: Instant,
 
     // only allocated when needed
     entries: Vec<ProcessedEntry>,
+    errors: Vec<ScanError>,
     ext_cnt: HashMap<String, usize>,
     dir_cnt: HashMap<String, usize>,
 }
 
 impl Worker {
-    fn new(mode: ProcessingMode, cfg: Arc<Code2PromptConfig>, tx: Sender<Batch>) -> Self {
+    fn new(
+        mode: ProcessingMode,
+        cfg: Arc<Code2PromptConfig>,
+        tx: Sender<Batch>,
+        cache_writes: Option<Sender<CacheWrite>>,
+    ) -> Self {
         Self {
             mode,
             cfg,
             tx,
+            cache_writes,
+            last_flush: Instant::now(),
             entries: Vec::new(),
+            errors: Vec::new(),
             ext_cnt: HashMap::default(),
             dir_cnt: HashMap::default(),
         }
     }
+
+    /// Hands over what was collected once the batch is full or old enough.
+    fn flush_if_due(&mut self) {
+        let pending = self.entries.len().max(self.dir_cnt.len());
+        if pending >= self.cfg.scan_batch_size.max(1)
+            || (pending > 0 && self.last_flush.elapsed() >= FLUSH_INTERVAL)
+        {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.entries.is_empty() {
+            let _ = self
+                .tx
+                .send(Batch::Entries(std::mem::take(&mut self.entries)));
+        }
+        if !self.errors.is_empty() {
+            let _ = self
+                .tx
+                .send(Batch::Errors(std::mem::take(&mut self.errors)));
+        }
+        if !self.ext_cnt.is_empty() {
+            let _ = self.tx.send(Batch::Ext(std::mem::take(&mut self.ext_cnt)));
+        }
+        if !self.dir_cnt.is_empty() {
+            let _ = self.tx.send(Batch::Dir(std::mem::take(&mut self.dir_cnt)));
+        }
+        self.last_flush = Instant::now();
+    }
+
+    fn push_entry(&mut self, entry: ProcessedEntry) {
+        if let Some(progress) = &self.cfg.progress {
+            let _ = progress.send(ScanProgress {
+                files: 1,
+                tokens: entry.token_count.unwrap_or(0),
+            });
+        }
+        self.entries.push(entry);
+        self.flush_if_due();
+    }
+
+    /// Runs `f`, adding its duration to `phase` if the scan is timed.
+    fn timed<T>(&self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let Some(timings) = &self.cfg.timings else {
+            return f();
+        };
+        let start = Instant::now();
+        let out = f();
+        timings.add(phase, start.elapsed());
+        out
+    }
 }
 impl Drop for Worker {
     fn drop(&mut self) {
-        match self.mode {
-            ProcessingMode::FullProcess if !self.entries.is_empty() => {
-                let _ = self
-                    .tx
-                    .send(Batch::Entries(std::mem::take(&mut self.entries)));
-            }
-            ProcessingMode::ExtensionCollection => {
-                if !self.ext_cnt.is_empty() {
-                    let _ = self.tx.send(Batch::Ext(std::mem::take(&mut self.ext_cnt)));
-                }
-                if !self.dir_cnt.is_empty() {
-                    let _ = self.tx.send(Batch::Dir(std::mem::take(&mut self.dir_cnt)));
-                }
-            }
-            _ => {}
-        }
+        self.flush();
     }
 }
 
@@ -96,6 +279,10 @@ thread_local! {
 // ────────────────────────────────────────────────────────────
 // Public entry point
 // ────────────────────────────────────────────────────────────
+/// Walks `cfg.path`. Returns the processed files (none when collecting
+/// extensions), the file counts by extension and by directory, and the
+/// paths that could not be read, sorted.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(cfg)))]
 pub fn process_codebase(
     cfg: &Code2PromptConfig,
     mode: ProcessingMode,
@@ -103,65 +290,152 @@ pub fn process_codebase(
     Vec<ProcessedEntry>,
     HashMap<String, usize>,
     HashMap<String, usize>,
+    Vec<ScanError>,
 )> {
-    let include_glob = build_globset(&cfg.include_patterns)?;
-    let exclude_glob = build_globset(&cfg.exclude_patterns)?;
-
-    let root = cfg
-        .path
-        .canonicalize()
+    let root = path::canonicalize(&cfg.path)
         .with_context(|| format!("Failed to canonicalize {}", cfg.path.display()))?;
 
-    // Single channel for all workers
-    let (tx, rx) = unbounded::<Batch>();
-
-    // ── start parallel walker ───────────────────────────────
-    WalkBuilder::new(&root)
-        .follow_links(cfg.follow_symlinks)
-        .hidden(!cfg.hidden)
-        .git_ignore(!cfg.no_ignore)
-        .build_parallel()
-        .run(|| {
-            let tx = tx.clone();
-            let cfg = Arc::new(cfg.clone());
-            let inc = include_glob.clone();
-            let exc = exclude_glob.clone();
-            let root = root.clone();
-
-            let mut w = Worker::new(mode, cfg, tx);
-
-            Box::new(move |res| {
-                THREAD_CACHE.with(|c| {
-                    // Lazily initialize the cache for this thread if needed.
-                    if w.cfg.cache && c.borrow().is_none() {
-                        *c.borrow_mut() = ScanCache::open(&root).ok();
-                    }
-
-                    // Now, handle the entry using the cache reference from within the closure.
-                    // c.borrow().as_ref() correctly yields an `Option<&ScanCache>`.
-                    handle_entry(res, &root, &inc, &exc, &mut w, c.borrow().as_ref());
-                });
-
-                WalkState::Continue
-            })
-        });
-
-    drop(tx); // close channel
-
-    // ── Aggregate batches ───────────────────────────────────
+    // Dependency and query selection add the chosen files as literal include
+    // patterns.
+    let mut include_patterns = cfg.include_patterns.clone();
+    if matches!(mode, ProcessingMode::FullProcess) {
+        let mut selected = Vec::new();
+        if !(cfg.with_deps.is_empty() && cfg.with_dependents.is_empty()) {
+            selected.extend(deps::related_files(cfg, &root)?);
+        }
+        if cfg.query.is_some() {
+            let matches = embeddings::select_files(cfg, &root)?;
+            if matches.is_empty() {
+                bail!("--query found no indexable files");
+            }
+            selected.extend(matches);
+        }
+        for file in selected {
+            include_patterns.push(Pattern::new(&Pattern::escape(&file))?);
+        }
+        // The tests of everything selected so far, unless excluded.
+        if cfg.with_tests && !include_patterns.is_empty() {
+            let files = list_files(&root, cfg)?;
+            let inc = build_globset(&include_patterns)?;
+            let exc = build_globset(&cfg.exclude_patterns)?;
+            let sources: Vec<&str> = files
+                .iter()
+                .filter(|f| {
+                    should_include_file(
+                        Path::new(f),
+                        Path::new(""),
+                        &inc,
+                        &exc,
+                        cfg.include_priority,
+                    )
+                })
+                .map(String::as_str)
+                .collect();
+            for test in test_files::tests_for(&sources, &files) {
+                include_patterns.push(Pattern::new(&Pattern::escape(&test))?);
+            }
+        }
+    }
+    let include_glob = build_globset(&include_patterns)?;
+    let exclude_glob = build_globset(&cfg.exclude_patterns)?;
+    let touched = touched_files(cfg, &root)?;
+    let cache_key = if cfg.cache { cfg.cache_key()? } else { None };
+    let prepare = prepare_hash(cfg);
+    let cache_writer = (cfg.cache && mode == ProcessingMode::FullProcess)
+        .then(|| ScanCache::open(&root).ok())
+        .flatten()
+        .map(CacheWriter::spawn);
+    let cache_writes = cache_writer.as_ref().map(CacheWriter::sender);
+
+    // Single bounded channel for all workers, drained while they run
+    let (tx, rx) = bounded::<Batch>(CHANNEL_CAPACITY);
     let mut entries = Vec::new();
+    let mut errors = Vec::new();
     let mut ext_cnt = HashMap::default();
     let mut dir_cnt = HashMap::default();
 
-    while let Ok(batch) = rx.recv() {
-        match batch {
-            Batch::Entries(mut v) => entries.append(&mut v),
-            Batch::Ext(m) => merge_usize(&mut ext_cnt, m),
-            Batch::Dir(m) => merge_usize(&mut dir_cnt, m),
+    let walk_start = Instant::now();
+    std::thread::scope(|s| {
+        // ── start parallel walker ───────────────────────────────
+        s.spawn(move || {
+            walk_builder(&root, cfg, None)
+                .follow_links(cfg.follow_symlinks)
+                .git_ignore(!cfg.no_ignore)
+                .threads(cfg.threads.unwrap_or(0))
+                .build_parallel()
+                .run(|| {
+                    let tx = tx.clone();
+                    let cfg = Arc::new(cfg.clone());
+                    let inc = include_glob.clone();
+                    let exc = exclude_glob.clone();
+                    let root = root.clone();
+                    let touched = touched.clone();
+                    let cache_key = cache_key.clone();
+
+                    let mut w = Worker::new(mode, cfg, tx, cache_writes.clone());
+
+                    Box::new(move |res| {
+                        THREAD_CACHE.with(|c| {
+                            // Lazily initialize the cache for this thread if needed.
+                            if w.cfg.cache && c.borrow().is_none() {
+                                *c.borrow_mut() = w.timed(Phase::Cache, || {
+                                    ScanCache::open(&root).ok().map(|cache| {
+                                        cache.with_key(cache_key.clone()).with_prepare_hash(prepare)
+                                    })
+                                });
+                            }
+
+                            // Now, handle the entry using the cache reference from within the closure.
+                            // c.borrow().as_ref() correctly yields an `Option<&ScanCache>`.
+                            handle_entry(
+                                res,
+                                &root,
+                                &inc,
+                                &exc,
+                                touched.as_deref(),
+                                &mut w,
+                                c.borrow().as_ref(),
+                            );
+                        });
+
+                        WalkState::Continue
+                    })
+                });
+            // `tx` drops here, closing the channel once every worker is done,
+            // and so does `cache_writes`.
+        });
+
+        // ── Aggregate batches ───────────────────────────────────
+        while let Ok(batch) = rx.recv() {
+            match batch {
+                Batch::Entries(mut v) => entries.append(&mut v),
+                Batch::Errors(mut v) => errors.append(&mut v),
+                Batch::Ext(m) => merge_usize(&mut ext_cnt, m),
+                Batch::Dir(m) => merge_usize(&mut dir_cnt, m),
+            }
         }
+    });
+    if let Some(writer) = cache_writer {
+        let cache_start = Instant::now();
+        let _failed = writer.finish()?;
+        #[cfg(feature = "logging")]
+        if _failed > 0 {
+            warn!("{_failed} files could not be written to the scan cache");
+        }
+        if let Some(timings) = &cfg.timings {
+            timings.add(Phase::Cache, cache_start.elapsed());
+        }
+    }
+    if let Some(timings) = &cfg.timings {
+        timings.add(Phase::Walk, walk_start.elapsed());
+    }
+    if mode == ProcessingMode::FullProcess {
+        let nanos = walk_start.elapsed().as_nanos() as u64;
+        cfg.stats.walk_nanos.fetch_add(nanos, Ordering::Relaxed);
     }
 
-    Ok((entries, ext_cnt, dir_cnt))
+    errors.sort_by(|a: &ScanError, b| a.path.cmp(&b.path));
+    Ok((entries, ext_cnt, dir_cnt, errors))
 }
 
 // ────────────────────────────────────────────────────────────
@@ -172,6 +446,7 @@ fn handle_entry(
     root: &Path,
     inc: &GlobSet,
     exc: &GlobSet,
+    touched: Option<&BTreeSet<String>>,
     w: &mut Worker,
     cache: Option<&ScanCache>,
 ) {
@@ -180,14 +455,29 @@ fn handle_entry(
         Err(e) => {
             #[cfg(feature = "logging")]
             warn!("Walk error: {e}");
+            if w.mode == ProcessingMode::FullProcess {
+                w.errors.push(walk_error(&e, root));
+            }
             return;
         }
     };
 
-    if !should_include_file(entry.path(), root, inc, exc, w.cfg.include_priority) {
+    let is_file = entry.file_type().is_some_and(|ft| ft.is_file());
+    let stats = (is_file && w.mode == ProcessingMode::FullProcess).then_some(&w.cfg.stats);
+    if let Some(stats) = &stats {
+        stats.visited.fetch_add(1, Ordering::Relaxed);
+    }
+    if !should_include_file(entry.path(), root, inc, exc, w.cfg.include_priority)
+        || (is_file && !carries_wanted_tag(entry.path(), root, &w.cfg))
+        || (is_file && !owned_by_wanted(entry.path(), root, &w.cfg))
+        || (is_file && !touched_by_wanted_commits(entry.path(), root, touched))
+    {
+        if let Some(stats) = &stats {
+            stats.skip(SkipReason::Filtered, 1);
+        }
         return;
     }
-    if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+    if !is_file {
         return; // skip dirs/symlinks here
     }
 
@@ -197,6 +487,60 @@ fn handle_entry(
     }
 }
 
+/// Whether a file passes `--tag`: it carries one of the wanted tags, or no
+/// tags are wanted.
+fn carries_wanted_tag(path: &Path, root: &Path, cfg: &Code2PromptConfig) -> bool {
+    if cfg.tag_filter.is_empty() {
+        return true;
+    }
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    has_any_tag(&cfg.tags, &path::to_fwd_slash(rel), &cfg.tag_filter)
+}
+
+/// Whether a file passes `--owner`: one of the wanted owners owns it, or no
+/// owners are wanted.
+fn owned_by_wanted(path: &Path, root: &Path, cfg: &Code2PromptConfig) -> bool {
+    if cfg.owner_filter.is_empty() {
+        return true;
+    }
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    cfg.codeowners
+        .is_owned_by(&path::to_fwd_slash(rel), &cfg.owner_filter)
+}
+
+/// The files touched by the commits `--changed-since` and `--author` select,
+/// or `None` when neither is given.
+fn touched_files(cfg: &Code2PromptConfig, root: &Path) -> Result<Option<Arc<BTreeSet<String>>>> {
+    let filter = ChangeFilter {
+        since: cfg.changed_since,
+        author: cfg.author.clone(),
+    };
+    if !filter.is_active() {
+        return Ok(None);
+    }
+    #[cfg(feature = "git")]
+    {
+        let touched = crate::engine::git::touched_files(root, &filter)
+            .context("--changed-since and --author need a git repository")?;
+        Ok(Some(Arc::new(touched)))
+    }
+    #[cfg(not(feature = "git"))]
+    {
+        let _ = root;
+        bail!("--changed-since and --author require the 'git' feature")
+    }
+}
+
+/// Whether a file passes `--changed-since` and `--author`: a selected
+/// commit touched it, or no commits are selected.
+fn touched_by_wanted_commits(path: &Path, root: &Path, touched: Option<&BTreeSet<String>>) -> bool {
+    let Some(touched) = touched else {
+        return true;
+    };
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    touched.contains(&path::to_fwd_slash(rel))
+}
+
 // ────────────────────────────────────────────────────────────
 //  ExtensionCollection fast path
 // ────────────────────────────────────────────────────────────
@@ -212,80 +556,330 @@ fn collect_ext_dir(path: &Path, root: &Path, w: &mut Worker) {
     if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
         *w.ext_cnt.entry(ext.to_ascii_lowercase()).or_default() += 1;
     }
+    w.flush_if_due();
 }
 
 // ────────────────────────────────────────────────────────────
 //  FullProcess path
 // ────────────────────────────────────────────────────────────
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path.strip_prefix(root).unwrap_or(path).display()))
+)]
 fn process_file(path: &Path, root: &Path, w: &mut Worker, cache: Option<&ScanCache>) {
     // --- Calculate relative path ONCE at the top ---
     let rel_path = path.strip_prefix(root).unwrap_or(path);
     let rel_path_str = path::to_fwd_slash(rel_path);
 
     // ------- cache fast path -------
+    let max_size = max_file_size(rel_path, &w.cfg);
     if let Ok(md) = fs::metadata(path) {
-        if md.len() == 0 || md.len() > MAX_FILE_SIZE_BYTES {
+        if md.len() == 0 || md.len() > max_size {
+            let reason = if md.len() == 0 {
+                SkipReason::Empty
+            } else {
+                SkipReason::TooLarge
+            };
+            w.cfg.stats.skip(reason, 1);
             return;
         }
         let mtime = md.modified().ok();
         // The `rel_path_str` is already calculated above
         if let (Some(c), Some(mt)) = (cache, mtime) {
-            if let Ok(Some(hit)) = c.lookup(&rel_path_str, mt, md.len()) {
+            let hit = w.timed(Phase::Cache, || {
+                c.lookup(&rel_path_str, mt, md.len(), w.cfg.tokenizer)
+            });
+            if let Ok(Some(hit)) = hit {
+                w.cfg.stats.cache_hits.fetch_add(1, Ordering::Relaxed);
+                if hit.stale
+                    && let Some(writes) = &w.cache_writes
+                {
+                    let _ = writes.send(CacheWrite::Touch {
+                        rel_path: rel_path_str.clone(),
+                        tokenizer: w.cfg.tokenizer,
+                    });
+                }
                 // CACHE HIT: Create entry with `code: None`. No I/O!
-                w.entries.push(make_entry(
+                let mut entry = make_entry(
                     path,
                     rel_path,
                     None, // Pass None for code
                     &w.cfg,
                     Some(hit.token_count),
                     Some(mt),
-                ));
+                );
+                entry.sha256 = Some(hit.sha256);
+                w.push_entry(entry);
                 return;
             }
+            w.cfg.stats.cache_misses.fetch_add(1, Ordering::Relaxed);
         }
     }
 
     // ------- slow path -------
-    let code = match fs::read_to_string(path) {
-        Ok(c) => c,
+    let code = match w.timed(Phase::Read, || encoding::read_source(path)) {
+        Ok(src) => {
+            #[cfg(feature = "logging")]
+            if let Some(enc) = src.transcoded_from {
+                warn!("Transcoded {} from {enc} to UTF-8", path.display());
+            }
+            src.text
+        }
         Err(e) => {
             #[cfg(feature = "logging")]
             warn!("Skipping {} ({e})", path.display());
+            w.cfg.stats.skip(SkipReason::Unreadable, 1);
+            // Files that are not text are skipped, not failed.
+            if e.kind() != std::io::ErrorKind::InvalidData {
+                w.errors.push(ScanError {
+                    path: Some(rel_path_str),
+                    kind: io_error_kind(&e, path),
+                    message: e.to_string(),
+                });
+            }
             return;
         }
     };
 
+    w.cfg
+        .stats
+        .bytes_read
+        .fetch_add(code.len() as u64, Ordering::Relaxed);
+    let content = w.timed(Phase::Read, || prepare_content(&code, rel_path, &w.cfg));
+    if max_size > MAX_FILE_SIZE_BYTES && content.len() as u64 > MAX_FILE_SIZE_BYTES {
+        w.cfg.stats.skip(SkipReason::TooLarge, 1);
+        return;
+    }
+
     // --- (passing rel_path) ---
     let mut entry = make_entry(
         path,
         rel_path, // pass the pre-calculated relative path
-        Some(&code),
+        Some(&content),
         &w.cfg,
         None,
         None,
     );
 
     if w.cfg.token_map_enabled {
-        entry.token_count = count_tokens(&code, w.cfg.tokenizer).ok();
+        entry.token_count = w.timed(Phase::Tokenize, || {
+            if w.cfg.approximate_tokens {
+                Some(estimate_tokens(&content, w.cfg.tokenizer))
+            } else {
+                count_tokens(&content, w.cfg.tokenizer).ok()
+            }
+        });
+    }
+
+    let caching = cache.is_some() && w.cache_writes.is_some();
+    if caching || w.cfg.provenance {
+        entry.sha256 = Some(Sha256::digest(code.as_bytes()).into());
     }
 
-    // insert into cache
-    if let (Some(c), Some(tok)) = (cache, entry.token_count) {
+    // insert into cache (estimates would shadow exact counts, so skip those)
+    if let (Some(c), Some(writes), Some(tok), Some(digest), false) = (
+        cache,
+        &w.cache_writes,
+        entry.token_count,
+        entry.sha256,
+        w.cfg.approximate_tokens,
+    ) {
         if let Ok(md) = fs::metadata(path) {
             if let Ok(mt) = md.modified() {
-                let digest = Sha256::digest(code.as_bytes());
                 // Use the `rel_path_str` from the top of the function
-                let _ = c.insert(&rel_path_str, mt, md.len(), digest.into(), tok, Some(&code));
+                let row = w.timed(Phase::Cache, || {
+                    c.row(
+                        &rel_path_str,
+                        mt,
+                        md.len(),
+                        digest,
+                        w.cfg.tokenizer,
+                        tok,
+                        (!w.cfg.no_content_cache).then_some(code.as_str()),
+                    )
+                });
+                if let Ok(row) = row {
+                    let _ = w.timed(Phase::Cache, || writes.send(CacheWrite::Insert(row)));
+                }
             }
         }
     }
 
-    w.entries.push(entry);
+    w.push_entry(entry);
 }
 
 // ────────────────────────────────────────────────────────────
 //  Utils
 // ────────────────────────────────────────────────────────────
+
+/// Describes an error of the walker, with the path it names if any.
+fn walk_error(err: &ignore::Error, root: &Path) -> ScanError {
+    let rel = |p: &Path| path::to_fwd_slash(p.strip_prefix(root).unwrap_or(p));
+    let mut inner = err;
+    let mut path = None;
+    loop {
+        match inner {
+            ignore::Error::WithPath { path: p, err } => {
+                path.get_or_insert_with(|| rel(p));
+                inner = err;
+            }
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+                inner = err
+            }
+            ignore::Error::Loop { child, .. } => {
+                return ScanError {
+                    path: Some(rel(child)),
+                    kind: ScanErrorKind::SymlinkLoop,
+                    message: err.to_string(),
+                };
+            }
+            _ => break,
+        }
+    }
+    let kind = match (err.io_error(), &path) {
+        (Some(io), Some(p)) => io_error_kind(io, &root.join(p)),
+        _ => ScanErrorKind::Other,
+    };
+    ScanError {
+        path,
+        kind,
+        message: inner.to_string(),
+    }
+}
+
+/// A missing `path` is a broken symlink if the link itself still exists.
+fn io_error_kind(err: &std::io::Error, path: &Path) -> ScanErrorKind {
+    match err.kind() {
+        std::io::ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+        std::io::ErrorKind::NotFound if path.is_symlink() => ScanErrorKind::BrokenSymlink,
+        std::io::ErrorKind::NotFound => ScanErrorKind::Vanished,
+        _ => ScanErrorKind::Other,
+    }
+}
+
+/// A walker over `root` applying the hidden-file settings: `--hidden` keeps
+/// everything, `--hidden-files` and `--hidden-dirs` keep some hidden entries.
+/// Directories matching `prune` as `dir/` are not descended into.
+fn walk_builder(root: &Path, cfg: &Code2PromptConfig, prune: Option<GlobSet>) -> WalkBuilder {
+    let mut walker = WalkBuilder::new(root);
+    let filter = HiddenFilter {
+        files: cfg.hidden_files,
+        dirs: cfg.hidden_dirs.clone(),
+    };
+    let filter = (!cfg.hidden && filter.is_active()).then_some(filter);
+    walker.hidden(!cfg.hidden && filter.is_none());
+    if filter.is_none() && prune.is_none() {
+        return walker;
+    }
+    let root = root.to_path_buf();
+    walker.filter_entry(move |e| {
+        let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+        let pruned = || {
+            let rel = e.path().strip_prefix(&root).unwrap_or(e.path());
+            prune
+                .as_ref()
+                .is_some_and(|p| p.is_match(format!("{}/", path::to_fwd_slash(rel))))
+        };
+        e.depth() == 0
+            || (filter
+                .as_ref()
+                .is_none_or(|f| f.allows(e.file_name(), is_dir))
+                && !(is_dir && pruned()))
+    });
+    walker
+}
+
+/// Lists the files under `root` (relative, forward-slash paths), honouring
+/// ignore files and exclude patterns but not include patterns. Excluded
+/// directories, such as `target/` or `.git/`, are not walked.
+pub(crate) fn list_files(root: &Path, cfg: &Code2PromptConfig) -> Result<Vec<String>> {
+    let exclude = build_globset(&cfg.exclude_patterns)?;
+    let files = walk_builder(root, cfg, Some(exclude.clone()))
+        .follow_links(cfg.follow_symlinks)
+        .git_ignore(!cfg.no_ignore)
+        .build()
+        .flatten()
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .filter(|e| {
+            let rel = e.path().strip_prefix(root).unwrap_or(e.path());
+            e.metadata()
+                .is_ok_and(|m| m.len() <= max_file_size(rel, cfg))
+        })
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(root).ok()?;
+            Some(path::to_fwd_slash(rel))
+        })
+        .filter(|rel| !exclude.is_match(rel))
+        .collect();
+    Ok(files)
+}
+
+/// Applies content-level cleaning (newline normalization, notebook conversion,
+/// configured transforms) to raw file content before it is counted and
+/// wrapped. The cache always stores the raw content, so this must also run
+/// when code is loaded back from the cache.
+pub(crate) fn prepare_content<'a>(
+    raw: &'a str,
+    rel_path: &Path,
+    cfg: &Code2PromptConfig,
+) -> Cow<'a, str> {
+    let mut content = if cfg.normalize_newlines {
+        code::normalize_newlines(raw)
+    } else {
+        Cow::Borrowed(raw)
+    };
+    let ext = rel_path.extension().and_then(|e| e.to_str());
+    if cfg.notebook.is_enabled()
+        && notebook::is_notebook(ext)
+        && let Some(cleaned) = notebook::clean_notebook(&content, &cfg.notebook)
+    {
+        content = Cow::Owned(cleaned);
+    }
+    if cfg.transforms.is_empty() {
+        return content;
+    }
+    let rel = path::to_fwd_slash(rel_path);
+    cfg.transforms.apply(content, &rel)
+}
+
+/// The largest file at `rel_path` that is read: notebooks that are cleaned
+/// may be larger, as long as their cleaned text is not.
+fn max_file_size(rel_path: &Path, cfg: &Code2PromptConfig) -> u64 {
+    let ext = rel_path.extension().and_then(|e| e.to_str());
+    if cfg.notebook.is_enabled() && notebook::is_notebook(ext) {
+        MAX_NOTEBOOK_SIZE_BYTES
+    } else {
+        MAX_FILE_SIZE_BYTES
+    }
+}
+
+/// Whether [`prepare_content`] returns the content of `rel_path` unchanged.
+pub(crate) fn prepares_as_is(rel_path: &Path, cfg: &Code2PromptConfig) -> bool {
+    let ext = rel_path.extension().and_then(|e| e.to_str());
+    let cleans_notebook = cfg.notebook.is_enabled() && notebook::is_notebook(ext);
+    !cfg.normalize_newlines && !cleans_notebook && cfg.transforms.is_empty()
+}
+
+/// A hash of the options [`prepare_content`] applies, which the scan cache
+/// keeps with token counts so that counts of differently prepared content
+/// are not reused.
+pub(crate) fn prepare_hash(cfg: &Code2PromptConfig) -> i64 {
+    let nb = &cfg.notebook;
+    let mut hasher = Sha256::new();
+    hasher.update([
+        cfg.normalize_newlines as u8,
+        nb.convert as u8,
+        nb.drop_outputs as u8,
+        nb.drop_images as u8,
+        nb.drop_execution_counts as u8,
+    ]);
+    for spec in cfg.transforms.specs() {
+        hasher.update(serde_json::to_vec(spec).unwrap_or_default());
+    }
+    let digest = hasher.finalize();
+    i64::from_le_bytes(digest[..8].try_into().expect("digest is 32 bytes"))
+}
+
 fn make_entry(
     path: &Path,
     relative_path: &Path,
@@ -295,14 +889,7 @@ fn make_entry(
     mtime: Option<SystemTime>,
 ) -> ProcessedEntry {
     let ext = path.extension().and_then(|e| e.to_str()).map(str::to_owned);
-    let wrapped_code = code_str.map(|c| {
-        code::wrap(
-            c,
-            ext.as_deref().unwrap_or(""),
-            cfg.line_numbers,
-            cfg.no_codeblock,
-        )
-    });
+    let wrapped_code = code_str.map(|c| cfg.wrap_code(c, ext.as_deref()));
     ProcessedEntry {
         path: path.to_path_buf(),
         relative_path: relative_path.to_path_buf(),
@@ -311,5 +898,7 @@ fn make_entry(
         extension: ext,
         token_count: tok_cnt,
         mtime,
+        source: code_str.map(|_| ContentSource::Disk),
+        sha256: None,
     }
 }
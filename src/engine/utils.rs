@@ -1,4 +1,5 @@
 use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use anyhow::{Context, Result};
 use sha2::{Digest, Sha256};
@@ -35,11 +36,34 @@ impl RepoCachePath {
     }
 
     pub fn get_cache_file_path(&self, prefix: &str, extension: &str) -> Result<PathBuf> {
-        let cache_dir = dirs::cache_dir()
-            .unwrap_or_else(std::env::temp_dir)
-            .join("code2prompt");
+        let cache_dir = cache_dir();
         std::fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
 
         Ok(cache_dir.join(format!("{}_{}.{}", prefix, self.repo_hash, extension)))
     }
 }
+
+/// The directory holding the caches, selections and history of every
+/// repository.
+pub fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("code2prompt")
+}
+
+/// Builds a command that runs `cmd` through the platform shell
+/// (`sh -c` or `cmd /C`), so users can write pipes and redirections.
+pub fn shell_command(cmd: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(cmd);
+        c
+    }
+    #[cfg(not(windows))]
+    {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(cmd);
+        c
+    }
+}
@@ -1,10 +1,10 @@
 use anyhow::Result;
-use clap::Parser;
 
 // ──────────────────────────────────────────────────────────────
 //  Entry point
 // ──────────────────────────────────────────────────────────────
 fn main() -> Result<()> {
-   let args = code2prompt_tui::ui::cli::Cli::parse();
+   let argv = code2prompt_tui::ui::cli::args_with_aliases()?;
+   let args = code2prompt_tui::ui::cli::parse_args(argv);
    code2prompt_tui::app_controller::run(args)
 }
@@ -0,0 +1,168 @@
+//! The arrange screen, shown after the selector when it is confirmed with
+//! `A`: lists the selected files in the order they will be rendered and lets
+//! `J`/`K` move them down and up.
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+
+use crate::common::format::{self, TokenFormatStyle};
+use crate::ui::tui_select::{
+    SignalGuard, TerminalGuard, drain_input_buffer, install_panic_hook, pane_block, setup_terminal,
+};
+
+const HELP_TEXT: &str =
+    "j/k: Move cursor | J/K: Move file down/up | Enter: Render | q/Esc: Keep order";
+
+/// The files being arranged, as `(path, tokens)` pairs, and the cursor.
+#[derive(Debug, Clone, Default)]
+pub struct Arrangement {
+    pub files: Vec<(String, usize)>,
+    pub cursor: usize,
+}
+
+impl Arrangement {
+    pub fn new(files: Vec<(String, usize)>) -> Self {
+        Self { files, cursor: 0 }
+    }
+
+    pub fn next(&mut self) {
+        if self.cursor + 1 < self.files.len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Moves the file under the cursor one place down, along with the cursor.
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.files.len() {
+            self.files.swap(self.cursor, self.cursor + 1);
+            self.cursor += 1;
+        }
+    }
+
+    /// Moves the file under the cursor one place up, along with the cursor.
+    pub fn move_up(&mut self) {
+        if self.cursor > 0 {
+            self.files.swap(self.cursor, self.cursor - 1);
+            self.cursor -= 1;
+        }
+    }
+
+    /// The paths, in their arranged order.
+    pub fn order(&self) -> Vec<String> {
+        self.files.iter().map(|(path, _)| path.clone()).collect()
+    }
+}
+
+/// Shows the arrange screen. Returns the arranged paths, or `None` if the
+/// screen was left without confirming, to keep the current order.
+pub fn arrange_files_tui(
+    repo_name: &str,
+    files: Vec<(String, usize)>,
+) -> Result<Option<Vec<String>>> {
+    install_panic_hook();
+    let signals = SignalGuard::install()?;
+    let mut guard = TerminalGuard(setup_terminal()?);
+    drain_input_buffer()?;
+
+    let mut arrangement = Arrangement::new(files);
+    let mut list_state = ListState::default();
+    loop {
+        if signals.interrupted.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        list_state.select(Some(arrangement.cursor));
+        guard
+            .0
+            .draw(|f| ui(f, repo_name, &arrangement, &mut list_state))?;
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(None);
+            }
+            KeyCode::Enter => return Ok(Some(arrangement.order())),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+            KeyCode::Down | KeyCode::Char('j') => arrangement.next(),
+            KeyCode::Up | KeyCode::Char('k') => arrangement.previous(),
+            KeyCode::Char('J') => arrangement.move_down(),
+            KeyCode::Char('K') => arrangement.move_up(),
+            _ => {}
+        }
+    }
+}
+
+fn ui(f: &mut Frame, repo_name: &str, arrangement: &Arrangement, list_state: &mut ListState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+    f.render_widget(
+        Paragraph::new(format!("code2prompt ▸ {repo_name} ▸ arrange"))
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+
+    let width = arrangement.files.len().to_string().len();
+    let items: Vec<ListItem> = arrangement
+        .files
+        .iter()
+        .enumerate()
+        .map(|(i, (path, tokens))| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:>width$}. ", i + 1),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(path.as_str()),
+                Span::styled(
+                    format!(
+                        "  {} tokens",
+                        format::format_tokens(*tokens, TokenFormatStyle::Compact)
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+    let title = Span::styled(
+        format!(" Render order ({} files) ", arrangement.files.len()),
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    );
+    let list = List::new(items)
+        .block(pane_block(true).title(title))
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[1], list_state);
+    f.render_widget(Paragraph::new(HELP_TEXT), chunks[2]);
+}
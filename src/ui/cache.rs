@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -13,6 +13,19 @@ use crate::{
 pub struct LastSelection {
     pub extensions: Vec<String>,
     pub directories: Vec<String>,
+    /// Files pinned in the tree pane, kept whatever the other selections.
+    #[serde(default)]
+    pub pinned: Vec<String>,
+}
+
+impl LastSelection {
+    /// Reads a selection file given with `--select-file`.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read selection file {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid selection file {}", path.display()))
+    }
 }
 
 impl Cacheable for LastSelection {
@@ -43,6 +56,7 @@ pub fn save_last_selection(
     let selection = LastSelection {
         extensions: extensions.to_vec(),
         directories: directories.to_vec(),
+        ..Default::default()
     };
     let json = serde_json::to_string_pretty(&selection)?;
     std::fs::write(cache_path, json)?;
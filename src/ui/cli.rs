@@ -1,11 +1,22 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
-use clap::{Parser, ValueEnum};
+use anyhow::{Result, bail};
+use clap::parser::ValueSource;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use regex::Regex;
 
 use crate::engine::config::{OutputFormat, TokenFormat};
+use crate::engine::config_file;
 use crate::engine::model::ProcessedEntry;
+use crate::engine::presets::Preset;
+use crate::engine::priority::FileOrder;
+use crate::engine::sample::SampleStrategy;
+use crate::engine::symbols::SymbolSource;
 use crate::engine::token::TokenizerChoice;
+use crate::engine::token_map::{TokenMapGroupBy, TokenMapSort};
+use crate::ui::preview::PreviewMode;
 
 // Define an enum for the sort argument for type safety
 #[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
@@ -28,6 +39,43 @@ impl FileSortMethod {
     }
 }
 
+/// What `--clipboard-content` copies to the clipboard.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClipboardContent {
+    /// The rendered prompt
+    #[default]
+    Prompt,
+    /// The JSON payload printed by `-F json`
+    Json,
+    /// The source tree only
+    Tree,
+    /// The processed file paths, one per line
+    FileList,
+}
+
+/// How `--print-selection` prints the files confirmed in the TUI.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelectionFormat {
+    /// The selection as JSON, in the shape `--select-file` reads
+    #[default]
+    Json,
+    /// Shell-quoted `-i` arguments, one per selected file
+    Args,
+}
+
+/// Where the scan starts when the path is below a repository's root.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RootMode {
+    /// Offer to scan from the nearest directory with `.git` or
+    /// `.code2prompt` above the path, when run from a terminal
+    #[default]
+    Auto,
+    /// Scan the path as given
+    Cwd,
+    /// Scan from the root of the git repository containing the path
+    Git,
+}
+
 // ~~~ CLI Arguments ~~~
 #[derive(Parser, Debug, Clone)]
 #[clap(
@@ -37,6 +85,7 @@ impl FileSortMethod {
 )]
 #[command(
     arg_required_else_help = true,
+    args_conflicts_with_subcommands = true,
     after_help = r#"EXAMPLES:
     code2prompt .
         Scans the current directory interactively.
@@ -53,8 +102,17 @@ impl FileSortMethod {
   "#
 )]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[clap(default_value = ".")]
     pub path: PathBuf,
 
+    /// Scan from the repository root when PATH is a subdirectory: auto
+    /// offers it in a terminal, git always does it and cwd scans PATH as given
+    #[clap(long, value_name = "MODE", default_value = "auto")]
+    pub root: RootMode,
+
     /// Patterns to include, comma-separated
     #[clap(short = 'i', long = "include", value_delimiter = ',')]
     pub include: Vec<String>,
@@ -63,6 +121,12 @@ pub struct Cli {
     #[clap(short = 'e', long = "exclude", value_delimiter = ',')]
     pub exclude: Vec<String>,
 
+    /// Exclude curated globs for the ecosystems detected from the repository's
+    /// manifests (Rust, JS, Python, Go), comma-separated; ci-context instead
+    /// includes only CI workflows, Dockerfiles, Makefiles and build scripts
+    #[clap(long, value_name = "PRESET", value_delimiter = ',')]
+    pub preset: Vec<Preset>,
+
     /// File extensions to include, comma-separated (e.g. "rs,toml")
     #[clap(long = "extensions", value_delimiter = ',')]
     pub extensions: Vec<String>,
@@ -71,17 +135,41 @@ pub struct Cli {
     #[clap(long)]
     pub include_priority: bool,
 
-    /// Optional output file path
+    /// Optional output file path. Supports {repo}, {date}, {time}, {timestamp},
+    /// {tokens} and {files} placeholders, e.g. "prompts/{repo}-{date}-{tokens}tok.md".
+    /// Files it names inside the scanned directory are left out of the scan.
+    /// Give one per -T when rendering several templates
     #[clap(short = 'O', long = "output-file")]
-    pub output_file: Option<String>,
-
-    /// Output format: markdown, json, or xml
+    pub output_file: Vec<String>,
+
+    /// Write one prompt per top-level directory, each with the shared
+    /// preamble and tree: prompt-src.md, prompt-docs.md, ... (named after
+    /// and placed next to -O when given)
+    #[clap(long, conflicts_with = "no_template")]
+    pub split_by_dir: bool,
+
+    /// Also write a response skeleton to PATH: the included files, each with
+    /// empty "Notes" and "Changes" sections, for structured reviews
+    #[clap(long, value_name = "PATH", conflicts_with = "no_template")]
+    pub scaffold: Option<PathBuf>,
+
+    /// Output format: markdown, json, or xml; rag-jsonl writes one JSON
+    /// record per file (path, language, tokens, content, sha256) instead;
+    /// messages writes chat messages, the files split to fit --max-tokens;
+    /// claude-xml puts the files in indexed <document> tags first
     #[clap(short = 'F', long = "output-format", default_value_t = OutputFormat::Markdown)]
     pub output_format: OutputFormat,
 
-    /// Optional Path to a custom Handlebars template
+    /// Optional Path to a custom Handlebars template. Repeat it, with an -O
+    /// for each, to render several templates from a single scan; each output
+    /// gets the post-render hook and a history entry
     #[clap(short = 'T', long)]
-    pub template: Option<PathBuf>,
+    pub template: Vec<PathBuf>,
+
+    /// Skip the template and write each file as "=== path ===" followed by
+    /// its contents, straight to the output file or stdout
+    #[clap(long, conflicts_with = "template")]
+    pub no_template: bool,
 
     /// Inline template variable, e.g., -V issue=123 -V author="Ada L." (repeatable)
     #[clap(short = 'V', long = "var", value_parser = parse_key_val, number_of_values = 1)]
@@ -91,6 +179,30 @@ pub struct Cli {
     #[clap(long = "vars-file")]
     pub vars_file: Option<PathBuf>,
 
+    /// Instructions placed before the templated body ("text" or "@file")
+    #[clap(long, value_name = "TEXT|@FILE")]
+    pub prepend: Option<String>,
+
+    /// Instructions placed after the templated body ("text" or "@file")
+    #[clap(long, value_name = "TEXT|@FILE")]
+    pub append: Option<String>,
+
+    /// Fetch a GitHub or GitLab issue (a number of the `origin` repository,
+    /// or a URL) and expose it as the `issue` template variable. Sends
+    /// GITHUB_TOKEN to github.com and GITLAB_TOKEN to gitlab.com; other hosts
+    /// need GH_HOST (with GH_ENTERPRISE_TOKEN) or GITLAB_HOST to name them
+    #[clap(long, value_name = "NUMBER|URL")]
+    pub issue: Option<String>,
+
+    /// Ask "What do you want the model to do?" after file selection and
+    /// expose the answer as the `task` template variable
+    #[clap(long)]
+    pub ask_task: bool,
+
+    /// Save the rendered prompt to the history (see `code2prompt history`)
+    #[clap(long)]
+    pub history: bool,
+
     /// List discovered templates and exit.
     #[clap(long = "list-templates")]
     pub list_templates: bool,
@@ -99,10 +211,28 @@ pub struct Cli {
     #[clap(long = "no-var-cache")]
     pub no_var_cache: bool,
 
+    /// List the cached values of the template's variables and offer to edit
+    /// them before they are reused (in the TUI when the selector is shown)
+    #[clap(long, conflicts_with_all = ["no_var_cache", "no_interactive"])]
+    pub review_vars: bool,
+
     /// List the full directory tree (opposite of current exclude_from_tree)
     #[clap(long)]
     pub full_directory_tree: bool,
 
+    /// Show each file's ID (e.g. F1a2b3c, also {{id}} in templates) in the
+    /// source tree, for the model to cite files by
+    #[clap(long)]
+    pub file_ids: bool,
+
+    /// Leave the source tree out of the prompt.
+    #[clap(long, conflicts_with = "tree_only")]
+    pub no_tree: bool,
+
+    /// Only include the source tree, without the files' contents.
+    #[clap(long)]
+    pub tree_only: bool,
+
     /// Tokenizer to use for token counting.
     ///
     /// Supported: o200k_base, cl100k
@@ -111,9 +241,54 @@ pub struct Cli {
 
     /// Display the token count of the generated prompt.
     /// Accepts a format: "raw" (machine parsable) or "format" (human readable).
+    /// "approximate" also estimates per-file counts from their size instead of
+    /// encoding every file, which is much faster on large repositories.
     #[clap(long, value_name = "FORMAT", default_value_t = TokenFormat::Format)]
     pub tokens: TokenFormat,
 
+    /// Token budget for the prompt (default: the tokenizer's context window).
+    /// Exceeding it prints the largest files and directories to exclude.
+    #[clap(long, value_name = "N")]
+    pub max_tokens: Option<usize>,
+
+    /// Drop the lowest-priority files until the file contents fit the token budget
+    #[clap(long)]
+    pub trim_to_budget: bool,
+
+    /// Keep at most N files, picked by --sample-strategy, e.g. for a
+    /// representative slice of a huge repository
+    #[clap(long, value_name = "N")]
+    pub max_files: Option<usize>,
+
+    /// Which files --max-files keeps
+    #[clap(long, value_name = "STRATEGY", default_value = "stratified")]
+    pub sample_strategy: SampleStrategy,
+
+    /// Cap every file at N tokens, keeping its head and tail around an
+    /// elision marker
+    #[clap(long, value_name = "N")]
+    pub max_file_tokens: Option<usize>,
+
+    /// Cap each git diff at N tokens, dropping context lines first and then
+    /// whole files, largest first
+    #[clap(long, value_name = "N")]
+    pub max_diff_tokens: Option<usize>,
+
+    /// Priority weight for a glob, e.g. --priority "src/core/**=10" (repeatable).
+    /// Higher-priority files are placed first and trimmed last.
+    #[clap(long, value_name = "GLOB=WEIGHT", value_parser = parse_priority_arg)]
+    pub priority: Vec<(String, i32)>,
+
+    /// Don't place READMEs, manifests (Cargo.toml, package.json, ...) and
+    /// docs overviews before the other files
+    #[clap(long)]
+    pub no_priority_docs: bool,
+
+    /// File listing relative paths, one per line, to place first and in that
+    /// order; the other files follow in the usual order
+    #[clap(long, value_name = "PATH", value_parser = parse_order_file_arg)]
+    pub order_file: Option<FileOrder>,
+
     #[clap(short, long)]
     pub diff: bool,
 
@@ -129,6 +304,22 @@ pub struct Cli {
     #[clap(short, long)]
     pub line_numbers: bool,
 
+    /// Number given to the first line when adding line numbers (for excerpts)
+    #[clap(long, value_name = "N", default_value_t = 1)]
+    pub line_numbers_start: usize,
+
+    /// Minimum width of the line-number column (default: fit the largest number)
+    #[clap(long, value_name = "N")]
+    pub line_numbers_width: Option<usize>,
+
+    /// Separator between the line number and the code
+    #[clap(long, value_name = "STR", default_value = " | ")]
+    pub line_numbers_separator: String,
+
+    /// Strip BOMs and convert CRLF line endings to LF
+    #[clap(long)]
+    pub normalize_newlines: bool,
+
     /// Use relative paths instead of absolute paths
     #[clap(long)]
     pub relative_paths: bool,
@@ -141,14 +332,37 @@ pub struct Cli {
     #[clap(long)]
     pub hidden: bool,
 
+    /// Include hidden files, but not what is inside hidden directories,
+    /// e.g. .env.example but not .cache/
+    #[clap(long)]
+    pub hidden_files: bool,
+
+    /// Hidden directories to descend into, comma-separated
+    /// (e.g. ".github,.devcontainer")
+    #[clap(long, value_name = "DIRS", value_delimiter = ',')]
+    pub hidden_dirs: Vec<String>,
+
     /// Disable wrapping code inside markdown code blocks
     #[clap(long)]
     pub no_codeblock: bool,
 
+    /// Preview the rendered prompt in a pager, or in the browser with `--preview html`
+    #[clap(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "terminal"
+    )]
+    pub preview: Option<PreviewMode>,
+
     /// Disable copying to clipboard
     #[clap(long)]
     pub no_clipboard: bool,
 
+    /// What to copy to the clipboard: prompt, json, tree or file-list
+    #[clap(long, value_name = "CONTENT", default_value = "prompt")]
+    pub clipboard_content: ClipboardContent,
+
     /// Skip .gitignore rules
     #[clap(long)]
     pub no_ignore: bool,
@@ -157,10 +371,67 @@ pub struct Cli {
     #[clap(long)]
     pub no_default_excludes: bool,
 
+    /// Don't detect the project type from its manifests (Cargo.toml,
+    /// package.json, pyproject.toml, go.mod) to apply matching default
+    /// excludes and code fence languages
+    #[clap(long)]
+    pub no_auto_detect: bool,
+
     /// Disable all interactive prompts (for use in scripts)
     #[clap(long)]
     pub no_interactive: bool,
 
+    /// Log more: -v for warnings such as skipped files, -vv for debug
+    /// output (needs the 'logging' feature; RUST_LOG applies otherwise)
+    #[clap(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Print only the prompt and errors: no token counts, notices or summary
+    #[clap(short, long)]
+    pub quiet: bool,
+
+    /// Record spans of the scan, token counting and rendering to PATH in the
+    /// Chrome trace format, for chrome://tracing or Perfetto (needs the
+    /// 'tracing' feature)
+    #[clap(long, value_name = "PATH")]
+    pub trace_out: Option<PathBuf>,
+
+    /// List the paths the scan could not read (permission denied, broken
+    /// symlinks, files that vanished) after the summary
+    #[clap(long)]
+    pub show_errors: bool,
+
+    /// Apply a selection saved by the TUI (JSON, same shape as its cached
+    /// selection) instead of opening it
+    #[clap(long, value_name = "FILE")]
+    pub select_file: Option<PathBuf>,
+
+    /// Apply the selection last confirmed in the TUI for this repository
+    /// instead of opening it
+    #[clap(long, conflicts_with = "select_file")]
+    pub use_last_selection: bool,
+
+    /// Print the selection confirmed in the TUI instead of generating a
+    /// prompt
+    #[clap(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "json"
+    )]
+    pub print_selection: Option<SelectionFormat>,
+
+    /// Ask for the file types and folders with plain numbered prompts
+    /// instead of the full-screen selector, e.g. for screen readers
+    #[clap(long)]
+    pub simple_ui: bool,
+
+    /// List the N largest files by tokens after the scan, to drop them from
+    /// the prompt or exclude them from future scans, instead of opening the
+    /// selector
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub top_files: Option<u16>,
+
     /// Sort order for files
     #[clap(long)]
     pub sort: Option<FileSortMethod>,
@@ -173,6 +444,23 @@ pub struct Cli {
     #[clap(long, value_name = "NUMBER")]
     pub token_map_lines: Option<usize>,
 
+    /// Group the token map by directory, file extension or git author
+    #[clap(long, value_name = "MODE", default_value = "dir")]
+    pub token_map_group_by: TokenMapGroupBy,
+
+    /// Sort token map entries by share of tokens or by name
+    #[clap(long, value_name = "ORDER", default_value = "percent")]
+    pub token_map_sort: TokenMapSort,
+
+    /// Write every file's token count as JSON, to compare runs with
+    /// `code2prompt map-diff`
+    #[clap(long, value_name = "PATH")]
+    pub token_map_json: Option<PathBuf>,
+
+    /// Highlight token map bars of entries above this percentage
+    #[clap(long, value_name = "PERCENT")]
+    pub token_map_threshold: Option<f64>,
+
     /// [DEBUG] Print the experimental directory tree and exit
     #[clap(long, hide = true)]
     pub experimental_tree: bool,
@@ -183,6 +471,292 @@ pub struct Cli {
 
     #[clap(long)]
     pub cache: bool,
+
+    /// Store only token counts in the scan cache, not file contents
+    #[clap(long)]
+    pub no_content_cache: bool,
+
+    /// Report whether each file's content came from the scan cache or a
+    /// fresh disk read, with its SHA-256, after the prompt or in the JSON
+    /// output
+    #[clap(long)]
+    pub provenance: bool,
+
+    /// Threads for scanning and reading files [default: one per CPU]
+    #[clap(long, value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// Scan with the lowest CPU and IO priority (Unix), on one thread unless
+    /// --threads is given, to keep laptops and network drives responsive
+    #[clap(long)]
+    pub low_priority: bool,
+
+    /// Files each scan thread collects before handing them over; lower it
+    /// to reduce peak memory on huge repositories [default: 256]
+    #[clap(long, value_name = "N")]
+    pub scan_batch_size: Option<usize>,
+
+    /// Render through a running `code2prompt daemon`, falling back to a
+    /// normal scan when none is listening or a flag it does not support is set
+    #[clap(long)]
+    pub use_daemon: bool,
+
+    /// Expose an index of every file's public symbols as `symbol_index` in
+    /// the template, using built-in patterns or Universal Ctags (--symbols=ctags)
+    #[clap(
+        long,
+        value_name = "SOURCE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "builtin"
+    )]
+    pub symbols: Option<SymbolSource>,
+
+    /// Select these files plus everything they (transitively) import, resolved
+    /// per language. Comma-delimited or repeated; paths are relative to the
+    /// repository or the working directory
+    #[clap(long, value_name = "PATH", value_delimiter = ',')]
+    pub with_deps: Vec<PathBuf>,
+
+    /// Select these files plus every file that (transitively) imports them,
+    /// i.e. the code affected by changing them. Comma-delimited or repeated
+    #[clap(long, value_name = "PATH", value_delimiter = ',')]
+    pub with_dependents: Vec<PathBuf>,
+
+    /// Also select the tests of the included files, by each ecosystem's
+    /// naming conventions (foo_test.go, tests/test_foo.py, foo.spec.ts, ...)
+    #[clap(long)]
+    pub with_tests: bool,
+
+    /// Follow at most this many levels of imports for --with-deps and
+    /// --with-dependents
+    #[clap(long, value_name = "N")]
+    pub dep_depth: Option<usize>,
+
+    /// Select the files most relevant to this description, ranked by
+    /// embedding similarity (see the `[embeddings]` config section)
+    #[clap(long, value_name = "TEXT")]
+    pub query: Option<String>,
+
+    /// How many files --query selects
+    #[clap(long, value_name = "N", default_value_t = 10, requires = "query")]
+    pub top_k: usize,
+
+    /// Include only files whose content matches this regular expression
+    #[clap(long, value_name = "REGEX", value_parser = parse_grep_pattern)]
+    pub grep: Option<Regex>,
+
+    /// Trim files selected by --grep to the matching lines and N lines around
+    /// them
+    #[clap(long, value_name = "N", requires = "grep")]
+    pub grep_context: Option<usize>,
+
+    /// Include only files carrying one of these tags, from
+    /// `.code2prompt/tags.toml` (comma-separated or repeated)
+    #[clap(long, value_name = "TAG", value_delimiter = ',')]
+    pub tag: Vec<String>,
+
+    /// Include only files owned by one of these teams or users in the
+    /// CODEOWNERS file, e.g. @org/payments (comma-separated or repeated)
+    #[clap(long, value_name = "OWNER", value_delimiter = ',')]
+    pub owner: Vec<String>,
+
+    /// Include only files changed by commits since this date (YYYY-MM-DD,
+    /// UTC), from the git history
+    #[clap(long, value_name = "DATE", value_parser = parse_date_arg)]
+    pub changed_since: Option<i64>,
+
+    /// Include only files changed by commits of this author, matched against
+    /// part of the name or email, e.g. alice@
+    #[clap(long, value_name = "AUTHOR")]
+    pub author: Option<String>,
+
+    /// Convert Jupyter notebooks (.ipynb) to a compact markdown-like form
+    #[clap(long)]
+    pub notebook_clean: bool,
+
+    /// Drop output cells from Jupyter notebooks (implies --notebook-clean)
+    #[clap(long)]
+    pub notebook_no_outputs: bool,
+
+    /// Drop base64-encoded images from notebook outputs (implies --notebook-clean)
+    #[clap(long)]
+    pub notebook_no_images: bool,
+
+    /// Drop execution counts from notebook cells (implies --notebook-clean)
+    #[clap(long)]
+    pub notebook_no_exec_counts: bool,
+
+    /// Ids of the arguments given rather than left at their defaults, as
+    /// recorded by [`parse_args`]
+    #[clap(skip)]
+    pub given: Vec<String>,
+}
+
+/// Subcommands that run instead of a scan.
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Work with Handlebars templates
+    #[command(subcommand)]
+    Template(TemplateCommand),
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Recall prompts saved with --history
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+        /// Repository whose history to use
+        #[arg(long, default_value = ".", global = true)]
+        repo: PathBuf,
+    },
+    /// Serve `POST /render` over HTTP for editor plugins and other tools
+    Serve {
+        /// Directories whose repositories may be rendered
+        #[arg(default_value = ".")]
+        roots: Vec<PathBuf>,
+        /// Address to listen on
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8080")]
+        http: String,
+    },
+    /// Build a commit-message prompt from the staged changes
+    Commit {
+        /// Repository with the staged changes
+        #[arg(default_value = ".")]
+        repo: PathBuf,
+        /// Also include the full contents of the changed files
+        #[arg(long)]
+        with_files: bool,
+        /// Send the prompt to the `[llm]` endpoint of the config file and
+        /// print the reply instead
+        #[arg(long)]
+        send: bool,
+    },
+    /// Build a code-review prompt for a branch: its diff against the base
+    /// branch, the changed files in full, the commit log and a tree overview
+    Review {
+        /// Repository to review
+        #[arg(default_value = ".")]
+        repo: PathBuf,
+        /// Branch the changes will be merged into
+        #[arg(long, default_value = "main")]
+        base: String,
+        /// Branch to review [default: the current branch]
+        #[arg(long)]
+        head: Option<String>,
+    },
+    /// Time the walk, read, tokenize, cache and render phases on a
+    /// repository, without and with the scan cache
+    Bench {
+        /// Repository to benchmark
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Scan a repository into the scan cache, counting the tokens of every
+    /// file and storing its contents, without rendering a prompt, so later
+    /// runs with --cache start warm (e.g. nightly in CI or on login)
+    WarmCache {
+        /// Repository to scan
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Compare two token maps written with --token-map-json and print how
+    /// many tokens each directory gained or lost
+    MapDiff {
+        /// Token map of the earlier run
+        old: PathBuf,
+        /// Token map of the later run
+        new: PathBuf,
+        /// Directory levels to group files by
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        depth: usize,
+        /// Fail if the total grew by more than this percentage, e.g. in CI
+        #[arg(long, value_name = "PERCENT")]
+        max_growth: Option<f64>,
+    },
+    /// Keep a warm index in memory and answer `--use-daemon` runs (Unix only)
+    Daemon {
+        /// Directories whose repositories may be rendered
+        #[arg(default_value = ".")]
+        roots: Vec<PathBuf>,
+        /// Socket to listen on [default: daemon.sock in the cache directory]
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TemplateCommand {
+    /// Compile a template, list the variables it needs and test-render it
+    Check {
+        /// Path to the Handlebars template
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigCommand {
+    /// Validate the config files and print every effective setting with
+    /// where it comes from: a flag, the config file, the repository config,
+    /// the detected ecosystems or the defaults
+    Check {
+        /// Path and flags of a scan, to include them in the merge,
+        /// e.g. `config check . --line-numbers`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        scan: Vec<String>,
+    },
+    /// Write a commented starter config and a starter template to the
+    /// user's config directory
+    Init {
+        /// Write them to the project's `.code2prompt/` directory instead
+        #[arg(long)]
+        project: bool,
+        /// Project to write them to with --project
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum HistoryCommand {
+    /// List saved prompts, most recent first
+    List,
+    /// Print a saved prompt
+    Show {
+        /// Entry number as shown by `history list` (1 = most recent)
+        #[arg(default_value_t = 1)]
+        n: usize,
+    },
+    /// Copy a saved prompt to the clipboard
+    Copy {
+        /// Entry number as shown by `history list` (1 = most recent)
+        #[arg(default_value_t = 1)]
+        n: usize,
+    },
+}
+
+/// A clap value-parser that reads the `--order-file`.
+fn parse_order_file_arg(s: &str) -> Result<FileOrder, String> {
+    FileOrder::load(Path::new(s)).map_err(|e| format!("{e:#}"))
+}
+
+/// A clap value-parser for `--priority GLOB=WEIGHT` arguments.
+fn parse_priority_arg(s: &str) -> Result<(String, i32), String> {
+    crate::engine::priority::parse_priority(s).map_err(|e| format!("{e:#}"))
+}
+
+/// A clap value-parser for `--grep REGEX` arguments.
+fn parse_grep_pattern(s: &str) -> Result<Regex, String> {
+    crate::engine::grep::parse_pattern(s).map_err(|e| format!("{e:#}"))
+}
+
+/// A clap value-parser for `--changed-since`: a date, in seconds since the
+/// Unix epoch.
+fn parse_date_arg(s: &str) -> Result<i64, String> {
+    crate::common::format::parse_date(s).ok_or_else(|| format!("'{s}' is not a YYYY-MM-DD date"))
 }
 
 /// A clap value-parser for `-V key=value` arguments.
@@ -191,3 +765,117 @@ fn parse_key_val(s: &str) -> Result<(String, String), String> {
         .map(|(k, v)| (k.to_owned(), v.to_owned()))
         .ok_or_else(|| "Variable must be in KEY=value format".to_string())
 }
+
+/// Parses `args` like [`Parser::parse_from`], recording in [`Cli::given`]
+/// which arguments they give.
+pub fn parse_args(args: Vec<OsString>) -> Cli {
+    let cmd = <Cli as clap::CommandFactory>::command();
+    let matches = cmd.clone().get_matches_from(args);
+    let mut cli =
+        <Cli as clap::FromArgMatches>::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // The ids also name argument groups, such as the one of the struct.
+    cli.given = matches
+        .ids()
+        .filter(|id| cmd.get_arguments().any(|arg| arg.get_id() == *id))
+        .filter(|id| {
+            matches!(
+                matches.value_source(id.as_str()),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        })
+        .map(|id| id.to_string())
+        .collect();
+    cli
+}
+
+// ~~~ Aliases ~~~
+
+/// The command line, with the aliases of the config in effect in the
+/// current directory expanded.
+pub fn args_with_aliases() -> Result<Vec<OsString>> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    if !args.iter().skip(1).any(|arg| alias_name(arg).is_some()) {
+        return Ok(args);
+    }
+    let cfg = config_file::load_for(Path::new("."))?;
+    expand_aliases(args, &cfg.aliases.unwrap_or_default())
+}
+
+/// Replaces each `@name` argument with the arguments of the alias `name`,
+/// split as a shell would. Arguments after `--`, values of options (as in
+/// `--append @TODO`), `@name`s of undefined aliases and `@name`s within an
+/// alias are kept as they are.
+pub fn expand_aliases(
+    args: Vec<OsString>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<Vec<OsString>> {
+    let cmd = <Cli as clap::CommandFactory>::command();
+    let mut expanded = Vec::with_capacity(args.len());
+    let mut args = args.into_iter();
+    // The program name.
+    expanded.extend(args.next());
+    let mut is_value = false;
+    for arg in args.by_ref() {
+        if arg == "--" {
+            expanded.push(arg);
+            break;
+        }
+        let value = match alias_name(&arg) {
+            Some(name) if !is_value => aliases.get(name).map(|value| (name, value)),
+            _ => None,
+        };
+        is_value = takes_value(&cmd, &arg);
+        let Some((name, value)) = value else {
+            expanded.push(arg);
+            continue;
+        };
+        let Some(words) = shlex::split(value) else {
+            bail!("The alias @{name} is not valid shell syntax: {value}");
+        };
+        expanded.extend(words.into_iter().map(OsString::from));
+    }
+    expanded.extend(args);
+    Ok(expanded)
+}
+
+/// Whether `arg` is an option, of the command or one of its subcommands,
+/// whose value is the next argument.
+fn takes_value(cmd: &clap::Command, arg: &OsStr) -> bool {
+    fn options(cmd: &clap::Command) -> Vec<&clap::Arg> {
+        cmd.get_arguments()
+            .chain(cmd.get_subcommands().flat_map(options))
+            .filter(|a| !a.is_positional() && a.get_action().takes_values())
+            .collect()
+    }
+    let Some(arg) = arg.to_str().filter(|a| !a.contains('=')) else {
+        return false;
+    };
+    if let Some(long) = arg.strip_prefix("--") {
+        return options(cmd).iter().any(|a| {
+            a.get_long_and_visible_aliases()
+                .is_some_and(|l| l.contains(&long))
+        });
+    }
+    // A cluster of short flags, such as `-qO`, ending with the option.
+    let Some(shorts) = arg.strip_prefix('-').filter(|s| !s.is_empty()) else {
+        return false;
+    };
+    let options = options(cmd);
+    let takes = |c: char| options.iter().any(|a| a.get_short() == Some(c));
+    let last = shorts.chars().count() - 1;
+    shorts
+        .chars()
+        .enumerate()
+        .find(|&(_, c)| takes(c))
+        .is_some_and(|(i, _)| i == last)
+}
+
+/// `name` if `arg` is `@name`, with a name of letters, digits, `-` and `_`.
+fn alias_name(arg: &OsStr) -> Option<&str> {
+    let name = arg.to_str()?.strip_prefix('@')?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(name)
+}
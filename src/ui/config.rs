@@ -1,10 +1,33 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
 use anyhow::{Context, Result};
 use glob::Pattern;
+use serde::Serialize;
 
-use crate::engine::{config::Code2PromptConfigBuilder, config_file, token::TokenizerChoice};
-use crate::ui::cli::Cli;
+use crate::common::{cache::CacheManager, code::LineNumberFormat, hash::HashMap, path};
+use crate::engine::{
+    codeowners::CodeOwners,
+    config::{Code2PromptConfigBuilder, OutputFormat, TokenFormat},
+    config_file::{self, ConfigFile, RepoConfig},
+    embeddings::{EmbeddingSettings, SemanticQuery},
+    grep::GrepFilter,
+    llm::LlmSettings,
+    notebook::NotebookOptions,
+    presets::{
+        CI_CONTEXT_GLOBS, CI_CONTEXT_HIDDEN_DIRS, Ecosystem, Preset, auto_excludes,
+        auto_fence_languages, detect_ecosystems, expand_presets,
+    },
+    priority::PriorityRule,
+    tags::TagFile,
+    token::TokenizerChoice,
+    transform::TransformPipeline,
+    utils::cache_dir,
+};
+use crate::ui::{cli::Cli, output::split_output_pattern};
 
-const DEFAULT_EXCLUDES: &[&str] = &[
+pub const DEFAULT_EXCLUDES: &[&str] = &[
     ".git/",
     ".svn/",
     ".hg/",
@@ -14,53 +37,346 @@ const DEFAULT_EXCLUDES: &[&str] = &[
     "node_modules/",
 ];
 
+/// Prompts committed to a repository, left out with
+/// `[output] auto_exclude_artifacts = true`.
+pub const ARTIFACT_EXCLUDES: &[&str] = &["**/*.c2p.md", "**/prompt*.md"];
+
 pub fn build_config_builder(
     args: &Cli,
     cfg_file: &config_file::ConfigFile,
     extra: impl FnOnce(&mut Code2PromptConfigBuilder),
 ) -> Code2PromptConfigBuilder {
+    let tokenizer = args
+        .tokenizer
+        .or(cfg_file.tokenizer)
+        .unwrap_or(TokenizerChoice::Cl100k);
     let mut b = Code2PromptConfigBuilder::default();
     b.path(args.path.clone())
         .line_numbers(args.line_numbers || cfg_file.line_numbers.unwrap_or(false))
+        .line_number_format(LineNumberFormat {
+            separator: args.line_numbers_separator.clone(),
+            width: args.line_numbers_width,
+            start: args.line_numbers_start,
+        })
+        .normalize_newlines(args.normalize_newlines || cfg_file.normalize_newlines.unwrap_or(false))
         .absolute_path(!args.relative_paths)
         .full_directory_tree(args.full_directory_tree)
-        .no_codeblock(args.no_codeblock || cfg_file.no_codeblock.unwrap_or(false))
-        .tokenizer(
-            args.tokenizer
-                .or(cfg_file.tokenizer)
-                .unwrap_or(TokenizerChoice::Cl100k),
+        .file_ids(args.file_ids)
+        .no_tree(args.no_tree)
+        .tree_only(args.tree_only)
+        // RAG records, XML documents and --no-template hold the plain file
+        // contents.
+        .no_codeblock(
+            args.no_codeblock
+                || args.no_template
+                || matches!(
+                    args.output_format,
+                    OutputFormat::RagJsonl | OutputFormat::ClaudeXml
+                )
+                || cfg_file.no_codeblock.unwrap_or(false),
+        )
+        .fence_languages(
+            auto_fence_languages(&detected_ecosystems(args))
+                .into_iter()
+                .chain(
+                    cfg_file
+                        .languages
+                        .iter()
+                        .flatten()
+                        .map(|(ext, lang)| (ext.to_ascii_lowercase(), lang.clone())),
+                )
+                .collect::<HashMap<_, _>>(),
         )
+        .tokenizer(tokenizer)
+        .approximate_tokens(args.tokens == TokenFormat::Approximate)
         .hidden(args.hidden)
+        .hidden_files(args.hidden_files || ci_context(args))
+        .hidden_dirs(hidden_dirs(args))
         .no_ignore(args.no_ignore)
         .follow_symlinks(args.follow_symlinks)
-        .include_priority(args.include_priority)
+        .include_priority(args.include_priority || ci_context(args))
         .sort(args.sort.clone())
-        .cache(args.cache);
+        .cache(args.cache)
+        .cache_limits(cfg_file.cache.limits())
+        .no_content_cache(args.no_content_cache || cfg_file.cache.content == Some(false))
+        .cache_key_env(cfg_file.cache.key_env.clone())
+        .provenance(args.provenance)
+        .symbols(args.symbols)
+        .max_file_tokens(args.max_file_tokens)
+        .max_diff_tokens(args.max_diff_tokens)
+        .with_deps(args.with_deps.clone())
+        .with_dependents(args.with_dependents.clone())
+        .with_tests(args.with_tests)
+        .dep_depth(args.dep_depth)
+        .query(args.query.clone().map(|text| SemanticQuery {
+            text,
+            top_k: args.top_k,
+        }))
+        .embeddings(cfg_file.embeddings.clone().unwrap_or_default())
+        .grep(args.grep.clone().map(|regex| GrepFilter {
+            regex,
+            context: args.grep_context,
+        }))
+        .notebook(NotebookOptions {
+            convert: args.notebook_clean,
+            drop_outputs: args.notebook_no_outputs,
+            drop_images: args.notebook_no_images,
+            drop_execution_counts: args.notebook_no_exec_counts,
+        });
+
+    let mut priorities: HashMap<String, i32> = cfg_file.priority.clone().unwrap_or_default();
+    priorities.extend(args.priority.iter().cloned());
+    let rules = priorities
+        .iter()
+        .filter_map(|(glob, weight)| match PriorityRule::new(glob, *weight) {
+            Ok(rule) => Some(rule),
+            Err(_e) => {
+                #[cfg(feature = "logging")]
+                log::warn!("Ignoring priority rule: {_e:#}");
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    b.priorities(rules);
+    match TagFile::load(&args.path).and_then(|tags| tags.rules()) {
+        Ok(rules) => {
+            b.tags(rules);
+        }
+        Err(_e) => {
+            #[cfg(feature = "logging")]
+            log::warn!("Ignoring file tags: {_e:#}");
+        }
+    }
+    b.tag_filter(args.tag.clone());
+    match CodeOwners::load(&args.path) {
+        Ok(owners) => {
+            b.codeowners(owners);
+        }
+        Err(_e) => {
+            #[cfg(feature = "logging")]
+            log::warn!("Ignoring CODEOWNERS: {_e:#}");
+        }
+    }
+    b.owner_filter(args.owner.clone());
+    b.changed_since(args.changed_since);
+    b.author(args.author.clone());
+    b.no_priority_docs(args.no_priority_docs);
+    b.max_files(args.max_files);
+    b.sample_strategy(args.sample_strategy);
+    if let Some(order) = &args.order_file {
+        b.file_order(order.0.clone());
+    }
+
+    b.threads(args.threads.or(args.low_priority.then_some(1)));
+    if let Some(n) = args.scan_batch_size {
+        b.scan_batch_size(n);
+    }
+    b.transforms(build_transforms(cfg_file));
+    if args.trim_to_budget {
+        b.token_budget(
+            args.max_tokens
+                .unwrap_or_else(|| tokenizer.context_window()),
+        );
+    }
 
     extra(&mut b);
     b
 }
 
+/// Compiles the config file's `[transform]` tables, skipping invalid ones.
+pub fn build_transforms(cfg_file: &config_file::ConfigFile) -> TransformPipeline {
+    let mut transforms = TransformPipeline::default();
+    for (glob, spec) in cfg_file.transform.iter().flatten() {
+        if let Err(_e) = transforms.add(glob, spec) {
+            #[cfg(feature = "logging")]
+            log::warn!("Ignoring transform: {_e:#}");
+        }
+    }
+    transforms
+}
+
 pub fn build_include_patterns(args: &Cli) -> Vec<String> {
     let mut inc = args.include.clone();
     inc.extend(args.extensions.iter().map(|e| format!("**/*.{e}")));
+    if ci_context(args) {
+        inc.extend(CI_CONTEXT_GLOBS.iter().map(|s| s.to_string()));
+    }
     inc
 }
 
+/// Whether `--preset ci-context` is given: its files are included even when
+/// hidden or matched by an exclude.
+pub fn ci_context(args: &Cli) -> bool {
+    args.preset.contains(&Preset::CiContext)
+}
+
+/// The hidden directories to descend into: `--hidden-dirs`, and those
+/// holding the files of `ci-context`.
+fn hidden_dirs(args: &Cli) -> Vec<String> {
+    let mut dirs = args.hidden_dirs.clone();
+    if ci_context(args) {
+        dirs.extend(CI_CONTEXT_HIDDEN_DIRS.iter().map(|s| s.to_string()));
+    }
+    dirs
+}
+
 pub fn build_exclude_patterns(
     args: &Cli,
     cfg_file: &config_file::ConfigFile,
     with_defaults: bool,
 ) -> Vec<String> {
-    let mut ex = cfg_file.exclude.clone().unwrap_or_default();
-    ex.extend(args.exclude.clone());
+    exclude_sources(args, cfg_file, with_defaults)
+        .into_iter()
+        .map(|(glob, _)| glob)
+        .collect()
+}
+
+/// The exclude globs, each with where it comes from.
+fn exclude_sources(
+    args: &Cli,
+    cfg_file: &config_file::ConfigFile,
+    with_defaults: bool,
+) -> Vec<(String, Source)> {
+    let mut ex = Vec::new();
+    let mut add = |globs: Vec<String>, source: Source| {
+        ex.extend(globs.into_iter().map(|glob| (glob, source)));
+    };
+    add(
+        cfg_file.exclude.clone().unwrap_or_default(),
+        Source::ConfigFile,
+    );
+    add(args.exclude.clone(), Source::Flag("--exclude"));
+    add(
+        expand_presets(&args.preset, &args.path),
+        Source::Flag("--preset"),
+    );
+    add(repo_excludes(&args.path), Source::RepoConfig);
+    if cfg_file.output.auto_exclude_artifacts.unwrap_or(false) {
+        add(
+            ARTIFACT_EXCLUDES.iter().map(|s| s.to_string()).collect(),
+            Source::ConfigFile,
+        );
+    }
+    add(generated_excludes(args), Source::Generated);
     if with_defaults && !(args.no_default_excludes || cfg_file.no_default_excludes.unwrap_or(false))
     {
-        ex.extend(DEFAULT_EXCLUDES.iter().map(|s| s.to_string()));
+        add(
+            DEFAULT_EXCLUDES.iter().map(|s| s.to_string()).collect(),
+            Source::Default,
+        );
+        add(auto_excludes(&detected_ecosystems(args)), Source::Detected);
     }
     ex
 }
 
+/// Globs for what code2prompt writes itself, so that a run never picks up
+/// earlier prompts or its own state: the project's `.code2prompt/`, and the
+/// cache directory and output files when they are inside the scanned
+/// directory. Placeholders in `-O` match any text, to leave out the prompts
+/// of earlier runs as well.
+pub fn generated_excludes(args: &Cli) -> Vec<String> {
+    let mut globs = vec![".code2prompt/**".to_string()];
+    if let Some(rel) = path_in_root(&args.path, &cache_dir()) {
+        globs.push(format!("{}/**", escape_glob(&rel)));
+    }
+    // With --split-by-dir, the prompts are written next to -O rather than to it.
+    let outputs = if args.split_by_dir {
+        vec![split_output_pattern(
+            args.output_file.first().map(String::as_str),
+        )]
+    } else {
+        args.output_file.clone()
+    };
+    for output in outputs {
+        if let Some(rel) = path_in_root(&args.path, Path::new(&output)) {
+            globs.push(output_glob(&rel));
+        }
+    }
+    for path in [&args.trace_out, &args.token_map_json, &args.scaffold]
+        .into_iter()
+        .flatten()
+    {
+        if let Some(rel) = path_in_root(&args.path, path) {
+            globs.push(escape_glob(&rel));
+        }
+    }
+    globs
+}
+
+/// `file`, which need not exist, relative to `root` if it is inside it.
+pub fn path_in_root(root: &Path, file: &Path) -> Option<String> {
+    let root = path::canonicalize(root).ok()?;
+    let file = if file.is_absolute() {
+        file.to_path_buf()
+    } else {
+        path::canonicalize(&std::env::current_dir().ok()?)
+            .ok()?
+            .join(file)
+    };
+    // Resolve symlinks in the part that exists, such as a temp directory.
+    let (existing, rest) = file
+        .ancestors()
+        .find_map(|a| Some((path::canonicalize(a).ok()?, file.strip_prefix(a).ok()?)))?;
+    let rel = existing.join(rest).strip_prefix(&root).ok()?.to_path_buf();
+    let normal = rel
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if rel.as_os_str().is_empty() || !normal {
+        return None;
+    }
+    Some(path::to_fwd_slash(&rel))
+}
+
+/// A glob matching `s` literally.
+fn escape_glob(s: &str) -> String {
+    let mut glob = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '*' | '?' | '[' | ']' | '{' | '}' => glob.extend(['[', c, ']']),
+            _ => glob.push(c),
+        }
+    }
+    glob
+}
+
+/// A glob matching every path `-O` can expand to: `{...}` placeholders
+/// become `*`.
+pub fn output_glob(pattern: &str) -> String {
+    let mut glob = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{')
+        && let Some(len) = rest[start..].find('}')
+    {
+        glob.push_str(&escape_glob(&rest[..start]));
+        glob.push('*');
+        rest = &rest[start + len + 1..];
+    }
+    glob.push_str(&escape_glob(rest));
+    glob
+}
+
+/// The exclude globs of the repository's own config, added from the TUI.
+fn repo_excludes(path: &Path) -> Vec<String> {
+    CacheManager::new(path)
+        .and_then(|m| m.load::<RepoConfig>())
+        .map(|cfg| cfg.map(|c| c.exclude).unwrap_or_default())
+        .unwrap_or_else(|_e| {
+            #[cfg(feature = "logging")]
+            log::warn!("Ignoring the repository config: {_e:#}");
+            Vec::new()
+        })
+}
+
+/// The ecosystems detected from the manifests in the scanned directory, or
+/// none with `--no-auto-detect`.
+pub fn detected_ecosystems(args: &Cli) -> Vec<Ecosystem> {
+    if args.no_auto_detect {
+        Vec::new()
+    } else {
+        detect_ecosystems(&args.path)
+    }
+}
+
 pub fn patterns_from_strings(v: &[String]) -> Result<Vec<Pattern>> {
     v.iter()
         .map(|p| Pattern::new(p).with_context(|| format!("Invalid glob pattern: '{p}'")))
@@ -70,10 +386,300 @@ pub fn patterns_from_strings(v: &[String]) -> Result<Vec<Pattern>> {
 pub fn needs_interactive_tui(args: &Cli) -> bool {
     #[cfg(feature = "tui")]
     {
-        !args.no_interactive && args.include.is_empty() && args.extensions.is_empty()
+        !args.no_interactive
+            && args.include.is_empty()
+            && args.extensions.is_empty()
+            && args.with_deps.is_empty()
+            && args.with_dependents.is_empty()
+            && args.query.is_none()
+            && args.grep.is_none()
+            && args.tag.is_empty()
+            && args.owner.is_empty()
+            && args.changed_since.is_none()
+            && args.author.is_none()
+            && !ci_context(args)
+            && args.select_file.is_none()
+            && args.top_files.is_none()
+            && !args.use_last_selection
     }
     #[cfg(not(feature = "tui"))]
     {
         false
     }
 }
+
+/// Where an effective setting comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    ConfigFile,
+    /// The repository's `.code2prompt/config.toml`.
+    ProjectConfig,
+    RepoConfig,
+    /// The flag that set it.
+    Flag(&'static str),
+    /// The ecosystems detected from the repository's manifests.
+    Detected,
+    /// What code2prompt writes itself: outputs, caches, `.code2prompt/`.
+    Generated,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => f.write_str("default"),
+            Self::ConfigFile => f.write_str("config file"),
+            Self::ProjectConfig => f.write_str("project config"),
+            Self::RepoConfig => f.write_str("repository config"),
+            Self::Flag(flag) => f.write_str(flag),
+            Self::Detected => f.write_str("detected"),
+            Self::Generated => f.write_str("generated"),
+        }
+    }
+}
+
+/// One effective setting, as listed by `config check`.
+#[derive(Debug, Clone)]
+pub struct Setting {
+    /// The setting's key in `config.toml`, e.g. `priority."src/**"`.
+    pub key: String,
+    pub value: toml::Value,
+    pub source: Source,
+}
+
+impl Setting {
+    fn new(key: impl Into<String>, value: impl Serialize, source: Source) -> Result<Self> {
+        let key = key.into();
+        let value = toml::Value::try_from(value)
+            .with_context(|| format!("Failed to print the value of {key}"))?;
+        Ok(Self { key, value, source })
+    }
+}
+
+/// The settings a scan with `args` uses, merged from the flags, the config
+/// file, the project config, the repository config and the defaults as
+/// [`build_config_builder`] and [`build_exclude_patterns`] merge them.
+pub fn effective_settings(
+    args: &Cli,
+    global: &ConfigFile,
+    project: Option<&ConfigFile>,
+) -> Result<Vec<Setting>> {
+    let Some(project) = project else {
+        return settings_of(args, global);
+    };
+    let mut settings = settings_of(args, &global.clone().overlaid_with(project.clone()))?;
+    // A value from the config files is the project's if the project config
+    // alone gives it too.
+    let from_project = settings_of(args, project)?;
+    for setting in settings
+        .iter_mut()
+        .filter(|s| s.source == Source::ConfigFile)
+    {
+        let is_project = from_project.iter().any(|p| {
+            p.source == Source::ConfigFile && p.key == setting.key && p.value == setting.value
+        });
+        if is_project {
+            setting.source = Source::ProjectConfig;
+        }
+    }
+    Ok(settings)
+}
+
+fn settings_of(args: &Cli, cfg_file: &ConfigFile) -> Result<Vec<Setting>> {
+    let mut settings = Vec::new();
+
+    let (tokenizer, source) = match (args.tokenizer, cfg_file.tokenizer) {
+        (Some(t), _) => (t, Source::Flag("--tokenizer")),
+        (None, Some(t)) => (t, Source::ConfigFile),
+        (None, None) => (TokenizerChoice::Cl100k, Source::Default),
+    };
+    settings.push(Setting::new("tokenizer", tokenizer, source)?);
+
+    for (key, flag, set, file) in [
+        (
+            "line_numbers",
+            "--line-numbers",
+            args.line_numbers,
+            cfg_file.line_numbers,
+        ),
+        (
+            "normalize_newlines",
+            "--normalize-newlines",
+            args.normalize_newlines,
+            cfg_file.normalize_newlines,
+        ),
+        (
+            "no_codeblock",
+            "--no-codeblock",
+            args.no_codeblock,
+            cfg_file.no_codeblock,
+        ),
+        (
+            "no_default_excludes",
+            "--no-default-excludes",
+            args.no_default_excludes,
+            cfg_file.no_default_excludes,
+        ),
+        ("history", "--history", args.history, cfg_file.history),
+    ] {
+        let (value, source) = match file {
+            _ if set => (true, Source::Flag(flag)),
+            Some(value) => (value, Source::ConfigFile),
+            None => (false, Source::Default),
+        };
+        settings.push(Setting::new(key, value, source)?);
+    }
+    let (value, source) = match cfg_file.output.auto_exclude_artifacts {
+        Some(value) => (value, Source::ConfigFile),
+        None => (false, Source::Default),
+    };
+    settings.push(Setting::new(
+        "output.auto_exclude_artifacts",
+        value,
+        source,
+    )?);
+    let (value, source) = match cfg_file.cache.content {
+        _ if args.no_content_cache => (false, Source::Flag("--no-content-cache")),
+        Some(value) => (value, Source::ConfigFile),
+        None => (true, Source::Default),
+    };
+    settings.push(Setting::new("cache.content", value, source)?);
+    if let Some(var) = &cfg_file.cache.key_env {
+        settings.push(Setting::new("cache.key_env", var, Source::ConfigFile)?);
+    }
+    for (key, limit) in [
+        ("cache.max_repo_mb", cfg_file.cache.max_repo_mb),
+        ("cache.max_total_mb", cfg_file.cache.max_total_mb),
+    ] {
+        if let Some(mb) = limit {
+            settings.push(Setting::new(key, mb, Source::ConfigFile)?);
+        }
+    }
+
+    for (glob, source) in exclude_sources(args, cfg_file, true) {
+        settings.push(Setting::new("exclude", glob, source)?);
+    }
+
+    let mut priorities: BTreeMap<&str, (i32, Source)> = BTreeMap::new();
+    for (glob, weight) in cfg_file.priority.iter().flatten() {
+        priorities.insert(glob, (*weight, Source::ConfigFile));
+    }
+    for (glob, weight) in &args.priority {
+        priorities.insert(glob, (*weight, Source::Flag("--priority")));
+    }
+    for (glob, (weight, source)) in priorities {
+        settings.push(Setting::new(
+            format!("priority.{}", key_part(glob)),
+            weight,
+            source,
+        )?);
+    }
+
+    let mut languages: BTreeMap<String, (&str, Source)> = BTreeMap::new();
+    let detected = auto_fence_languages(&detected_ecosystems(args));
+    for (ext, lang) in &detected {
+        languages.insert(ext.clone(), (lang, Source::Detected));
+    }
+    for (ext, lang) in cfg_file.languages.iter().flatten() {
+        languages.insert(ext.to_ascii_lowercase(), (lang, Source::ConfigFile));
+    }
+    for (ext, (lang, source)) in languages {
+        settings.push(Setting::new(
+            format!("languages.{}", key_part(&ext)),
+            lang,
+            source,
+        )?);
+    }
+
+    for (glob, spec) in cfg_file.transform.iter().flatten() {
+        let key = format!("transform.{}", key_part(glob));
+        settings.push(Setting::new(key, spec, Source::ConfigFile)?);
+    }
+    if let Some(hooks) = &cfg_file.hooks {
+        for (key, cmd) in [
+            ("pre_scan", &hooks.pre_scan),
+            ("post_render", &hooks.post_render),
+        ] {
+            if let Some(cmd) = cmd {
+                settings.push(Setting::new(
+                    format!("hooks.{key}"),
+                    cmd,
+                    Source::ConfigFile,
+                )?);
+            }
+        }
+    }
+    settings.push(match &cfg_file.embeddings {
+        Some(embeddings) => Setting::new("embeddings", embeddings, Source::ConfigFile)?,
+        None => Setting::new("embeddings", EmbeddingSettings::default(), Source::Default)?,
+    });
+    settings.push(match &cfg_file.llm {
+        Some(llm) => Setting::new("llm", llm, Source::ConfigFile)?,
+        None => Setting::new("llm", LlmSettings::default(), Source::Default)?,
+    });
+
+    let mut defaults: Vec<_> = cfg_file.template.defaults.iter().flatten().collect();
+    defaults.sort();
+    for (name, value) in defaults {
+        let key = format!("template.defaults.{}", key_part(name));
+        settings.push(Setting::new(key, value, Source::ConfigFile)?);
+    }
+    for (name, args) in cfg_file.aliases.iter().flatten() {
+        let key = format!("aliases.{}", key_part(name));
+        settings.push(Setting::new(key, args, Source::ConfigFile)?);
+    }
+    if let Some(trusted) = &cfg_file.trusted_projects {
+        settings.push(Setting::new(
+            "trusted_projects",
+            trusted,
+            Source::ConfigFile,
+        )?);
+    }
+    Ok(settings)
+}
+
+/// A key of a TOML table, quoted unless it is a bare key.
+fn key_part(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+/// Settings that are silently skipped during a scan: invalid globs and
+/// transforms, and a repository config that cannot be read.
+pub fn config_problems(args: &Cli, cfg_file: &ConfigFile) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (glob, source) in exclude_sources(args, cfg_file, true) {
+        if let Err(e) = Pattern::new(&glob) {
+            problems.push(format!(
+                "exclude {glob:?} ({source}) is not a valid glob: {e}; no excludes apply"
+            ));
+        }
+    }
+    let priorities = cfg_file
+        .priority
+        .iter()
+        .flatten()
+        .chain(args.priority.iter().map(|(glob, weight)| (glob, weight)));
+    for (glob, weight) in priorities {
+        if let Err(e) = PriorityRule::new(glob, *weight) {
+            problems.push(format!("priority {glob:?}: {e:#}"));
+        }
+    }
+    let mut transforms = TransformPipeline::default();
+    for (glob, spec) in cfg_file.transform.iter().flatten() {
+        if let Err(e) = transforms.add(glob, spec) {
+            problems.push(format!("transform {glob:?}: {e:#}"));
+        }
+    }
+    if let Err(e) = CacheManager::new(&args.path).and_then(|m| m.load::<RepoConfig>()) {
+        problems.push(format!("{e:#}"));
+    }
+    problems
+}
@@ -0,0 +1,98 @@
+//! `code2prompt daemon`: a long-running process that keeps sessions (and
+//! their scan caches) warm, answering `code2prompt . --use-daemon` over a
+//! Unix socket.
+//!
+//! The protocol is one JSON [`RenderRequest`] per line, answered by one JSON
+//! [`DaemonReply`] per line.
+
+use std::fs::Permissions;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::config_file::ConfigFile;
+use crate::ui::serve::{RenderRequest, RenderResponse, RenderService};
+
+/// The answer to one request.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonReply {
+    Ok(RenderResponse),
+    Error(String),
+}
+
+/// The default socket, `code2prompt/daemon.sock` in the cache directory.
+pub fn default_socket_path() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("code2prompt")
+        .join("daemon.sock")
+}
+
+/// Binds the socket, replacing a stale one left by a daemon that died. Only
+/// the current user may connect to it.
+pub fn bind(socket: &Path) -> Result<UnixListener> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            bail!("A daemon is already listening on {}", socket.display());
+        }
+        std::fs::remove_file(socket)
+            .with_context(|| format!("Failed to remove stale socket {}", socket.display()))?;
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(socket)
+        .with_context(|| format!("Failed to bind {}", socket.display()))?;
+    std::fs::set_permissions(socket, Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed to restrict {}", socket.display()))?;
+    Ok(listener)
+}
+
+/// Answers requests on `listener` for repositories inside `roots` until the
+/// process is stopped.
+pub fn run(listener: UnixListener, roots: &[PathBuf], cfg_file: ConfigFile) -> Result<()> {
+    let mut service = RenderService::new(cfg_file).with_roots(roots)?;
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        if let Err(_e) = handle_connection(&mut service, stream) {
+            #[cfg(feature = "logging")]
+            log::warn!("Daemon connection failed: {_e:#}");
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(service: &mut RenderService, stream: UnixStream) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        let reply = match serde_json::from_str::<RenderRequest>(&line) {
+            Ok(req) => match service.render(&req) {
+                Ok(resp) => DaemonReply::Ok(resp),
+                Err(e) => DaemonReply::Error(format!("{e:#}")),
+            },
+            Err(e) => DaemonReply::Error(format!("Invalid request: {e}")),
+        };
+        writeln!(writer, "{}", serde_json::to_string(&reply)?)?;
+    }
+    Ok(())
+}
+
+/// Sends one request to the daemon listening on `socket`.
+pub fn request(socket: &Path, req: &RenderRequest) -> Result<RenderResponse> {
+    let mut stream = UnixStream::connect(socket)
+        .with_context(|| format!("No daemon listening on {}", socket.display()))?;
+    writeln!(stream, "{}", serde_json::to_string(req)?)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    match serde_json::from_str(&line).context("Invalid reply from daemon")? {
+        DaemonReply::Ok(resp) => Ok(resp),
+        DaemonReply::Error(e) => Err(anyhow!(e)),
+    }
+}
@@ -1,8 +1,16 @@
+#[cfg(feature = "tui")]
+pub mod arrange;
+
 pub mod cache;
 pub mod cli;
 pub mod clipboard;
 pub mod config;
 pub mod output;
+pub mod preview;
+pub mod serve;
+
+#[cfg(unix)]
+pub mod daemon;
 
 #[cfg(feature = "tui")]
 pub mod pane;
@@ -11,11 +19,20 @@ pub mod template;
 pub mod token_map_view;
 pub mod tree_view;
 
+#[cfg(feature = "tui")]
+pub mod simple_select;
+
 #[cfg(feature = "tui")]
 pub mod tree_arena;
 
 #[cfg(feature = "tui")]
 pub mod tree_pane;
 
+#[cfg(feature = "tui")]
+pub mod triage;
+
 #[cfg(feature = "tui")]
 pub mod tui_select;
+
+#[cfg(feature = "tui")]
+pub mod vars_review;
@@ -1,29 +1,47 @@
-use anyhow::Result;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
 use serde_json::json;
 
+use thousands::Separable;
+
+use crate::common::{
+    format::{self, civil_from_days, format_path_label},
+    path,
+};
 use crate::engine::{
     config::{Code2PromptConfig, OutputFormat, TokenFormat},
-    model::ProcessedEntry,
+    file_id,
+    model::{ProcessedEntry, RunSummary, ScanError, SkippedFiles, TokenBreakdown},
+    presets::Ecosystem,
     token::get_model_info,
+    token_map::{TokenMapExport, largest_contributors},
 };
 use crate::ui::cli::Cli;
-use crate::ui::template::write_to_file;
+use crate::ui::preview;
+use crate::ui::template::{handlebars_setup, write_to_file};
 
 #[cfg(feature = "clipboard")]
-use crate::ui::clipboard;
+use {crate::ui::cli::ClipboardContent, crate::ui::clipboard, std::borrow::Cow};
 
 /// Handles all final output generation based on CLI arguments.
 pub struct OutputHandler<'a> {
     rendered: &'a str,
+    #[cfg_attr(not(feature = "clipboard"), allow(dead_code))]
+    source_tree: &'a str,
     token_count: usize,
     processed_entries: &'a [ProcessedEntry],
     args: &'a Cli,
     config: &'a Code2PromptConfig,
+    breakdown: Option<TokenBreakdown>,
+    summary: Option<RunSummary>,
 }
 
 impl<'a> OutputHandler<'a> {
     pub fn new(
         rendered: &'a str,
+        source_tree: &'a str,
         token_count: usize,
         processed_entries: &'a [ProcessedEntry],
         args: &'a Cli,
@@ -31,14 +49,30 @@ impl<'a> OutputHandler<'a> {
     ) -> Self {
         Self {
             rendered,
+            source_tree,
             token_count,
             processed_entries,
             args,
             config,
+            breakdown: None,
+            summary: None,
         }
     }
 
-    pub fn handle(&self) -> Result<()> {
+    /// Shows `breakdown` under the total token count.
+    pub fn with_breakdown(mut self, breakdown: TokenBreakdown) -> Self {
+        self.breakdown = Some(breakdown);
+        self
+    }
+
+    /// Includes `summary` in the JSON output.
+    pub fn with_summary(mut self, summary: RunSummary) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Emits the prompt and returns the path of the written file, if any.
+    pub fn handle(&self) -> Result<Option<String>> {
         #[cfg(feature = "token_map")]
         if self.args.token_map {
             self.handle_token_map()?;
@@ -51,25 +85,83 @@ impl<'a> OutputHandler<'a> {
             );
         }
 
+        if let Some(path) = &self.args.token_map_json {
+            let export = TokenMapExport::from_entries(self.processed_entries, self.config.tokenizer);
+            std::fs::write(path, serde_json::to_string_pretty(&export)?)
+                .with_context(|| format!("Failed to write token map to {}", path.display()))?;
+        }
+
         if self.args.output_format == OutputFormat::Json {
-            return self.handle_json_output(self.token_count);
+            self.handle_json_output(self.token_count)?;
+            return Ok(None);
         }
 
         if self.should_show_tokens() {
             self.display_token_count(self.token_count);
+            if let Some(breakdown) = &self.breakdown {
+                display_token_breakdown(breakdown);
+            }
         }
+        self.warn_if_over_budget();
 
-        self.handle_final_output()
+        let written = self.handle_final_output()?;
+        if self.args.provenance {
+            write_provenance(&mut std::io::stderr().lock(), self.processed_entries)?;
+        }
+        Ok(written)
+    }
+
+    /// Reports the largest contributors, with ready-to-paste exclude
+    /// patterns, when the prompt exceeds `--max-tokens` or the context window.
+    fn warn_if_over_budget(&self) {
+        let limit = self
+            .args
+            .max_tokens
+            .unwrap_or_else(|| self.config.tokenizer.context_window());
+        if self.token_count <= limit || self.args.quiet {
+            return;
+        }
+        eprintln!(
+            "\n[!] The prompt has {} tokens, over the budget of {} by {}.",
+            self.token_count.separate_with_commas(),
+            limit.separate_with_commas(),
+            (self.token_count - limit).separate_with_commas()
+        );
+
+        let (files, dirs) = largest_contributors(self.processed_entries, self.config.tokenizer, 5);
+        let width = files.iter().chain(&dirs).map(|(p, _)| p.len()).max();
+        let width = width.unwrap_or(0) + 10;
+        let suggest = |pattern: String, tokens: &usize| {
+            let tokens = tokens.separate_with_commas();
+            eprintln!("      {pattern:<width$} # {tokens}");
+        };
+        if !dirs.is_empty() {
+            eprintln!("    Largest directories:");
+            for (dir, tokens) in &dirs {
+                suggest(format!("-e \"{dir}/**\""), tokens);
+            }
+        }
+        eprintln!("    Largest files:");
+        for (file, tokens) in &files {
+            suggest(format!("-e \"{file}\""), tokens);
+        }
     }
 
     fn should_show_tokens(&self) -> bool {
-        self.args.output_format != OutputFormat::Json && self.args.tokens == TokenFormat::Format
+        self.args.output_format != OutputFormat::Json
+            && self.args.tokens != TokenFormat::Raw
+            && !self.args.quiet
     }
 
     #[cfg(feature = "token_map")]
     fn handle_token_map(&self) -> Result<()> {
        // Move the necessary imports inside the conditionally compiled function.
-       use crate::engine::token_map::generate_token_map_with_limit;
+       #[cfg(feature = "git")]
+       use crate::engine::token_map::group_by_author;
+       use crate::engine::token_map::{
+           TokenMapGroupBy, generate_grouped_token_map, generate_token_map_with_limit,
+           group_by_extension, sort_token_map,
+       };
        use crate::ui::token_map_view;
        use terminal_size;
         let sum: usize = self
@@ -87,32 +179,79 @@ impl<'a> OutputHandler<'a> {
                 })
                 .unwrap_or(20)
                 .max(5);
-            let map = generate_token_map_with_limit(
-                self.processed_entries,
-                Some(lines),
-                self.args.token_map_min_percent,
-            );
-            token_map_view::display_token_map(&map, sum);
+            let min_percent = self.args.token_map_min_percent;
+            let map = match self.args.token_map_group_by {
+                TokenMapGroupBy::Dir => {
+                    generate_token_map_with_limit(self.processed_entries, Some(lines), min_percent)
+                }
+                TokenMapGroupBy::Ext => generate_grouped_token_map(
+                    group_by_extension(self.processed_entries),
+                    TokenMapGroupBy::Ext,
+                    Some(lines),
+                    min_percent,
+                ),
+                #[cfg(feature = "git")]
+                TokenMapGroupBy::Author => generate_grouped_token_map(
+                    group_by_author(&self.config.path, self.processed_entries)?,
+                    TokenMapGroupBy::Author,
+                    Some(lines),
+                    min_percent,
+                ),
+                #[cfg(not(feature = "git"))]
+                TokenMapGroupBy::Author => anyhow::bail!(
+                    "--token-map-group-by author requires the 'git' feature."
+                ),
+            };
+            let map = sort_token_map(map, self.args.token_map_sort);
+            token_map_view::display_token_map(&map, sum, self.args.token_map_threshold);
         }
         Ok(())
     }
 
     fn handle_json_output(&self, total_tokens: usize) -> Result<()> {
+        println!("{}", self.json_payload(total_tokens)?);
+        Ok(())
+    }
+
+    fn json_payload(&self, total_tokens: usize) -> Result<String> {
         let paths: Vec<_> = self
             .processed_entries
             .iter()
-            .map(|e| e.path.to_string_lossy().into_owned())
+            .map(|e| path::display(&e.path))
             .collect();
 
-        let json_out = json!({
+        let mut json_out = json!({
             "prompt": self.rendered,
             "directory_name": self.config.path.file_name().and_then(|s| s.to_str()).unwrap_or(""),
             "token_count": total_tokens,
             "model_info": get_model_info(self.config.tokenizer),
             "files": paths,
         });
-        println!("{}", serde_json::to_string_pretty(&json_out)?);
-        Ok(())
+        if let Some(summary) = &self.summary {
+            json_out["summary"] = serde_json::to_value(summary)?;
+        }
+        if self.args.provenance {
+            json_out["provenance"] = provenance_json(self.processed_entries);
+        }
+        Ok(serde_json::to_string_pretty(&json_out)?)
+    }
+
+    /// The text copied to the clipboard, as selected by `--clipboard-content`.
+    #[cfg(feature = "clipboard")]
+    fn clipboard_text(&self) -> Result<Cow<'a, str>> {
+        Ok(match self.args.clipboard_content {
+            ClipboardContent::Prompt => Cow::Borrowed(self.rendered),
+            ClipboardContent::Json => Cow::Owned(self.json_payload(self.token_count)?),
+            ClipboardContent::Tree => Cow::Borrowed(self.source_tree),
+            ClipboardContent::FileList => Cow::Owned(
+                self.processed_entries
+                    .iter()
+                    .filter(|e| e.is_file)
+                    .map(|e| path::display(&e.relative_path))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        })
     }
 
     fn display_token_count(&self, total_tokens: usize) {
@@ -126,27 +265,281 @@ impl<'a> OutputHandler<'a> {
         println!("[i] Token count unavailable: 'token_map' feature not enabled.");
     }
 
-    fn handle_final_output(&self) -> Result<()> {
+    fn handle_final_output(&self) -> Result<Option<String>> {
         let mut clipboard_ok = false;
         #[cfg(feature = "clipboard")]
-        if !self.args.no_clipboard && clipboard::copy_to_clipboard(self.rendered).is_ok() {
-            clipboard_ok = true;
-            println!("[✓] Copied to clipboard.");
+        if !self.args.no_clipboard && clipboard::copy_to_clipboard(&self.clipboard_text()?).is_ok()
+        {
+            // Only a copied prompt replaces printing it below.
+            clipboard_ok = self.args.clipboard_content == ClipboardContent::Prompt;
+            if !self.args.quiet {
+                match self.args.clipboard_content {
+                    ClipboardContent::Prompt => println!("[✓] Copied to clipboard."),
+                    ClipboardContent::Json => println!("[✓] Copied JSON payload to clipboard."),
+                    ClipboardContent::Tree => println!("[✓] Copied source tree to clipboard."),
+                    ClipboardContent::FileList => println!("[✓] Copied file list to clipboard."),
+                }
+            }
         }
 
-        if let Some(path) = &self.args.output_file {
-            write_to_file(path, self.rendered)?;
-        } else if !clipboard_ok {
+        if let Some(mode) = self.args.preview {
+            preview::show(self.rendered, mode)?;
+        }
+
+        if let Some(pattern) = self.args.output_file.first() {
+            let vars = OutputPathVars {
+                repo: format_path_label(&self.config.path),
+                tokens: self.token_count,
+                files: self.processed_entries.iter().filter(|e| e.is_file).count(),
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default(),
+            };
+            let path = expand_output_path(pattern, &vars);
+            write_to_file(&path, self.rendered)?;
+            return Ok(Some(path));
+        } else if !clipboard_ok && self.args.preview.is_none() {
             println!(
                 "\n--- PROMPT START ---\n{}\n--- PROMPT END ---",
                 self.rendered
             );
         }
-        Ok(())
+        Ok(None)
+    }
+}
+
+/// Writes each file with content as `=== path ===` and its code, for
+/// `--no-template`.
+pub fn write_concatenated(
+    out: &mut impl Write,
+    entries: &[ProcessedEntry],
+    absolute_paths: bool,
+) -> std::io::Result<()> {
+    for entry in entries.iter().filter(|e| e.is_file) {
+        let Some(code) = &entry.code else {
+            continue;
+        };
+        let path = if absolute_paths {
+            path::display(&entry.path)
+        } else {
+            path::display(&entry.relative_path)
+        };
+        writeln!(out, "=== {path} ===")?;
+        out.write_all(code.as_bytes())?;
+        if !code.ends_with('\n') {
+            writeln!(out)?;
+        }
     }
+    Ok(())
 }
 
-pub fn print_summary(path: &str, files: usize) {
+/// Writes where each file's content came from, for `--provenance`: the
+/// scan cache or disk, and the SHA-256 of the file's text when known.
+pub fn write_provenance(out: &mut impl Write, entries: &[ProcessedEntry]) -> std::io::Result<()> {
+    let files: Vec<_> = entries.iter().filter(|e| e.is_file).collect();
+    writeln!(out, "\n[i] Provenance of {} files:", files.len())?;
+    for entry in files {
+        let source = entry.source.map_or("-".to_string(), |s| s.to_string());
+        let sha256 = entry.sha256.map_or("-".to_string(), hex::encode);
+        writeln!(
+            out,
+            "    {source:<5}  {sha256:<64}  {}",
+            path::display(&entry.relative_path)
+        )?;
+    }
+    Ok(())
+}
+
+/// The `provenance` section of the JSON output: one record per file, see
+/// [`write_provenance`].
+pub fn provenance_json(entries: &[ProcessedEntry]) -> serde_json::Value {
+    entries
+        .iter()
+        .filter(|e| e.is_file)
+        .map(|e| {
+            json!({
+                "path": path::to_fwd_slash(&e.relative_path),
+                "source": e.source,
+                "sha256": e.sha256.map(hex::encode),
+            })
+        })
+        .collect()
+}
+
+/// The `--scaffold` response skeleton for the files among `entries`, in
+/// their order, rendered with its built-in template.
+pub fn scaffold(entries: &[ProcessedEntry]) -> Result<String> {
+    let ids = file_id::entry_ids(entries);
+    let files: Vec<_> = entries
+        .iter()
+        .filter(|e| e.is_file)
+        .map(|e| {
+            json!({
+                "path": path::to_fwd_slash(&e.relative_path),
+                "id": ids.get(&e.relative_path),
+                "extension": e.extension.as_deref().unwrap_or(""),
+            })
+        })
+        .collect();
+    let hb = handlebars_setup(include_str!("../../scaffold_template.hbs"), "scaffold")?;
+    Ok(hb.render("scaffold", &json!({ "files": files }))?)
+}
+
+/// Run metadata available as `{placeholders}` in the `-O` file name.
+pub struct OutputPathVars {
+    pub repo: String,
+    pub tokens: usize,
+    pub files: usize,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+/// The file name pattern of `--split-by-dir`: `prompt-{dir}.md`, or the `-O`
+/// file name with `-{dir}` before its extension, e.g. `out/review.md` becomes
+/// `out/review-{dir}.md`.
+pub fn split_output_pattern(output_file: Option<&str>) -> String {
+    let Some(output) = output_file else {
+        return "prompt-{dir}.md".to_string();
+    };
+    let path = std::path::Path::new(output);
+    let stem = path
+        .file_stem()
+        .map_or_else(|| "prompt".into(), |s| s.to_string_lossy());
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{{dir}}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{{dir}}"),
+    };
+    path::display(&path.with_file_name(name))
+}
+
+/// Fills in the `{dir}` of a split pattern with a top-level directory, made
+/// safe for a file name; the files at the root go to `root`.
+pub fn split_output_path(pattern: &str, dir: &str) -> String {
+    let name: String = if dir == "." {
+        "root".to_string()
+    } else {
+        dir.chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    pattern.replace("{dir}", &name)
+}
+
+/// Expands `{repo}`, `{date}`, `{time}`, `{timestamp}`, `{tokens}` and
+/// `{files}` in an output path. Dates and times are in UTC; unknown
+/// placeholders are left untouched.
+pub fn expand_output_path(pattern: &str, vars: &OutputPathVars) -> String {
+    if !pattern.contains('{') {
+        return pattern.to_owned();
+    }
+    let days = vars.timestamp / 86_400;
+    let secs = vars.timestamp % 86_400;
+    let (y, m, d) = civil_from_days(days as i64);
+    let (hh, mm, ss) = (secs / 3_600, secs % 3_600 / 60, secs % 60);
+    let time = format!("{hh:02}{mm:02}{ss:02}");
+    pattern
+        .replace("{repo}", &vars.repo)
+        .replace("{date}", &format!("{y:04}-{m:02}-{d:02}"))
+        .replace("{time}", &time)
+        .replace("{timestamp}", &vars.timestamp.to_string())
+        .replace("{tokens}", &vars.tokens.to_string())
+        .replace("{files}", &vars.files.to_string())
+}
+
+fn display_token_breakdown(breakdown: &TokenBreakdown) {
+    let total = breakdown.total().max(1);
+    for (label, tokens) in [
+        ("files", breakdown.files),
+        ("source tree", breakdown.source_tree),
+        ("git diff", breakdown.git_diff),
+        ("git log", breakdown.git_log),
+        ("template", breakdown.template),
+    ] {
+        println!(
+            "    {label:<12} {:>10}  {:>5.1}%",
+            tokens.separate_with_commas(),
+            tokens as f64 * 100.0 / total as f64
+        );
+    }
+}
+
+/// Prints the run summary: what was scanned and included, what was skipped
+/// and why, how much was read and the tokens of each part of the prompt.
+pub fn print_summary(path: &str, summary: &RunSummary, ecosystems: &[Ecosystem]) {
     let line = "=".repeat(40);
-    println!("\n{line}\n📂 Directory Processed: {path}\n📄 Files Processed: {files}\n{line}");
+    println!("\n{line}\n📂 Directory Processed: {path}");
+    println!(
+        "📄 Files: {} included of {} scanned",
+        summary.files_included.separate_with_commas(),
+        summary.files_scanned.separate_with_commas()
+    );
+    let reasons = skip_reasons(&summary.skipped);
+    if !reasons.is_empty() {
+        let reasons: Vec<String> = reasons
+            .iter()
+            .map(|(label, n)| format!("{} {label}", n.separate_with_commas()))
+            .collect();
+        println!("   Skipped: {}", reasons.join(", "));
+    }
+    let mut read = format!(
+        "📥 Read {} in {} ms",
+        format::format_size(summary.bytes_read),
+        summary.scan_duration_ms.separate_with_commas()
+    );
+    if let Some(rate) = summary.cache_hit_rate() {
+        read.push_str(&format!(", cache hit rate {:.0}%", rate * 100.0));
+    }
+    println!("{read}");
+    if summary.scan_errors > 0 {
+        println!(
+            "⚠️  {} path(s) could not be read, so the prompt is incomplete (see --show-errors)",
+            summary.scan_errors.separate_with_commas()
+        );
+    }
+    println!("🔢 Tokens: {}", summary.tokens.separate_with_commas());
+    if let Some(sections) = &summary.sections {
+        display_token_breakdown(sections);
+    }
+    if !ecosystems.is_empty() {
+        let names: Vec<String> = ecosystems.iter().map(ToString::to_string).collect();
+        println!("🧭 Detected: {}", names.join(", "));
+    }
+    println!("{line}");
+}
+
+/// Lists the paths the scan could not read, for `--show-errors`.
+pub fn print_scan_errors(errors: &[ScanError]) {
+    if errors.is_empty() {
+        eprintln!("[i] The scan read every path.");
+        return;
+    }
+    eprintln!("[!] The scan could not read {} path(s):", errors.len());
+    for error in errors {
+        let path = error.path.as_deref().unwrap_or("(unknown path)");
+        eprintln!("    {path}: {} ({})", error.kind, error.message);
+    }
+}
+
+/// The non-zero skip counts, with their labels.
+fn skip_reasons(skipped: &SkippedFiles) -> Vec<(&'static str, usize)> {
+    [
+        ("filtered out", skipped.filtered),
+        ("empty", skipped.empty),
+        ("over 1 MiB", skipped.too_large),
+        ("unreadable", skipped.unreadable),
+        ("without a --grep match", skipped.no_grep_match),
+        ("over the token budget", skipped.over_budget),
+        ("outside the --max-files sample", skipped.not_sampled),
+        ("not selected", skipped.not_selected),
+    ]
+    .into_iter()
+    .filter(|(_, n)| *n > 0)
+    .collect()
 }
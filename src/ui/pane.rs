@@ -2,6 +2,8 @@
 
 //! Defines a common trait for interactive, navigable panes in the TUI.
 
+use std::ops::RangeInclusive;
+
 /// A trait for TUI panes that contain a list of items that can be
 /// navigated and selected.
 pub trait NavigablePane {
@@ -20,4 +22,17 @@ pub trait NavigablePane {
     /// `true` if the selection state was changed and a data recalculation
     /// is likely required. `false` otherwise.
     fn toggle_current_selection(&mut self) -> bool;
+
+    /// Position of the cursor among the visible items, if any.
+    fn cursor_position(&self) -> Option<usize>;
+
+    /// Whether every visible item in `range` is selected.
+    fn is_range_selected(&self, range: RangeInclusive<usize>) -> bool;
+
+    /// Selects or deselects every visible item in `range`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if any selection state changed.
+    fn set_range_selected(&mut self, range: RangeInclusive<usize>, select: bool) -> bool;
 }
@@ -0,0 +1,191 @@
+//! Previewing the rendered prompt in a pager or a browser.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+#[cfg(feature = "colors")]
+use colored::Colorize;
+
+use crate::common::format::escape_markup;
+use crate::ui::template::hash_content;
+
+/// Where the rendered prompt is previewed.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Default)]
+pub enum PreviewMode {
+    /// Page the prompt in the terminal with basic ANSI styling.
+    #[default]
+    Terminal,
+    /// Write a temporary HTML file and open it in the browser.
+    Html,
+}
+
+/// Shows the rendered prompt using the given preview mode.
+pub fn show(rendered: &str, mode: PreviewMode) -> Result<()> {
+    match mode {
+        PreviewMode::Terminal => page(&style_for_terminal(rendered)),
+        PreviewMode::Html => {
+            let path = write_html(rendered)?;
+            open_in_browser(&path)
+        }
+    }
+}
+
+/// Applies basic styling to markdown: bold headings, dimmed fences and
+/// un-styled code. Without the `colors` feature the text is returned as-is.
+pub fn style_for_terminal(markdown: &str) -> String {
+    #[cfg(feature = "colors")]
+    {
+        let mut out = String::with_capacity(markdown.len());
+        let mut fence: Option<String> = None;
+        for line in markdown.lines() {
+            let trimmed = line.trim_start();
+            let styled = match &fence {
+                Some(open) if trimmed.starts_with(open.as_str()) => {
+                    fence = None;
+                    line.dimmed().to_string()
+                }
+                Some(_) => line.to_string(),
+                None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                    fence = Some(fence_marker(trimmed));
+                    line.dimmed().to_string()
+                }
+                None if trimmed.starts_with('#') => line.bold().cyan().to_string(),
+                None if trimmed.starts_with('`') && trimmed.ends_with("`:") => {
+                    line.bold().yellow().to_string()
+                }
+                None => line.to_string(),
+            };
+            out.push_str(&styled);
+            out.push('\n');
+        }
+        out
+    }
+    #[cfg(not(feature = "colors"))]
+    {
+        markdown.to_owned()
+    }
+}
+
+/// Returns the run of fence characters opening a code block.
+fn fence_marker(line: &str) -> String {
+    let first = line.chars().next().unwrap_or('`');
+    line.chars().take_while(|&c| c == first).collect()
+}
+
+/// Pipes text through `$PAGER` (default `less -R`), falling back to stdout.
+fn page(text: &str) -> Result<()> {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".into());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        print!("{text}");
+        return Ok(());
+    };
+
+    match Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The user quitting the pager early closes the pipe; that's fine.
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait().context("Failed to wait for pager")?;
+        }
+        Err(_) => print!("{text}"),
+    }
+    Ok(())
+}
+
+/// Converts the markdown prompt to a minimal standalone HTML page.
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut body = String::new();
+    // The marker that opened the current code block: only a run of the same
+    // character at least as long closes it.
+    let mut fence: Option<String> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        match &fence {
+            Some(open) if trimmed.starts_with(open.as_str()) => {
+                body.push_str("</code></pre>\n");
+                fence = None;
+                continue;
+            }
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                body.push_str("<pre><code>");
+                fence = Some(fence_marker(trimmed));
+                continue;
+            }
+            _ => {}
+        }
+        if fence.is_some() {
+            body.push_str(&escape_markup(line));
+            body.push('\n');
+        } else if let Some(level) = heading_level(trimmed) {
+            let text = escape_markup(trimmed[level..].trim());
+            body.push_str(&format!("<h{level}>{text}</h{level}>\n"));
+        } else if trimmed.is_empty() {
+            body.push_str("<br>\n");
+        } else {
+            body.push_str(&format!("<p>{}</p>\n", escape_markup(line)));
+        }
+    }
+    if fence.is_some() {
+        body.push_str("</code></pre>\n");
+    }
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>code2prompt preview</title>\
+         <style>body{{font-family:sans-serif;max-width:60em;margin:2em auto}}\
+         pre{{background:#f4f4f4;padding:1em;overflow-x:auto}}p{{margin:0}}</style>\
+         </head><body>\n{body}</body></html>\n"
+    )
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+}
+
+fn write_html(rendered: &str) -> Result<PathBuf> {
+    let html = markdown_to_html(rendered);
+    let path = std::env::temp_dir().join(format!(
+        "code2prompt-preview-{}.html",
+        &hash_content(rendered)[..12]
+    ));
+    std::fs::write(&path, html)
+        .with_context(|| format!("Failed to write preview to {}", path.display()))?;
+
+    #[cfg(feature = "colors")]
+    println!(
+        "{}",
+        format!("[i] Preview written to {}", path.display()).cyan()
+    );
+    #[cfg(not(feature = "colors"))]
+    println!("[i] Preview written to {}", path.display());
+
+    Ok(path)
+}
+
+fn open_in_browser(path: &std::path::Path) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut cmd = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut cmd = Command::new("xdg-open");
+
+    cmd.arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to open the preview in a browser")?;
+    Ok(())
+}
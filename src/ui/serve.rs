@@ -0,0 +1,328 @@
+//! `code2prompt serve`: render prompts over HTTP.
+//!
+//! `POST /render` takes a JSON [`RenderRequest`] and answers with a
+//! [`RenderResponse`]; `GET /health` answers `ok`. Sessions are kept per
+//! distinct request (ignoring `vars`), so repeated renders of a repository
+//! reuse its open scan cache instead of starting cold. The same service backs
+//! the local daemon (see [`crate::ui::daemon`]).
+//!
+//! Only requests addressed to a local host name are answered, so a web page
+//! cannot reach the server by rebinding its own domain to 127.0.0.1, and
+//! `/render` needs the token printed at startup as `Authorization: Bearer`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serve")]
+use {
+    serde_json::json,
+    tiny_http::{Header, Method, Response, Server},
+};
+
+use crate::{
+    Code2PromptConfig, Code2PromptSession,
+    common::{hash::HashMap, path},
+    engine::{
+        config::Code2PromptConfigBuilder,
+        config_file::{self, ConfigFile},
+        token::TokenizerChoice,
+        token::count_tokens,
+    },
+    ui::{
+        config::{ARTIFACT_EXCLUDES, DEFAULT_EXCLUDES, build_transforms, patterns_from_strings},
+        template,
+        tree_view::build_tree_view,
+    },
+};
+
+/// The body of `POST /render`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RenderRequest {
+    /// Repository to scan.
+    pub path: PathBuf,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Skip the built-in excludes (`.git/`, `target/`, ...).
+    pub no_default_excludes: bool,
+    /// Template file; defaults to the usual project/user/built-in lookup.
+    pub template: Option<PathBuf>,
+    /// Template variables.
+    pub vars: HashMap<String, String>,
+    pub tokenizer: Option<TokenizerChoice>,
+    pub line_numbers: bool,
+    pub absolute_paths: bool,
+    pub no_codeblock: bool,
+    pub hidden: bool,
+    pub no_ignore: bool,
+}
+
+/// The answer to `POST /render`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RenderResponse {
+    pub prompt: String,
+    pub token_count: usize,
+    pub tokenizer: TokenizerChoice,
+    /// Relative paths of the included files.
+    pub files: Vec<String>,
+}
+
+/// Sessions kept open at most; the least recently used one is closed to
+/// make room for another.
+pub const MAX_SESSIONS: usize = 16;
+
+/// Renders requests, keeping one session per distinct request.
+pub struct RenderService {
+    cfg_file: ConfigFile,
+    /// Directories requests may scan and read templates from, canonical;
+    /// empty allows any.
+    roots: Vec<PathBuf>,
+    max_sessions: usize,
+    /// Each session with the number of the request that last used it.
+    sessions: HashMap<String, (Code2PromptSession, u64)>,
+    requests: u64,
+}
+
+impl RenderService {
+    pub fn new(cfg_file: ConfigFile) -> Self {
+        Self {
+            cfg_file,
+            roots: Vec::new(),
+            max_sessions: MAX_SESSIONS,
+            sessions: HashMap::default(),
+            requests: 0,
+        }
+    }
+
+    /// Refuses requests for paths outside `roots`.
+    pub fn with_roots(mut self, roots: &[PathBuf]) -> Result<Self> {
+        self.roots = roots
+            .iter()
+            .map(|root| {
+                root.canonicalize()
+                    .with_context(|| format!("Failed to resolve {}", root.display()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Keeps at most `max` sessions open instead of [`MAX_SESSIONS`].
+    pub fn with_max_sessions(mut self, max: usize) -> Self {
+        self.max_sessions = max.max(1);
+        self
+    }
+
+    /// The number of sessions kept open.
+    pub fn session_count(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn render(&mut self, req: &RenderRequest) -> Result<RenderResponse> {
+        let req = &RenderRequest {
+            path: self.allowed(&req.path)?,
+            template: req
+                .template
+                .as_deref()
+                .map(|t| self.allowed(t))
+                .transpose()?,
+            ..req.clone()
+        };
+        let key = serde_json::to_string(&RenderRequest {
+            vars: HashMap::default(),
+            ..req.clone()
+        })?;
+        self.requests += 1;
+        if !self.sessions.contains_key(&key) {
+            // The repository's project config applies as it does to a scan
+            // on the command line.
+            let cfg_file = match config_file::load_project_for(&self.cfg_file, &req.path)? {
+                Some(project) => self.cfg_file.clone().overlaid_with(project),
+                None => self.cfg_file.clone(),
+            };
+            let session = Code2PromptSession::new(config_for(&cfg_file, req)?)?;
+            if self.sessions.len() >= self.max_sessions
+                && let Some(oldest) = self
+                    .sessions
+                    .iter()
+                    .min_by_key(|(_, (_, used))| *used)
+                    .map(|(key, _)| key.clone())
+            {
+                self.sessions.remove(&oldest);
+            }
+            self.sessions.insert(key.clone(), (session, 0));
+        }
+        let (session, used) = self.sessions.get_mut(&key).expect("inserted above");
+        *used = self.requests;
+
+        // Rescan on every request so edits are picked up; unchanged files
+        // come from the scan cache.
+        session.process_codebase()?;
+        session.sort_files();
+        let mut context = session.build_template_data(None, None, None)?;
+        context.source_tree = build_tree_view(
+            &session.config.path,
+            &session.processed_entries,
+            session.config.full_directory_tree,
+            session.config.file_ids,
+        );
+
+        let (tpl_content, tpl_hash) = template::resolve_template(&req.path, &req.template)?;
+        let (front_matter, _) = template::split_front_matter(&tpl_content)?;
+        let mut vars = req.vars.clone();
+        template::apply_var_specs(&front_matter.vars, &mut vars)?;
+
+        let mut data = serde_json::to_value(context)?;
+        if let Some(obj) = data.as_object_mut() {
+            obj.extend(template::typed_vars(&front_matter.vars, vars));
+        }
+
+        let name = if tpl_hash == "builtin" {
+            "default"
+        } else {
+            "custom"
+        };
+        let hb = template::handlebars_setup(&tpl_content, name)?;
+        let prompt = template::render_template(&hb, name, &data)?;
+        let token_count = count_tokens(&prompt, session.config.tokenizer)?;
+
+        Ok(RenderResponse {
+            prompt,
+            token_count,
+            tokenizer: session.config.tokenizer,
+            files: session
+                .processed_entries
+                .iter()
+                .filter(|e| e.is_file)
+                .map(|e| path::display(&e.relative_path))
+                .collect(),
+        })
+    }
+}
+
+impl RenderService {
+    /// `path` made canonical, if it is inside one of the roots.
+    fn allowed(&self, path: &Path) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", path.display()))?;
+        if !self.roots.is_empty() && !self.roots.iter().any(|root| canonical.starts_with(root)) {
+            bail!("{} is outside the served directories", path.display());
+        }
+        Ok(canonical)
+    }
+}
+
+fn config_for(cfg: &ConfigFile, req: &RenderRequest) -> Result<Code2PromptConfig> {
+    let mut exclude = cfg.exclude.clone().unwrap_or_default();
+    exclude.extend(req.exclude.iter().cloned());
+    if !(req.no_default_excludes || cfg.no_default_excludes.unwrap_or(false)) {
+        exclude.extend(DEFAULT_EXCLUDES.iter().map(|s| s.to_string()));
+    }
+    if cfg.output.auto_exclude_artifacts.unwrap_or(false) {
+        exclude.extend(ARTIFACT_EXCLUDES.iter().map(|s| s.to_string()));
+    }
+
+    Code2PromptConfigBuilder::default()
+        .path(req.path.clone())
+        .include_patterns(patterns_from_strings(&req.include)?)
+        .exclude_patterns(patterns_from_strings(&exclude)?)
+        .tokenizer(req.tokenizer.or(cfg.tokenizer).unwrap_or_default())
+        .line_numbers(req.line_numbers || cfg.line_numbers.unwrap_or(false))
+        .absolute_path(req.absolute_paths)
+        .no_codeblock(req.no_codeblock || cfg.no_codeblock.unwrap_or(false))
+        .normalize_newlines(cfg.normalize_newlines.unwrap_or(false))
+        .hidden(req.hidden)
+        .no_ignore(req.no_ignore)
+        .transforms(build_transforms(cfg))
+        .cache(true)
+        .build()
+        .context("Failed to build configuration for request")
+}
+
+/// Whether the `Host` header `host` names this machine: `localhost`,
+/// `127.0.0.1` or `[::1]`, with or without a port.
+pub fn is_local_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(rest),
+        None => host.split(':').next().unwrap_or(host),
+    };
+    matches!(
+        name.to_ascii_lowercase().as_str(),
+        "localhost" | "127.0.0.1" | "::1"
+    )
+}
+
+/// A random token, as 32 hexadecimal digits.
+#[cfg(feature = "serve")]
+fn new_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow::anyhow!("Failed to create token: {e}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Serves `POST /render` on `addr` until the process is stopped, for
+/// repositories inside `roots`.
+#[cfg(feature = "serve")]
+pub fn serve(addr: &str, roots: &[PathBuf], cfg_file: ConfigFile) -> Result<()> {
+    let mut service = RenderService::new(cfg_file).with_roots(roots)?;
+    let token = new_token()?;
+    let server =
+        Server::http(addr).map_err(|e| anyhow::anyhow!("Failed to listen on {addr}: {e}"))?;
+    eprintln!("[i] Listening on http://{addr} (POST /render)");
+    eprintln!("[i] Authorization: Bearer {token}");
+
+    let expected = format!("Bearer {token}");
+    for mut request in server.incoming_requests() {
+        let header = |name: &str| {
+            request
+                .headers()
+                .iter()
+                .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+                .map(|h| h.value.as_str().to_string())
+        };
+        let local = header("Host").is_some_and(|host| is_local_host(&host));
+        let authorized = header("Authorization").is_some_and(|auth| {
+            // Compared in constant time, so the token cannot be guessed
+            // from response times.
+            auth.len() == expected.len()
+                && auth
+                    .bytes()
+                    .zip(expected.bytes())
+                    .fold(0, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        });
+        let (status, body) = match (request.method(), request.url()) {
+            _ if !local => (403, json!({ "error": "Only local host names are served" })),
+            (Method::Get, "/health") => (200, json!("ok")),
+            (Method::Post, "/render") if !authorized => {
+                (401, json!({ "error": "Missing or wrong bearer token" }))
+            }
+            (Method::Post, "/render") => {
+                let mut raw = String::new();
+                let result = request
+                    .as_reader()
+                    .read_to_string(&mut raw)
+                    .context("Failed to read request body")
+                    .and_then(|_| serde_json::from_str(&raw).context("Invalid render request"))
+                    .and_then(|req: RenderRequest| service.render(&req));
+                match result {
+                    Ok(resp) => (200, serde_json::to_value(resp)?),
+                    Err(e) => (400, json!({ "error": format!("{e:#}") })),
+                }
+            }
+            _ => (404, json!({ "error": "Not found" })),
+        };
+
+        let header =
+            Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(header);
+        if let Err(_e) = request.respond(response) {
+            #[cfg(feature = "logging")]
+            log::warn!("Failed to send response: {_e}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+//! A line-based alternative to the full-screen selector (`--simple-ui`).
+//!
+//! Lists the file types and folders as numbered lines and reads the chosen
+//! numbers from a plain prompt, which works with screen readers and in
+//! terminals without an alternate screen.
+
+#[cfg(feature = "interactive")]
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+#[cfg(feature = "interactive")]
+use crate::common::format::{self, TokenFormatStyle};
+use crate::ui::cache::LastSelection;
+use crate::ui::tree_arena::DirNode;
+use crate::ui::tui_select::{ExtStats, TuiAction};
+#[cfg(feature = "interactive")]
+use crate::ui::{tree_arena::DirFlags, tree_pane::TreePane};
+
+/// Parses a list of 1-based numbers and ranges such as `1, 3-5`, separated
+/// by commas or spaces, into sorted 0-based indices below `count`.
+pub fn parse_numbers(input: &str, count: usize) -> Result<Vec<usize>> {
+    let mut picked = Vec::new();
+    for token in input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+    {
+        let (start, end) = token.split_once('-').unwrap_or((token, token));
+        let (Ok(start), Ok(end)) = (start.trim().parse::<usize>(), end.trim().parse::<usize>())
+        else {
+            bail!("{token} is not a number or a range like 3-5");
+        };
+        for n in [start, end] {
+            if n == 0 || n > count {
+                bail!("{n} is not between 1 and {count}");
+            }
+        }
+        picked.extend(start.min(end) - 1..start.max(end));
+    }
+    picked.sort_unstable();
+    picked.dedup();
+    Ok(picked)
+}
+
+/// The folders, and the files at the top of the repository, that can be
+/// picked, as `(arena index, path)` pairs sorted by path.
+#[cfg(feature = "interactive")]
+fn path_choices(arena: &[DirNode]) -> Vec<(usize, String)> {
+    let mut choices: Vec<_> = arena
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, n)| !n.flags.intersects(DirFlags::HIDDEN | DirFlags::IGNORED))
+        .filter(|(_, n)| n.flags.contains(DirFlags::IS_DIR) || n.parent == Some(0))
+        .map(|(i, _)| (i, TreePane::get_path(arena, i as u32)))
+        .collect();
+    choices.sort_by(|a, b| a.1.cmp(&b.1));
+    choices
+}
+
+/// Asks for the file types, then the folders, to include.
+#[cfg(feature = "interactive")]
+pub fn select_filters_simple(
+    extensions: &[ExtStats],
+    arena: &[DirNode],
+    last_selection: Option<&LastSelection>,
+) -> Result<TuiAction> {
+    let tokens = |n| format::format_tokens(n, TokenFormatStyle::Compact);
+
+    let mut exts = Vec::new();
+    if !extensions.is_empty() {
+        eprintln!("File types:");
+        for (i, ext) in extensions.iter().enumerate() {
+            eprintln!(
+                "  {}. {}: {} files, {} tokens",
+                i + 1,
+                ext.name,
+                ext.files,
+                tokens(ext.tokens)
+            );
+        }
+        let previous = last_selection
+            .map(|s| numbers_of(extensions.iter().map(|e| e.name.as_str()), &s.extensions));
+        let Some(picked) = ask(
+            "Numbers of the file types to include:",
+            "e.g. 1,3-5. Leave empty for all types.",
+            extensions.len(),
+            previous.as_deref(),
+        )?
+        else {
+            return Ok(TuiAction::Cancel);
+        };
+        exts = if picked.is_empty() {
+            extensions.iter().map(|e| e.name.clone()).collect()
+        } else {
+            picked.iter().map(|&i| extensions[i].name.clone()).collect()
+        };
+    }
+
+    let choices = path_choices(arena);
+    let mut paths = Vec::new();
+    if !choices.is_empty() {
+        eprintln!("Folders and top-level files:");
+        for (i, (idx, path)) in choices.iter().enumerate() {
+            let node = &arena[*idx];
+            if node.flags.contains(DirFlags::IS_DIR) {
+                eprintln!(
+                    "  {}. {path}/: {} files, {} tokens",
+                    i + 1,
+                    node.file_count,
+                    tokens(node.total_toks)
+                );
+            } else {
+                eprintln!("  {}. {path}: {} tokens", i + 1, tokens(node.total_toks));
+            }
+        }
+        let previous = last_selection
+            .map(|s| numbers_of(choices.iter().map(|(_, p)| p.as_str()), &s.directories));
+        let Some(picked) = ask(
+            "Numbers of the folders and files to include:",
+            "e.g. 1,3-5. Leave empty for the whole repository.",
+            choices.len(),
+            previous.as_deref(),
+        )?
+        else {
+            return Ok(TuiAction::Cancel);
+        };
+        paths = picked
+            .iter()
+            .map(|&i| PathBuf::from(&choices[i].1))
+            .collect();
+    }
+
+    Ok(TuiAction::Confirm {
+        exts,
+        paths,
+        pinned: Vec::new(),
+        skipped: Vec::new(),
+        arrange: false,
+        triage: false,
+    })
+}
+
+#[cfg(not(feature = "interactive"))]
+pub fn select_filters_simple(
+    _extensions: &[ExtStats],
+    _arena: &[DirNode],
+    _last_selection: Option<&LastSelection>,
+) -> Result<TuiAction> {
+    bail!("--simple-ui requires the 'interactive' feature.")
+}
+
+/// Reads a list of numbers; `None` if the prompt was cancelled.
+#[cfg(feature = "interactive")]
+fn ask(
+    prompt: &str,
+    help: &str,
+    count: usize,
+    default: Option<&str>,
+) -> Result<Option<Vec<usize>>> {
+    use inquire::{InquireError, Text, validator::Validation};
+
+    let mut text = Text::new(prompt)
+        .with_help_message(help)
+        .with_validator(move |input: &str| {
+            Ok(match parse_numbers(input, count) {
+                Ok(_) => Validation::Valid,
+                Err(e) => Validation::Invalid(e.to_string().into()),
+            })
+        });
+    if let Some(default) = default.filter(|d| !d.is_empty()) {
+        text = text.with_default(default);
+    }
+    match text.prompt() {
+        Ok(answer) => parse_numbers(&answer, count).map(Some),
+        Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The 1-based numbers of the `names` that are in `wanted`, as `1,3,4`.
+#[cfg(feature = "interactive")]
+fn numbers_of<'a>(names: impl Iterator<Item = &'a str>, wanted: &[String]) -> String {
+    names
+        .enumerate()
+        .filter(|(_, name)| wanted.iter().any(|w| w == name))
+        .map(|(i, _)| (i + 1).to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
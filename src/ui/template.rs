@@ -2,18 +2,19 @@
 //! such as prompting for variables and copying to the clipboard.
 
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use colored::Colorize;
 use handlebars::{
     Handlebars, Template, no_escape,
     template::{Parameter, TemplateElement},
 };
 #[cfg(feature = "interactive")]
-use inquire::Text;
+use inquire::{Confirm, Editor, MultiSelect, Select, Text};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::common::hash::HashMap;
@@ -53,6 +54,36 @@ impl TemplateSource for BuiltinTemplateSource {
     }
 }
 
+/// The template a project uses by default.
+pub fn project_template_path(project_path: &Path) -> PathBuf {
+    project_path.join(".code2prompt/template.hbs")
+}
+
+/// The template used by default for projects without their own.
+pub fn global_template_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_default()
+        .join("code2prompt/template.hbs")
+}
+
+/// The starter template written by `config init`: the built-in template,
+/// headed by a comment on the variables it can use.
+pub fn starter_template() -> String {
+    let mut template = String::from("{{!--\n");
+    template.push_str("  Starter template for code2prompt, a copy of the built-in one.\n\n");
+    template.push_str("  Variables provided by code2prompt:\n    ");
+    template.push_str(&BUILTIN_VARIABLES.join(", "));
+    template.push_str(
+        "\n\n  Any other variable is asked for when rendering, unless it is given\n  \
+         with -V name=value, a C2P_NAME environment variable or the\n  \
+         [template.defaults] table of the config. Variables can also be\n  \
+         declared in a +++ TOML front matter block above this comment.\n\n  \
+         Run `code2prompt template check <file>` after editing.\n--}}\n",
+    );
+    template.push_str(include_str!("../../default_template.hbs"));
+    template
+}
+
 /// Hashes a string using SHA256 and returns a hex string.
 pub fn hash_content(content: &str) -> String {
     let mut hasher = Sha256::new();
@@ -76,12 +107,7 @@ pub fn resolve_template(
 
     // 2. Try file-based sources.
     let file_source = FileTemplateSource {
-        candidates: vec![
-            project_path.join(".code2prompt/template.hbs"),
-            dirs::config_dir()
-                .unwrap_or_default()
-                .join("code2prompt/template.hbs"),
-        ],
+        candidates: vec![project_template_path(project_path), global_template_path()],
     };
 
     if let Ok(result) = file_source.load() {
@@ -92,38 +118,469 @@ pub fn resolve_template(
     BuiltinTemplateSource.load()
 }
 
-/// A more robust method to extract placeholder names from a template using the Handlebars parser.
+/// The front matter of a template: a TOML block between two `+++` lines at
+/// its very start, declaring the variables it expects.
+///
+/// ```text
+/// +++
+/// [vars.audience]
+/// description = "Who the prompt is written for"
+/// default = "a senior reviewer"
+///
+/// [vars.task]
+/// description = "What the model should do"
+/// required = true
+///
+/// [vars.depth]
+/// type = "enum"
+/// options = ["quick", "thorough"]
+/// default = "quick"
+/// +++
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrontMatter {
+    #[serde(default)]
+    pub vars: BTreeMap<String, VarSpec>,
+}
+
+/// A variable declared in a template's front matter or in the config's
+/// `[template.vars]`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VarSpec {
+    /// Shown when asking for the value.
+    pub description: Option<String>,
+    /// Suggested when asking for the value, and used when it is not given.
+    pub default: Option<String>,
+    /// Rendering fails if the variable is empty and has no default.
+    #[serde(default)]
+    pub required: bool,
+    /// How the value is asked for and which values it takes.
+    #[serde(default, rename = "type")]
+    pub kind: VarType,
+    /// The values an `enum` variable takes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+/// The type of a declared variable.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VarType {
+    /// One line of text.
+    #[default]
+    String,
+    /// `true` or `false` (also `yes`/`no`, `on`/`off`, `1`/`0`), passed to
+    /// the template as a boolean so that `{{#if}}` works.
+    Bool,
+    /// One of the variable's `options`.
+    Enum,
+    /// Text over several lines, written in `$EDITOR` when asked for.
+    Multiline,
+}
+
+impl VarSpec {
+    /// Checks a value against the variable's type, spelling booleans as
+    /// `true` or `false`.
+    pub fn check(&self, name: &str, value: &str) -> Result<String> {
+        match self.kind {
+            VarType::Bool => match parse_bool(value) {
+                Some(b) => Ok(b.to_string()),
+                None => bail!("'{name}' must be true or false, not '{value}'"),
+            },
+            VarType::Enum if self.options.is_empty() => {
+                bail!("'{name}' is an enum but declares no options")
+            }
+            VarType::Enum if !self.options.iter().any(|o| o == value) => bail!(
+                "'{name}' must be one of {}, not '{value}'",
+                self.options.join(", ")
+            ),
+            _ => Ok(value.to_string()),
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "y" | "on" | "1" => Some(true),
+        "false" | "no" | "n" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Splits a template into its front matter, if any, and its Handlebars body.
+pub fn split_front_matter(template_str: &str) -> Result<(FrontMatter, &str)> {
+    let Some(rest) = template_str
+        .strip_prefix("+++")
+        .and_then(|r| r.strip_prefix('\n').or_else(|| r.strip_prefix("\r\n")))
+    else {
+        return Ok((FrontMatter::default(), template_str));
+    };
+    let mut end = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "+++" {
+            let front = toml::from_str(&rest[..end]).context("Invalid template front matter")?;
+            return Ok((front, &rest[end + line.len()..]));
+        }
+        end += line.len();
+    }
+    bail!("The template front matter is not closed by a +++ line")
+}
+
+/// Fills the declared variables missing from `vars` with their defaults
+/// and checks the values against their types. Fails, listing them, if
+/// required variables are still missing or empty.
+pub fn apply_var_specs(
+    specs: &BTreeMap<String, VarSpec>,
+    vars: &mut HashMap<String, String>,
+) -> Result<()> {
+    let mut missing = Vec::new();
+    for (name, spec) in specs {
+        let given = vars.get(name).is_some_and(|v| !v.is_empty());
+        match &spec.default {
+            Some(default) if !given => {
+                vars.insert(name.clone(), default.clone());
+            }
+            None if !given && spec.required => missing.push(match &spec.description {
+                Some(description) => format!("{name} ({description})"),
+                None => name.clone(),
+            }),
+            _ => {}
+        }
+        if let Some(value) = vars.get_mut(name)
+            && !value.is_empty()
+        {
+            *value = spec.check(name, value)?;
+        }
+    }
+    if !missing.is_empty() {
+        bail!(
+            "The template requires {}; pass it with -V name=value.",
+            missing.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// The variables as template data: strings, except for `bool` variables,
+/// which are booleans.
+pub fn typed_vars(
+    specs: &BTreeMap<String, VarSpec>,
+    vars: HashMap<String, String>,
+) -> serde_json::Map<String, serde_json::Value> {
+    vars.into_iter()
+        .map(|(name, value)| {
+            let bool_value = specs
+                .get(&name)
+                .filter(|spec| spec.kind == VarType::Bool)
+                .and_then(|_| parse_bool(&value));
+            let value = match bool_value {
+                Some(b) => serde_json::Value::Bool(b),
+                None => serde_json::Value::String(value),
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+/// The variables a template needs from the user, sorted: the root-level
+/// names it references besides the built-ins, and those its front matter declares.
 pub fn extract_placeholders(template_str: &str) -> Result<Vec<String>> {
-    let template = Template::compile(template_str)
+    let (front, body) = split_front_matter(template_str)?;
+    let template = Template::compile(body)
         .map_err(|e| anyhow::anyhow!("Failed to parse template for variable extraction: {}", e))?;
 
-    let mut placeholders = HashSet::new(); // Use HashSet to avoid duplicates
-    let registered_identifiers: HashSet<&str> = [
-        "path",
-        "code",
-        "git_diff",
-        "source_tree",
-        "absolute_code_path",
-        "files",
-        "git_diff_branch",
-        "git_log_branch",
-    ]
-    .iter()
-    .cloned()
-    .collect();
+    let mut names = HashSet::new();
+    collect_names(&template, false, &mut names, &mut HashSet::new());
+    names.retain(|name| !BUILTIN_VARIABLES.contains(&name.as_str()));
+    names.extend(front.vars.into_keys());
+
+    let mut placeholders: Vec<String> = names.into_iter().collect();
+    placeholders.sort();
+    Ok(placeholders)
+}
+
+/// Resolves a `--prepend`/`--append` argument: `@path` reads the file,
+/// anything else is used verbatim.
+pub fn load_text_arg(arg: &str) -> Result<String> {
+    match arg.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read instructions file: {path}")),
+        None => Ok(arg.to_owned()),
+    }
+}
+
+/// Places the prelude and epilogue around the rendered body, unless the
+/// template already positions them itself via `{{user_prelude}}`/`{{user_epilogue}}`.
+pub fn wrap_with_user_blocks(
+    rendered: String,
+    template_str: &str,
+    prelude: Option<&str>,
+    epilogue: Option<&str>,
+) -> String {
+    let prelude = prelude.filter(|_| !template_str.contains("user_prelude"));
+    let epilogue = epilogue.filter(|_| !template_str.contains("user_epilogue"));
+    if prelude.is_none() && epilogue.is_none() {
+        return rendered;
+    }
+    let mut out = String::new();
+    if let Some(p) = prelude {
+        out.push_str(p.trim_end());
+        out.push_str("\n\n");
+    }
+    out.push_str(&rendered);
+    if let Some(e) = epilogue {
+        out.push_str("\n\n");
+        out.push_str(e.trim());
+    }
+    out
+}
+
+/// Helpers registered by default in Handlebars.
+const BUILTIN_HELPERS: &[&str] = &[
+    "if", "unless", "each", "with", "lookup", "raw", "log", "eq", "ne", "gt", "gte", "lt", "lte",
+    "and", "or", "not", "len",
+];
+
+/// Variables provided by code2prompt itself at the template root.
+const BUILTIN_VARIABLES: &[&str] = &[
+    "absolute_code_path",
+    "files",
+    "file_groups",
+    "source_tree",
+    "git_diff",
+    "git_diff_files",
+    "git_diff_branch",
+    "git_log_branch",
+    "user_prelude",
+    "user_epilogue",
+    "symbol_index",
+    "issue",
+    "git_branch",
+    "git_commit_sha",
+    "git_remote_url",
+    "repo_name",
+    "generated_at",
+];
+
+/// The outcome of `code2prompt template check`.
+#[derive(Debug, Default)]
+pub struct TemplateCheck {
+    /// Root-level variables that must be supplied by the user.
+    pub variables: Vec<String>,
+    /// Root-level variables provided by code2prompt.
+    pub builtins: Vec<String>,
+    /// Helpers that are not registered with Handlebars.
+    pub unknown_helpers: Vec<String>,
+    /// The error raised when rendering against a synthetic context, if any.
+    pub render_error: Option<String>,
+    /// The variables declared in the front matter.
+    pub declared: BTreeMap<String, VarSpec>,
+}
+
+impl TemplateCheck {
+    pub fn is_ok(&self) -> bool {
+        self.unknown_helpers.is_empty() && self.render_error.is_none()
+    }
+}
+
+/// Compiles a template, classifies the names it references and renders it
+/// against a synthetic context. Returns an error if the template does not compile.
+pub fn check_template(template_str: &str) -> Result<TemplateCheck> {
+    let (front, body) = split_front_matter(template_str)?;
+    let template = Template::compile(body).map_err(|e| anyhow!("{e}"))?;
 
+    let mut names = HashSet::new();
+    let mut helpers = HashSet::new();
+    collect_names(&template, false, &mut names, &mut helpers);
+
+    let mut check = TemplateCheck::default();
+    for name in names {
+        if BUILTIN_VARIABLES.contains(&name.as_str()) {
+            check.builtins.push(name);
+        } else {
+            check.variables.push(name);
+        }
+    }
+    check.unknown_helpers = helpers
+        .into_iter()
+        .filter(|h| !BUILTIN_HELPERS.contains(&h.as_str()))
+        .collect();
+    check.variables.sort();
+    check.builtins.sort();
+    check.unknown_helpers.sort();
+    check.declared = front.vars;
+
+    let mut data = synthetic_context();
+    if let Some(obj) = data.as_object_mut() {
+        for var in &check.variables {
+            obj.insert(var.clone(), serde_json::Value::String(format!("<{var}>")));
+        }
+    }
+    let hb = handlebars_setup(template_str, "check")?;
+    check.render_error = hb.render("check", &data).err().map(|e| e.to_string());
+
+    Ok(check)
+}
+
+/// A small stand-in for the real template context.
+fn synthetic_context() -> serde_json::Value {
+    serde_json::json!({
+        "absolute_code_path": "/path/to/project",
+        "source_tree": "project\n├── src\n│   └── main.rs\n└── README.md",
+        "files": [
+            {
+                "path": "src/main.rs",
+                "extension": "rs",
+                "code": "```rust\nfn main() {}\n```\n",
+                "token_count": 4
+            },
+            {
+                "path": "README.md",
+                "extension": "md",
+                "code": "```markdown\n# project\n```\n",
+                "token_count": 3
+            }
+        ],
+        "file_groups": [
+            {
+                "dir": "src",
+                "files": [{
+                    "path": "src/main.rs",
+                    "extension": "rs",
+                    "code": "```rust\nfn main() {}\n```\n",
+                    "token_count": 4
+                }]
+            },
+            {
+                "dir": ".",
+                "files": [{
+                    "path": "README.md",
+                    "extension": "md",
+                    "code": "```markdown\n# project\n```\n",
+                    "token_count": 3
+                }]
+            }
+        ],
+        "git_diff": "diff --git a/src/main.rs b/src/main.rs",
+        "git_diff_files": [
+            {
+                "path": "src/main.rs",
+                "status": "modified",
+                "hunks": ["@@ -1 +1 @@\n-fn main() {}\n+fn main() { run() }\n"],
+                "additions": 1,
+                "deletions": 1
+            }
+        ],
+        "git_diff_branch": "diff --git a/src/main.rs b/src/main.rs",
+        "git_log_branch": "commit 0000000 Initial commit",
+        "symbol_index": [
+            {
+                "path": "src/main.rs",
+                "symbols": [{ "name": "main", "kind": "fn", "line": 1 }]
+            }
+        ],
+        "issue": {
+            "number": 1,
+            "title": "main does nothing",
+            "body": "Running the binary prints nothing.",
+            "url": "https://github.com/owner/project/issues/1",
+            "labels": ["bug"]
+        },
+        "git_branch": "main",
+        "git_commit_sha": "0000000000000000000000000000000000000000",
+        "git_remote_url": "https://github.com/owner/project.git",
+        "repo_name": "project",
+        "generated_at": "2024-01-01T00:00:00Z",
+    })
+}
+
+/// Walks a compiled template, collecting root-level variable names and
+/// helper names. Names inside `each`/`with` blocks refer to the block's
+/// context and are only collected when addressed through `@root` or `../`.
+fn collect_names(
+    template: &Template,
+    scoped: bool,
+    names: &mut HashSet<String>,
+    helpers: &mut HashSet<String>,
+) {
     for element in &template.elements {
-        if let TemplateElement::Expression(expr) = element {
-            if let Parameter::Name(name) = &expr.name {
-                if !registered_identifiers.contains(name.as_str()) {
-                    placeholders.insert(name.clone());
+        match element {
+            TemplateElement::Expression(ht) | TemplateElement::HtmlExpression(ht) => {
+                let is_call = !ht.params.is_empty() || !ht.hash.is_empty();
+                match &ht.name {
+                    Parameter::Name(name) if is_call => {
+                        helpers.insert(name.clone());
+                    }
+                    name => collect_param(name, scoped, names, helpers),
                 }
+                collect_params(&ht.params, &ht.hash, scoped, names, helpers);
             }
+            TemplateElement::HelperBlock(ht) => {
+                let helper = ht.name.as_name().unwrap_or_default();
+                helpers.insert(helper.to_owned());
+                collect_params(&ht.params, &ht.hash, scoped, names, helpers);
+                let inner_scoped = scoped || matches!(helper, "each" | "with");
+                if let Some(t) = &ht.template {
+                    collect_names(t, inner_scoped, names, helpers);
+                }
+                if let Some(t) = &ht.inverse {
+                    collect_names(t, scoped, names, helpers);
+                }
+            }
+            _ => {}
         }
     }
+}
+
+fn collect_params(
+    params: &[Parameter],
+    hash: &std::collections::HashMap<String, Parameter>,
+    scoped: bool,
+    names: &mut HashSet<String>,
+    helpers: &mut HashSet<String>,
+) {
+    for p in params.iter().chain(hash.values()) {
+        collect_param(p, scoped, names, helpers);
+    }
+}
 
-    // Convert HashSet to Vec for the final result
-    Ok(placeholders.into_iter().collect())
+fn collect_param(
+    param: &Parameter,
+    scoped: bool,
+    names: &mut HashSet<String>,
+    helpers: &mut HashSet<String>,
+) {
+    let raw = match param {
+        Parameter::Name(name) => name.as_str(),
+        Parameter::Path(handlebars::Path::Relative((_, raw))) => raw.as_str(),
+        Parameter::Subexpression(sub) => {
+            helpers.insert(sub.name().to_owned());
+            let empty = std::collections::HashMap::new();
+            let params = sub.params().map(Vec::as_slice).unwrap_or_default();
+            collect_params(params, sub.hash().unwrap_or(&empty), scoped, names, helpers);
+            return;
+        }
+        _ => return,
+    };
+    let (raw, scoped) = match raw
+        .strip_prefix("@root.")
+        .or_else(|| raw.strip_prefix("@root/"))
+    {
+        Some(rest) => (rest, false),
+        None => (
+            raw.trim_start_matches("../"),
+            scoped && !raw.starts_with("../"),
+        ),
+    };
+    if scoped || raw.is_empty() || raw == "this" || raw.starts_with('@') {
+        return;
+    }
+    let root = raw.split(['.', '/', '[']).next().unwrap_or(raw);
+    if !root.is_empty() {
+        names.insert(root.to_owned());
+    }
 }
 
 /// Set up the Handlebars template engine.
@@ -131,14 +588,16 @@ pub fn handlebars_setup<'a>(template_str: &str, template_name: &str) -> Result<H
     let mut handlebars = Handlebars::new();
     handlebars.register_escape_fn(no_escape);
 
+    let (_, body) = split_front_matter(template_str)?;
     handlebars
-        .register_template_string(template_name, template_str)
+        .register_template_string(template_name, body)
         .map_err(|e| anyhow::anyhow!("Failed to register template: {}", e))?;
 
     Ok(handlebars)
 }
 
 /// Renders the template with the provided data.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(handlebars, data)))]
 pub fn render_template(
     handlebars: &Handlebars,
     template_name: &str,
@@ -152,6 +611,12 @@ pub fn render_template(
 
 /// Writes the rendered template to a specified output file.
 pub fn write_to_file(output_path: &str, rendered: &str) -> Result<()> {
+    if let Some(parent) = Path::new(output_path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
     let file = std::fs::File::create(output_path)?;
     let mut writer = std::io::BufWriter::new(file);
     write!(writer, "{rendered}")?;
@@ -175,22 +640,105 @@ pub fn write_to_file(output_path: &str, rendered: &str) -> Result<()> {
 pub fn prompt_for_variables(
     vars_to_prompt: &[String],
     cached_vars: &HashMap<String, String>,
+    specs: &BTreeMap<String, VarSpec>,
 ) -> Result<HashMap<String, String>> {
     let mut new_vars = HashMap::default();
     for var in vars_to_prompt {
-        let prompt_text = format!("Enter value for '{var}': ");
-        let mut prompt = Text::new(&prompt_text);
+        let spec = specs.get(var).cloned().unwrap_or_default();
+        let current = cached_vars.get(var).or(spec.default.as_ref());
+        let answer = prompt_for_value(var, &spec, current.map(String::as_str)).unwrap_or_default();
+        new_vars.insert(var.clone(), answer);
+    }
+    Ok(new_vars)
+}
 
-        if let Some(cached_val) = cached_vars.get(var) {
-            prompt = prompt.with_default(cached_val);
+/// Lists cached variable values and asks for new values of the ones picked.
+/// Returns the changed values; none if the list is skipped with Esc.
+#[cfg(feature = "interactive")]
+pub fn review_cached_variables(
+    vars: &[(String, String)],
+    specs: &BTreeMap<String, VarSpec>,
+) -> Result<HashMap<String, String>> {
+    let options: Vec<String> = vars.iter().map(|(k, v)| format!("{k} = {v}")).collect();
+    let picked = MultiSelect::new(
+        "Cached template variables. Pick the ones to change:",
+        options,
+    )
+    .with_help_message("Space to pick, Enter to continue, Esc to keep every value")
+    .raw_prompt_skippable()
+    .context("Failed to read the variables to change")?
+    .unwrap_or_default();
+
+    let mut changed = HashMap::default();
+    for option in picked {
+        let (var, value) = &vars[option.index];
+        let spec = specs.get(var).cloned().unwrap_or_default();
+        let answer = prompt_for_value(var, &spec, Some(value))
+            .with_context(|| format!("Failed to read the value of '{var}'"))?;
+        changed.insert(var.clone(), answer);
+    }
+    Ok(changed)
+}
+
+/// Asks for the value of `var` in the way its type calls for: a yes/no
+/// question, a choice between its options, an editor or a line of text.
+/// `current` is suggested.
+#[cfg(feature = "interactive")]
+fn prompt_for_value(var: &str, spec: &VarSpec, current: Option<&str>) -> Result<String> {
+    use inquire::validator::ValueRequiredValidator;
+
+    let help = match &spec.description {
+        Some(description) => format!("{description} (cached for the next run)"),
+        None => "This value will be cached for the next run.".to_string(),
+    };
+    let answer = match spec.kind {
+        VarType::Bool => Confirm::new(&format!("Set '{var}'?"))
+            .with_help_message(&help)
+            .with_default(current.and_then(parse_bool).unwrap_or(false))
+            .prompt()?
+            .to_string(),
+        VarType::Enum => {
+            let cursor = current
+                .and_then(|c| spec.options.iter().position(|o| o == c))
+                .unwrap_or(0);
+            Select::new(
+                &format!("Choose a value for '{var}':"),
+                spec.options.clone(),
+            )
+            .with_help_message(&help)
+            .with_starting_cursor(cursor)
+            .prompt()?
         }
+        VarType::Multiline => Editor::new(&format!("Enter value for '{var}':"))
+            .with_help_message(&help)
+            .with_predefined_text(current.unwrap_or_default())
+            .prompt()?
+            .trim_end()
+            .to_string(),
+        VarType::String => {
+            let prompt_text = format!("Enter value for '{var}': ");
+            let mut prompt = Text::new(&prompt_text).with_help_message(&help);
+            if let Some(current) = current {
+                prompt = prompt.with_default(current);
+            } else if spec.required {
+                prompt = prompt.with_validator(ValueRequiredValidator::default());
+            }
+            prompt.prompt()?
+        }
+    };
+    Ok(answer)
+}
 
-        let answer = prompt
-            .with_help_message("This value will be cached for the next run.")
-            .prompt()
-            .unwrap_or_default();
+/// Asks for a one-off task statement, exposed to templates as `{{task}}`.
+#[cfg(feature = "interactive")]
+pub fn prompt_for_task() -> Result<String> {
+    Text::new("What do you want the model to do?")
+        .with_help_message("Available in the template as {{task}}. Not cached.")
+        .prompt()
+        .context("Failed to read the task")
+}
 
-        new_vars.insert(var.clone(), answer);
-    }
-    Ok(new_vars)
+#[cfg(not(feature = "interactive"))]
+pub fn prompt_for_task() -> Result<String> {
+    Err(anyhow!("--ask-task requires the 'interactive' feature."))
 }
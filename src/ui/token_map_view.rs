@@ -8,10 +8,11 @@ use atty;
 #[cfg(feature = "colors")]
 use lscolors::{Indicator, LsColors, Style as LsStyle};
 use terminal_size;
-use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use unicode_width::UnicodeWidthStr;
 
 use crate::common::format::{self, TokenFormatStyle};
 use crate::engine::model::TokenMapEntry;
+use crate::engine::token_map::is_other;
 
 #[cfg(feature = "colors")]
 fn should_enable_colors() -> bool {
@@ -115,7 +116,13 @@ fn generate_hierarchical_bar(
     result
 }
 
-pub fn display_token_map(entries: &[TokenMapEntry], total_tokens: usize) {
+/// Prints the token map. Bars of entries at or above `highlight_percent` are
+/// drawn in red when colors are enabled.
+pub fn display_token_map(
+    entries: &[TokenMapEntry],
+    total_tokens: usize,
+    highlight_percent: Option<f64>,
+) {
     if entries.is_empty() {
         println!("No files to display in token map.");
         return;
@@ -171,23 +178,23 @@ pub fn display_token_map(entries: &[TokenMapEntry], total_tokens: usize) {
                 prefix.push_str("  ");
             }
         }
-        if entry.depth == 0 && i == 0 && entry.name != "(other files)" {
+        if entry.depth == 0 && i == 0 && !is_other(entry) {
             prefix = "".to_string();
         }
-        if entry.depth > 0 || (entry.depth == 0 && entry.name == "(other files)") {
+        if entry.depth > 0 || is_other(entry) {
             if entry.is_last {
                 prefix.push_str("└─");
             } else {
                 prefix.push_str("├─");
             }
-        } else if i == 0 && entry.name != "(other files)" {
+        } else if i == 0 && !is_other(entry) {
             prefix.push_str("┌─");
         }
         let has_children_to_display = entries
             .get(i + 1)
             .map(|next_entry| next_entry.depth > entry.depth)
             .unwrap_or(false);
-        if entry.depth > 0 || entry.name == "(other files)" || i == 0 {
+        if entry.depth > 0 || is_other(entry) || i == 0 {
             if has_children_to_display {
                 prefix.push('┬');
             } else {
@@ -215,29 +222,13 @@ pub fn display_token_map(entries: &[TokenMapEntry], total_tokens: usize) {
         }
         let percentage_str = format!("{:>4.0}%", entry.percentage);
         let current_prefix_width = UnicodeWidthStr::width(prefix.as_str());
-        let name_display_width = UnicodeWidthStr::width(entry.name.as_str());
         let available_for_name = max_name_length.saturating_sub(current_prefix_width);
-        let (truncated_name, remaining_padding) = if name_display_width > available_for_name {
-            let mut truncated_width = 0;
-            let mut take_chars = 0;
-            // Subtract 1 for the '…' character.
-            let max_width = available_for_name.saturating_sub(1);
-            for c in entry.name.chars() {
-                let char_width = c.width().unwrap_or(0);
-                if truncated_width + char_width > max_width {
-                    break;
-                }
-                truncated_width += char_width;
-                take_chars += 1;
-            }
-            let truncated: String = entry.name.chars().take(take_chars).collect();
-            (format!("{truncated}…"), 0)
-        } else {
-            (entry.name.clone(), available_for_name - name_display_width)
-        };
-        let name_with_padding = format!("{}{}", truncated_name, " ".repeat(remaining_padding));
+        let name_with_padding = format::pad_to_width(
+            &format::truncate_to_width(&entry.name, available_for_name),
+            available_for_name,
+        );
 
-        let colored_name_with_padding = if colors_enabled && entry.name != "(other files)" {
+        let colored_name_with_padding = if colors_enabled && !is_other(entry) {
             #[cfg(feature = "colors")]
             {
                 let style = if entry.metadata.is_dir {
@@ -260,6 +251,13 @@ pub fn display_token_map(entries: &[TokenMapEntry], total_tokens: usize) {
             name_with_padding.to_string()
         };
 
+        let hot = highlight_percent.is_some_and(|t| entry.percentage >= t);
+        let bar = if colors_enabled && hot {
+            format!("\x1b[31m{bar}\x1b[0m")
+        } else {
+            bar
+        };
+
         println!(
             "{:>max_token_width$}   {}{} │{}│ {}",
             tokens_str,
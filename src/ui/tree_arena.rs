@@ -13,6 +13,10 @@ bitflags! {
         const EXPANDED  = 0b0000_0010;
         const SELECTED  = 0b0000_0100;
         const PARTIAL_SELECTION  = 0b0000_1000;
+        /// Skipped by the scan as a hidden file.
+        const HIDDEN    = 0b0001_0000;
+        /// Skipped by the scan as a gitignored file.
+        const IGNORED   = 0b0010_0000;
     }
 }
 
@@ -23,6 +27,10 @@ pub trait PathInfo {
     fn count(&self) -> usize;
     fn extension(&self) -> Option<&String>;
     fn token_count(&self) -> Option<usize>;
+    /// Size of the file on disk, in bytes.
+    fn byte_size(&self) -> Option<u64> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -36,6 +44,7 @@ pub struct DirNode {
     pub total_toks: usize,
     pub visible_toks: usize,
     pub visible_files: usize,
+    pub total_bytes: u64,
     pub extension: Option<String>,
     pub ext_slot: u16,
 }
@@ -66,6 +75,7 @@ pub fn build_dir_arena<T: PathInfo>(
         total_toks: 0,
         visible_toks: 0,
         visible_files: 0,
+        total_bytes: 0,
         extension: None,
         ext_slot: 0,
     });
@@ -99,10 +109,13 @@ pub fn build_dir_arena<T: PathInfo>(
 
             // propagate file count up the chain (including file node itself)
             let file_count = path_info.count();
-            let token_count = if is_last {
-                path_info.token_count().unwrap_or(0)
+            let (token_count, byte_size) = if is_last {
+                (
+                    path_info.token_count().unwrap_or(0),
+                    path_info.byte_size().unwrap_or(0),
+                )
             } else {
-                0
+                (0, 0)
             };
 
             if file_count > 0 {
@@ -112,6 +125,7 @@ pub fn build_dir_arena<T: PathInfo>(
                     arena[idx as usize].total_toks += token_count;
                     arena[idx as usize].visible_toks += token_count;
                     arena[idx as usize].visible_files += file_count;
+                    arena[idx as usize].total_bytes += byte_size;
                     node_idx_to_update = arena[idx as usize].parent;
                 }
             }
@@ -123,6 +137,49 @@ pub fn build_dir_arena<T: PathInfo>(
     arena
 }
 
+/// Flags the files the scan skipped (`skipped` maps their relative paths to
+/// `HIDDEN` and/or `IGNORED`). Directories get the flags shared by all
+/// their children.
+pub fn mark_skipped(arena: &mut [DirNode], skipped: &HashMap<String, DirFlags>) {
+    const SKIPPED: DirFlags = DirFlags::HIDDEN.union(DirFlags::IGNORED);
+    if skipped.is_empty() {
+        return;
+    }
+
+    // Parents always precede their children in the arena.
+    let mut paths: Vec<String> = Vec::with_capacity(arena.len());
+    paths.push(String::new());
+    for node in arena.iter_mut().skip(1) {
+        let parent = node.parent.unwrap_or(0) as usize;
+        let path = if parent == 0 {
+            node.name.clone()
+        } else {
+            format!("{}/{}", paths[parent], node.name)
+        };
+        if !node.flags.contains(DirFlags::IS_DIR)
+            && let Some(flags) = skipped.get(&path)
+        {
+            node.flags.insert(*flags & SKIPPED);
+        }
+        paths.push(path);
+    }
+
+    for i in (1..arena.len()).rev() {
+        if !arena[i].flags.contains(DirFlags::IS_DIR) {
+            continue;
+        }
+        let mut shared = SKIPPED;
+        let mut child_opt = arena[i].first_child;
+        while let Some(child) = child_opt {
+            shared &= arena[child as usize].flags;
+            child_opt = arena[child as usize].next_sibling;
+        }
+        if arena[i].first_child.is_some() {
+            arena[i].flags.insert(shared);
+        }
+    }
+}
+
 // It takes mutable references to the arena and index, so its borrows are temporary.
 fn ensure_child<'a>(
     arena: &'a mut Vec<DirNode>,
@@ -159,6 +216,7 @@ fn ensure_child<'a>(
         total_toks: 0,
         visible_toks: 0,
         visible_files: 0,
+        total_bytes: 0,
         extension: extension.cloned(),
         ext_slot: ext_slot_val,
     };
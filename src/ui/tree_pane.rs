@@ -1,3 +1,4 @@
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 
 use ratatui::widgets::TableState;
@@ -17,6 +18,14 @@ pub struct TreePane {
     pub cursor: usize, // Index into visible_nodes
     pub list_state: TableState,
     pub last_filter: FxHashSet<String>,
+    /// Pinned files, in pin order.
+    pub pinned: Vec<Idx>,
+    /// Pinned files and their ancestors, shown whatever the extension filter.
+    pub pinned_nodes: FxHashSet<Idx>,
+    /// Whether hidden files the scan skipped count as included.
+    pub include_hidden: bool,
+    /// Whether gitignored files the scan skipped count as included.
+    pub include_ignored: bool,
 }
 
 impl NavigablePane for TreePane {
@@ -34,10 +43,35 @@ impl NavigablePane for TreePane {
         // of visible tokens and files.
         true
     }
+
+    fn cursor_position(&self) -> Option<usize> {
+        (!self.visible_nodes.is_empty()).then_some(self.cursor)
+    }
+
+    fn is_range_selected(&self, range: RangeInclusive<usize>) -> bool {
+        self.visible_nodes
+            .get(range)
+            .unwrap_or_default()
+            .iter()
+            .all(|&idx| self.arena[idx as usize].flags.contains(DirFlags::SELECTED))
+    }
+
+    fn set_range_selected(&mut self, range: RangeInclusive<usize>, select: bool) -> bool {
+        let nodes = self.visible_nodes.get(range).unwrap_or_default().to_vec();
+        let mut changed = false;
+        for idx in nodes {
+            if self.arena[idx as usize].flags.contains(DirFlags::SELECTED) != select {
+                self.set_node_selection(idx, select);
+                changed = true;
+            }
+        }
+        changed
+    }
 }
 
 impl TreePane {
     pub fn new(mut arena: Vec<DirNode>, last_selection: Option<&LastSelection>) -> Self {
+        let mut pinned = Vec::new();
         if let Some(selection) = last_selection {
             if !selection.directories.is_empty() {
                 let key_set: std::collections::HashSet<_> = selection.directories.iter().collect();
@@ -72,6 +106,16 @@ impl TreePane {
                     }
                 }
             }
+
+            if !selection.pinned.is_empty() {
+                for i in 1..arena.len() {
+                    if !arena[i].flags.contains(DirFlags::IS_DIR)
+                        && selection.pinned.contains(&Self::get_path(&arena, i as Idx))
+                    {
+                        pinned.push(i as Idx);
+                    }
+                }
+            }
         }
 
         let mut pane = Self {
@@ -81,7 +125,12 @@ impl TreePane {
             cursor: 0,
             list_state: TableState::default(),
             last_filter: FxHashSet::default(),
+            pinned,
+            pinned_nodes: FxHashSet::default(),
+            include_hidden: false,
+            include_ignored: false,
         };
+        pane.rebuild_pinned_nodes();
 
         if !pane.visible_nodes.is_empty() {
             pane.list_state.select(Some(0));
@@ -94,7 +143,7 @@ impl TreePane {
         let old_cursor_id = self.visible_nodes.get(self.cursor).copied();
         self.visible_nodes.clear();
 
-        if active_extensions.is_empty() {
+        if active_extensions.is_empty() && self.pinned.is_empty() {
             self.cursor = 0;
             self.list_state.select(Some(0));
             return;
@@ -107,11 +156,13 @@ impl TreePane {
             for i in 1..self.arena.len() {
                 let node = &self.arena[i];
                 if !node.flags.contains(DirFlags::IS_DIR) {
-                    // It's a file
-                    if node
-                        .extension
-                        .as_ref()
-                        .is_some_and(|ext| active_extensions.contains(ext))
+                    // It's a file. Skipped files have no extension totals and
+                    // are always shown.
+                    if node.flags.intersects(DirFlags::HIDDEN | DirFlags::IGNORED)
+                        || node
+                            .extension
+                            .as_ref()
+                            .is_some_and(|ext| active_extensions.contains(ext))
                     {
                         // Mark this file and all its ancestors as allowed
                         let mut current_idx = Some(i as Idx);
@@ -129,7 +180,7 @@ impl TreePane {
         let mut current_child_opt = self.arena[0].first_child;
         while let Some(child_idx) = current_child_opt {
             // We only need to start a walk if the top-level directory is allowed
-            if self.allowed_nodes.contains(&child_idx) {
+            if self.is_allowed(child_idx) {
                 self.walk_and_add(child_idx);
             }
             current_child_opt = self.arena[child_idx as usize].next_sibling;
@@ -161,7 +212,7 @@ impl TreePane {
             while let Some(child_idx) = current_child_opt {
                 // IMPORTANT: Only descend into children that are part of the filtered set.
                 // This prevents showing empty branches of an expanded directory.
-                if self.allowed_nodes.contains(&child_idx) {
+                if self.is_allowed(child_idx) {
                     self.walk_and_add(child_idx);
                 }
                 current_child_opt = self.arena[child_idx as usize].next_sibling;
@@ -169,6 +220,109 @@ impl TreePane {
         }
     }
 
+    fn is_allowed(&self, node_idx: Idx) -> bool {
+        self.allowed_nodes.contains(&node_idx) || self.pinned_nodes.contains(&node_idx)
+    }
+
+    /// Whether a node is left out under the current Hidden / gitignore
+    /// settings, so it is drawn dimmed.
+    pub fn is_excluded(&self, node_idx: Idx) -> bool {
+        let flags = self.arena[node_idx as usize].flags;
+        (flags.contains(DirFlags::HIDDEN) && !self.include_hidden)
+            || (flags.contains(DirFlags::IGNORED) && !self.include_ignored)
+    }
+
+    /// Applies the Hidden / gitignore settings to the files the scan skipped:
+    /// those the settings exclude are deselected, the others selected.
+    pub fn apply_exclusions(&mut self, include_hidden: bool, include_ignored: bool) {
+        self.include_hidden = include_hidden;
+        self.include_ignored = include_ignored;
+        for i in 1..self.arena.len() as Idx {
+            let flags = self.arena[i as usize].flags;
+            if flags.contains(DirFlags::IS_DIR)
+                || !flags.intersects(DirFlags::HIDDEN | DirFlags::IGNORED)
+            {
+                continue;
+            }
+            let select = !self.is_excluded(i);
+            if flags.contains(DirFlags::SELECTED) != select {
+                self.set_node_selection(i, select);
+            }
+        }
+    }
+
+    /// The selected or pinned files the scan skipped, which must be processed
+    /// before they can be included.
+    pub fn get_skipped_paths(&self) -> Vec<PathBuf> {
+        (1..self.arena.len() as Idx)
+            .filter(|&i| {
+                let flags = self.arena[i as usize].flags;
+                !flags.contains(DirFlags::IS_DIR)
+                    && flags.intersects(DirFlags::HIDDEN | DirFlags::IGNORED)
+                    && (self.is_effectively_selected(i) || self.is_pinned(i))
+            })
+            .map(|i| Self::get_path_buf(&self.arena, i))
+            .collect()
+    }
+
+    /// Whether a node or any of its ancestors is selected.
+    fn is_effectively_selected(&self, node_idx: Idx) -> bool {
+        let mut current = Some(node_idx);
+        while let Some(idx) = current {
+            if idx == 0 {
+                break;
+            }
+            if self.arena[idx as usize].flags.contains(DirFlags::SELECTED) {
+                return true;
+            }
+            current = self.arena[idx as usize].parent;
+        }
+        false
+    }
+
+    /// Pins or unpins the file under the cursor. Directories cannot be pinned.
+    ///
+    /// Returns `true` if the pins changed.
+    pub fn toggle_pin(&mut self) -> bool {
+        let Some(idx) = self.get_current_node_idx() else {
+            return false;
+        };
+        if self.arena[idx as usize].flags.contains(DirFlags::IS_DIR) {
+            return false;
+        }
+        match self.pinned.iter().position(|&p| p == idx) {
+            Some(pos) => {
+                self.pinned.remove(pos);
+            }
+            None => self.pinned.push(idx),
+        }
+        self.rebuild_pinned_nodes();
+        true
+    }
+
+    pub fn is_pinned(&self, node_idx: Idx) -> bool {
+        self.pinned.contains(&node_idx)
+    }
+
+    /// The paths of all pinned files.
+    pub fn get_pinned_paths(&self) -> Vec<PathBuf> {
+        self.pinned
+            .iter()
+            .map(|&idx| Self::get_path_buf(&self.arena, idx))
+            .collect()
+    }
+
+    fn rebuild_pinned_nodes(&mut self) {
+        self.pinned_nodes.clear();
+        for &pin in &self.pinned {
+            let mut current_idx = Some(pin);
+            while let Some(idx) = current_idx {
+                self.pinned_nodes.insert(idx);
+                current_idx = self.arena[idx as usize].parent;
+            }
+        }
+    }
+
     /// A helper to get the depth of a node for indentation.
     pub fn get_depth(&self, node_idx: Idx) -> usize {
         let mut depth = 0;
@@ -254,16 +408,22 @@ impl TreePane {
             let is_selected = self.arena[node_idx as usize]
                 .flags
                 .contains(DirFlags::SELECTED);
-            Self::set_selection_recursive(&mut self.arena, node_idx, !is_selected);
+            self.set_node_selection(node_idx, !is_selected);
+        }
+    }
 
-            let mut current_ancestor = self.arena[node_idx as usize].parent;
-            while let Some(parent_idx) = current_ancestor {
-                if parent_idx == 0 {
-                    break;
-                }
-                Self::update_parent_selection_state(&mut self.arena, parent_idx);
-                current_ancestor = self.arena[parent_idx as usize].parent;
+    /// Selects or deselects a node with its subtree, then refreshes the state
+    /// of its ancestors.
+    fn set_node_selection(&mut self, node_idx: Idx, select: bool) {
+        Self::set_selection_recursive(&mut self.arena, node_idx, select);
+
+        let mut current_ancestor = self.arena[node_idx as usize].parent;
+        while let Some(parent_idx) = current_ancestor {
+            if parent_idx == 0 {
+                break;
             }
+            Self::update_parent_selection_state(&mut self.arena, parent_idx);
+            current_ancestor = self.arena[parent_idx as usize].parent;
         }
     }
 
@@ -1,25 +1,34 @@
 use std::path::Path;
 
-use crate::common::format::format_path_label;
-use crate::engine::model::ProcessedEntry;
+use crate::common::{format::format_path_label, path};
+use crate::engine::{file_id, model::ProcessedEntry};
 
-/// Builds a string representation of the directory tree for display.
+/// Builds a string representation of the directory tree for display. With
+/// `file_ids`, each file is followed by its ID, e.g. `main.rs [F1a2b3c]`.
 pub fn build_tree_view(
     root_path: &Path,
     entries: &[ProcessedEntry],
     full_directory_tree: bool,
+    file_ids: bool,
 ) -> String {
     use termtree::Tree;
 
-    let canonical_root = root_path
-        .canonicalize()
-        .unwrap_or_else(|_| root_path.to_path_buf());
+    let canonical_root = path::canonicalize(root_path).unwrap_or_else(|_| root_path.to_path_buf());
     let mut root_tree = Tree::new(format_path_label(&canonical_root));
+    let ids = if file_ids {
+        file_id::entry_ids(entries)
+    } else {
+        Default::default()
+    };
+    let label = |name: String, e: &ProcessedEntry| match ids.get(&e.relative_path) {
+        Some(id) => format!("{name} [{id}]"),
+        None => name,
+    };
 
     if !full_directory_tree {
         let mut leaves: Vec<_> = entries
             .iter()
-            .map(|e| Tree::new(e.relative_path.to_string_lossy().into_owned()))
+            .map(|e| Tree::new(label(path::display(&e.relative_path), e)))
             .collect();
         leaves.sort_by(|a, b| a.root.cmp(&b.root));
         root_tree.leaves = leaves;
@@ -29,8 +38,12 @@ pub fn build_tree_view(
         for e in &sorted_entries {
             if let Ok(rel) = e.path.strip_prefix(&canonical_root) {
                 let mut cur = &mut root_tree;
-                for comp in rel.components() {
-                    let s = comp.as_os_str().to_string_lossy().into_owned();
+                let mut comps = rel.components().peekable();
+                while let Some(comp) = comps.next() {
+                    let mut s = comp.as_os_str().to_string_lossy().into_owned();
+                    if comps.peek().is_none() {
+                        s = label(s, e);
+                    }
                     cur = if let Some(pos) = cur.leaves.iter_mut().position(|t| t.root == s) {
                         &mut cur.leaves[pos]
                     } else {
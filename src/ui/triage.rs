@@ -0,0 +1,218 @@
+//! The triage screen, shown with `--top-files N` or after the selector when
+//! it is confirmed with `L`: lists the largest files by tokens so the ones
+//! not worth their tokens can be dropped from the prompt (`Space`) or
+//! excluded from future scans too (`x`), without going through the tree.
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+
+use crate::common::format::{self, TokenFormatStyle};
+use crate::ui::tui_select::{
+    SignalGuard, TerminalGuard, drain_input_buffer, install_panic_hook, pane_block, setup_terminal,
+};
+
+/// How many files the screen lists when opened from the selector.
+pub const DEFAULT_TOP_FILES: usize = 20;
+
+const HELP_TEXT: &str =
+    "j/k: Move cursor | Space: Keep/drop | x: Exclude from future scans | Enter: Render | q/Esc: Keep all";
+
+/// What happens to a listed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Keep,
+    /// Left out of this prompt.
+    Drop,
+    /// Left out of this prompt and added to the repository's excludes.
+    Exclude,
+}
+
+/// The largest files, as `(path, tokens, verdict)`, and the cursor.
+#[derive(Debug, Clone, Default)]
+pub struct Triage {
+    pub files: Vec<(String, usize, Verdict)>,
+    pub cursor: usize,
+}
+
+impl Triage {
+    /// Lists the `n` files with the most tokens out of `(path, tokens)`
+    /// pairs, largest first, all kept.
+    pub fn largest(mut files: Vec<(String, usize)>, n: usize) -> Self {
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        files.truncate(n);
+        Self {
+            files: files
+                .into_iter()
+                .map(|(path, tokens)| (path, tokens, Verdict::Keep))
+                .collect(),
+            cursor: 0,
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.cursor + 1 < self.files.len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Drops the file under the cursor, or keeps it again.
+    pub fn toggle_drop(&mut self) {
+        self.set_or_keep(Verdict::Drop);
+    }
+
+    /// Excludes the file under the cursor from future scans, or keeps it
+    /// again.
+    pub fn toggle_exclude(&mut self) {
+        self.set_or_keep(Verdict::Exclude);
+    }
+
+    fn set_or_keep(&mut self, verdict: Verdict) {
+        if let Some((_, _, current)) = self.files.get_mut(self.cursor) {
+            *current = if *current == verdict {
+                Verdict::Keep
+            } else {
+                verdict
+            };
+        }
+    }
+
+    /// The paths left out of this prompt, excluded ones included.
+    pub fn dropped(&self) -> Vec<String> {
+        self.paths(|v| v != Verdict::Keep)
+    }
+
+    /// The paths to add to the repository's excludes.
+    pub fn excluded(&self) -> Vec<String> {
+        self.paths(|v| v == Verdict::Exclude)
+    }
+
+    /// The tokens of the files left out of this prompt.
+    pub fn dropped_tokens(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|(_, _, v)| *v != Verdict::Keep)
+            .map(|(_, tokens, _)| tokens)
+            .sum()
+    }
+
+    fn paths(&self, wanted: impl Fn(Verdict) -> bool) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|(_, _, v)| wanted(*v))
+            .map(|(path, _, _)| path.clone())
+            .collect()
+    }
+}
+
+/// Shows the triage screen. Returns the triaged files, or `None` if the
+/// screen was left without confirming, to keep every file.
+pub fn triage_files_tui(repo_name: &str, triage: Triage) -> Result<Option<Triage>> {
+    install_panic_hook();
+    let signals = SignalGuard::install()?;
+    let mut guard = TerminalGuard(setup_terminal()?);
+    drain_input_buffer()?;
+
+    let mut triage = triage;
+    let mut list_state = ListState::default();
+    loop {
+        if signals.interrupted.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        list_state.select(Some(triage.cursor));
+        guard
+            .0
+            .draw(|f| ui(f, repo_name, &triage, &mut list_state))?;
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(None);
+            }
+            KeyCode::Enter => return Ok(Some(triage)),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+            KeyCode::Down | KeyCode::Char('j') => triage.next(),
+            KeyCode::Up | KeyCode::Char('k') => triage.previous(),
+            KeyCode::Char(' ') => triage.toggle_drop(),
+            KeyCode::Char('x') => triage.toggle_exclude(),
+            _ => {}
+        }
+    }
+}
+
+fn ui(f: &mut Frame, repo_name: &str, triage: &Triage, list_state: &mut ListState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+    f.render_widget(
+        Paragraph::new(format!("code2prompt ▸ {repo_name} ▸ largest files"))
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+
+    let tokens = |n| format::format_tokens(n, TokenFormatStyle::Compact);
+    let items: Vec<ListItem> = triage
+        .files
+        .iter()
+        .map(|(path, count, verdict)| {
+            let (mark, style) = match verdict {
+                Verdict::Keep => ("[x]", Style::default()),
+                Verdict::Drop => ("[ ]", Style::default().fg(Color::DarkGray)),
+                Verdict::Exclude => ("[-]", Style::default().fg(Color::Red)),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{mark} "), style),
+                Span::styled(path.as_str(), style),
+                Span::styled(
+                    format!("  {} tokens", tokens(*count)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+    let title = Span::styled(
+        format!(
+            " {} largest files, {} tokens dropped ",
+            triage.files.len(),
+            tokens(triage.dropped_tokens())
+        ),
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    );
+    let list = List::new(items)
+        .block(pane_block(true).title(title))
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[1], list_state);
+    f.render_widget(Paragraph::new(HELP_TEXT), chunks[2]);
+}
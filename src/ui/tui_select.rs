@@ -1,31 +1,45 @@
 use std::collections::HashSet;
-use std::io::{Stdout, stdout};
+use std::io::{IsTerminal, Write, stdout};
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
+use std::sync::{Arc, Once};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{Scope, ScopedJoinHandle};
 use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use crossbeam_channel::Receiver;
 use crossterm::{
+    cursor,
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton,
-        MouseEvent, MouseEventKind,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
     },
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+        is_raw_mode_enabled,
+    },
 };
 use ratatui::{
     Frame, Terminal,
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table},
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, Wrap,
+    },
 };
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
 use thousands::Separable;
+use unicode_width::UnicodeWidthStr;
 
 use crate::common::format::{self, TokenFormatStyle};
+use crate::common::path;
 use crate::engine::token::TokenizerChoice;
+use crate::engine::traverse::ScanProgress;
 use crate::ui::cache::LastSelection;
 use crate::ui::pane::NavigablePane;
 use crate::ui::tree_arena::{DirFlags, DirNode};
@@ -33,12 +47,15 @@ use crate::ui::tree_pane::TreePane;
 
 /// Settings that can be modified in the TUI. Mirrors a subset of `Code2PromptConfig`.
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct TuiSettings {
     pub line_numbers: bool,
     pub hidden: bool,
     pub follow_symlinks: bool,
     pub no_codeblock: bool,
     pub tokenizer: TokenizerChoice,
+    #[serde(default)]
+    pub no_ignore: bool,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -48,13 +65,15 @@ enum SettingFlag {
     FollowSymlinks,
     NoCodeblock,
     Tokenizer,
+    NoIgnore,
 }
 
 impl SettingFlag {
-    const ALL: [SettingFlag; 5] = [
+    const ALL: [SettingFlag; 6] = [
         // Update the count and content
         SettingFlag::LineNumbers,
         SettingFlag::Hidden,
+        SettingFlag::NoIgnore,
         SettingFlag::FollowSymlinks,
         SettingFlag::NoCodeblock,
         SettingFlag::Tokenizer,
@@ -64,6 +83,7 @@ impl SettingFlag {
         match self {
             Self::LineNumbers => "Show line numbers",
             Self::Hidden => "Include hidden files",
+            Self::NoIgnore => "Include gitignored files",
             Self::FollowSymlinks => "Follow symlinks",
             Self::NoCodeblock => "Disable ``` code blocks",
             Self::Tokenizer => "Tokenizer",
@@ -73,10 +93,15 @@ impl SettingFlag {
     /// Generates the full display line for the settings menu.
     fn display_line(&self, s: &TuiSettings, is_selected: bool) -> Line<'static> {
         match self {
-            Self::LineNumbers | Self::Hidden | Self::FollowSymlinks | Self::NoCodeblock => {
+            Self::LineNumbers
+            | Self::Hidden
+            | Self::NoIgnore
+            | Self::FollowSymlinks
+            | Self::NoCodeblock => {
                 let is_enabled = match self {
                     Self::LineNumbers => s.line_numbers,
                     Self::Hidden => s.hidden,
+                    Self::NoIgnore => s.no_ignore,
                     Self::FollowSymlinks => s.follow_symlinks,
                     Self::NoCodeblock => s.no_codeblock,
                     _ => unreachable!(),
@@ -106,6 +131,7 @@ impl SettingFlag {
         match self {
             Self::LineNumbers => s.line_numbers = !s.line_numbers,
             Self::Hidden => s.hidden = !s.hidden,
+            Self::NoIgnore => s.no_ignore = !s.no_ignore,
             Self::FollowSymlinks => s.follow_symlinks = !s.follow_symlinks,
             Self::NoCodeblock => s.no_codeblock = !s.no_codeblock,
             Self::Tokenizer => s.tokenizer = s.tokenizer.next(),
@@ -115,32 +141,122 @@ impl SettingFlag {
     fn cycle_previous(&self, s: &mut TuiSettings) {
         match self {
             // Booleans just toggle, so it's the same as cycle_next
-            Self::LineNumbers | Self::Hidden | Self::FollowSymlinks | Self::NoCodeblock => {
-                self.cycle_next(s)
-            }
+            Self::LineNumbers
+            | Self::Hidden
+            | Self::NoIgnore
+            | Self::FollowSymlinks
+            | Self::NoCodeblock => self.cycle_next(s),
             // For the tokenizer, we call the new `previous` method
             Self::Tokenizer => s.tokenizer = s.tokenizer.previous(),
         }
     }
 }
 
+/// Per-extension totals shown in the extensions pane. Tokens, files and
+/// bytes follow the current directory selection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExtStats {
+    pub name: String,
+    pub tokens: usize,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// The column the extensions pane is sorted by, cycled with `o`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ExtSort {
+    #[default]
+    Tokens,
+    Files,
+    Size,
+}
+
+impl ExtSort {
+    const ALL: [ExtSort; 3] = [ExtSort::Files, ExtSort::Size, ExtSort::Tokens];
+
+    fn next(self) -> Self {
+        match self {
+            Self::Tokens => Self::Files,
+            Self::Files => Self::Size,
+            Self::Size => Self::Tokens,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Tokens => "tokens",
+            Self::Files => "files",
+            Self::Size => "size",
+        }
+    }
+
+    /// Largest first; ties are broken by extension name.
+    fn compare(self, a: &ExtStats, b: &ExtStats) -> std::cmp::Ordering {
+        let by_key = match self {
+            Self::Tokens => b.tokens.cmp(&a.tokens),
+            Self::Files => b.files.cmp(&a.files),
+            Self::Size => b.bytes.cmp(&a.bytes),
+        };
+        by_key.then_with(|| a.name.cmp(&b.name))
+    }
+}
+
 /// Defines the possible outcomes of the TUI selection process.
 pub enum TuiAction {
     /// User confirmed their file/directory selections.
     Confirm {
         exts: Vec<String>,
         paths: Vec<PathBuf>,
+        pinned: Vec<PathBuf>,
+        /// Selected files the scan skipped as hidden or gitignored.
+        skipped: Vec<PathBuf>,
+        /// Whether to arrange the selected files before rendering.
+        arrange: bool,
+        /// Whether to triage the largest selected files before rendering.
+        triage: bool,
     },
     /// User requested to quit the application.
     Cancel,
-    /// User changed settings and wants to re-scan the codebase.
-    RescanWithConfig {
-        settings: TuiSettings,
-        show_msg: bool,
+    /// User hid a file or directory from all future scans of the repository.
+    ExcludePath { path: PathBuf, is_dir: bool },
+    /// User tagged a file or directory in the repository's `tags.toml`.
+    TagPath {
+        path: PathBuf,
+        is_dir: bool,
+        tag: String,
     },
 }
 
-struct TerminalGuard(Terminal<CrosstermBackend<Stdout>>);
+/// The extensions and directory arena of a finished scan.
+pub type ScanResult = (Vec<ExtStats>, Vec<DirNode>);
+
+/// Rescans the codebase with the given settings, sending progress as it goes.
+pub type RescanFn<'a> =
+    dyn Fn(&TuiSettings, crossbeam_channel::Sender<ScanProgress>) -> Result<ScanResult> + Sync + 'a;
+
+/// A rescan running on a worker thread while the TUI stays up.
+struct RunningScan<'scope> {
+    handle: ScopedJoinHandle<'scope, Result<ScanResult>>,
+    progress: Receiver<ScanProgress>,
+}
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Draws on stdout, or on the controlling terminal when stdout is piped
+/// (e.g. `--print-selection` feeding another tool).
+pub(crate) type TuiBackend = CrosstermBackend<Box<dyn Write + Send>>;
+
+fn tty_writer() -> Box<dyn Write + Send> {
+    #[cfg(unix)]
+    if !stdout().is_terminal()
+        && let Ok(tty) = std::fs::OpenOptions::new().write(true).open("/dev/tty")
+    {
+        return Box::new(tty);
+    }
+    Box::new(stdout())
+}
+
+pub(crate) struct TerminalGuard(pub(crate) Terminal<TuiBackend>);
 
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
@@ -149,19 +265,84 @@ impl Drop for TerminalGuard {
     }
 }
 
+/// Turns SIGINT and SIGTERM into a cancel while the TUI is up, so the
+/// terminal is restored on the way out. Ctrl-C itself arrives as a key press
+/// in raw mode; this covers signals sent from elsewhere.
+pub(crate) struct SignalGuard {
+    pub(crate) interrupted: Arc<AtomicBool>,
+    #[cfg(unix)]
+    ids: Vec<signal_hook::SigId>,
+}
+
+impl SignalGuard {
+    pub(crate) fn install() -> Result<Self> {
+        let interrupted = Arc::new(AtomicBool::new(false));
+        #[cfg(unix)]
+        let ids = [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM]
+            .into_iter()
+            .map(|sig| signal_hook::flag::register(sig, Arc::clone(&interrupted)))
+            .collect::<std::io::Result<_>>()?;
+        Ok(Self {
+            interrupted,
+            #[cfg(unix)]
+            ids,
+        })
+    }
+}
+
+impl Drop for SignalGuard {
+    fn drop(&mut self) {
+        // Give the signals back their default behaviour.
+        #[cfg(unix)]
+        for id in self.ids.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+    }
+}
+
+/// Restores the terminal before the panic message is printed. The
+/// `TerminalGuard` can't be relied on for this: release builds abort on
+/// panic, so it never drops.
+pub(crate) fn install_panic_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if is_raw_mode_enabled().unwrap_or(false) {
+                let _ = disable_raw_mode();
+                let _ = execute!(
+                    tty_writer(),
+                    LeaveAlternateScreen,
+                    DisableMouseCapture,
+                    cursor::Show
+                );
+            }
+            default_hook(info);
+        }));
+    });
+}
+
+/// Below this size the panes are replaced by a placeholder.
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 12;
+
 // Help text constant
 const HELP_TEXT: &str =
-    "Tab: Switch panes | Space: Toggle | s: Settings | Enter: Confirm | q/Esc: Quit | /: Filter";
+    "Tab: Switch panes | Space: Toggle | V: Range | s: Settings | Enter: Confirm | A: Arrange | L: Largest | q/Esc: Quit | /: Filter";
 
 // Application input mode
 pub(crate) enum AppMode {
     Normal,
     Filtering,
     Settings,
+    /// Extending a range from `App::visual_anchor` to the cursor.
+    Visual,
+    /// Typing a tag for the directory tree's current node into `App::tag_input`.
+    Tagging,
 }
 
 /// A helper to create a styled block for a TUI pane, now simpler without title.
-fn pane_block(active: bool) -> Block<'static> {
+pub(crate) fn pane_block(active: bool) -> Block<'static> {
     let border_style = if active {
         Style::default().fg(Color::Yellow)
     } else {
@@ -195,6 +376,29 @@ impl<T: Clone> NavigablePane for ListPane<T> {
     fn toggle_current_selection(&mut self) -> bool {
         self.toggle_selection()
     }
+
+    fn cursor_position(&self) -> Option<usize> {
+        self.state.selected()
+    }
+
+    fn is_range_selected(&self, range: RangeInclusive<usize>) -> bool {
+        self.filtered_indices
+            .get(range)
+            .unwrap_or_default()
+            .iter()
+            .all(|&i| self.selected[i])
+    }
+
+    fn set_range_selected(&mut self, range: RangeInclusive<usize>, select: bool) -> bool {
+        let mut changed = false;
+        for &i in self.filtered_indices.get(range).unwrap_or_default() {
+            if self.selected[i] != select {
+                self.selected[i] = select;
+                changed = true;
+            }
+        }
+        changed
+    }
 }
 
 fn find_substring_case_insensitive(hay: &[u8], pat: &[u8]) -> bool {
@@ -347,7 +551,7 @@ where
 
 pub(crate) struct App {
     pub repo_name: String,
-    pub extensions: ListPane<(String, usize)>,
+    pub extensions: ListPane<ExtStats>,
     pub directories: TreePane,
     pub active_pane: Pane,
     pub mode: AppMode,
@@ -356,8 +560,18 @@ pub(crate) struct App {
     pub list_render_buffer: Vec<ListItem<'static>>,
     pub ext_to_slot: FxHashMap<String, u16>,
     pub ext_totals: Vec<usize>,
+    pub ext_files: Vec<usize>,
+    pub ext_bytes: Vec<u64>,
+    pub ext_sort: ExtSort,
     pub settings: TuiSettings,
+    /// The settings the codebase was scanned with.
+    pub scanned_settings: TuiSettings,
     pub settings_state: ListState,
+    pub visual_anchor: usize,
+    pub tag_input: String,
+    /// Files and tokens counted so far by a running rescan.
+    pub scan_progress: Option<ScanProgress>,
+    pub spinner_frame: usize,
 }
 
 enum DfsState {
@@ -375,7 +589,7 @@ impl App {
         }
     }
 
-    fn ext_pane(&self) -> &ListPane<(String, usize)> {
+    fn ext_pane(&self) -> &ListPane<ExtStats> {
         &self.extensions
     }
 
@@ -392,10 +606,121 @@ impl App {
     }
 
     fn apply_active_filter(&mut self) {
-        let item_to_string_fn: fn(&(String, usize)) -> &str = |item| &item.0;
+        let item_to_string_fn: fn(&ExtStats) -> &str = |item| &item.name;
         self.extensions.apply_filter(item_to_string_fn);
     }
 
+    /// Switches the extensions pane to the next sort column, keeping the
+    /// cursor on the same extension.
+    fn cycle_ext_sort(&mut self) {
+        self.ext_sort = self.ext_sort.next();
+        self.sort_extensions();
+    }
+
+    /// Sorts the extensions pane by the current sort column, keeping the
+    /// cursor on the same extension.
+    fn sort_extensions(&mut self) {
+        let current = self
+            .extensions
+            .get_real_selected_index()
+            .map(|i| self.extensions.items[i].name.clone());
+
+        let sort = self.ext_sort;
+        let pane = &mut self.extensions;
+        let mut rows: Vec<(ExtStats, bool)> =
+            pane.items.drain(..).zip(pane.selected.drain(..)).collect();
+        rows.sort_by(|(a, _), (b, _)| sort.compare(a, b));
+        (pane.items, pane.selected) = rows.into_iter().unzip();
+
+        self.apply_active_filter();
+        if let Some(name) = current
+            && let Some(pos) = self
+                .extensions
+                .filtered_indices
+                .iter()
+                .position(|&i| self.extensions.items[i].name == name)
+        {
+            self.extensions.state.select(Some(pos));
+        }
+    }
+
+    /// Whether the edited settings only include files the scan skipped as
+    /// hidden or gitignored, which the tree already holds, so no rescan is
+    /// needed.
+    fn can_apply_settings_in_place(&self) -> bool {
+        let scanned = &self.scanned_settings;
+        let others_unchanged = TuiSettings {
+            hidden: scanned.hidden,
+            no_ignore: scanned.no_ignore,
+            ..self.settings.clone()
+        } == *scanned;
+        others_unchanged
+            && (self.settings.hidden || !scanned.hidden)
+            && (self.settings.no_ignore || !scanned.no_ignore)
+    }
+
+    /// Replaces both panes with the result of a rescan, keeping the current
+    /// selections and pins.
+    fn swap_in_scan(&mut self, extensions: Vec<ExtStats>, dir_arena: Vec<DirNode>) {
+        let to_strings = |paths: Vec<PathBuf>| -> Vec<String> {
+            paths
+                .into_iter()
+                .map(|p| path::display(&p))
+                .collect()
+        };
+        let selection = LastSelection {
+            extensions: self.active_exts.iter().cloned().collect(),
+            directories: to_strings(self.directories.get_selected_paths()),
+            pinned: to_strings(self.directories.get_pinned_paths()),
+        };
+
+        let ext_count = extensions.len();
+        self.ext_to_slot = ext_slots(&extensions);
+        self.ext_totals = vec![0; ext_count + 1];
+        self.ext_files = vec![0; ext_count + 1];
+        self.ext_bytes = vec![0; ext_count + 1];
+        self.extensions = ListPane::new(extensions, Some(&selection.extensions), |item| &item.name);
+        self.directories = TreePane::new(dir_arena, Some(&selection));
+        self.scanned_settings = self.settings.clone();
+
+        self.sort_extensions();
+        self.directories
+            .apply_exclusions(self.settings.hidden, self.settings.no_ignore);
+        self.recalculate_all_visible_counts();
+    }
+
+    fn enter_visual_mode(&mut self) {
+        if let Some(pos) = self.active_pane().cursor_position() {
+            self.visual_anchor = pos;
+            self.mode = AppMode::Visual;
+        }
+    }
+
+    /// The visible rows between the visual anchor and the cursor, while in
+    /// visual mode.
+    fn visual_range(&mut self, pane: Pane) -> Option<RangeInclusive<usize>> {
+        if !matches!(self.mode, AppMode::Visual) || self.active_pane != pane {
+            return None;
+        }
+        let cursor = self.active_pane().cursor_position()?;
+        Some(self.visual_anchor.min(cursor)..=self.visual_anchor.max(cursor))
+    }
+
+    /// Applies the visual range: selects it, or deselects it when it is
+    /// already fully selected.
+    fn apply_visual_range(&mut self) {
+        self.mode = AppMode::Normal;
+        let Some(cursor) = self.active_pane().cursor_position() else {
+            return;
+        };
+        let range = self.visual_anchor.min(cursor)..=self.visual_anchor.max(cursor);
+        let pane = self.active_pane();
+        let select = !pane.is_range_selected(range.clone());
+        if pane.set_range_selected(range, select) {
+            self.recalculate_all_visible_counts();
+        }
+    }
+
     fn enter_filtering_mode(&mut self) {
         self.mode = AppMode::Filtering;
     }
@@ -415,16 +740,20 @@ impl App {
     fn recalculate_all_visible_counts(&mut self) {
         // 1. Rebuild active extensions set
         self.active_exts.clear();
-        for (i, (ext, _)) in self.extensions.items.iter().enumerate() {
+        for (i, ext) in self.extensions.items.iter().enumerate() {
             if self.extensions.selected[i] {
-                self.active_exts.insert(ext.clone());
+                self.active_exts.insert(ext.name.clone());
             }
         }
 
         // 2. Zero out totals
         self.ext_totals.fill(0);
+        self.ext_files.fill(0);
+        self.ext_bytes.fill(0);
 
         let arena = &mut self.directories.arena;
+        let pinned = &self.directories.pinned_nodes;
+        let skipped = DirFlags::HIDDEN | DirFlags::IGNORED;
         // The stack holds: (node_index, traversal_state, is_ancestor_selected)
         let mut stack = vec![(0, DfsState::Descend, false)];
 
@@ -472,17 +801,22 @@ impl App {
                     node.visible_toks = children_visible_toks;
 
                     if !node.flags.contains(DirFlags::IS_DIR) {
-                        // It's a file. Handle its own contribution.
-                        if effective_selection {
-                            if self
-                                .active_exts
-                                .contains(node.extension.as_deref().unwrap_or_default())
-                            {
-                                node.visible_files = 1;
-                                node.visible_toks = node.total_toks;
-                                if node.ext_slot != 0 {
-                                    self.ext_totals[node.ext_slot as usize] += node.total_toks;
-                                }
+                        // It's a file. Handle its own contribution; pinned files
+                        // count whatever the selections.
+                        let is_included = pinned.contains(&idx)
+                            || (effective_selection
+                                && (node.flags.intersects(skipped)
+                                    || self
+                                        .active_exts
+                                        .contains(node.extension.as_deref().unwrap_or_default())));
+                        if is_included {
+                            node.visible_files = 1;
+                            node.visible_toks = node.total_toks;
+                            if node.ext_slot != 0 {
+                                let slot = node.ext_slot as usize;
+                                self.ext_totals[slot] += node.total_toks;
+                                self.ext_files[slot] += 1;
+                                self.ext_bytes[slot] += node.total_bytes;
                             }
                         }
                     }
@@ -494,9 +828,11 @@ impl App {
         self.total_selected_files = self.directories.arena[0].visible_files;
 
         // 5. Write back extension totals to the ListPane's data
-        for (ext, count) in &mut self.extensions.items {
-            if let Some(slot) = self.ext_to_slot.get(ext) {
-                *count = self.ext_totals[*slot as usize];
+        for ext in &mut self.extensions.items {
+            if let Some(&slot) = self.ext_to_slot.get(&ext.name) {
+                ext.tokens = self.ext_totals[slot as usize];
+                ext.files = self.ext_files[slot as usize];
+                ext.bytes = self.ext_bytes[slot as usize];
             }
         }
 
@@ -511,23 +847,33 @@ pub(crate) enum Pane {
     Directories,
 }
 
+/// Maps each extension to its slot in the per-extension totals; slot 0 is
+/// for files without a listed extension.
+fn ext_slots(extensions: &[ExtStats]) -> FxHashMap<String, u16> {
+    extensions
+        .iter()
+        .enumerate()
+        .map(|(i, ext)| (ext.name.clone(), (i + 1) as u16))
+        .collect()
+}
+
 pub fn select_filters_tui(
     repo_path: &std::path::Path,
-    extensions: Vec<(String, usize)>,
+    extensions: Vec<ExtStats>,
     dir_arena: Vec<DirNode>,
     last_selection: Option<LastSelection>,
     initial_config: &crate::engine::config::Code2PromptConfig,
+    rescan: &RescanFn,
 ) -> Result<TuiAction> {
     // 1. Setup terminal and immediately pass ownership to the guard.
+    install_panic_hook();
+    let signals = SignalGuard::install()?;
     let terminal = setup_terminal()?;
     let mut guard = TerminalGuard(terminal);
 
     drain_input_buffer()?;
 
-    let mut ext_to_slot: FxHashMap<String, u16> = FxHashMap::default();
-    for (i, (ext, _)) in extensions.iter().enumerate() {
-        ext_to_slot.insert(ext.clone(), (i + 1) as u16);
-    }
+    let ext_to_slot = ext_slots(&extensions);
     let ext_count = extensions.len();
 
     let initial_settings = TuiSettings {
@@ -536,18 +882,15 @@ pub fn select_filters_tui(
         follow_symlinks: initial_config.follow_symlinks,
         no_codeblock: initial_config.no_codeblock,
         tokenizer: initial_config.tokenizer,
+        no_ignore: initial_config.no_ignore,
     };
 
     let mut app = App {
-        repo_name: repo_path
-            .file_name()
-            .unwrap_or(repo_path.as_os_str())
-            .to_string_lossy()
-            .to_string(),
+        repo_name: format::format_path_label(repo_path),
         extensions: ListPane::new(
             extensions,
             last_selection.as_ref().map(|s| s.extensions.as_slice()),
-            |item| &item.0,
+            |item| &item.name,
         ),
         directories: TreePane::new(dir_arena, last_selection.as_ref()),
         active_pane: Pane::Extensions,
@@ -557,10 +900,20 @@ pub fn select_filters_tui(
         active_exts: FxHashSet::default(),
         ext_to_slot,
         ext_totals: vec![0; ext_count + 1],
-        settings: initial_settings,
+        ext_files: vec![0; ext_count + 1],
+        ext_bytes: vec![0; ext_count + 1],
+        ext_sort: ExtSort::default(),
+        settings: initial_settings.clone(),
+        scanned_settings: initial_settings,
         settings_state: ListState::default(),
+        visual_anchor: 0,
+        tag_input: String::new(),
+        scan_progress: None,
+        spinner_frame: 0,
     };
 
+    app.directories
+        .apply_exclusions(app.settings.hidden, app.settings.no_ignore);
     app.recalculate_all_visible_counts();
 
     if app.extensions.items.is_empty() && !app.directories.arena.is_empty() {
@@ -568,42 +921,100 @@ pub fn select_filters_tui(
     }
 
     // 2. Pass a mutable borrow of the terminal *from the guard's field* to the loop.
-    let action = run_event_loop(&mut guard.0, &mut app)?;
+    // Rescans run on a scoped thread so they can borrow the caller's state.
+    let action = std::thread::scope(|scope| {
+        run_event_loop(&mut guard.0, &mut app, scope, rescan, &signals.interrupted)
+    })?;
 
     match action {
-        TuiAction::Confirm { .. } => {
+        TuiAction::Confirm {
+            arrange, triage, ..
+        } => {
             let chosen_ext = app
                 .extensions
                 .items
                 .iter()
                 .zip(&app.extensions.selected)
-                .filter_map(|((e, _), sel)| sel.then(|| e.clone()))
+                .filter_map(|(e, sel)| sel.then(|| e.name.clone()))
                 .collect();
             let chosen_dir = app.directories.get_selected_paths();
             Ok(TuiAction::Confirm {
                 exts: chosen_ext,
                 paths: chosen_dir,
+                pinned: app.directories.get_pinned_paths(),
+                skipped: app.directories.get_skipped_paths(),
+                arrange,
+                triage,
             })
         }
         other_action => Ok(other_action),
     }
 }
 
-fn run_event_loop(
-    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+fn run_event_loop<'scope>(
+    terminal: &mut Terminal<TuiBackend>,
     app: &mut App,
+    scope: &'scope Scope<'scope, '_>,
+    rescan: &'scope RescanFn,
+    interrupted: &AtomicBool,
 ) -> Result<TuiAction> {
+    let mut running: Option<RunningScan<'scope>> = None;
     loop {
+        if interrupted.load(Ordering::Relaxed) {
+            return Ok(TuiAction::Cancel);
+        }
+        if let Some(scan) = &running {
+            let counted = app.scan_progress.get_or_insert_default();
+            for delta in scan.progress.try_iter() {
+                counted.files += delta.files;
+                counted.tokens += delta.tokens;
+            }
+            app.spinner_frame = (app.spinner_frame + 1) % SPINNER.len();
+            if scan.handle.is_finished() {
+                let scan = running.take().unwrap();
+                let (extensions, dir_arena) = scan
+                    .handle
+                    .join()
+                    .map_err(|_| anyhow!("rescan thread panicked"))??;
+                app.scan_progress = None;
+                app.swap_in_scan(extensions, dir_arena);
+            }
+        }
+
         terminal.draw(|f| ui(f, app))?;
-        if event::poll(Duration::from_millis(250))? {
-            match event::read()? {
+        let timeout = if running.is_some() { 80 } else { 250 };
+        if event::poll(Duration::from_millis(timeout))? {
+            let event = event::read()?;
+            if let Event::Resize(..) = event {
+                // Resize the buffers now so the next frame lays out against
+                // the new size.
+                terminal.autoresize()?;
+                continue;
+            }
+            if let Event::Key(key) = event
+                && key.code == KeyCode::Char('c')
+                && key.modifiers.contains(KeyModifiers::CONTROL)
+            {
+                return Ok(TuiAction::Cancel);
+            }
+            // Input waits until the rescan's result is swapped in.
+            if running.is_some() {
+                continue;
+            }
+            let area = terminal.size()?;
+            let too_small = area.width < MIN_WIDTH || area.height < MIN_HEIGHT;
+            match event {
                 Event::Key(key) if key.kind == KeyEventKind::Press => match app.mode {
                     AppMode::Normal => match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => return Ok(TuiAction::Cancel),
-                        KeyCode::Enter => {
+                        KeyCode::Enter | KeyCode::Char('A') | KeyCode::Char('L') => {
                             return Ok(TuiAction::Confirm {
                                 exts: vec![],
                                 paths: vec![],
+                                pinned: vec![],
+                                skipped: vec![],
+                                arrange: key.code == KeyCode::Char('A'),
+                                triage: key.code == KeyCode::Char('L'),
                             });
                         }
                         KeyCode::Char('/') => {
@@ -623,12 +1034,25 @@ fn run_event_loop(
                         _ => handle_key_press_filtering(app, key.code),
                     },
                     AppMode::Settings => {
-                        if let Some(action) = handle_key_press_settings(app, key.code) {
+                        if handle_key_press_settings(app, key.code) {
+                            let (tx, rx) = crossbeam_channel::unbounded();
+                            let settings = app.settings.clone();
+                            app.scan_progress = Some(ScanProgress::default());
+                            running = Some(RunningScan {
+                                handle: scope.spawn(move || rescan(&settings, tx)),
+                                progress: rx,
+                            });
+                        }
+                    }
+                    AppMode::Visual => handle_key_press_visual(app, key.code),
+                    AppMode::Tagging => {
+                        if let Some(action) = handle_key_press_tagging(app, key.code) {
                             return Ok(action);
                         }
                     }
                 },
-                Event::Mouse(mouse_event) => handle_mouse_event(app, mouse_event),
+                // Clicks map to rows of panes that are not drawn.
+                Event::Mouse(mouse_event) if !too_small => handle_mouse_event(app, mouse_event),
                 _ => {}
             }
         }
@@ -692,6 +1116,7 @@ fn handle_key_press_normal(app: &mut App, key_code: KeyCode) -> Option<TuiAction
         KeyCode::Down | KeyCode::Char('j') => app.active_pane().next(),
         KeyCode::Char(' ') => needs_recalc = app.active_pane().toggle_current_selection(),
         KeyCode::Tab => app.switch_pane(),
+        KeyCode::Char('V') => app.enter_visual_mode(),
         KeyCode::Char('s') => {
             app.mode = AppMode::Settings;
             app.settings_state.select(Some(0));
@@ -701,10 +1126,26 @@ fn handle_key_press_normal(app: &mut App, key_code: KeyCode) -> Option<TuiAction
                 KeyCode::Char('a') => needs_recalc = app.extensions.select_all(),
                 KeyCode::Char('n') => needs_recalc = app.extensions.deselect_all(),
                 KeyCode::Char('i') => needs_recalc = app.extensions.invert_selection(),
+                KeyCode::Char('o') => app.cycle_ext_sort(),
                 KeyCode::Right | KeyCode::Char('l') => app.switch_pane(),
                 _ => {}
             },
             Pane::Directories => match key_code {
+                KeyCode::Char('P') => needs_recalc = app.directories.toggle_pin(),
+                KeyCode::Char('T') if app.directories.get_current_node_idx().is_some() => {
+                    app.tag_input.clear();
+                    app.mode = AppMode::Tagging;
+                }
+                KeyCode::Char('x') => {
+                    if let Some(idx) = app.directories.get_current_node_idx() {
+                        return Some(TuiAction::ExcludePath {
+                            path: TreePane::get_path_buf(&app.directories.arena, idx),
+                            is_dir: app.directories.arena[idx as usize]
+                                .flags
+                                .contains(DirFlags::IS_DIR),
+                        });
+                    }
+                }
                 KeyCode::Right | KeyCode::Char('l') => {
                     app.directories.toggle_expand();
                     needs_rebuild_visible = true;
@@ -740,6 +1181,43 @@ fn handle_key_press_normal(app: &mut App, key_code: KeyCode) -> Option<TuiAction
     None
 }
 
+fn handle_key_press_visual(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Up | KeyCode::Char('k') => app.active_pane().previous(),
+        KeyCode::Down | KeyCode::Char('j') => app.active_pane().next(),
+        KeyCode::Char(' ') | KeyCode::Char('V') | KeyCode::Enter => app.apply_visual_range(),
+        KeyCode::Esc | KeyCode::Char('q') => app.mode = AppMode::Normal,
+        _ => {}
+    }
+}
+
+fn handle_key_press_tagging(app: &mut App, key_code: KeyCode) -> Option<TuiAction> {
+    match key_code {
+        KeyCode::Enter => {
+            app.mode = AppMode::Normal;
+            let tag = app.tag_input.trim();
+            let idx = app.directories.get_current_node_idx()?;
+            if tag.is_empty() {
+                return None;
+            }
+            return Some(TuiAction::TagPath {
+                path: TreePane::get_path_buf(&app.directories.arena, idx),
+                is_dir: app.directories.arena[idx as usize]
+                    .flags
+                    .contains(DirFlags::IS_DIR),
+                tag: tag.to_string(),
+            });
+        }
+        KeyCode::Esc => app.mode = AppMode::Normal,
+        KeyCode::Backspace => {
+            app.tag_input.pop();
+        }
+        KeyCode::Char(c) => app.tag_input.push(c),
+        _ => {}
+    }
+    None
+}
+
 fn handle_key_press_filtering(app: &mut App, key_code: KeyCode) {
     // This only applies to the Extensions pane, so we don't use the trait here.
     if app.active_pane == Pane::Extensions {
@@ -765,11 +1243,21 @@ fn handle_key_press_filtering(app: &mut App, key_code: KeyCode) {
 
 fn ui(f: &mut Frame, app: &mut App) {
     let size = f.area();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        render_too_small(f);
+        return;
+    }
+    let pinned_height = if app.directories.pinned.is_empty() {
+        0
+    } else {
+        1
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1),
             Constraint::Min(0),
+            Constraint::Length(pinned_height),
             Constraint::Length(1),
         ])
         .split(size);
@@ -825,6 +1313,22 @@ fn ui(f: &mut Frame, app: &mut App) {
                 )),
             ])
         }
+        AppMode::Visual => {
+            let count = app.visual_range(app.active_pane).map_or(0, |r| r.count());
+            Line::from(vec![
+                Span::styled("VISUAL", Style::default().fg(Color::Black).bg(Color::Cyan)),
+                Span::raw(format!(" {count} row(s) | ")),
+                Span::styled(
+                    "j/k: Extend | Space/V/Enter: Toggle range | Esc: Cancel",
+                    Style::default().fg(Color::Cyan),
+                ),
+            ])
+        }
+        AppMode::Tagging => Line::from(vec![
+            Span::raw("TAG: "),
+            Span::styled(&app.tag_input, Style::default().fg(Color::Yellow)),
+            Span::raw(" (Esc to Cancel, Enter to Tag)"),
+        ]),
         AppMode::Settings => Line::from(vec![
             Span::raw("SETTINGS"),
             Span::raw(" | "),
@@ -836,31 +1340,67 @@ fn ui(f: &mut Frame, app: &mut App) {
     };
     f.render_widget(
         Paragraph::new(footer_text).style(Style::default().fg(Color::DarkGray)),
-        chunks[2],
+        chunks[3],
     );
+
+    // --- Pinned strip ---
+    if pinned_height > 0 {
+        let names: Vec<String> = app
+            .directories
+            .get_pinned_paths()
+            .iter()
+            .map(|p| path::display(p))
+            .collect();
+        f.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    format!(" ⚑ Pinned ({}) ", names.len()),
+                    Style::default().fg(Color::Black).bg(Color::Magenta),
+                ),
+                Span::raw(" "),
+                Span::styled(names.join(", "), Style::default().fg(Color::Magenta)),
+            ])),
+            chunks[2],
+        );
+    }
     let content_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
         .split(chunks[1]);
 
+    let range_style = Style::default().bg(Color::Blue);
+
     // --- Extensions Pane ---
+    let ext_range = app.visual_range(Pane::Extensions);
     app.list_render_buffer.clear();
     app.extensions
         .filtered_indices
         .iter()
-        .for_each(|&real_index| {
+        .enumerate()
+        .for_each(|(pos, &real_index)| {
             let item = &app.extensions.items[real_index];
             let is_selected = app.extensions.selected[real_index];
-            let (ext, tokens) = item;
             let mark = if is_selected { "●" } else { "○" };
-            let toks = format::format_tokens(*tokens, TokenFormatStyle::Compact);
-            let line = format!("{mark} {ext:<8} {toks:>6}");
-            app.list_render_buffer.push(ListItem::new(line));
+            let toks = format::format_tokens(item.tokens, TokenFormatStyle::Compact);
+            let size = format::format_size(item.bytes);
+            let line = format!(
+                "{mark} {} {:>5} {size:>5} {toks:>6}",
+                format::pad_to_width(&item.name, 8),
+                item.files
+            );
+            let list_item = ListItem::new(line);
+            app.list_render_buffer
+                .push(if ext_range.as_ref().is_some_and(|r| r.contains(&pos)) {
+                    list_item.style(range_style)
+                } else {
+                    list_item
+                });
         });
 
     let is_dir_active = app.active_pane == Pane::Directories;
     let ext_list = create_styled_list(
         "File Types",
+        sort_legend(app.ext_sort),
         !is_dir_active,
         std::mem::take(&mut app.list_render_buffer),
     );
@@ -868,8 +1408,11 @@ fn ui(f: &mut Frame, app: &mut App) {
 
     // ---------- Tree Pane ----------
     // Build rows for Table widget (3 columns)
+    let dir_range = app.visual_range(Pane::Directories);
+    // Borders, the highlight symbol, both number columns and their spacing.
+    let name_width = usize::from(content_chunks[1].width).saturating_sub(2 + 3 + 7 + 7 + 2);
     let mut rows: Vec<Row> = Vec::with_capacity(app.directories.visible_nodes.len());
-    for &idx in &app.directories.visible_nodes {
+    for (pos, &idx) in app.directories.visible_nodes.iter().enumerate() {
         let n = &app.directories.arena[idx as usize];
         let depth = app.directories.get_depth(idx);
         let indent = " ".repeat(depth);
@@ -892,7 +1435,28 @@ fn ui(f: &mut Frame, app: &mut App) {
             (false, true) => "◐",
             _ => "○",
         };
-        let name_cell = Cell::from(format!("{indent}{tri} {tick} {}", n.name));
+        let mut tags = Vec::new();
+        if n.flags.contains(DirFlags::HIDDEN) {
+            tags.push(Span::styled(
+                " ·hidden",
+                Style::default().fg(Color::DarkGray),
+            ));
+        } else if n.flags.contains(DirFlags::IGNORED) {
+            tags.push(Span::styled(
+                " ·ignored",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if app.directories.is_pinned(idx) {
+            tags.push(Span::styled(" ⚑", Style::default().fg(Color::Magenta)));
+        }
+        // Shorten the name rather than letting the cell cut off its tags.
+        let prefix = format!("{indent}{tri} {tick} ");
+        let fixed_width = prefix.width() + tags.iter().map(Span::width).sum::<usize>();
+        let name = format::truncate_to_width(&n.name, name_width.saturating_sub(fixed_width));
+        let mut name_spans = vec![Span::raw(format!("{prefix}{name}"))];
+        name_spans.extend(tags);
+        let name_cell = Cell::from(Line::from(name_spans));
 
         // files column
         let files_txt = n.file_count.separate_with_dots();
@@ -902,20 +1466,33 @@ fn ui(f: &mut Frame, app: &mut App) {
             Cell::from(files_txt)
         };
 
-        // tokens column
-        let toks_txt = format::format_tokens(n.visible_toks, TokenFormatStyle::Compact);
+        // tokens column; skipped files are only counted once included
+        let is_skipped_file = !n.flags.contains(DirFlags::IS_DIR)
+            && n.flags.intersects(DirFlags::HIDDEN | DirFlags::IGNORED);
+        let toks_txt = if is_skipped_file {
+            "?".to_string()
+        } else {
+            format::format_tokens(n.visible_toks, TokenFormatStyle::Compact)
+        };
         let toks_cell = if n.visible_toks == 0 {
             Cell::from(toks_txt).style(Style::default().fg(Color::DarkGray))
         } else {
             Cell::from(toks_txt)
         };
 
-        rows.push(Row::new(vec![name_cell, files_cell, toks_cell]));
+        let mut row_style = Style::default();
+        if app.directories.is_excluded(idx) {
+            row_style = row_style.fg(Color::DarkGray);
+        }
+        if dir_range.as_ref().is_some_and(|r| r.contains(&pos)) {
+            row_style = row_style.patch(range_style);
+        }
+        rows.push(Row::new(vec![name_cell, files_cell, toks_cell]).style(row_style));
     }
 
     // column widths: name flexible, numbers fixed
     let widths = [
-        Constraint::Percentage(70),
+        Constraint::Fill(1),
         Constraint::Length(7),
         Constraint::Length(7),
     ];
@@ -937,23 +1514,26 @@ fn ui(f: &mut Frame, app: &mut App) {
     if matches!(app.mode, AppMode::Settings) {
         render_settings_popup(f, app);
     }
+    if let Some(progress) = app.scan_progress {
+        render_scan_overlay(f, progress, app.spinner_frame);
+    }
 }
 
-fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
-    let mut stdout = stdout();
+pub(crate) fn setup_terminal() -> Result<Terminal<TuiBackend>> {
+    let mut out = tty_writer();
     enable_raw_mode()?;
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    Ok(Terminal::new(CrosstermBackend::new(stdout))?)
+    execute!(out, EnterAlternateScreen, EnableMouseCapture)?;
+    Ok(Terminal::new(CrosstermBackend::new(out))?)
 }
 
-fn drain_input_buffer() -> Result<()> {
+pub(crate) fn drain_input_buffer() -> Result<()> {
     while event::poll(Duration::from_millis(0))? {
         let _ = event::read()?;
     }
     Ok(())
 }
 
-fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+fn restore_terminal(terminal: &mut Terminal<TuiBackend>) -> Result<()> {
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -964,7 +1544,35 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result
     Ok(())
 }
 
-fn create_styled_list<'a>(title: &'a str, is_active: bool, items: Vec<ListItem<'a>>) -> List<'a> {
+/// The column legend of the extensions pane, with the sort column highlighted.
+fn sort_legend(sort: ExtSort) -> Line<'static> {
+    let mut spans = vec![Span::raw(" ")];
+    for (i, column) in ExtSort::ALL.into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" · "));
+        }
+        let style = if column == sort {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        spans.push(Span::styled(column.label(), style));
+    }
+    spans.push(Span::styled(
+        " (o: sort) ",
+        Style::default().fg(Color::DarkGray),
+    ));
+    Line::from(spans)
+}
+
+fn create_styled_list<'a>(
+    title: &'a str,
+    legend: Line<'a>,
+    is_active: bool,
+    items: Vec<ListItem<'a>>,
+) -> List<'a> {
     let title_style = if is_active {
         Style::default()
             .fg(Color::Yellow)
@@ -972,7 +1580,9 @@ fn create_styled_list<'a>(title: &'a str, is_active: bool, items: Vec<ListItem<'
     } else {
         Style::default()
     };
-    let block = pane_block(is_active).title(Span::styled(format!(" {title} "), title_style));
+    let block = pane_block(is_active)
+        .title(Span::styled(format!(" {title} "), title_style))
+        .title_bottom(legend.right_aligned());
     List::new(items)
         .block(block)
         .highlight_style(
@@ -983,7 +1593,9 @@ fn create_styled_list<'a>(title: &'a str, is_active: bool, items: Vec<ListItem<'
         .highlight_symbol(">> ")
 }
 
-fn handle_key_press_settings(app: &mut App, key_code: KeyCode) -> Option<TuiAction> {
+/// Handles a key in the settings popup; returns whether the confirmed
+/// settings need a rescan.
+fn handle_key_press_settings(app: &mut App, key_code: KeyCode) -> bool {
     let len = SettingFlag::ALL.len();
     if let Some(idx) = app.settings_state.selected() {
         let selected_flag = SettingFlag::ALL[idx];
@@ -1009,17 +1621,20 @@ fn handle_key_press_settings(app: &mut App, key_code: KeyCode) -> Option<TuiActi
                 selected_flag.cycle_previous(&mut app.settings);
             }
             KeyCode::Enter => {
-                return Some(TuiAction::RescanWithConfig {
-                    settings: app.settings.clone(),
-                    show_msg: true,
-                });
+                app.mode = AppMode::Normal;
+                if !app.can_apply_settings_in_place() {
+                    return true;
+                }
+                app.directories
+                    .apply_exclusions(app.settings.hidden, app.settings.no_ignore);
+                app.recalculate_all_visible_counts();
             }
             _ => {}
         }
     } else if key_code == KeyCode::Esc {
         app.mode = AppMode::Normal;
     }
-    None
+    false
 }
 
 fn render_settings_popup(f: &mut Frame, app: &mut App) {
@@ -1051,6 +1666,46 @@ fn render_settings_popup(f: &mut Frame, app: &mut App) {
     f.render_stateful_widget(list, area, &mut app.settings_state);
 }
 
+fn render_scan_overlay(f: &mut Frame, progress: ScanProgress, spinner_frame: usize) {
+    let text = vec![
+        Line::from(format!("{} Rescanning…", SPINNER[spinner_frame])),
+        Line::from(""),
+        Line::from(format!("Files:  {}", progress.files.separate_with_commas())),
+        Line::from(format!(
+            "Tokens: {}",
+            progress.tokens.separate_with_commas()
+        )),
+    ];
+    let area = centered_rect(40, 30, f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(" Scan ")),
+        area,
+    );
+}
+
+fn render_too_small(f: &mut Frame) {
+    let size = f.area();
+    let text = vec![
+        Line::from("Terminal too small").style(Style::default().add_modifier(Modifier::BOLD)),
+        Line::from(format!(
+            "{}x{}, need at least {MIN_WIDTH}x{MIN_HEIGHT}",
+            size.width, size.height
+        )),
+        Line::from("Enlarge the window, or press q to quit.")
+            .style(Style::default().fg(Color::DarkGray)),
+    ];
+    let [area] = Layout::vertical([Constraint::Length(text.len() as u16)])
+        .flex(Flex::Center)
+        .areas(size);
+    f.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        area,
+    );
+}
+
 /// Helper to create a centered rectangle for popups.
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -0,0 +1,187 @@
+//! The variable review screen, shown before the selector with
+//! `--review-vars`: lists the cached values of the template's variables and
+//! lets each be edited, so that stale values are not reused unnoticed.
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+
+use crate::ui::tui_select::{
+    SignalGuard, TerminalGuard, drain_input_buffer, install_panic_hook, pane_block, setup_terminal,
+};
+
+const HELP_TEXT: &str =
+    "j/k: Move cursor | e: Edit value | Enter: Continue | q/Esc: Keep cached values";
+const EDIT_HELP_TEXT: &str = "Enter: Done | Esc: Cancel edit";
+
+/// The variables being reviewed, as `(name, value)` pairs, the cursor and
+/// the value being edited, if any.
+#[derive(Debug, Clone, Default)]
+pub struct VarReview {
+    pub vars: Vec<(String, String)>,
+    pub cursor: usize,
+    pub editing: Option<String>,
+}
+
+impl VarReview {
+    pub fn new(vars: Vec<(String, String)>) -> Self {
+        Self {
+            vars,
+            cursor: 0,
+            editing: None,
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.cursor + 1 < self.vars.len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Starts editing the value under the cursor, from its current text.
+    pub fn start_edit(&mut self) {
+        if let Some((_, value)) = self.vars.get(self.cursor) {
+            self.editing = Some(value.clone());
+        }
+    }
+
+    /// Replaces the value under the cursor with the edited text.
+    pub fn finish_edit(&mut self) {
+        if let Some(text) = self.editing.take()
+            && let Some((_, value)) = self.vars.get_mut(self.cursor)
+        {
+            *value = text;
+        }
+    }
+
+    pub fn cancel_edit(&mut self) {
+        self.editing = None;
+    }
+}
+
+/// Shows the review screen. Returns the variables with their reviewed
+/// values, or `None` if the screen was left without confirming, to keep the
+/// cached values.
+pub fn review_vars_tui(
+    repo_name: &str,
+    vars: Vec<(String, String)>,
+) -> Result<Option<Vec<(String, String)>>> {
+    install_panic_hook();
+    let signals = SignalGuard::install()?;
+    let mut guard = TerminalGuard(setup_terminal()?);
+    drain_input_buffer()?;
+
+    let mut review = VarReview::new(vars);
+    let mut list_state = ListState::default();
+    loop {
+        if signals.interrupted.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        list_state.select(Some(review.cursor));
+        guard
+            .0
+            .draw(|f| ui(f, repo_name, &review, &mut list_state))?;
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if let KeyCode::Char('c') = key.code
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            return Ok(None);
+        }
+        if let Some(text) = &mut review.editing {
+            match key.code {
+                KeyCode::Enter => review.finish_edit(),
+                KeyCode::Esc => review.cancel_edit(),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(Some(review.vars)),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+            KeyCode::Char('e') => review.start_edit(),
+            KeyCode::Down | KeyCode::Char('j') => review.next(),
+            KeyCode::Up | KeyCode::Char('k') => review.previous(),
+            _ => {}
+        }
+    }
+}
+
+fn ui(f: &mut Frame, repo_name: &str, review: &VarReview, list_state: &mut ListState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+    f.render_widget(
+        Paragraph::new(format!("code2prompt ▸ {repo_name} ▸ variables"))
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+
+    let items: Vec<ListItem> = review
+        .vars
+        .iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let value = match &review.editing {
+                Some(text) if i == review.cursor => {
+                    Span::styled(format!("{text}▏"), Style::default().fg(Color::Yellow))
+                }
+                _ => Span::raw(value.as_str()),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{name} = "), Style::default().fg(Color::DarkGray)),
+                value,
+            ]))
+        })
+        .collect();
+    let title = Span::styled(
+        format!(" Cached variables ({}) ", review.vars.len()),
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    );
+    let list = List::new(items)
+        .block(pane_block(true).title(title))
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[1], list_state);
+    let help = if review.editing.is_some() {
+        EDIT_HELP_TEXT
+    } else {
+        HELP_TEXT
+    };
+    f.render_widget(Paragraph::new(help), chunks[2]);
+}
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+use code2prompt_tui::ui::cli::expand_aliases;
+
+fn args(list: &[&str]) -> Vec<OsString> {
+    list.iter().map(OsString::from).collect()
+}
+
+fn aliases() -> BTreeMap<String, String> {
+    BTreeMap::from([(
+        "review".to_string(),
+        "--diff --template review.hbs -e 'tests/**'".to_string(),
+    )])
+}
+
+#[test]
+fn test_aliases_are_replaced_by_their_arguments() {
+    let expanded = expand_aliases(args(&["c2p", ".", "@review", "-q"]), &aliases()).unwrap();
+    assert_eq!(
+        expanded,
+        args(&[
+            "c2p",
+            ".",
+            "--diff",
+            "--template",
+            "review.hbs",
+            "-e",
+            "tests/**",
+            "-q"
+        ])
+    );
+}
+
+#[test]
+fn test_arguments_that_are_not_aliases_are_kept() {
+    let list = ["c2p", "@", "@a/b.rs", "-V", "who=@review", "--", "@review"];
+    assert_eq!(
+        expand_aliases(args(&list), &aliases()).unwrap(),
+        args(&list)
+    );
+}
+
+#[test]
+fn test_option_values_and_unknown_aliases_are_kept() {
+    let list = [
+        "c2p",
+        "--append",
+        "@TODO",
+        "-qO",
+        "@review",
+        "@reveiw",
+        "--prepend=@review",
+    ];
+    assert_eq!(
+        expand_aliases(args(&list), &aliases()).unwrap(),
+        args(&list)
+    );
+}
+
+#[test]
+fn test_invalid_aliases_are_errors() {
+    let broken = BTreeMap::from([("x".to_string(), "-e 'tests/**".to_string())]);
+    let err = expand_aliases(args(&["c2p", "@x"]), &broken).unwrap_err();
+    assert!(err.to_string().contains("not valid shell syntax"), "{err}");
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "tui")]
+
+use code2prompt_tui::ui::arrange::Arrangement;
+
+fn arrangement() -> Arrangement {
+    Arrangement::new(
+        ["intro.md", "src/lib.rs", "src/main.rs"]
+            .map(|p| (p.to_string(), 10))
+            .to_vec(),
+    )
+}
+
+#[test]
+fn test_moving_a_file_moves_the_cursor_with_it() {
+    let mut a = arrangement();
+    a.move_down();
+    a.move_down();
+    assert_eq!(a.order(), ["src/lib.rs", "src/main.rs", "intro.md"]);
+    assert_eq!(a.cursor, 2);
+
+    a.move_down();
+    assert_eq!(a.cursor, 2);
+
+    a.previous();
+    a.move_up();
+    assert_eq!(a.order(), ["src/main.rs", "src/lib.rs", "intro.md"]);
+    assert_eq!(a.cursor, 0);
+    a.move_up();
+    assert_eq!(a.cursor, 0);
+}
+
+#[test]
+fn test_cursor_stays_in_the_list() {
+    let mut a = arrangement();
+    a.previous();
+    assert_eq!(a.cursor, 0);
+    for _ in 0..5 {
+        a.next();
+    }
+    assert_eq!(a.cursor, 2);
+
+    let mut empty = Arrangement::new(Vec::new());
+    empty.next();
+    empty.move_down();
+    empty.move_up();
+    assert_eq!(empty.cursor, 0);
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use code2prompt_tui::engine::{
+    model::ProcessedEntry, token::TokenizerChoice, token_map::largest_contributors,
+};
+
+fn file(path: &str, tokens: Option<usize>, code: Option<&str>) -> ProcessedEntry {
+    ProcessedEntry {
+        path: PathBuf::from(path),
+        relative_path: PathBuf::from(path),
+        is_file: true,
+        code: code.map(str::to_owned),
+        token_count: tokens,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_largest_contributors_ranks_files_and_directories() {
+    let entries = vec![
+        file("src/core/engine.rs", Some(500), None),
+        file("src/core/util.rs", Some(100), None),
+        file("src/main.rs", Some(50), None),
+        file("assets/data.json", Some(900), None),
+        file("README.md", Some(10), None),
+    ];
+    let (files, dirs) = largest_contributors(&entries, TokenizerChoice::Cl100k, 2);
+    assert_eq!(
+        files,
+        vec![
+            ("assets/data.json".to_string(), 900),
+            ("src/core/engine.rs".to_string(), 500)
+        ]
+    );
+    assert_eq!(
+        dirs,
+        vec![("assets".to_string(), 900), ("src".to_string(), 650)]
+    );
+}
+
+#[test]
+fn test_largest_contributors_estimates_missing_counts() {
+    let code = "x".repeat(459);
+    let entries = vec![file("big.txt", None, Some(&code))];
+    let (files, dirs) = largest_contributors(&entries, TokenizerChoice::Cl100k, 5);
+    assert_eq!(files, vec![("big.txt".to_string(), 100)]);
+    assert!(dirs.is_empty());
+}
@@ -0,0 +1,153 @@
+use std::fs;
+use std::path::Path;
+
+use code2prompt_tui::common::format::parse_date;
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder, filter::ChangeFilter, git::touched_files,
+    session::Code2PromptSession,
+};
+use git2::{Repository, Signature, Time};
+use tempfile::tempdir;
+
+/// Writes `files` and commits them as `name <email>` at `date`.
+fn commit(repo: &Repository, files: &[&str], name: &str, email: &str, date: &str) {
+    let root = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for file in files {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, format!("{file} at {date}\n")).unwrap();
+        index.add_path(Path::new(file)).unwrap();
+    }
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::new(name, email, &Time::new(parse_date(date).unwrap(), 0)).unwrap();
+    let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        date,
+        &tree,
+        &parent.iter().collect::<Vec<_>>(),
+    )
+    .unwrap();
+}
+
+fn history(root: &Path) -> Repository {
+    let repo = Repository::init(root).unwrap();
+    commit(
+        &repo,
+        &["main.rs", "api/old.rs"],
+        "Bob",
+        "bob@example.com",
+        "2023-06-01",
+    );
+    commit(
+        &repo,
+        &["api/user.rs"],
+        "Alice",
+        "alice@example.com",
+        "2024-02-10",
+    );
+    commit(&repo, &["main.rs"], "Bob", "bob@example.com", "2024-03-05");
+    repo
+}
+
+#[test]
+fn test_parse_date_is_midnight_utc() {
+    assert_eq!(parse_date("1970-01-01"), Some(0));
+    assert_eq!(parse_date("2024-01-01"), Some(1_704_067_200));
+    assert_eq!(parse_date("2024-02-29"), Some(1_709_164_800));
+    assert_eq!(parse_date("2023-02-29"), None);
+    assert_eq!(parse_date("2024-13-01"), None);
+    assert_eq!(parse_date("yesterday"), None);
+}
+
+#[test]
+fn test_change_filter_matches_part_of_the_author() {
+    let filter = ChangeFilter {
+        since: parse_date("2024-01-01"),
+        author: Some("ALICE@".into()),
+    };
+    assert!(filter.is_active());
+    assert!(filter.selects(
+        parse_date("2024-01-01").unwrap(),
+        "Alice",
+        "alice@example.com"
+    ));
+    assert!(!filter.selects(
+        parse_date("2023-12-31").unwrap(),
+        "Alice",
+        "alice@example.com"
+    ));
+    assert!(!filter.selects(parse_date("2024-05-01").unwrap(), "Bob", "bob@example.com"));
+    assert!(!ChangeFilter::default().is_active());
+}
+
+#[test]
+fn test_touched_files_since_a_date_and_by_an_author() {
+    let dir = tempdir().unwrap();
+    history(dir.path());
+
+    let since = ChangeFilter {
+        since: parse_date("2024-01-01"),
+        author: None,
+    };
+    assert_eq!(
+        touched_files(dir.path(), &since).unwrap(),
+        ["api/user.rs".to_string(), "main.rs".to_string()].into()
+    );
+
+    let by_bob = ChangeFilter {
+        since: None,
+        author: Some("bob@".into()),
+    };
+    assert_eq!(
+        touched_files(dir.path(), &by_bob).unwrap(),
+        ["api/old.rs".to_string(), "main.rs".to_string()].into()
+    );
+
+    // Paths are relative to the directory scanned, not the repository.
+    assert_eq!(
+        touched_files(&dir.path().join("api"), &since).unwrap(),
+        ["user.rs".to_string()].into()
+    );
+}
+
+#[test]
+fn test_session_keeps_only_the_touched_files() {
+    let dir = tempdir().unwrap();
+    history(dir.path());
+    fs::write(dir.path().join("untracked.rs"), "fn new() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .changed_since(parse_date("2024-01-01"))
+        .author(Some("alice".into()))
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+
+    let files: Vec<_> = session
+        .processed_entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    assert_eq!(files, ["api/user.rs"]);
+}
+
+#[test]
+fn test_changed_since_outside_a_repository_is_an_error() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .changed_since(parse_date("2024-01-01"))
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    assert!(session.process_codebase().is_err());
+}
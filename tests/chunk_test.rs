@@ -0,0 +1,68 @@
+use code2prompt_tui::engine::{
+    chunk::{chat_messages, chunk_files},
+    model::FileContext,
+    token::{TokenizerChoice, count_tokens},
+};
+
+const TOKENIZER: TokenizerChoice = TokenizerChoice::Cl100k;
+
+fn file(path: &str, code: &str) -> FileContext {
+    FileContext {
+        path: path.into(),
+        id: String::new(),
+        extension: "rs".into(),
+        code: code.into(),
+        token_count: None,
+        tags: Vec::new(),
+        owners: Vec::new(),
+    }
+}
+
+#[test]
+fn test_files_are_packed_in_order_under_the_limit() {
+    let files: Vec<FileContext> = (0..6)
+        .map(|i| {
+            file(
+                &format!("src/f{i}.rs"),
+                &format!("fn f{i}() {{ let x = {i}; }}\n"),
+            )
+        })
+        .collect();
+    let chunks = chunk_files(&files, 40, TOKENIZER).unwrap();
+    assert!(chunks.len() > 1, "{chunks:?}");
+    for chunk in &chunks {
+        assert!(count_tokens(chunk, TOKENIZER).unwrap() <= 40, "{chunk}");
+    }
+    let joined = chunks.join("\n\n");
+    let positions: Vec<usize> = (0..6)
+        .map(|i| joined.find(&format!("`src/f{i}.rs`")).unwrap())
+        .collect();
+    assert!(positions.is_sorted());
+}
+
+#[test]
+fn test_large_files_are_split_into_parts() {
+    let code: String = (0..200).map(|i| format!("let v{i} = {i};\n")).collect();
+    let chunks = chunk_files(&[file("big.rs", &code)], 300, TOKENIZER).unwrap();
+    let n = chunks.len();
+    assert!(n > 1);
+    for (i, chunk) in chunks.iter().enumerate() {
+        assert!(chunk.starts_with(&format!("`big.rs` (part {} of {n}):", i + 1)));
+        assert!(count_tokens(chunk, TOKENIZER).unwrap() <= 300, "{chunk}");
+    }
+    assert!(chunks.concat().contains("let v199 = 199;"));
+}
+
+#[test]
+fn test_messages_start_with_the_system_preamble() {
+    let files = [file("a.rs", "fn a() {}\n"), file("b.rs", "fn b() {}\n")];
+    let messages = chat_messages("Review this code.".into(), &files, 1000, TOKENIZER).unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].role, "system");
+    assert_eq!(messages[0].content, "Review this code.");
+    assert_eq!(messages[1].role, "user");
+    assert!(messages[1].content.contains("`a.rs`") && messages[1].content.contains("`b.rs`"));
+
+    let err = chat_messages("Review this code.".into(), &files, 2, TOKENIZER).unwrap_err();
+    assert!(err.to_string().contains("over the limit"), "{err}");
+}
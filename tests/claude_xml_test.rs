@@ -0,0 +1,43 @@
+use code2prompt_tui::engine::{claude_xml::documents_xml, model::FileContext};
+
+fn file(path: &str, code: &str) -> FileContext {
+    FileContext {
+        path: path.into(),
+        id: String::new(),
+        extension: String::new(),
+        code: code.into(),
+        token_count: None,
+        tags: Vec::new(),
+        owners: Vec::new(),
+    }
+}
+
+#[test]
+fn test_documents_are_indexed_and_escaped() {
+    let xml = documents_xml(&[
+        file("src/a&b.rs", "if a < b && c > d {}\n"),
+        file("empty.rs", ""),
+        file("README.md", "say \"hi\""),
+    ]);
+    assert_eq!(
+        xml,
+        "<document_index>\n\
+         1. src/a&amp;b.rs\n\
+         2. README.md\n\
+         </document_index>\n\n\
+         <documents>\n\
+         <document index=\"1\">\n\
+         <source>src/a&amp;b.rs</source>\n\
+         <document_contents>\n\
+         if a &lt; b &amp;&amp; c &gt; d {}\n\
+         </document_contents>\n\
+         </document>\n\
+         <document index=\"2\">\n\
+         <source>README.md</source>\n\
+         <document_contents>\n\
+         say &quot;hi&quot;\n\
+         </document_contents>\n\
+         </document>\n\
+         </documents>"
+    );
+}
@@ -0,0 +1,84 @@
+use code2prompt_tui::common::code::{LineNumberFormat, fence_language, normalize_newlines, wrap};
+
+#[test]
+fn test_wrap_with_and_without_block() {
+    assert_eq!(
+        wrap("let x = 1;", "rs", None, false),
+        "```rs\nlet x = 1;\n```"
+    );
+    assert_eq!(wrap("let x = 1;", "rs", None, true), "let x = 1;");
+}
+
+#[test]
+fn test_normalize_newlines_strips_bom_and_crlf() {
+    assert_eq!(normalize_newlines("\u{feff}a\r\nb\r\n"), "a\nb\n");
+    assert_eq!(normalize_newlines("a\rb"), "a\nb");
+}
+
+#[test]
+fn test_normalize_newlines_borrows_clean_input() {
+    assert!(matches!(
+        normalize_newlines("a\nb\n"),
+        std::borrow::Cow::Borrowed(_)
+    ));
+}
+
+#[test]
+fn test_line_numbers_default_width_fits_largest_number() {
+    let code = (1..=12).map(|i| format!("l{i}\n")).collect::<String>();
+    let out = LineNumberFormat::default().apply(&code);
+    assert!(out.starts_with(" 1 | l1\n"));
+    assert!(out.ends_with("12 | l12\n"));
+}
+
+#[test]
+fn test_line_numbers_custom_start_separator_and_width() {
+    let fmt = LineNumberFormat {
+        separator: ": ".into(),
+        width: Some(5),
+        start: 98,
+    };
+    let out = wrap("a\nb\nc", "rs", Some(&fmt), false);
+    assert_eq!(out, "```rs\n   98: a\n   99: b\n  100: c\n```");
+}
+
+#[test]
+fn test_line_numbers_skipped_for_single_line() {
+    let out = wrap(
+        "one-liner",
+        "txt",
+        Some(&LineNumberFormat::default()),
+        false,
+    );
+    assert_eq!(out, "```txt\none-liner\n```");
+}
+
+#[test]
+fn test_fence_language_mapping() {
+    assert_eq!(fence_language("rs"), "rust");
+    assert_eq!(fence_language("PY"), "python");
+    assert_eq!(fence_language("kt"), "kotlin");
+    assert_eq!(fence_language("hbs"), "handlebars");
+    assert_eq!(fence_language("unknownext"), "unknownext");
+}
+
+#[test]
+fn test_wrap_markdown_with_nested_fence() {
+    let md = "# Readme\n\n```rust\nfn main() {}\n```\n";
+    let out = wrap(md, "markdown", None, false);
+    assert_eq!(out, format!("````markdown\n{md}````"));
+}
+
+#[test]
+fn test_wrap_uses_longer_fence_than_longest_run() {
+    let md = "`````\nfive\n`````\nand ``` three\n";
+    let out = wrap(md, "md", None, false);
+    assert!(out.starts_with("``````md\n"));
+    assert!(out.ends_with("\n``````"));
+}
+
+#[test]
+fn test_wrap_inline_backticks_keep_default_fence() {
+    let out = wrap("let s = `x`;", "js", None, false);
+    assert_eq!(out, "```js\nlet s = `x`;\n```");
+}
@@ -0,0 +1,90 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    codeowners::CodeOwners, config::Code2PromptConfigBuilder, session::Code2PromptSession,
+};
+use tempfile::tempdir;
+
+const CODEOWNERS: &str = "\
+# Default owners
+*                       @org/core
+*.md                    @org/docs docs@example.com
+/payments/              @org/payments
+api/*.proto             @org/api
+payments/legacy/        # no owners
+invalid/[               @org/nobody
+";
+
+#[test]
+fn test_the_last_matching_pattern_wins() {
+    let owners = CodeOwners::parse(CODEOWNERS);
+
+    assert_eq!(owners.owners_of("src/main.rs"), ["@org/core"]);
+    assert_eq!(
+        owners.owners_of("src/README.md"),
+        ["@org/docs", "docs@example.com"]
+    );
+    assert_eq!(owners.owners_of("payments/charge.rs"), ["@org/payments"]);
+    assert_eq!(owners.owners_of("payments/README.md"), ["@org/payments"]);
+    assert!(owners.owners_of("payments/legacy/old.rs").is_empty());
+}
+
+#[test]
+fn test_patterns_follow_gitignore_anchoring() {
+    let owners = CodeOwners::parse("/build/ @org/ci\ndocs @org/docs\napi/*.proto @org/api\n");
+
+    assert_eq!(owners.owners_of("build/out.txt"), ["@org/ci"]);
+    assert!(owners.owners_of("src/build/out.txt").is_empty());
+    assert_eq!(owners.owners_of("src/docs/guide.txt"), ["@org/docs"]);
+    assert_eq!(owners.owners_of("api/user.proto"), ["@org/api"]);
+    assert!(owners.owners_of("api/v1/user.proto").is_empty());
+    assert!(owners.owners_of("src/api/user.proto").is_empty());
+}
+
+#[test]
+fn test_owners_compare_without_case_or_at_sign() {
+    let owners = CodeOwners::parse(CODEOWNERS);
+
+    assert!(owners.is_owned_by("payments/charge.rs", &["@Org/Payments".into()]));
+    assert!(owners.is_owned_by("payments/charge.rs", &["org/payments".into()]));
+    assert!(!owners.is_owned_by("src/main.rs", &["@org/payments".into()]));
+}
+
+#[test]
+fn test_codeowners_is_found_in_the_github_directory_first() {
+    let dir = tempdir().unwrap();
+    assert!(
+        CodeOwners::load(dir.path())
+            .unwrap()
+            .owners_of("main.rs")
+            .is_empty()
+    );
+
+    fs::create_dir(dir.path().join(".github")).unwrap();
+    fs::write(dir.path().join(".github/CODEOWNERS"), "* @org/core\n").unwrap();
+    fs::write(dir.path().join("CODEOWNERS"), "* @org/other\n").unwrap();
+    let owners = CodeOwners::load(dir.path()).unwrap();
+    assert_eq!(owners.owners_of("main.rs"), ["@org/core"]);
+}
+
+#[test]
+fn test_session_keeps_owned_files_and_exposes_their_owners() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("payments")).unwrap();
+    fs::write(dir.path().join("payments/charge.rs"), "fn charge() {}\n").unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .codeowners(CodeOwners::parse(CODEOWNERS))
+        .owner_filter(vec!["@org/payments".into()])
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let context = session.build_template_data(None, None, None).unwrap();
+
+    assert_eq!(context.files.len(), 1);
+    assert_eq!(context.files[0].path, "payments/charge.rs");
+    assert_eq!(context.files[0].owners, ["@org/payments"]);
+}
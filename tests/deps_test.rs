@@ -0,0 +1,278 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    deps::DependencyGraph,
+    traverse::{ProcessingMode, process_codebase},
+};
+use tempfile::tempdir;
+
+fn graph(sources: &[(&str, &str)], others: &[&str], go_module: Option<&str>) -> DependencyGraph {
+    let files: Vec<String> = sources
+        .iter()
+        .map(|(p, _)| p.to_string())
+        .chain(others.iter().map(|p| p.to_string()))
+        .collect();
+    DependencyGraph::from_sources(
+        sources.iter().map(|(p, s)| (p.to_string(), s.to_string())),
+        &files,
+        go_module,
+    )
+}
+
+#[test]
+fn test_js_relative_imports() {
+    let g = graph(
+        &[(
+            "src/app.ts",
+            "import { a } from './a';\nimport b from \"../lib/b.js\";\n\
+             const c = require('./c');\nimport React from 'react';\nimport './styles';\n",
+        )],
+        &["src/a.ts", "lib/b.ts", "src/c/index.js", "src/styles.css"],
+        None,
+    );
+    assert_eq!(
+        g.imports_of("src/app.ts"),
+        ["lib/b.ts", "src/a.ts", "src/c/index.js"]
+    );
+}
+
+#[test]
+fn test_python_imports() {
+    let g = graph(
+        &[
+            (
+                "pkg/main.py",
+                "import os\nimport pkg.util as u\nfrom . import models\nfrom .sub.helpers import f\n",
+            ),
+            ("pkg/sub/helpers.py", "from .. import util\n"),
+        ],
+        &["pkg/__init__.py", "pkg/util.py", "pkg/models.py"],
+        None,
+    );
+    assert_eq!(
+        g.imports_of("pkg/main.py"),
+        [
+            "pkg/__init__.py",
+            "pkg/models.py",
+            "pkg/sub/helpers.py",
+            "pkg/util.py"
+        ]
+    );
+    assert_eq!(
+        g.imports_of("pkg/sub/helpers.py"),
+        ["pkg/__init__.py", "pkg/util.py"]
+    );
+}
+
+#[test]
+fn test_rust_modules_and_uses() {
+    let g = graph(
+        &[
+            ("src/lib.rs", "pub mod engine;\nmod util;\n"),
+            (
+                "src/engine/mod.rs",
+                "pub mod walk;\nuse crate::util::helper;\n",
+            ),
+            ("src/engine/walk.rs", "use super::Config;\nuse std::fs;\n"),
+        ],
+        &["src/util.rs"],
+        None,
+    );
+    assert_eq!(
+        g.imports_of("src/lib.rs"),
+        ["src/engine/mod.rs", "src/util.rs"]
+    );
+    assert_eq!(
+        g.imports_of("src/engine/mod.rs"),
+        ["src/engine/walk.rs", "src/util.rs"]
+    );
+    assert_eq!(g.imports_of("src/engine/walk.rs"), ["src/engine/mod.rs"]);
+}
+
+#[test]
+fn test_rust_grouped_uses() {
+    let g = graph(
+        &[(
+            "src/engine/walk.rs",
+            "use crate::{\n    common::path,\n    // use crate::old;\n    engine::{config::{Config, Mode as M}, model::*},\n};\n\
+             use super::super::util::{self, helper};\nuse crate::Missing;\n",
+        )],
+        &[
+            "src/lib.rs",
+            "src/common/mod.rs",
+            "src/common/path.rs",
+            "src/engine/mod.rs",
+            "src/engine/config.rs",
+            "src/engine/model.rs",
+            "src/old.rs",
+            "src/util.rs",
+        ],
+        None,
+    );
+    assert_eq!(
+        g.imports_of("src/engine/walk.rs"),
+        [
+            "src/common/path.rs",
+            "src/engine/config.rs",
+            "src/engine/model.rs",
+            "src/util.rs"
+        ]
+    );
+}
+
+#[test]
+fn test_modules_reached_through_use_do_not_bring_their_submodules() {
+    let g = graph(
+        &[
+            ("src/lib.rs", "pub mod engine;\npub mod ui;\n"),
+            ("src/engine/mod.rs", "pub mod walk;\npub mod scan;\n"),
+            ("src/engine/walk.rs", "use super::Shared;\n"),
+            ("src/ui/mod.rs", "use crate::engine::walk;\n"),
+        ],
+        &["src/engine/scan.rs"],
+        None,
+    );
+    let deps = |seed: &str| {
+        g.dependencies(&[seed.to_string()], None)
+            .into_iter()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        deps("src/ui/mod.rs"),
+        ["src/engine/mod.rs", "src/engine/walk.rs", "src/ui/mod.rs"]
+    );
+    assert_eq!(
+        deps("src/engine/mod.rs"),
+        [
+            "src/engine/mod.rs",
+            "src/engine/scan.rs",
+            "src/engine/walk.rs"
+        ]
+    );
+}
+
+#[test]
+fn test_go_and_c_imports() {
+    let g = graph(
+        &[
+            (
+                "cmd/main.go",
+                "package main\n\nimport (\n\t\"fmt\"\n\tdb \"example.com/app/store\"\n)\n",
+            ),
+            (
+                "src/main.c",
+                "#include <stdio.h>\n#include \"util.h\"\n#include \"common.h\"\n",
+            ),
+        ],
+        &[
+            "store/store.go",
+            "store/store_test.go",
+            "src/util.h",
+            "include/common.h",
+        ],
+        Some("example.com/app"),
+    );
+    assert_eq!(g.imports_of("cmd/main.go"), ["store/store.go"]);
+    assert_eq!(
+        g.imports_of("src/main.c"),
+        ["include/common.h", "src/util.h"]
+    );
+}
+
+#[test]
+fn test_dependency_closure_depth() {
+    let g = graph(
+        &[
+            ("a.py", "import b\n"),
+            ("b.py", "import c\n"),
+            ("c.py", "import a\n"),
+        ],
+        &[],
+        None,
+    );
+    let seeds = ["a.py".to_string()];
+    let one: Vec<_> = g.dependencies(&seeds, Some(1)).into_iter().collect();
+    assert_eq!(one, ["a.py", "b.py"]);
+    let all: Vec<_> = g.dependencies(&seeds, None).into_iter().collect();
+    assert_eq!(all, ["a.py", "b.py", "c.py"]);
+}
+
+#[test]
+fn test_with_deps_selects_closure() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "mod cli;\n").unwrap();
+    fs::write(dir.path().join("src/cli.rs"), "use crate::util::x;\n").unwrap();
+    fs::write(dir.path().join("src/util.rs"), "pub fn x() {}\n").unwrap();
+    fs::write(dir.path().join("src/unrelated.rs"), "pub fn y() {}\n").unwrap();
+
+    let selected = |depth| {
+        let config = Code2PromptConfigBuilder::default()
+            .path(dir.path().to_path_buf())
+            .with_deps(vec!["src/main.rs".into()])
+            .dep_depth(depth)
+            .build()
+            .unwrap();
+        let (entries, ..) = process_codebase(&config, ProcessingMode::FullProcess).unwrap();
+        let mut paths: Vec<String> = entries
+            .iter()
+            .filter(|e| e.is_file)
+            .map(|e| e.relative_path.to_string_lossy().replace('\\', "/"))
+            .collect();
+        paths.sort();
+        paths
+    };
+    assert_eq!(selected(None), ["src/cli.rs", "src/main.rs", "src/util.rs"]);
+    assert_eq!(selected(Some(1)), ["src/cli.rs", "src/main.rs"]);
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .with_deps(vec!["src/missing.rs".into()])
+        .build()
+        .unwrap();
+    assert!(process_codebase(&config, ProcessingMode::FullProcess).is_err());
+}
+
+#[test]
+fn test_dependents_closure() {
+    let g = graph(
+        &[
+            ("app.py", "import service\n"),
+            ("service.py", "import models\n"),
+            ("cli.py", "import models\n"),
+            ("models.py", ""),
+        ],
+        &[],
+        None,
+    );
+    assert_eq!(g.importers_of("models.py"), ["cli.py", "service.py"]);
+    let seeds = ["models.py".to_string()];
+    let one: Vec<_> = g.dependents(&seeds, Some(1)).into_iter().collect();
+    assert_eq!(one, ["cli.py", "models.py", "service.py"]);
+    let all: Vec<_> = g.dependents(&seeds, None).into_iter().collect();
+    assert_eq!(all, ["app.py", "cli.py", "models.py", "service.py"]);
+}
+
+#[test]
+fn test_with_dependents_selects_importers() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("util.js"), "export const x = 1;\n").unwrap();
+    fs::write(dir.path().join("a.js"), "import { x } from './util.js';\n").unwrap();
+    fs::write(dir.path().join("b.js"), "import './a';\n").unwrap();
+    fs::write(dir.path().join("other.js"), "export const y = 2;\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .with_dependents(vec!["util.js".into()])
+        .build()
+        .unwrap();
+    let (entries, ..) = process_codebase(&config, ProcessingMode::FullProcess).unwrap();
+    let mut paths: Vec<String> = entries
+        .iter()
+        .filter(|e| e.is_file)
+        .map(|e| e.relative_path.to_string_lossy().into_owned())
+        .collect();
+    paths.sort();
+    assert_eq!(paths, ["a.js", "b.js", "util.js"]);
+}
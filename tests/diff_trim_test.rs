@@ -0,0 +1,47 @@
+use code2prompt_tui::engine::{diff_trim::trim_diff, token::TokenizerChoice};
+
+fn file_diff(path: &str, changed: usize, context: usize) -> String {
+    let mut diff = format!(
+        "diff --git a/{path} b/{path}\nindex 1111111..2222222 100644\n--- a/{path}\n+++ b/{path}\n@@ -1,{n} +1,{n} @@\n",
+        n = changed + context
+    );
+    for i in 0..context {
+        diff.push_str(&format!(" unchanged line {i} of {path}\n"));
+    }
+    for i in 0..changed {
+        diff.push_str(&format!("-old line {i}\n+new line {i}\n"));
+    }
+    diff
+}
+
+fn trim(diff: &str, max: usize) -> Option<String> {
+    trim_diff(diff, max, TokenizerChoice::Cl100k, true)
+}
+
+#[test]
+fn test_small_diff_is_untouched() {
+    assert_eq!(trim(&file_diff("a.rs", 2, 2), 1000), None);
+}
+
+#[test]
+fn test_context_lines_go_first() {
+    let diff = format!("--- Staged Changes ---\n{}", file_diff("a.rs", 3, 40));
+    let out = trim(&diff, 150).unwrap();
+    assert!(out.starts_with("--- Staged Changes ---\ndiff --git a/a.rs b/a.rs\n"));
+    assert!(!out.contains("unchanged line"));
+    assert!(out.contains("-old line 2\n+new line 2\n"));
+}
+
+#[test]
+fn test_largest_files_are_dropped_next() {
+    let diff = format!(
+        "--- Staged Changes ---\n{}\n--- Unstaged Changes ---\n{}",
+        file_diff("big.rs", 200, 0),
+        file_diff("small.rs", 2, 5)
+    );
+    let out = trim(&diff, 200).unwrap();
+    assert!(out.contains("... [diff of big.rs omitted: "));
+    assert!(out.contains("--- Unstaged Changes ---\n"));
+    assert!(out.contains("+new line 1\n"));
+    assert!(!out.contains(" unchanged line"));
+}
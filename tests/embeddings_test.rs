@@ -0,0 +1,177 @@
+use std::cell::Cell;
+use std::fs;
+
+use anyhow::Result;
+use code2prompt_tui::engine::embeddings::{
+    Embedder, EmbeddingIndex, SemanticQuery, chunk_text, rank_files,
+};
+use tempfile::tempdir;
+
+const VOCAB: &[&str] = &["payment", "retry", "user", "login", "render"];
+
+/// Embeds texts as word counts over a tiny vocabulary.
+struct WordCounts {
+    model: &'static str,
+    embedded: Cell<usize>,
+}
+
+impl WordCounts {
+    fn new(model: &'static str) -> Self {
+        Self {
+            model,
+            embedded: Cell::new(0),
+        }
+    }
+}
+
+impl Embedder for WordCounts {
+    fn model(&self) -> &str {
+        self.model
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.embedded.set(self.embedded.get() + texts.len());
+        Ok(texts
+            .iter()
+            .map(|t| {
+                let t = t.to_lowercase();
+                VOCAB.iter().map(|w| t.matches(w).count() as f32).collect()
+            })
+            .collect())
+    }
+}
+
+fn query(text: &str, top_k: usize) -> SemanticQuery {
+    SemanticQuery {
+        text: text.to_string(),
+        top_k,
+    }
+}
+
+#[test]
+fn test_chunk_text() {
+    let chunks = chunk_text("a.rs", "one\ntwo\n\n\nthree\n", 2);
+    assert_eq!(chunks, ["a.rs\none\ntwo", "a.rs\nthree"]);
+}
+
+#[test]
+fn test_rank_files_and_incremental_update() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("billing.rs"),
+        "fn payment_retry() {}\n// retry payment\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("auth.rs"), "fn user_login() {}\n").unwrap();
+    fs::write(dir.path().join("view.rs"), "fn render() {}\n").unwrap();
+    let files: Vec<String> = ["auth.rs", "billing.rs", "view.rs"]
+        .map(String::from)
+        .into();
+
+    let embedder = WordCounts::new("counts");
+    let mut index = EmbeddingIndex::default();
+    let ranked = rank_files(
+        &mut index,
+        dir.path(),
+        &files,
+        &embedder,
+        10,
+        &query("payment retry logic", 1),
+    )
+    .unwrap();
+    assert_eq!(ranked, ["billing.rs"]);
+    assert_eq!(index.len(), 3);
+    // Three files plus the query.
+    assert_eq!(embedder.embedded.get(), 4);
+
+    // Only the changed file is embedded again; removed files are dropped.
+    fs::write(dir.path().join("view.rs"), "fn render_login() {}\n").unwrap();
+    let files: Vec<String> = ["auth.rs", "view.rs"].map(String::from).into();
+    let ranked = rank_files(
+        &mut index,
+        dir.path(),
+        &files,
+        &embedder,
+        10,
+        &query("login", 5),
+    )
+    .unwrap();
+    assert_eq!(embedder.embedded.get(), 6);
+    assert_eq!(index.len(), 2);
+    assert_eq!(ranked, ["auth.rs", "view.rs"]);
+
+    // A different model invalidates the index.
+    let other = WordCounts::new("other");
+    assert_eq!(index.update(dir.path(), &files, &other, 10).unwrap(), 2);
+}
+
+#[test]
+fn test_index_roundtrip() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "payment\n").unwrap();
+    let files = vec!["a.rs".to_string()];
+    let embedder = WordCounts::new("counts");
+
+    let mut index = EmbeddingIndex::default();
+    index.update(dir.path(), &files, &embedder, 10).unwrap();
+    let path = dir.path().join("index.json");
+    index.save(&path).unwrap();
+
+    let mut loaded = EmbeddingIndex::load(&path);
+    assert_eq!(loaded.update(dir.path(), &files, &embedder, 10).unwrap(), 0);
+    assert!(EmbeddingIndex::load(&dir.path().join("missing.json")).is_empty());
+}
+
+#[cfg(all(feature = "embeddings", feature = "serve"))]
+#[test]
+fn test_query_selects_files_through_endpoint() {
+    use code2prompt_tui::engine::{
+        config::Code2PromptConfigBuilder,
+        embeddings::EmbeddingSettings,
+        traverse::{ProcessingMode, process_codebase},
+    };
+
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let addr = server.server_addr().to_ip().unwrap();
+    std::thread::spawn(move || {
+        let embedder = WordCounts::new("stub");
+        for mut request in server.incoming_requests() {
+            let body: serde_json::Value = serde_json::from_reader(request.as_reader()).unwrap();
+            let inputs: Vec<String> = serde_json::from_value(body["input"].clone()).unwrap();
+            let data: Vec<_> = embedder
+                .embed(&inputs)
+                .unwrap()
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| serde_json::json!({ "index": i, "embedding": v }))
+                .collect();
+            let reply = serde_json::json!({ "data": data }).to_string();
+            request
+                .respond(tiny_http::Response::from_string(reply))
+                .unwrap();
+        }
+    });
+
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("billing.rs"), "fn payment_retry() {}\n").unwrap();
+    fs::write(dir.path().join("auth.rs"), "fn user_login() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .query(Some(query("retry the payment", 1)))
+        .embeddings(EmbeddingSettings {
+            endpoint: format!("http://{addr}/v1"),
+            model: "stub".to_string(),
+            api_key_env: None,
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let (entries, ..) = process_codebase(&config, ProcessingMode::FullProcess).unwrap();
+    let files: Vec<_> = entries
+        .iter()
+        .filter(|e| e.is_file)
+        .map(|e| e.relative_path.to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(files, ["billing.rs"]);
+}
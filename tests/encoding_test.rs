@@ -0,0 +1,42 @@
+#![cfg(feature = "encoding")]
+
+use code2prompt_tui::engine::encoding::decode_bytes;
+
+#[test]
+fn test_utf8_is_not_transcoded() {
+    let src = decode_bytes("héllo".as_bytes().to_vec()).unwrap();
+    assert_eq!(src.text, "héllo");
+    assert!(src.transcoded_from.is_none());
+}
+
+#[test]
+fn test_latin1_is_transcoded() {
+    // "café déjà vu" in ISO-8859-1 / windows-1252
+    let bytes = b"// caf\xe9 d\xe9j\xe0 vu, na\xefve r\xe9sum\xe9\n".to_vec();
+    let src = decode_bytes(bytes).unwrap();
+    assert!(src.text.contains("café déjà vu"));
+    assert_eq!(src.transcoded_from, Some("windows-1252"));
+}
+
+#[test]
+fn test_utf16_with_and_without_bom() {
+    let text = "fn main() {}\n";
+    let mut le: Vec<u8> = vec![0xFF, 0xFE];
+    le.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    let src = decode_bytes(le).unwrap();
+    assert_eq!(src.text, text);
+    assert_eq!(src.transcoded_from, Some("UTF-16LE"));
+
+    let be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    let src = decode_bytes(be).unwrap();
+    assert_eq!(src.text, text);
+    assert_eq!(src.transcoded_from, Some("UTF-16BE"));
+}
+
+#[test]
+fn test_binary_is_rejected() {
+    let bytes = vec![
+        0x89, 0x50, 0x4E, 0x47, 0x00, 0x00, 0x00, 0x0D, 0x00, 0x01, 0xFF, 0x00,
+    ];
+    assert!(decode_bytes(bytes).is_none());
+}
@@ -0,0 +1,55 @@
+use std::fs;
+
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use code2prompt_tui::ui::template::{check_template, handlebars_setup, render_template};
+use tempfile::tempdir;
+
+const TEMPLATE: &str =
+    "{{#each file_groups}}## {{dir}}\n{{#each files}}{{path}}\n{{/each}}{{/each}}";
+
+#[test]
+fn test_files_are_grouped_by_top_level_directory() {
+    let dir = tempdir().unwrap();
+    for (path, text) in [
+        ("README.md", "# project\n"),
+        ("crates/api/lib.rs", "pub fn api() {}\n"),
+        ("crates/core/lib.rs", "pub fn core() {}\n"),
+        ("docs/intro.md", "Intro\n"),
+    ] {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .absolute_path(false)
+        .no_priority_docs(true)
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    session
+        .processed_entries
+        .sort_by(|a, b| a.path.cmp(&b.path));
+    let context = session.build_template_data(None, None, None).unwrap();
+
+    let dirs: Vec<_> = context.file_groups.iter().map(|g| g.dir.as_str()).collect();
+    assert_eq!(dirs, [".", "crates", "docs"]);
+    assert_eq!(context.file_groups[1].files.len(), 2);
+
+    let hb = handlebars_setup(TEMPLATE, "groups").unwrap();
+    let prompt = render_template(&hb, "groups", &serde_json::to_value(&context).unwrap()).unwrap();
+    assert_eq!(
+        prompt,
+        "## .\nREADME.md\n## crates\ncrates/api/lib.rs\ncrates/core/lib.rs\n## docs\ndocs/intro.md"
+    );
+}
+
+#[test]
+fn test_file_groups_is_a_builtin() {
+    let check = check_template(TEMPLATE).unwrap();
+    assert!(check.is_ok(), "{check:?}");
+    assert_eq!(check.builtins, ["file_groups"]);
+    assert!(check.variables.is_empty());
+}
@@ -0,0 +1,75 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder, file_id::file_ids, session::Code2PromptSession,
+};
+use code2prompt_tui::ui::tree_view::build_tree_view;
+use tempfile::tempdir;
+
+#[test]
+fn test_ids_depend_only_on_the_path() {
+    let ids = file_ids(&["src/main.rs", "src/lib.rs"]);
+    assert_eq!(ids.len(), 2);
+    assert_ne!(ids[0], ids[1]);
+    for id in &ids {
+        assert!(id.starts_with('F') && id.len() == 7, "{id}");
+        assert!(id[1..].chars().all(|c| c.is_ascii_hexdigit()), "{id}");
+    }
+    // The same path keeps its ID whatever else is in the prompt.
+    assert_eq!(file_ids(&["README.md", "src/main.rs"])[1], ids[0]);
+    assert!(file_ids::<&str>(&[]).is_empty());
+}
+
+#[test]
+fn test_only_colliding_ids_get_longer() {
+    // Enough paths that some of them share their first six hex digits.
+    let paths: Vec<String> = (0..20_000).map(|i| format!("src/file{i}.rs")).collect();
+    let ids = file_ids(&paths);
+    let distinct: std::collections::HashSet<_> = ids.iter().collect();
+    assert_eq!(distinct.len(), paths.len());
+    let longer: Vec<_> = ids.iter().filter(|id| id.len() > 7).collect();
+    assert!(!longer.is_empty());
+    assert!(longer.len() < 100, "{}", longer.len());
+    assert!(ids.iter().all(|id| id.len() >= 7));
+    // No ID is the start of another, so they cannot be mistaken for each
+    // other in a model's answer either.
+    for long in &longer {
+        assert!(
+            !ids.iter()
+                .any(|id| id != *long && long.starts_with(id.as_str()))
+        );
+    }
+}
+
+#[test]
+fn test_adding_a_file_keeps_the_other_ids() {
+    let paths: Vec<String> = (0..500).map(|i| format!("src/file{i}.rs")).collect();
+    let ids = file_ids(&paths);
+    let mut more = paths.clone();
+    more.push("docs/unrelated.md".to_string());
+    assert_eq!(file_ids(&more)[..paths.len()], ids[..]);
+}
+
+#[test]
+fn test_template_files_and_tree_share_ids() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let context = session.build_template_data(None, None, None).unwrap();
+    let id = &context.files[0].id;
+    assert_eq!(id, &file_ids(&["src/main.rs"])[0]);
+
+    for full in [false, true] {
+        let tree = build_tree_view(dir.path(), &session.processed_entries, full, true);
+        assert!(tree.contains(&format!("main.rs [{id}]")), "{tree}");
+    }
+    let tree = build_tree_view(dir.path(), &session.processed_entries, false, false);
+    assert!(!tree.contains('['), "{tree}");
+}
@@ -0,0 +1,79 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    session::Code2PromptSession,
+    token::{TokenizerChoice, estimate_tokens, truncate_middle},
+};
+use tempfile::tempdir;
+
+fn numbered_lines(n: usize) -> String {
+    (1..=n).map(|i| format!("line {i:04}\n")).collect()
+}
+
+#[test]
+fn test_short_text_is_untouched() {
+    assert_eq!(
+        truncate_middle("fn main() {}\n", 100, TokenizerChoice::Cl100k, true),
+        None
+    );
+}
+
+#[test]
+fn test_estimated_truncation_keeps_head_and_tail_lines() {
+    let text = numbered_lines(500);
+    let out = truncate_middle(&text, 100, TokenizerChoice::Cl100k, true).unwrap();
+    assert!(out.starts_with("line 0001\n"));
+    assert!(out.ends_with("line 0500\n"));
+    assert!(out.contains(" tokens elided] ...\n"));
+    // Cuts fall on line boundaries.
+    assert!(
+        out.lines()
+            .all(|l| l.starts_with("line ") || l.starts_with("... ["))
+    );
+    let budget = estimate_tokens(&out, TokenizerChoice::Cl100k);
+    assert!(budget <= 110, "{budget}");
+}
+
+#[cfg(feature = "token_map")]
+#[test]
+fn test_exact_truncation_fits_the_cap() {
+    use code2prompt_tui::engine::token::count_tokens;
+
+    let text = numbered_lines(500);
+    let out = truncate_middle(&text, 200, TokenizerChoice::Cl100k, false).unwrap();
+    let count = count_tokens(&out, TokenizerChoice::Cl100k).unwrap();
+    assert!(count <= 215, "{count}");
+    assert!(out.starts_with("line 0001\n") && out.ends_with("line 0500\n"));
+}
+
+#[test]
+fn test_session_caps_each_file() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("big.txt"), numbered_lines(1000)).unwrap();
+    fs::write(dir.path().join("small.txt"), "tiny\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .max_file_tokens(Some(50))
+        .approximate_tokens(true)
+        .line_numbers(true)
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+
+    let code = |name: &str| {
+        session
+            .processed_entries
+            .iter()
+            .find(|e| e.relative_path.ends_with(name))
+            .and_then(|e| e.code.clone())
+            .unwrap()
+    };
+    let big = code("big.txt");
+    assert!(big.contains("tokens elided] ..."));
+    // Line numbers are those of the whole file.
+    assert!(big.contains("1000 | line 1000"));
+    assert_eq!(code("small.txt"), "```txt\ntiny\n```");
+}
@@ -0,0 +1,89 @@
+//! A fixed Rust source the token estimate is compared against.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Counts the lines of each file under a directory, by extension.
+#[derive(Debug, Default)]
+pub struct LineCounter {
+    counts: HashMap<String, usize>,
+    skipped: Vec<PathBuf>,
+}
+
+impl LineCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Walks `root` and adds the lines of every readable file.
+    pub fn walk(&mut self, root: &Path) -> std::io::Result<()> {
+        for entry in fs::read_dir(root)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.walk(&path)?;
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(text) => self.add(&path, &text),
+                Err(_) => self.skipped.push(path),
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, path: &Path, text: &str) {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("(none)")
+            .to_lowercase();
+        *self.counts.entry(ext).or_insert(0) += text.lines().count();
+    }
+
+    /// The extensions sorted by line count, largest first.
+    pub fn ranked(&self) -> Vec<(&str, usize)> {
+        let mut ranked: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(ext, lines)| (ext.as_str(), *lines))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked
+    }
+
+    pub fn skipped(&self) -> &[PathBuf] {
+        &self.skipped
+    }
+}
+
+fn format_report(counter: &LineCounter, width: usize) -> String {
+    let mut out = String::new();
+    let total: usize = counter.ranked().iter().map(|(_, n)| n).sum();
+    for (ext, lines) in counter.ranked() {
+        let share = if total == 0 {
+            0.0
+        } else {
+            lines as f64 * 100.0 / total as f64
+        };
+        let bar = "#".repeat((share / 100.0 * width as f64).round() as usize);
+        out.push_str(&format!("{ext:>8} {lines:>8} {share:5.1}% {bar}\n"));
+    }
+    if !counter.skipped().is_empty() {
+        out.push_str(&format!("{} files could not be read\n", counter.skipped().len()));
+    }
+    out
+}
+
+fn main() {
+    let root = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let mut counter = LineCounter::new();
+    if let Err(e) = counter.walk(&root) {
+        eprintln!("error: failed to walk {}: {e}", root.display());
+        std::process::exit(1);
+    }
+    print!("{}", format_report(&counter, 40));
+}
@@ -0,0 +1,25 @@
+use code2prompt_tui::common::format::{format_size, format_utc_timestamp, shell_quote};
+
+#[test]
+fn test_format_size_uses_binary_units() {
+    assert_eq!(format_size(0), "0B");
+    assert_eq!(format_size(1023), "1023B");
+    assert_eq!(format_size(4 * 1024 + 100), "4.1K");
+    assert_eq!(format_size(12 * 1024 * 1024), "12M");
+    assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0G");
+}
+
+#[test]
+fn test_shell_quote_only_quotes_when_needed() {
+    assert_eq!(shell_quote("src/main.rs"), "src/main.rs");
+    assert_eq!(shell_quote("my file.rs"), "'my file.rs'");
+    assert_eq!(shell_quote("[ab]*.rs"), "'[ab]*.rs'");
+    assert_eq!(shell_quote("it's.md"), r"'it'\''s.md'");
+    assert_eq!(shell_quote(""), "''");
+}
+
+#[test]
+fn test_format_utc_timestamp() {
+    assert_eq!(format_utc_timestamp(0), "1970-01-01T00:00:00Z");
+    assert_eq!(format_utc_timestamp(1_709_210_096), "2024-02-29T12:34:56Z");
+}
@@ -0,0 +1,126 @@
+use code2prompt_tui::common::hash::HashMap;
+use code2prompt_tui::ui::template::{
+    VarType, apply_var_specs, check_template, extract_placeholders, handlebars_setup,
+    render_template, split_front_matter, typed_vars,
+};
+
+const TEMPLATE: &str = "+++\n\
+[vars.audience]\n\
+description = \"Who the prompt is written for\"\n\
+default = \"a senior reviewer\"\n\
+\n\
+[vars.task]\n\
+description = \"What the model should do\"\n\
+required = true\n\
++++\n\
+For {{audience}}: {{task}}";
+
+#[test]
+fn test_front_matter_is_split_from_the_body() {
+    let (front, body) = split_front_matter(TEMPLATE).unwrap();
+    assert_eq!(body, "For {{audience}}: {{task}}");
+    assert_eq!(
+        front.vars["audience"].default.as_deref(),
+        Some("a senior reviewer")
+    );
+    assert!(front.vars["task"].required);
+
+    let (front, body) = split_front_matter("{{task}}").unwrap();
+    assert!(front.vars.is_empty());
+    assert_eq!(body, "{{task}}");
+}
+
+#[test]
+fn test_invalid_front_matter_is_rejected() {
+    assert!(split_front_matter("+++\n[vars.task]\nrequird = true\n+++\n{{task}}").is_err());
+    assert!(split_front_matter("+++\n[vars.task]\n{{task}}").is_err());
+}
+
+#[test]
+fn test_front_matter_is_not_rendered() {
+    let hb = handlebars_setup(TEMPLATE, "t").unwrap();
+    let data = serde_json::json!({ "audience": "me", "task": "fix it" });
+    assert_eq!(render_template(&hb, "t", &data).unwrap(), "For me: fix it");
+
+    let check = check_template(TEMPLATE).unwrap();
+    assert!(check.is_ok(), "{check:?}");
+    assert_eq!(check.variables, ["audience", "task"]);
+    assert_eq!(check.declared.len(), 2);
+}
+
+#[test]
+fn test_declared_variables_are_placeholders() {
+    let tpl = "+++\n[vars.ticket]\n+++\n{{task}}";
+    assert_eq!(extract_placeholders(tpl).unwrap(), ["task", "ticket"]);
+    assert_eq!(
+        extract_placeholders("{{#each files}}{{path}}{{/each}}{{#if ticket}}{{ticket}}{{/if}}")
+            .unwrap(),
+        ["ticket"]
+    );
+}
+
+#[test]
+fn test_defaults_and_required_variables() {
+    let (front, _) = split_front_matter(TEMPLATE).unwrap();
+
+    let mut vars = HashMap::default();
+    let err = apply_var_specs(&front.vars, &mut vars).unwrap_err();
+    assert!(
+        err.to_string().contains("task (What the model should do)"),
+        "{err}"
+    );
+
+    vars.insert("task".to_string(), "fix it".to_string());
+    apply_var_specs(&front.vars, &mut vars).unwrap();
+    assert_eq!(vars["audience"], "a senior reviewer");
+
+    vars.insert("audience".to_string(), "me".to_string());
+    apply_var_specs(&front.vars, &mut vars).unwrap();
+    assert_eq!(vars["audience"], "me");
+}
+
+const TYPED: &str = "+++\n\
+[vars.strict]\n\
+type = \"bool\"\n\
+default = \"no\"\n\
+\n\
+[vars.depth]\n\
+type = \"enum\"\n\
+options = [\"quick\", \"thorough\"]\n\
++++\n\
+{{#if strict}}Be strict. {{/if}}Review: {{depth}}";
+
+#[test]
+fn test_typed_variables_are_checked() {
+    let (front, _) = split_front_matter(TYPED).unwrap();
+    assert_eq!(front.vars["strict"].kind, VarType::Bool);
+    assert_eq!(front.vars["depth"].options, ["quick", "thorough"]);
+
+    let mut vars = HashMap::default();
+    vars.insert("depth".to_string(), "deep".to_string());
+    let err = apply_var_specs(&front.vars, &mut vars).unwrap_err();
+    assert!(err.to_string().contains("one of quick, thorough"), "{err}");
+
+    vars.insert("depth".to_string(), "quick".to_string());
+    vars.insert("strict".to_string(), "maybe".to_string());
+    let err = apply_var_specs(&front.vars, &mut vars).unwrap_err();
+    assert!(err.to_string().contains("true or false"), "{err}");
+
+    // Booleans are spelled `true` or `false`.
+    vars.insert("strict".to_string(), "Yes".to_string());
+    apply_var_specs(&front.vars, &mut vars).unwrap();
+    assert_eq!(vars["strict"], "true");
+}
+
+#[test]
+fn test_bool_variables_are_booleans_in_the_template() {
+    let (front, _) = split_front_matter(TYPED).unwrap();
+    let mut vars = HashMap::default();
+    vars.insert("depth".to_string(), "quick".to_string());
+    apply_var_specs(&front.vars, &mut vars).unwrap();
+    assert_eq!(vars["strict"], "false");
+
+    let data = serde_json::Value::Object(typed_vars(&front.vars, vars));
+    let hb = handlebars_setup(TYPED, "t").unwrap();
+    assert_eq!(render_template(&hb, "t", &data).unwrap(), "Review: quick");
+}
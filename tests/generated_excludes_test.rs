@@ -0,0 +1,63 @@
+use std::fs;
+
+use code2prompt_tui::ui::config::{ARTIFACT_EXCLUDES, output_glob, path_in_root};
+use globset::{Glob, GlobSetBuilder};
+use tempfile::tempdir;
+
+#[test]
+fn test_output_path_inside_the_root_is_found() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("out")).unwrap();
+    let root = dir.path().join(".");
+
+    assert_eq!(
+        path_in_root(&root, &dir.path().join("out/prompt.md")).as_deref(),
+        Some("out/prompt.md")
+    );
+    // Directories that do not exist yet are fine.
+    assert_eq!(
+        path_in_root(&root, &dir.path().join("new/dir/prompt.md")).as_deref(),
+        Some("new/dir/prompt.md")
+    );
+    assert_eq!(
+        path_in_root(&dir.path().join("out"), &dir.path().join("prompt.md")),
+        None
+    );
+    assert_eq!(path_in_root(&root, dir.path()), None);
+}
+
+#[test]
+fn test_output_glob_matches_every_expansion() {
+    let glob = output_glob("prompts/{repo}-{date}.md");
+    assert_eq!(glob, "prompts/*-*.md");
+    let matcher = Glob::new(&glob).unwrap().compile_matcher();
+    assert!(matcher.is_match("prompts/app-2024-05-01.md"));
+    assert!(!matcher.is_match("prompts/notes.txt"));
+
+    // Glob syntax in the file name is matched literally.
+    let matcher = Glob::new(&output_glob("out[1]*.md"))
+        .unwrap()
+        .compile_matcher();
+    assert!(matcher.is_match("out[1]*.md"));
+    assert!(!matcher.is_match("out1x.md"));
+}
+
+#[test]
+fn test_artifact_excludes_match_committed_prompts() {
+    let mut builder = GlobSetBuilder::new();
+    for glob in ARTIFACT_EXCLUDES {
+        builder.add(Glob::new(glob).unwrap());
+    }
+    let set = builder.build().unwrap();
+    for path in [
+        "review.c2p.md",
+        "docs/api.c2p.md",
+        "prompt.md",
+        "ai/prompt-v2.md",
+    ] {
+        assert!(set.is_match(path), "{path}");
+    }
+    for path in ["README.md", "src/prompt.rs", "docs/prompting-guide.txt"] {
+        assert!(!set.is_match(path), "{path}");
+    }
+}
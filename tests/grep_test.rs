@@ -0,0 +1,75 @@
+use std::fs;
+use std::ops::Range;
+
+use code2prompt_tui::common::code::LineNumberFormat;
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder, grep::GrepFilter, session::Code2PromptSession,
+};
+use tempfile::tempdir;
+
+const SOURCE: &str = "a\nb\nFLAG_X on\nc\nd\ne\nf\ng\nFLAG_X off\nh\n";
+
+#[test]
+fn test_regions_merge_overlapping_context() {
+    let grep = GrepFilter::new("FLAG_X", Some(1)).unwrap();
+    assert_eq!(grep.regions(SOURCE), [1..4, 7..10]);
+    let grep = GrepFilter::new("FLAG_X", Some(3)).unwrap();
+    assert_eq!(grep.regions(SOURCE), vec![Range { start: 0, end: 10 }]);
+    assert!(
+        GrepFilter::new("nothing", Some(1))
+            .unwrap()
+            .regions(SOURCE)
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_apply_without_context_keeps_whole_file() {
+    let grep = GrepFilter::new("(?i)flag_x", None).unwrap();
+    assert_eq!(grep.apply(SOURCE, None).as_deref(), Some(SOURCE));
+    assert_eq!(grep.apply("unrelated\n", None), None);
+}
+
+#[test]
+fn test_excerpt_marks_omissions_and_keeps_line_numbers() {
+    let grep = GrepFilter::new("FLAG_X", Some(0)).unwrap();
+    assert_eq!(
+        grep.apply(SOURCE, None).unwrap(),
+        "... [2 lines omitted]\nFLAG_X on\n... [5 lines omitted]\nFLAG_X off\n... [1 line omitted]\n"
+    );
+    let fmt = LineNumberFormat::default();
+    assert_eq!(
+        grep.apply(SOURCE, Some(&fmt)).unwrap(),
+        "... [2 lines omitted]\n 3 | FLAG_X on\n... [5 lines omitted]\n 9 | FLAG_X off\n... [1 line omitted]\n"
+    );
+}
+
+#[test]
+fn test_invalid_pattern_is_an_error() {
+    assert!(GrepFilter::new("(unclosed", None).is_err());
+}
+
+#[test]
+fn test_session_keeps_only_matching_files() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("flags.rs"), SOURCE).unwrap();
+    fs::write(dir.path().join("other.rs"), "fn main() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .grep(Some(GrepFilter::new("FLAG_X", Some(0)).unwrap()))
+        .no_codeblock(true)
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+
+    let files: Vec<_> = session
+        .processed_entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(files, ["flags.rs"]);
+    let code = session.processed_entries[0].code.as_deref().unwrap();
+    assert!(code.starts_with("... [2 lines omitted]\nFLAG_X on\n"));
+}
@@ -0,0 +1,119 @@
+use std::fs;
+use std::path::Path;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder, filter::HiddenFilter, session::Code2PromptSession,
+};
+use tempfile::tempdir;
+
+fn scanned(root: &Path, builder: &mut Code2PromptConfigBuilder) -> Vec<String> {
+    let config = builder.path(root.to_path_buf()).build().unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let mut files: Vec<String> = session
+        .processed_entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn write_tree(root: &Path) {
+    for (path, content) in [
+        ("main.rs", "fn main() {}\n"),
+        (".env.example", "KEY=value\n"),
+        (".github/workflows/ci.yml", "on: push\n"),
+        (".github/.keep", "keep\n"),
+        (".cache/blob.txt", "cached\n"),
+    ] {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
+
+#[test]
+fn test_hidden_filter_keeps_named_directories_and_dotfiles() {
+    let filter = HiddenFilter {
+        files: true,
+        dirs: vec![".github/".into()],
+    };
+    assert!(filter.is_active());
+    assert!(filter.allows(".env".as_ref(), false));
+    assert!(filter.allows(".github".as_ref(), true));
+    assert!(!filter.allows(".cache".as_ref(), true));
+    assert!(filter.allows("src".as_ref(), true));
+
+    let filter = HiddenFilter::default();
+    assert!(!filter.is_active());
+    assert!(!filter.allows(".env".as_ref(), false));
+}
+
+#[test]
+fn test_hidden_files_without_hidden_directories() {
+    let dir = tempdir().unwrap();
+    write_tree(dir.path());
+
+    assert_eq!(
+        scanned(
+            dir.path(),
+            Code2PromptConfigBuilder::default().hidden_files(true)
+        ),
+        [".env.example", "main.rs"]
+    );
+}
+
+#[test]
+fn test_hidden_dirs_are_descended_into_by_name() {
+    let dir = tempdir().unwrap();
+    write_tree(dir.path());
+
+    assert_eq!(
+        scanned(
+            dir.path(),
+            Code2PromptConfigBuilder::default().hidden_dirs(vec![".github".into()])
+        ),
+        [".github/workflows/ci.yml", "main.rs"]
+    );
+    assert_eq!(
+        scanned(
+            dir.path(),
+            Code2PromptConfigBuilder::default()
+                .hidden_files(true)
+                .hidden_dirs(vec![".github".into()])
+        ),
+        [
+            ".env.example",
+            ".github/.keep",
+            ".github/workflows/ci.yml",
+            "main.rs"
+        ]
+    );
+}
+
+#[test]
+fn test_hidden_still_includes_everything() {
+    let dir = tempdir().unwrap();
+    write_tree(dir.path());
+
+    assert_eq!(
+        scanned(
+            dir.path(),
+            Code2PromptConfigBuilder::default()
+                .hidden(true)
+                .hidden_dirs(vec![".github".into()])
+        ),
+        [
+            ".cache/blob.txt",
+            ".env.example",
+            ".github/.keep",
+            ".github/workflows/ci.yml",
+            "main.rs"
+        ]
+    );
+    assert_eq!(
+        scanned(dir.path(), &mut Code2PromptConfigBuilder::default()),
+        ["main.rs"]
+    );
+}
@@ -0,0 +1,42 @@
+use code2prompt_tui::engine::{
+    history::{History, HistoryEntry},
+    token::TokenizerChoice,
+};
+use tempfile::tempdir;
+
+fn entry(prompt: &str) -> HistoryEntry {
+    HistoryEntry::new(
+        prompt.into(),
+        prompt.len(),
+        1,
+        TokenizerChoice::Cl100k,
+        "builtin".into(),
+    )
+}
+
+#[test]
+fn test_history_returns_most_recent_first() {
+    let dir = tempdir().unwrap();
+    let history = History::at(dir.path().join("history.jsonl"));
+    assert!(history.entries().unwrap().is_empty());
+
+    history.record(&entry("first")).unwrap();
+    history.record(&entry("second")).unwrap();
+
+    assert_eq!(history.get(1).unwrap().prompt, "second");
+    assert_eq!(history.get(2).unwrap().prompt, "first");
+    assert!(history.get(0).is_err());
+    assert!(history.get(3).is_err());
+}
+
+#[test]
+fn test_history_prunes_old_entries() {
+    let dir = tempdir().unwrap();
+    let history = History::at(dir.path().join("history.jsonl"));
+    for i in 0..History::MAX_ENTRIES + 5 {
+        history.record(&entry(&format!("prompt {i}"))).unwrap();
+    }
+    let entries = history.entries().unwrap();
+    assert_eq!(entries.len(), History::MAX_ENTRIES);
+    assert_eq!(entries.last().unwrap().prompt, format!("prompt {}", 5));
+}
@@ -0,0 +1,89 @@
+#![cfg(unix)]
+
+use code2prompt_tui::engine::{
+    config_file::ConfigFile,
+    hooks::{HookMetadata, Hooks},
+    token::TokenizerChoice,
+};
+use tempfile::tempdir;
+
+fn metadata(output_path: Option<String>) -> HookMetadata {
+    HookMetadata {
+        repo: "/tmp/repo".into(),
+        output_path,
+        files: 3,
+        token_count: 42,
+        tokenizer: TokenizerChoice::Cl100k,
+        template_hash: "builtin".into(),
+    }
+}
+
+#[test]
+fn test_hooks_config_syntax() {
+    let cfg: ConfigFile = toml::from_str(
+        r#"
+        [hooks]
+        pre_scan = "true"
+        post_render = "cat > /dev/null"
+        "#,
+    )
+    .unwrap();
+    let hooks = cfg.hooks.unwrap();
+    assert_eq!(hooks.pre_scan.as_deref(), Some("true"));
+    assert_eq!(hooks.post_render.as_deref(), Some("cat > /dev/null"));
+}
+
+#[test]
+fn test_post_render_receives_prompt_and_env() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("log");
+    let hooks = Hooks {
+        pre_scan: None,
+        post_render: Some(format!(
+            "{{ cat; echo; echo \"$CODE2PROMPT_OUTPUT\"; echo \"$CODE2PROMPT_METADATA\"; }} > '{}'",
+            log.display()
+        )),
+    };
+    hooks
+        .run_post_render("the prompt", &metadata(Some("out.md".into())))
+        .unwrap();
+
+    let written = std::fs::read_to_string(&log).unwrap();
+    let mut lines = written.lines();
+    assert_eq!(lines.next(), Some("the prompt"));
+    assert_eq!(lines.next(), Some("out.md"));
+    let meta: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(meta["token_count"], 42);
+    assert_eq!(meta["files"], 3);
+    assert_eq!(meta["repo"], "/tmp/repo");
+}
+
+#[test]
+fn test_pre_scan_receives_repo() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("log");
+    let hooks = Hooks {
+        pre_scan: Some(format!("echo \"$CODE2PROMPT_REPO\" > '{}'", log.display())),
+        post_render: None,
+    };
+    hooks.run_pre_scan(dir.path()).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&log).unwrap().trim(),
+        dir.path().to_str().unwrap()
+    );
+}
+
+#[test]
+fn test_failing_hook_is_an_error() {
+    let hooks = Hooks {
+        pre_scan: Some("exit 2".into()),
+        post_render: None,
+    };
+    assert!(hooks.run_pre_scan(std::path::Path::new(".")).is_err());
+    // Unset hooks are no-ops.
+    assert!(
+        Hooks::default()
+            .run_post_render("", &metadata(None))
+            .is_ok()
+    );
+}
@@ -0,0 +1,102 @@
+use code2prompt_tui::engine::issue::IssueRef;
+
+#[test]
+fn test_parse_github_issue_url() {
+    let issue = IssueRef::parse("https://github.com/owner/project/issues/42", None).unwrap();
+    assert_eq!(
+        issue,
+        IssueRef::GitHub {
+            host: "github.com".into(),
+            owner: "owner".into(),
+            repo: "project".into(),
+            number: 42,
+        }
+    );
+    assert_eq!(
+        issue.api_url(),
+        "https://api.github.com/repos/owner/project/issues/42"
+    );
+
+    let pull = IssueRef::parse("https://git.example.com/owner/project/pull/7/", None).unwrap();
+    assert_eq!(
+        pull.api_url(),
+        "https://git.example.com/api/v3/repos/owner/project/issues/7"
+    );
+}
+
+#[test]
+fn test_parse_gitlab_issue_url() {
+    let issue = IssueRef::parse("https://gitlab.com/group/sub/project/-/issues/3", None).unwrap();
+    assert_eq!(
+        issue.api_url(),
+        "https://gitlab.com/api/v4/projects/group%2Fsub%2Fproject/issues/3"
+    );
+}
+
+#[test]
+fn test_parse_issue_number_uses_remote() {
+    for remote in [
+        "https://github.com/owner/project.git",
+        "git@github.com:owner/project.git",
+        "ssh://git@github.com:22/owner/project",
+    ] {
+        let issue = IssueRef::parse("#12", Some(remote)).unwrap();
+        assert_eq!(
+            issue.api_url(),
+            "https://api.github.com/repos/owner/project/issues/12",
+            "{remote}"
+        );
+    }
+
+    let issue = IssueRef::parse("5", Some("git@gitlab.example.com:group/project.git")).unwrap();
+    assert_eq!(
+        issue.api_url(),
+        "https://gitlab.example.com/api/v4/projects/group%2Fproject/issues/5"
+    );
+}
+
+#[test]
+fn test_parse_invalid_issue() {
+    assert!(IssueRef::parse("12", None).is_err());
+    assert!(IssueRef::parse("not-an-issue", None).is_err());
+    assert!(IssueRef::parse("https://github.com/owner/project", None).is_err());
+    assert!(IssueRef::parse("https://github.com/owner/project/issues/abc", None).is_err());
+}
+
+#[test]
+fn test_tokens_are_only_sent_to_their_own_host() {
+    let env = |var: &str| match var {
+        "GITHUB_TOKEN" => Some("gh-secret".to_string()),
+        "GITLAB_TOKEN" => Some("gl-secret".to_string()),
+        "GH_ENTERPRISE_TOKEN" => Some("ghe-secret".to_string()),
+        "GH_HOST" => Some("git.corp.example".to_string()),
+        _ => None,
+    };
+    let header = |spec: &str, remote: &str| {
+        IssueRef::parse(spec, Some(remote))
+            .unwrap()
+            .auth_header_from(env)
+    };
+
+    assert_eq!(
+        header("1", "https://github.com/o/r.git"),
+        Some(("Authorization", "Bearer gh-secret".to_string()))
+    );
+    assert_eq!(
+        header("1", "https://gitlab.com/o/r.git"),
+        Some(("PRIVATE-TOKEN", "gl-secret".to_string()))
+    );
+    assert_eq!(
+        header("1", "https://git.corp.example/o/r.git"),
+        Some(("Authorization", "Bearer ghe-secret".to_string()))
+    );
+    assert_eq!(header("1", "https://attacker.example/o/r.git"), None);
+    assert_eq!(header("1", "git@gitlab.attacker.example:o/r.git"), None);
+    assert_eq!(
+        header(
+            "https://attacker.example/o/r/issues/1",
+            "https://github.com/o/r"
+        ),
+        None
+    );
+}
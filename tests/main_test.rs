@@ -5,20 +5,9 @@ use std::path::PathBuf;
 fn create_test_session() -> Code2PromptSession {
     let config = Code2PromptConfig {
         path: PathBuf::from("."),
-        include_patterns: vec![],
-        exclude_patterns: vec![],
-        include_priority: false,
-        line_numbers: false,
-        absolute_path: false,
-        full_directory_tree: false,
-        no_codeblock: false,
         tokenizer: TokenizerChoice::Cl100k,
-        token_map_enabled: false,
-        no_ignore: false,
-        hidden: false,
-        follow_symlinks: false,
-        sort: None,
-        cache: false,
+        scan_batch_size: 256,
+        ..Default::default()
     };
     let mut session = Code2PromptSession::new(config).unwrap();
     session.processed_entries = vec![
@@ -29,7 +18,7 @@ fn create_test_session() -> Code2PromptSession {
             code: Some("fn main {}".to_string()),
             extension: Some("rs".to_string()),
             token_count: Some(10),
-            mtime: None,
+            ..Default::default()
         },
         ProcessedEntry {
             path: PathBuf::from("./src/ui/tui.rs"),
@@ -38,7 +27,7 @@ fn create_test_session() -> Code2PromptSession {
             code: Some("...".to_string()),
             extension: Some("rs".to_string()),
             token_count: Some(20),
-            mtime: None,
+            ..Default::default()
         },
         ProcessedEntry {
             path: PathBuf::from("./docs/guide.md"),
@@ -47,7 +36,7 @@ fn create_test_session() -> Code2PromptSession {
             code: Some("...".to_string()),
             extension: Some("md".to_string()),
             token_count: Some(30),
-            mtime: None,
+            ..Default::default()
         },
         ProcessedEntry {
             path: PathBuf::from("./Cargo.toml"),
@@ -56,7 +45,7 @@ fn create_test_session() -> Code2PromptSession {
             code: Some("...".to_string()),
             extension: Some("toml".to_string()),
             token_count: Some(5),
-            mtime: None,
+            ..Default::default()
         },
     ];
     session
@@ -66,8 +55,13 @@ fn create_test_session() -> Code2PromptSession {
 fn test_filter_by_extension() {
     let mut session = create_test_session();
     let sel_exts = vec!["rs".to_string()];
-    let sel_paths: Vec<String> = vec![];
-    code2prompt_tui::ui::tui_select::filter_session_entries(&mut session, &sel_exts, &sel_paths);
+    let sel_paths: Vec<PathBuf> = vec![];
+    code2prompt_tui::app_controller::filter_session_entries(
+        &mut session,
+        &sel_exts,
+        &sel_paths,
+        &[],
+    );
     assert_eq!(session.processed_entries.len(), 2);
     assert!(
         session
@@ -81,8 +75,13 @@ fn test_filter_by_extension() {
 fn test_filter_by_path() {
     let mut session = create_test_session();
     let sel_exts: Vec<String> = vec![];
-    let sel_paths = vec!["src".to_string()];
-    code2prompt_tui::ui::tui_select::filter_session_entries(&mut session, &sel_exts, &sel_paths);
+    let sel_paths = vec![PathBuf::from("src")];
+    code2prompt_tui::app_controller::filter_session_entries(
+        &mut session,
+        &sel_exts,
+        &sel_paths,
+        &[],
+    );
     assert_eq!(session.processed_entries.len(), 2);
     assert!(
         session
@@ -96,8 +95,13 @@ fn test_filter_by_path() {
 fn test_filter_by_extension_and_path() {
     let mut session = create_test_session();
     let sel_exts = vec!["rs".to_string()];
-    let sel_paths = vec!["src/ui".to_string()];
-    code2prompt_tui::ui::tui_select::filter_session_entries(&mut session, &sel_exts, &sel_paths);
+    let sel_paths = vec![PathBuf::from("src/ui")];
+    code2prompt_tui::app_controller::filter_session_entries(
+        &mut session,
+        &sel_exts,
+        &sel_paths,
+        &[],
+    );
     assert_eq!(session.processed_entries.len(), 1);
     assert_eq!(
         session.processed_entries[0].relative_path,
@@ -109,7 +113,12 @@ fn test_filter_by_extension_and_path() {
 fn test_filter_with_no_matches() {
     let mut session = create_test_session();
     let sel_exts = vec!["java".to_string()];
-    let sel_paths: Vec<String> = vec![];
-    code2prompt_tui::ui::tui_select::filter_session_entries(&mut session, &sel_exts, &sel_paths);
+    let sel_paths: Vec<PathBuf> = vec![];
+    code2prompt_tui::app_controller::filter_session_entries(
+        &mut session,
+        &sel_exts,
+        &sel_paths,
+        &[],
+    );
     assert!(session.processed_entries.is_empty());
 }
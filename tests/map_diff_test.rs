@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use code2prompt_tui::engine::{
+    token::TokenizerChoice,
+    token_map::{DirChange, TokenMapExport, diff_by_dir, dir_at_depth},
+};
+use tempfile::tempdir;
+
+fn export(files: &[(&str, usize)]) -> TokenMapExport {
+    let files: BTreeMap<String, usize> = files.iter().map(|(p, t)| (p.to_string(), *t)).collect();
+    TokenMapExport {
+        tokenizer: TokenizerChoice::Cl100k,
+        total_tokens: files.values().sum(),
+        files,
+    }
+}
+
+#[test]
+fn test_dir_at_depth() {
+    assert_eq!(dir_at_depth("README.md", 1), ".");
+    assert_eq!(dir_at_depth("src/main.rs", 1), "src");
+    assert_eq!(dir_at_depth("src/ui/cli.rs", 1), "src");
+    assert_eq!(dir_at_depth("src/ui/cli.rs", 2), "src/ui");
+    assert_eq!(dir_at_depth("src/ui/cli.rs", 5), "src/ui");
+}
+
+#[test]
+fn test_diff_by_dir_lists_changed_directories_largest_first() {
+    let old = export(&[
+        ("README.md", 100),
+        ("src/main.rs", 500),
+        ("src/lib.rs", 200),
+        ("docs/guide.md", 300),
+        ("old/gone.rs", 50),
+    ]);
+    let new = export(&[
+        ("README.md", 100),
+        ("src/main.rs", 900),
+        ("src/lib.rs", 200),
+        ("docs/guide.md", 100),
+        ("tests/new.rs", 250),
+    ]);
+    let changes = diff_by_dir(&old, &new, 1);
+    let dir = |dir: &str, old, new| DirChange {
+        dir: dir.to_string(),
+        old,
+        new,
+    };
+    assert_eq!(
+        changes,
+        [
+            dir("src", 700, 1100),
+            dir("tests", 0, 250),
+            dir("docs", 300, 100),
+            dir("old", 50, 0),
+        ]
+    );
+    assert_eq!(changes[2].delta(), -200);
+}
+
+#[test]
+fn test_export_round_trips_through_json() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("tokens.json");
+    let map = export(&[("src/main.rs", 42)]);
+    fs::write(&path, serde_json::to_string_pretty(&map).unwrap()).unwrap();
+    assert_eq!(TokenMapExport::load(&path).unwrap(), map);
+
+    fs::write(&path, "{\"files\": {}}").unwrap();
+    let err = format!("{:#}", TokenMapExport::load(&path).unwrap_err());
+    assert!(err.contains("--token-map-json"), "{err}");
+}
@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+use code2prompt_tui::engine::model::ProcessedEntry;
+use code2prompt_tui::ui::output::write_concatenated;
+
+fn file(path: &str, code: Option<&str>) -> ProcessedEntry {
+    ProcessedEntry {
+        path: PathBuf::from("/repo").join(path),
+        relative_path: PathBuf::from(path),
+        is_file: true,
+        code: code.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_files_are_concatenated_under_path_headers() {
+    let entries = [
+        file("src/main.rs", Some("fn main() {}\n")),
+        file("skipped.rs", None),
+        file("notes.txt", Some("no trailing newline")),
+    ];
+    let mut out = Vec::new();
+    write_concatenated(&mut out, &entries, false).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "=== src/main.rs ===\nfn main() {}\n=== notes.txt ===\nno trailing newline\n"
+    );
+}
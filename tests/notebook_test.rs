@@ -0,0 +1,170 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    notebook::{NotebookOptions, clean_notebook, is_notebook},
+    session::Code2PromptSession,
+};
+use tempfile::tempdir;
+
+const NOTEBOOK: &str = r##"{
+  "cells": [
+    {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n", "Some prose."]},
+    {
+      "cell_type": "code",
+      "execution_count": 3,
+      "metadata": {},
+      "source": ["print('hi')\n", "1 + 1"],
+      "outputs": [
+        {"output_type": "stream", "name": "stdout", "text": ["hi\n"]},
+        {
+          "output_type": "execute_result",
+          "execution_count": 3,
+          "data": {"text/plain": ["2"], "image/png": "iVBORw0KGgoAAAANSUhEUg=="},
+          "metadata": {}
+        }
+      ]
+    }
+  ],
+  "metadata": {},
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"##;
+
+#[test]
+fn test_is_notebook() {
+    assert!(is_notebook(Some("ipynb")));
+    assert!(is_notebook(Some("IPYNB")));
+    assert!(!is_notebook(Some("py")));
+    assert!(!is_notebook(None));
+}
+
+#[test]
+fn test_clean_keeps_sources_and_outputs() {
+    let opts = NotebookOptions {
+        convert: true,
+        ..Default::default()
+    };
+    let out = clean_notebook(NOTEBOOK, &opts).unwrap();
+    assert!(out.contains("# %% [markdown]\n# Title\nSome prose."));
+    assert!(out.contains("# %% In[3]\nprint('hi')\n1 + 1"));
+    assert!(out.contains("# Out[3]:\nhi"));
+    assert!(out.contains("data:image/png;base64,iVBORw0KGgoAAAANSUhEUg=="));
+    assert!(!out.contains("nbformat"));
+}
+
+#[test]
+fn test_clean_drops_outputs_images_and_counts() {
+    let opts = NotebookOptions {
+        drop_images: true,
+        drop_execution_counts: true,
+        ..Default::default()
+    };
+    assert!(opts.is_enabled());
+    let out = clean_notebook(NOTEBOOK, &opts).unwrap();
+    assert!(out.contains("[image/png omitted]"));
+    assert!(!out.contains("iVBOR"));
+    assert!(!out.contains("In[3]"));
+    assert!(out.contains("# Out:\nhi"));
+
+    let opts = NotebookOptions {
+        drop_outputs: true,
+        ..Default::default()
+    };
+    let out = clean_notebook(NOTEBOOK, &opts).unwrap();
+    assert!(!out.contains("Out["));
+    assert!(!out.contains("image/png"));
+}
+
+#[test]
+fn test_invalid_notebook_returns_none() {
+    assert!(clean_notebook("not json", &NotebookOptions::default()).is_none());
+}
+
+#[test]
+fn test_only_converted_notebooks_are_fenced_as_python() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("good.ipynb"), NOTEBOOK).unwrap();
+    // Valid JSON, but not a notebook, so it cannot be converted.
+    fs::write(dir.path().join("bad.ipynb"), "{\"cells\": 1}").unwrap();
+
+    for convert in [false, true] {
+        let config = Code2PromptConfigBuilder::default()
+            .path(dir.path().to_path_buf())
+            .notebook(NotebookOptions {
+                convert,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config).unwrap();
+        session.process_codebase().unwrap();
+        let code = |name: &str| {
+            session
+                .processed_entries
+                .iter()
+                .find(|e| e.relative_path.to_str() == Some(name))
+                .and_then(|e| e.code.clone())
+                .unwrap()
+        };
+        let good = if convert { "```python\n" } else { "```json\n" };
+        assert!(
+            code("good.ipynb").starts_with(good),
+            "{}",
+            code("good.ipynb")
+        );
+        assert!(
+            code("bad.ipynb").starts_with("```json\n"),
+            "{}",
+            code("bad.ipynb")
+        );
+    }
+}
+
+#[test]
+fn test_size_limit_applies_to_the_cleaned_notebook() {
+    let notebook = |source: &str, image: &str| {
+        serde_json::json!({
+            "cells": [{
+                "cell_type": "code",
+                "execution_count": 1,
+                "metadata": {},
+                "source": [source],
+                "outputs": [{
+                    "output_type": "display_data",
+                    "data": { "image/png": image },
+                    "metadata": {}
+                }]
+            }],
+            "metadata": {},
+            "nbformat": 4,
+            "nbformat_minor": 5
+        })
+        .to_string()
+    };
+    let dir = tempdir().unwrap();
+    let big = "A".repeat(2 * 1024 * 1024);
+    fs::write(dir.path().join("plot.ipynb"), notebook("plot()", &big)).unwrap();
+    fs::write(dir.path().join("long.ipynb"), notebook(&big, "")).unwrap();
+
+    let files = |opts: NotebookOptions| {
+        let config = Code2PromptConfigBuilder::default()
+            .path(dir.path().to_path_buf())
+            .notebook(opts)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config).unwrap();
+        session.process_codebase().unwrap();
+        session
+            .processed_entries
+            .iter()
+            .map(|e| e.relative_path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+    };
+    assert!(files(NotebookOptions::default()).is_empty());
+    let cleaned = NotebookOptions {
+        drop_images: true,
+        ..Default::default()
+    };
+    assert_eq!(files(cleaned), ["plot.ipynb"]);
+}
@@ -0,0 +1,29 @@
+use code2prompt_tui::ui::output::{OutputPathVars, expand_output_path};
+
+fn vars() -> OutputPathVars {
+    OutputPathVars {
+        repo: "myrepo".into(),
+        tokens: 1234,
+        files: 7,
+        // 2024-02-29 13:05:09 UTC
+        timestamp: 1_709_211_909,
+    }
+}
+
+#[test]
+fn test_output_path_expands_placeholders() {
+    assert_eq!(
+        expand_output_path("prompts/{repo}-{date}-{tokens}tok.md", &vars()),
+        "prompts/myrepo-2024-02-29-1234tok.md"
+    );
+    assert_eq!(
+        expand_output_path("{time}_{files}_{timestamp}.txt", &vars()),
+        "130509_7_1709211909.txt"
+    );
+}
+
+#[test]
+fn test_output_path_keeps_plain_and_unknown_names() {
+    assert_eq!(expand_output_path("prompt.txt", &vars()), "prompt.txt");
+    assert_eq!(expand_output_path("{branch}.md", &vars()), "{branch}.md");
+}
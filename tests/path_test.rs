@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+
+use code2prompt_tui::common::path::{canonicalize, display, find_ancestor_with, simplify};
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use code2prompt_tui::ui::tree_view::build_tree_view;
+use tempfile::TempDir;
+
+#[test]
+fn test_simplify_strips_verbatim_prefixes() {
+    assert_eq!(
+        simplify(Path::new(r"\\?\C:\Users\dev\repo")),
+        PathBuf::from(r"C:\Users\dev\repo")
+    );
+    assert_eq!(simplify(Path::new(r"\\?\D:")), PathBuf::from(r"D:"));
+    assert_eq!(
+        simplify(Path::new(r"\\?\UNC\server\share\repo")),
+        PathBuf::from(r"\\server\share\repo")
+    );
+}
+
+#[test]
+fn test_simplify_keeps_paths_windows_would_alter() {
+    for path in [
+        r"\\?\C:\repo\trailing.",
+        r"\\?\C:\repo\trailing \file.rs",
+        r"\\?\GLOBALROOT\Device\HarddiskVolume1",
+        r"\\?\Volume{01234567-89ab-cdef-0123-456789abcdef}\repo",
+    ] {
+        assert_eq!(simplify(Path::new(path)), PathBuf::from(path), "{path}");
+    }
+}
+
+#[test]
+fn test_simplify_leaves_ordinary_paths_alone() {
+    for path in [
+        r"C:\repo",
+        r"\\server\share",
+        "/home/dev/repo",
+        "src/main.rs",
+    ] {
+        assert_eq!(simplify(Path::new(path)), PathBuf::from(path), "{path}");
+    }
+}
+
+#[test]
+fn test_canonicalize_has_no_verbatim_prefix() {
+    let dir = TempDir::new().unwrap();
+    let nested = dir.path().join("a");
+    std::fs::create_dir(&nested).unwrap();
+
+    let canonical = canonicalize(&nested.join("..").join("a")).unwrap();
+    assert!(!canonical.to_string_lossy().starts_with(r"\\?\"));
+    assert!(canonical.ends_with("a"));
+    assert_eq!(canonical, canonicalize(&nested).unwrap());
+}
+
+#[test]
+fn test_display_uses_forward_slashes_for_relative_paths() {
+    assert_eq!(display(Path::new("src/engine/mod.rs")), "src/engine/mod.rs");
+    assert_eq!(
+        display(Path::new(r"src\engine\mod.rs")),
+        "src/engine/mod.rs"
+    );
+    assert_eq!(display(&Path::new("src").join("main.rs")), "src/main.rs");
+}
+
+#[test]
+fn test_display_keeps_absolute_paths_native() {
+    let dir = TempDir::new().unwrap();
+    let abs = canonicalize(dir.path()).unwrap().join("a.rs");
+    assert_eq!(display(&abs), abs.to_string_lossy());
+}
+
+#[test]
+fn test_outputs_use_forward_slashes() {
+    let dir = TempDir::new().unwrap();
+    let nested = dir.path().join("src").join("engine");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(nested.join("mod.rs"), "pub mod a;\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let context = session.build_template_data(None, None, None).unwrap();
+    let paths: Vec<_> = context.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["src/engine/mod.rs"]);
+
+    let tree = build_tree_view(dir.path(), &session.processed_entries, false, false);
+    assert!(tree.contains("src/engine/mod.rs"), "{tree}");
+}
+
+#[test]
+fn test_nearest_marked_ancestor_is_found() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("repo");
+    let sub = root.join("src/ui");
+    std::fs::create_dir_all(&sub).unwrap();
+    std::fs::create_dir(root.join(".git")).unwrap();
+
+    assert_eq!(find_ancestor_with(&sub, &[".git"]), Some(root.clone()));
+    assert_eq!(find_ancestor_with(&root, &[".git"]), Some(root.clone()));
+
+    // The nearest marker wins.
+    std::fs::create_dir(root.join("src/.code2prompt")).unwrap();
+    assert_eq!(
+        find_ancestor_with(&sub, &[".git", ".code2prompt"]),
+        Some(root.join("src"))
+    );
+    assert_eq!(find_ancestor_with(&sub, &[".hg"]), None);
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    session::Code2PromptSession,
+    traverse::{Phase, PhaseTimings},
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_scan_records_phase_timings() {
+    let dir = tempdir().unwrap();
+    for i in 0..5 {
+        fs::write(
+            dir.path().join(format!("file{i}.rs")),
+            "fn main() { println!(\"hi\"); }\n".repeat(50),
+        )
+        .unwrap();
+    }
+
+    let timings = Arc::new(PhaseTimings::default());
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .token_map_enabled(true)
+        .timings(Some(timings.clone()))
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+
+    assert_eq!(session.processed_entries.len(), 5);
+    assert!(timings.get(Phase::Walk) > Duration::ZERO);
+    assert!(timings.get(Phase::Read) > Duration::ZERO);
+    assert!(timings.get(Phase::Tokenize) > Duration::ZERO);
+    // The cache is off, so it is never opened or queried.
+    assert_eq!(timings.get(Phase::Cache), Duration::ZERO);
+}
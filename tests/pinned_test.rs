@@ -0,0 +1,68 @@
+#![cfg(feature = "tui")]
+
+use std::path::PathBuf;
+
+use code2prompt_tui::app_controller::filter_session_entries;
+use code2prompt_tui::common::hash::HashMap;
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder, model::ProcessedEntry, session::Code2PromptSession,
+};
+use code2prompt_tui::ui::{cache::LastSelection, tree_arena::build_dir_arena, tree_pane::TreePane};
+
+fn entry(path: &str, ext: &str) -> ProcessedEntry {
+    ProcessedEntry {
+        path: PathBuf::from(path),
+        relative_path: PathBuf::from(path),
+        is_file: true,
+        extension: Some(ext.to_string()),
+        token_count: Some(1),
+        ..Default::default()
+    }
+}
+
+fn session() -> Code2PromptSession {
+    let config = Code2PromptConfigBuilder::default().build().unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.processed_entries = vec![
+        entry("src/main.rs", "rs"),
+        entry("docs/guide.md", "md"),
+        entry("README.md", "md"),
+    ];
+    session
+}
+
+fn kept(session: &Code2PromptSession) -> Vec<String> {
+    session
+        .processed_entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn test_pinned_files_survive_extension_deselection() {
+    let mut s = session();
+    let pinned = [PathBuf::from("README.md")];
+    filter_session_entries(&mut s, &["rs".to_string()], &[], &pinned);
+    assert_eq!(kept(&s), ["src/main.rs", "README.md"]);
+
+    // With nothing else selected, only the pins remain.
+    let mut s = session();
+    filter_session_entries(&mut s, &[], &[], &pinned);
+    assert_eq!(kept(&s), ["README.md"]);
+}
+
+#[test]
+fn test_pins_are_restored_from_last_selection() {
+    let entries = session().processed_entries;
+    let arena = build_dir_arena(&entries, &HashMap::default());
+    let selection = LastSelection {
+        pinned: vec!["docs/guide.md".to_string(), "docs".to_string()],
+        ..Default::default()
+    };
+    let pane = TreePane::new(arena, Some(&selection));
+    // Directories are never pinned.
+    assert_eq!(pane.get_pinned_paths(), [PathBuf::from("docs/guide.md")]);
+    assert!(pane.pinned_nodes.contains(&pane.pinned[0]));
+    assert_eq!(pane.pinned_nodes.len(), 3); // file, `docs` and the root
+}
@@ -0,0 +1,146 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    presets::{
+        CI_CONTEXT_GLOBS, CI_CONTEXT_HIDDEN_DIRS, Ecosystem, Preset, auto_excludes,
+        auto_fence_languages, detect_ecosystems, expand_presets, preset_globs,
+    },
+    traverse::{ProcessingMode, process_codebase},
+};
+use glob::Pattern;
+use tempfile::tempdir;
+
+#[test]
+fn test_detect_ecosystems_from_manifests() {
+    let dir = tempdir().unwrap();
+    assert!(detect_ecosystems(dir.path()).is_empty());
+    fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+    fs::write(dir.path().join("go.mod"), "module x\n").unwrap();
+    assert_eq!(
+        detect_ecosystems(dir.path()),
+        [Ecosystem::Rust, Ecosystem::Go]
+    );
+}
+
+#[test]
+fn test_preset_globs_follow_ecosystems() {
+    let go = preset_globs(Preset::NoTests, &[Ecosystem::Go]);
+    assert!(go.contains(&"**/*_test.go"));
+    assert!(go.contains(&"**/tests/**"));
+    assert!(!go.contains(&"**/*.spec.*"));
+
+    // Without a detected ecosystem every list applies.
+    let all = preset_globs(Preset::NoVendor, &[]);
+    assert!(all.contains(&"**/node_modules/**") && all.contains(&"**/.venv/**"));
+
+    let src = preset_globs(Preset::SrcOnly, &[Ecosystem::Rust]);
+    for glob in ["**/tests/**", "**/vendor/**", "**/Cargo.lock", "**/*.md"] {
+        assert!(src.contains(&glob), "{glob}");
+    }
+}
+
+#[test]
+fn test_src_only_preset_excludes_non_source_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    for (path, content) in [
+        ("package.json", "{}"),
+        ("src/app.js", "export {}"),
+        ("src/app.test.js", "test()"),
+        ("src/__tests__/util.js", "test()"),
+        ("node_modules/lib/index.js", "x"),
+        ("README.md", "# readme"),
+        ("yarn.lock", "lock"),
+    ] {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    let excludes: Vec<Pattern> = expand_presets(&[Preset::SrcOnly, Preset::NoTests], root)
+        .iter()
+        .map(|g| Pattern::new(g).unwrap())
+        .collect();
+    let config = Code2PromptConfigBuilder::default()
+        .path(root.to_path_buf())
+        .exclude_patterns(excludes)
+        .no_ignore(true)
+        .build()
+        .unwrap();
+    let (entries, ..) = process_codebase(&config, ProcessingMode::FullProcess).unwrap();
+    let mut files: Vec<_> = entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    files.sort();
+    assert_eq!(files, ["package.json", "src/app.js"]);
+}
+
+#[test]
+fn test_ci_context_includes_hidden_and_excluded_build_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    for (path, content) in [
+        ("Cargo.toml", "[package]\n"),
+        ("build.rs", "fn main() {}"),
+        ("src/main.rs", "fn main() {}"),
+        (".github/workflows/ci.yml", "on: push"),
+        (".github/CODEOWNERS", "* @team"),
+        (".gitlab-ci.yml", "stages: []"),
+        (".env", "SECRET=1"),
+        (".cache/Makefile", "all:"),
+        ("docker/Dockerfile", "FROM rust"),
+        ("Makefile", "all:"),
+    ] {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    assert!(preset_globs(Preset::CiContext, &[]).is_empty());
+
+    let strings = |globs: &[&str]| -> Vec<String> { globs.iter().map(|s| s.to_string()).collect() };
+    let patterns = |globs: Vec<String>| -> Vec<Pattern> {
+        globs.iter().map(|g| Pattern::new(g).unwrap()).collect()
+    };
+    // src-only excludes `.github/**`; ci-context's includes win.
+    let config = Code2PromptConfigBuilder::default()
+        .path(root.to_path_buf())
+        .include_patterns(patterns(strings(CI_CONTEXT_GLOBS)))
+        .exclude_patterns(patterns(expand_presets(&[Preset::SrcOnly], root)))
+        .include_priority(true)
+        .hidden_files(true)
+        .hidden_dirs(strings(CI_CONTEXT_HIDDEN_DIRS))
+        .build()
+        .unwrap();
+    let (entries, ..) = process_codebase(&config, ProcessingMode::FullProcess).unwrap();
+    let mut files: Vec<_> = entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            ".github/workflows/ci.yml",
+            ".gitlab-ci.yml",
+            "Makefile",
+            "build.rs",
+            "docker/Dockerfile"
+        ]
+    );
+}
+
+#[test]
+fn test_auto_detected_defaults() {
+    let rust_go = [Ecosystem::Rust, Ecosystem::Go];
+    let excludes = auto_excludes(&rust_go);
+    assert!(excludes.contains(&"**/target/**".to_string()));
+    assert!(excludes.contains(&"**/go.sum".to_string()));
+    assert!(!excludes.contains(&"**/node_modules/**".to_string()));
+    assert!(auto_excludes(&[]).is_empty());
+
+    let langs = auto_fence_languages(&[Ecosystem::JavaScript]);
+    assert!(langs.contains(&("mts".to_string(), "typescript".to_string())));
+    assert!(!langs.iter().any(|(ext, _)| ext == "pyi"));
+}
@@ -0,0 +1,26 @@
+use code2prompt_tui::ui::preview::markdown_to_html;
+
+#[test]
+fn test_markdown_to_html_escapes_code_and_headings() {
+    let md = "# Project <x>\n\n`src/main.rs`:\n\n```rust\nif a < b && c > d {}\n```";
+    let html = markdown_to_html(md);
+    assert!(html.contains("<h1>Project &lt;x&gt;</h1>"));
+    assert!(html.contains("<pre><code>if a &lt; b &amp;&amp; c &gt; d {}\n</code></pre>"));
+    assert!(html.contains("<p>`src/main.rs`:</p>"));
+}
+
+#[test]
+fn test_markdown_to_html_closes_unterminated_block() {
+    let html = markdown_to_html("```\nunterminated");
+    assert!(html.contains("<pre><code>unterminated\n</code></pre>"));
+}
+
+#[test]
+fn test_markdown_to_html_keeps_shorter_fences_inside_a_block() {
+    // A markdown file in the prompt, wrapped in a longer fence.
+    let md = "````md\n# Title\n```rust\nfn a() {}\n```\n~~~\n````\n# After";
+    let html = markdown_to_html(md);
+    assert!(html.contains(
+        "<pre><code># Title\n```rust\nfn a() {}\n```\n~~~\n</code></pre>\n<h1>After</h1>"
+    ));
+}
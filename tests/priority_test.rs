@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+
+use code2prompt_tui::engine::{
+    model::ProcessedEntry,
+    priority::{
+        FileOrder, PriorityRule, order_by_list, order_by_priority, order_docs_first,
+        orientation_rank, parse_priority, trim_to_budget, weight_of,
+    },
+    token::TokenizerChoice,
+};
+
+fn file(path: &str, tokens: usize) -> ProcessedEntry {
+    ProcessedEntry {
+        path: PathBuf::from(path),
+        relative_path: PathBuf::from(path),
+        is_file: true,
+        token_count: Some(tokens),
+        ..Default::default()
+    }
+}
+
+fn rules() -> Vec<PriorityRule> {
+    vec![
+        PriorityRule::new("src/core/**", 10).unwrap(),
+        PriorityRule::new("src/**", 5).unwrap(),
+        PriorityRule::new("tests/**", -1).unwrap(),
+    ]
+}
+
+fn paths(entries: &[ProcessedEntry]) -> Vec<&str> {
+    entries
+        .iter()
+        .map(|e| e.relative_path.to_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_parse_priority() {
+    assert_eq!(
+        parse_priority("src/core/**=10").unwrap(),
+        ("src/core/**".to_string(), 10)
+    );
+    assert_eq!(parse_priority("a=b=-2").unwrap(), ("a=b".to_string(), -2));
+    assert!(parse_priority("src/**").is_err());
+    assert!(parse_priority("src/**=high").is_err());
+}
+
+#[test]
+fn test_weight_takes_highest_matching_rule() {
+    let rules = rules();
+    assert_eq!(weight_of(&rules, "src/core/engine.rs"), 10);
+    assert_eq!(weight_of(&rules, "src/main.rs"), 5);
+    assert_eq!(weight_of(&rules, "tests/it.rs"), -1);
+    assert_eq!(weight_of(&rules, "README.md"), 0);
+}
+
+#[test]
+fn test_order_is_stable_within_a_weight() {
+    let mut entries = vec![
+        file("README.md", 1),
+        file("tests/it.rs", 1),
+        file("src/b.rs", 1),
+        file("src/core/x.rs", 1),
+        file("src/a.rs", 1),
+    ];
+    order_by_priority(&mut entries, &rules());
+    assert_eq!(
+        paths(&entries),
+        vec![
+            "src/core/x.rs",
+            "src/b.rs",
+            "src/a.rs",
+            "README.md",
+            "tests/it.rs"
+        ]
+    );
+}
+
+#[test]
+fn test_trim_drops_lowest_priority_first() {
+    let mut entries = vec![
+        file("src/core/x.rs", 50),
+        file("src/a.rs", 30),
+        file("README.md", 20),
+        file("tests/it.rs", 40),
+    ];
+    let dropped = trim_to_budget(&mut entries, &rules(), 90, TokenizerChoice::Cl100k);
+    assert_eq!(dropped, 2);
+    assert_eq!(paths(&entries), vec!["src/core/x.rs", "src/a.rs"]);
+
+    let dropped = trim_to_budget(&mut entries, &rules(), 1_000, TokenizerChoice::Cl100k);
+    assert_eq!(dropped, 0);
+    assert_eq!(entries.len(), 2);
+}
+
+#[test]
+fn test_parse_order_file() {
+    let order = FileOrder::parse("# Read in this order\n./src/lib.rs\n\n  docs\\intro.md  \n");
+    assert_eq!(order.0, ["src/lib.rs", "docs/intro.md"]);
+}
+
+#[test]
+fn test_listed_files_come_first_in_order() {
+    let mut entries = vec![
+        file("a.rs", 1),
+        file("src/b.rs", 1),
+        file("src/c.rs", 1),
+        file("d.rs", 1),
+    ];
+    let order = ["d.rs", "missing.rs", "src/b.rs"].map(String::from);
+    order_by_list(&mut entries, &order);
+    assert_eq!(paths(&entries), ["d.rs", "src/b.rs", "a.rs", "src/c.rs"]);
+}
+
+#[test]
+fn test_orientation_rank() {
+    assert_eq!(orientation_rank("README.md"), Some((0, 0)));
+    assert_eq!(orientation_rank("crates/api/readme.txt"), Some((0, 2)));
+    assert_eq!(orientation_rank("Cargo.toml"), Some((1, 0)));
+    assert_eq!(orientation_rank("web/package.json"), Some((1, 1)));
+    assert_eq!(orientation_rank("docs/overview.md"), Some((2, 1)));
+    assert_eq!(orientation_rank("docs/api.md"), None);
+    assert_eq!(orientation_rank("src/index.ts"), None);
+}
+
+#[test]
+fn test_docs_come_first_within_a_weight() {
+    let mut entries = vec![
+        file("src/core/a.rs", 1),
+        file("docs/index.md", 1),
+        file("build.rs", 1),
+        file("crates/x/README.md", 1),
+        file("Cargo.toml", 1),
+        file("README.md", 1),
+    ];
+    order_docs_first(&mut entries, &[]);
+    assert_eq!(
+        paths(&entries),
+        [
+            "README.md",
+            "crates/x/README.md",
+            "Cargo.toml",
+            "docs/index.md",
+            "src/core/a.rs",
+            "build.rs"
+        ]
+    );
+
+    // Explicit weights still come first.
+    order_docs_first(&mut entries, &rules());
+    assert_eq!(paths(&entries)[..2], ["src/core/a.rs", "README.md"]);
+}
@@ -0,0 +1,50 @@
+use std::fs;
+
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use code2prompt_tui::ui::template::{handlebars_setup, render_template};
+use tempfile::tempdir;
+
+fn render(no_tree: bool, tree_only: bool) -> String {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .absolute_path(false)
+        .no_tree(no_tree)
+        .tree_only(tree_only)
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let mut context = session.build_template_data(None, None, None).unwrap();
+    if !no_tree {
+        context.source_tree = "project\n└── main.rs".to_string();
+    }
+
+    let hb = handlebars_setup(include_str!("../default_template.hbs"), "default").unwrap();
+    render_template(&hb, "default", &serde_json::to_value(&context).unwrap()).unwrap()
+}
+
+#[test]
+fn test_full_prompt_has_tree_and_contents() {
+    let prompt = render(false, false);
+    assert!(prompt.contains("Source Tree:"), "{prompt}");
+    assert!(prompt.contains("fn main() {}"), "{prompt}");
+}
+
+#[test]
+fn test_no_tree_leaves_out_the_tree() {
+    let prompt = render(true, false);
+    assert!(!prompt.contains("Source Tree:"), "{prompt}");
+    assert!(prompt.contains("`main.rs`:"), "{prompt}");
+    assert!(prompt.contains("fn main() {}"), "{prompt}");
+}
+
+#[test]
+fn test_tree_only_leaves_out_the_contents() {
+    let prompt = render(false, true);
+    assert!(prompt.contains("└── main.rs"), "{prompt}");
+    assert!(!prompt.contains("fn main"), "{prompt}");
+    assert!(!prompt.contains("`main.rs`:"), "{prompt}");
+}
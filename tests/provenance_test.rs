@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::PathBuf;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    model::{ContentSource, ProcessedEntry},
+    session::Code2PromptSession,
+};
+use code2prompt_tui::ui::output::{provenance_json, write_provenance};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tempfile::tempdir;
+
+fn entry(path: &str, source: Option<ContentSource>, sha256: Option<[u8; 32]>) -> ProcessedEntry {
+    ProcessedEntry {
+        path: PathBuf::from(path),
+        relative_path: PathBuf::from(path),
+        is_file: true,
+        source,
+        sha256,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_files_read_from_disk_are_hashed_with_provenance() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+
+    for provenance in [false, true] {
+        let config = Code2PromptConfigBuilder::default()
+            .path(dir.path().to_path_buf())
+            .provenance(provenance)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config).unwrap();
+        session.process_codebase().unwrap();
+
+        let file = &session.processed_entries[0];
+        assert_eq!(file.source, Some(ContentSource::Disk));
+        let expected: [u8; 32] = Sha256::digest(b"fn a() {}\n").into();
+        assert_eq!(file.sha256, provenance.then_some(expected));
+    }
+}
+
+#[test]
+fn test_provenance_report_lists_every_file() {
+    let entries = [
+        entry("src/a.rs", Some(ContentSource::Cache), Some([0xab; 32])),
+        entry("src/b.rs", Some(ContentSource::Disk), None),
+        entry("src/c.rs", None, None),
+    ];
+    let mut out = Vec::new();
+    write_provenance(&mut out, &entries).unwrap();
+    let report = String::from_utf8(out).unwrap();
+
+    let lines: Vec<_> = report.lines().collect();
+    assert_eq!(lines[1], "[i] Provenance of 3 files:");
+    assert_eq!(
+        lines[2],
+        format!("    cache  {}  src/a.rs", "ab".repeat(32))
+    );
+    assert_eq!(lines[3], format!("    disk   {:<64}  src/b.rs", "-"));
+    assert_eq!(lines[4], format!("    -      {:<64}  src/c.rs", "-"));
+}
+
+#[test]
+fn test_provenance_json_has_a_record_per_file() {
+    let entries = [
+        entry("src/a.rs", Some(ContentSource::Cache), Some([0x01; 32])),
+        entry("src/b.rs", None, None),
+    ];
+    assert_eq!(
+        provenance_json(&entries),
+        json!([
+            { "path": "src/a.rs", "source": "cache", "sha256": "01".repeat(32) },
+            { "path": "src/b.rs", "source": null, "sha256": null },
+        ])
+    );
+}
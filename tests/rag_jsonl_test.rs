@@ -0,0 +1,41 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder, model::RagRecord, session::Code2PromptSession,
+};
+use sha2::{Digest, Sha256};
+use tempfile::tempdir;
+
+#[test]
+fn test_rag_records_hold_the_plain_file_contents() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.path().join("notes.md"), "# Notes\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .absolute_path(false)
+        .no_codeblock(true)
+        .no_priority_docs(true)
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    session
+        .processed_entries
+        .sort_by(|a, b| a.path.cmp(&b.path));
+    let records = session.rag_records().unwrap();
+
+    assert_eq!(records.len(), 2);
+    let main = &records[1];
+    assert_eq!(main.path, "src/main.rs");
+    assert_eq!(main.language, "rust");
+    assert_eq!(main.content, "fn main() {}\n");
+    assert!(main.tokens > 0);
+    assert_eq!(main.sha256, hex::encode(Sha256::digest(b"fn main() {}\n")));
+
+    let line = serde_json::to_string(main).unwrap();
+    assert!(!line.contains('\n'));
+    assert_eq!(serde_json::from_str::<RagRecord>(&line).unwrap(), *main);
+}
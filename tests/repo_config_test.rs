@@ -0,0 +1,49 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    config_file::RepoConfig,
+    traverse::{ProcessingMode, process_codebase},
+};
+use glob::Pattern;
+use tempfile::tempdir;
+
+#[test]
+fn test_add_exclude_escapes_and_dedups() {
+    let mut cfg = RepoConfig::default();
+    assert!(cfg.add_exclude("gen", true));
+    assert!(cfg.add_exclude("notes[old].txt", false));
+    assert!(!cfg.add_exclude("gen", true));
+    assert_eq!(cfg.exclude, ["gen/**", "notes[[]old[]].txt"]);
+}
+
+#[test]
+fn test_excluded_nodes_disappear_from_scans() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    for path in ["src/main.rs", "gen/api/client.rs", "notes[old].txt"] {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "x\n").unwrap();
+    }
+
+    let mut cfg = RepoConfig::default();
+    cfg.add_exclude("gen", true);
+    cfg.add_exclude("notes[old].txt", false);
+    let excludes: Vec<Pattern> = cfg
+        .exclude
+        .iter()
+        .map(|g| Pattern::new(g).unwrap())
+        .collect();
+    let config = Code2PromptConfigBuilder::default()
+        .path(root.to_path_buf())
+        .exclude_patterns(excludes)
+        .build()
+        .unwrap();
+    let (entries, ..) = process_codebase(&config, ProcessingMode::FullProcess).unwrap();
+    let files: Vec<_> = entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    assert_eq!(files, ["src/main.rs"]);
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use code2prompt_tui::engine::{
+    model::ProcessedEntry,
+    sample::{SampleStrategy, sample_files},
+    token::TokenizerChoice,
+};
+
+fn file(path: &str, tokens: usize) -> ProcessedEntry {
+    ProcessedEntry {
+        path: PathBuf::from(path),
+        relative_path: PathBuf::from(path),
+        is_file: true,
+        token_count: Some(tokens),
+        ..Default::default()
+    }
+}
+
+fn paths(entries: &[ProcessedEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn sample(entries: &mut Vec<ProcessedEntry>, max: usize, strategy: SampleStrategy) -> usize {
+    sample_files(entries, max, strategy, TokenizerChoice::Cl100k)
+}
+
+#[test]
+fn test_largest_and_smallest_first_keep_the_order() {
+    let files = vec![
+        file("a.rs", 10),
+        file("b.rs", 300),
+        file("c.rs", 20),
+        file("d.rs", 200),
+    ];
+
+    let mut largest = files.clone();
+    assert_eq!(sample(&mut largest, 2, SampleStrategy::LargestFirst), 2);
+    assert_eq!(paths(&largest), ["b.rs", "d.rs"]);
+
+    let mut smallest = files.clone();
+    assert_eq!(sample(&mut smallest, 3, SampleStrategy::SmallestFirst), 1);
+    assert_eq!(paths(&smallest), ["a.rs", "c.rs", "d.rs"]);
+}
+
+#[test]
+fn test_nothing_is_dropped_under_the_limit() {
+    let mut files = vec![file("a.rs", 1), file("b.rs", 2)];
+    assert_eq!(sample(&mut files, 2, SampleStrategy::Stratified), 0);
+    assert_eq!(files.len(), 2);
+}
+
+#[test]
+fn test_stratified_sampling_covers_every_directory() {
+    let mut files: Vec<ProcessedEntry> = (0..8)
+        .map(|i| file(&format!("src/m{i}.rs"), 1))
+        .chain((0..2).map(|i| file(&format!("tests/t{i}.rs"), 1)))
+        .chain([file("README.md", 1)])
+        .collect();
+    assert_eq!(sample(&mut files, 5, SampleStrategy::Stratified), 6);
+    // One each, then the two left go to src, which has the most files.
+    assert_eq!(
+        paths(&files),
+        [
+            "src/m0.rs",
+            "src/m2.rs",
+            "src/m5.rs",
+            "tests/t0.rs",
+            "README.md"
+        ]
+    );
+}
+
+#[test]
+fn test_stratified_sampling_prefers_large_directories_when_short() {
+    let mut files = vec![
+        file("a/1.rs", 1),
+        file("b/1.rs", 1),
+        file("b/2.rs", 1),
+        file("c/1.rs", 1),
+        file("c/2.rs", 1),
+        file("c/3.rs", 1),
+    ];
+    sample(&mut files, 2, SampleStrategy::Stratified);
+    assert_eq!(paths(&files), ["b/1.rs", "c/1.rs"]);
+}
@@ -0,0 +1,35 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder, file_id::file_ids, session::Code2PromptSession,
+};
+use code2prompt_tui::ui::output::scaffold;
+use tempfile::tempdir;
+
+#[test]
+fn test_scaffold_has_a_section_per_included_file() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    session.build_template_data(None, None, None).unwrap();
+
+    let skeleton = scaffold(&session.processed_entries).unwrap();
+    let ids = file_ids(&["src/lib.rs", "src/main.rs"]);
+    for (path, id) in ["src/lib.rs", "src/main.rs"].iter().zip(&ids) {
+        assert!(
+            skeleton.contains(&format!("## `{path}` ({id})")),
+            "{skeleton}"
+        );
+    }
+    assert_eq!(skeleton.matches("### Notes").count(), 2);
+    assert_eq!(skeleton.matches("### Changes").count(), 2);
+    assert!(!skeleton.contains("fn main"));
+}
@@ -0,0 +1,40 @@
+use std::fs;
+
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use tempfile::tempdir;
+
+fn scanned_paths(root: &std::path::Path, batch_size: usize) -> Vec<String> {
+    let config = Code2PromptConfigBuilder::default()
+        .path(root.to_path_buf())
+        .scan_batch_size(batch_size)
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let mut paths: Vec<String> = session
+        .processed_entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().into_owned())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_batch_size_does_not_change_the_scan() {
+    let dir = tempdir().unwrap();
+    for d in 0..10 {
+        let sub = dir.path().join(format!("dir{d}"));
+        fs::create_dir(&sub).unwrap();
+        for f in 0..30 {
+            fs::write(sub.join(format!("file{f}.txt")), format!("{d} {f}\n")).unwrap();
+        }
+    }
+
+    let one_by_one = scanned_paths(dir.path(), 1);
+    assert_eq!(one_by_one.len(), 300);
+    assert_eq!(one_by_one, scanned_paths(dir.path(), 7));
+    assert_eq!(one_by_one, scanned_paths(dir.path(), 10_000));
+    // Zero is treated as one rather than never flushing.
+    assert_eq!(one_by_one, scanned_paths(dir.path(), 0));
+}
@@ -0,0 +1,422 @@
+#![cfg(any(feature = "cache", feature = "tui"))]
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use code2prompt_tui::engine::{
+    cache::{CacheKey, CacheLimits, CacheWrite, CacheWriter, ScanCache, prune_caches_in},
+    token::TokenizerChoice,
+};
+use filetime::FileTime;
+use tempfile::tempdir;
+
+fn insert(cache: &ScanCache, path: &str, content: Option<&str>) {
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    cache
+        .insert(
+            path,
+            mtime,
+            10,
+            [0; 32],
+            TokenizerChoice::Cl100k,
+            1,
+            content,
+        )
+        .unwrap();
+}
+
+fn cached(cache: &ScanCache, path: &str) -> bool {
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    cache
+        .lookup(path, mtime, 10, TokenizerChoice::Cl100k)
+        .unwrap()
+        .is_some()
+}
+
+/// Writes a cache file of `bytes` bytes last written `age` seconds ago.
+fn fake_cache(path: &Path, bytes: usize, age: i64) {
+    fs::write(path, vec![0; bytes]).unwrap();
+    let when = FileTime::from_unix_time(FileTime::now().unix_seconds() - age, 0);
+    filetime::set_file_mtime(path, when).unwrap();
+}
+
+#[test]
+fn test_token_counts_are_kept_per_tokenizer() {
+    let dir = tempdir().unwrap();
+    let cache = ScanCache::open_at(&dir.path().join("cache.sqlite")).unwrap();
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+    cache
+        .insert(
+            "a.rs",
+            mtime,
+            10,
+            [0; 32],
+            TokenizerChoice::Cl100k,
+            7,
+            Some("fn a() {}"),
+        )
+        .unwrap();
+    cache
+        .insert(
+            "a.rs",
+            mtime,
+            10,
+            [0; 32],
+            TokenizerChoice::O200kBase,
+            5,
+            Some("fn a() {}"),
+        )
+        .unwrap();
+
+    let hit = |t| {
+        cache
+            .lookup("a.rs", mtime, 10, t)
+            .unwrap()
+            .map(|m| m.token_count)
+    };
+    assert_eq!(hit(TokenizerChoice::Cl100k), Some(7));
+    assert_eq!(hit(TokenizerChoice::O200kBase), Some(5));
+    assert_eq!(hit(TokenizerChoice::P50kBase), None);
+}
+
+#[test]
+fn test_changed_file_drops_counts_for_other_tokenizers() {
+    let dir = tempdir().unwrap();
+    let cache = ScanCache::open_at(&dir.path().join("cache.sqlite")).unwrap();
+    let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    let new = old + Duration::from_secs(60);
+
+    cache
+        .insert(
+            "a.rs",
+            old,
+            10,
+            [0; 32],
+            TokenizerChoice::Cl100k,
+            7,
+            Some("old"),
+        )
+        .unwrap();
+    cache
+        .insert(
+            "a.rs",
+            new,
+            12,
+            [1; 32],
+            TokenizerChoice::O200kBase,
+            9,
+            Some("new!"),
+        )
+        .unwrap();
+
+    assert!(
+        cache
+            .lookup("a.rs", old, 10, TokenizerChoice::Cl100k)
+            .unwrap()
+            .is_none()
+    );
+    let contents = cache.get_cached_contents(&["a.rs"]).unwrap();
+    assert_eq!(contents.get("a.rs").map(String::as_str), Some("new!"));
+}
+
+#[test]
+fn test_prune_evicts_the_least_recently_used_rows() {
+    let dir = tempdir().unwrap();
+    let cache = ScanCache::open_at(&dir.path().join("cache.sqlite")).unwrap();
+    let content = "x".repeat(4_000);
+    for path in ["a.rs", "b.rs", "c.rs"] {
+        insert(&cache, path, Some(&content));
+    }
+    let size = cache.size().unwrap();
+
+    assert_eq!(cache.prune(size).unwrap(), 0);
+    assert_eq!(cache.prune(size / 2).unwrap(), 2);
+    assert!(cache.size().unwrap() <= size / 2);
+    assert!(!cached(&cache, "a.rs"));
+    assert!(!cached(&cache, "b.rs"));
+    assert!(cached(&cache, "c.rs"));
+}
+
+#[test]
+fn test_rows_without_content_keep_their_counts() {
+    let dir = tempdir().unwrap();
+    let cache = ScanCache::open_at(&dir.path().join("cache.sqlite")).unwrap();
+    insert(&cache, "a.rs", None);
+
+    assert!(cached(&cache, "a.rs"));
+    assert!(cache.get_cached_contents(&["a.rs"]).unwrap().is_empty());
+}
+
+fn user_version(db: &Path) -> i64 {
+    rusqlite::Connection::open(db)
+        .unwrap()
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn test_new_caches_are_at_the_current_schema_version() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("cache.sqlite");
+    drop(ScanCache::open_at(&db).unwrap());
+    assert_eq!(user_version(&db), 3);
+}
+
+#[test]
+fn test_caches_from_before_eviction_are_migrated() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("cache.sqlite");
+    rusqlite::Connection::open(&db)
+        .unwrap()
+        .execute_batch(
+            "CREATE TABLE file_cache (
+                 path TEXT NOT NULL, tokenizer TEXT NOT NULL, mtime_nanos INTEGER NOT NULL,
+                 size_bytes INTEGER NOT NULL, sha256 BLOB NOT NULL, token_count INTEGER NOT NULL,
+                 content BLOB, cache_version INTEGER NOT NULL, PRIMARY KEY (path, tokenizer));
+             INSERT INTO file_cache VALUES
+                 ('a.rs', 'cl100k', 1000000000000, 10, zeroblob(32), 1, NULL, 2);",
+        )
+        .unwrap();
+
+    let cache = ScanCache::open_at(&db).unwrap();
+    assert_eq!(user_version(&db), 3);
+    // The row is kept, but its count is not known to be of the content as
+    // prepared now.
+    assert!(!cached(&cache, "a.rs"));
+    insert(&cache, "b.rs", Some("fn b() {}"));
+    assert_eq!(cache.prune(0).unwrap(), 2);
+}
+
+#[test]
+fn test_caches_that_cannot_be_migrated_are_rebuilt() {
+    for setup in [
+        // Token counts not keyed by tokenizer.
+        "CREATE TABLE file_cache (path TEXT PRIMARY KEY, token_count INTEGER NOT NULL);
+         INSERT INTO file_cache VALUES ('a.rs', 1);",
+        // Written by a newer version.
+        "CREATE TABLE file_cache (path TEXT PRIMARY KEY, token_count INTEGER NOT NULL);
+         INSERT INTO file_cache VALUES ('a.rs', 1);
+         PRAGMA user_version = 99;",
+    ] {
+        let dir = tempdir().unwrap();
+        let db = dir.path().join("cache.sqlite");
+        rusqlite::Connection::open(&db)
+            .unwrap()
+            .execute_batch(setup)
+            .unwrap();
+
+        let cache = ScanCache::open_at(&db).unwrap();
+        assert_eq!(user_version(&db), 3);
+        assert_eq!(cache.size().unwrap(), 0);
+        insert(&cache, "a.rs", Some("fn a() {}"));
+        assert!(cached(&cache, "a.rs"));
+    }
+}
+
+#[test]
+fn test_rows_of_other_cache_versions_are_not_read() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("cache.sqlite");
+    let cache = ScanCache::open_at(&db).unwrap();
+    insert(&cache, "a.rs", Some("fn a() {}"));
+    rusqlite::Connection::open(&db)
+        .unwrap()
+        .execute("UPDATE file_cache SET cache_version = 1", [])
+        .unwrap();
+
+    assert!(!cached(&cache, "a.rs"));
+    assert!(cache.get_cached_contents(&["a.rs"]).unwrap().is_empty());
+}
+
+#[test]
+fn test_counts_of_other_prepare_options_are_not_read() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("cache.sqlite");
+    insert(
+        &ScanCache::open_at(&db).unwrap().with_prepare_hash(1),
+        "a.rs",
+        Some("fn a() {}"),
+    );
+
+    let cache = ScanCache::open_at(&db).unwrap().with_prepare_hash(2);
+    assert!(!cached(&cache, "a.rs"));
+    // Contents are cached as read, so they are still shared.
+    assert_eq!(cache.get_cached_contents(&["a.rs"]).unwrap().len(), 1);
+    assert!(cached(&cache.with_prepare_hash(1), "a.rs"));
+}
+
+#[test]
+fn test_total_limit_removes_other_repositories_oldest_first() {
+    let dir = tempdir().unwrap();
+    let own = dir.path().join("scan_cache_own.sqlite");
+    let cache = ScanCache::open_at(&own).unwrap();
+    insert(&cache, "a.rs", Some("fn a() {}"));
+    drop(cache);
+    let oldest = dir.path().join("scan_cache_old.sqlite");
+    let newer = dir.path().join("scan_cache_new.sqlite");
+    fake_cache(&oldest, 50_000, 3_600);
+    fake_cache(&newer, 50_000, 60);
+    fake_cache(&dir.path().join("selection_old.json"), 50_000, 7_200);
+
+    let limits = CacheLimits {
+        repo_bytes: None,
+        total_bytes: Some(60_000),
+    };
+    prune_caches_in(dir.path(), &own, limits).unwrap();
+
+    assert!(!oldest.exists());
+    assert!(newer.exists());
+    assert!(dir.path().join("selection_old.json").exists());
+    assert!(cached(&ScanCache::open_at(&own).unwrap(), "a.rs"));
+}
+
+#[test]
+fn test_own_cache_is_kept_within_the_repository_limit() {
+    let dir = tempdir().unwrap();
+    let own = dir.path().join("scan_cache_own.sqlite");
+    let cache = ScanCache::open_at(&own).unwrap();
+    insert(&cache, "a.rs", Some("fn a() {}"));
+    insert(&cache, "b.rs", Some("fn b() {}"));
+    let size = cache.size().unwrap();
+    drop(cache);
+
+    let limits = CacheLimits {
+        repo_bytes: Some(size - 1),
+        total_bytes: None,
+    };
+    prune_caches_in(dir.path(), &own, limits).unwrap();
+
+    let cache = ScanCache::open_at(&own).unwrap();
+    assert!(!cached(&cache, "a.rs"));
+    assert!(cached(&cache, "b.rs"));
+}
+
+#[test]
+fn test_cache_keys_are_64_hex_digits() {
+    assert!(CacheKey::from_hex(&"ab".repeat(32)).is_ok());
+    assert!(CacheKey::from_hex(&"ab".repeat(16)).is_err());
+    assert!(CacheKey::from_hex(&"zz".repeat(32)).is_err());
+}
+
+#[test]
+fn test_encrypted_contents_need_the_key() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("cache.sqlite");
+    let key = CacheKey::from_hex(&"01".repeat(32)).unwrap();
+    let cache = ScanCache::open_at(&db).unwrap().with_key(Some(key.clone()));
+    insert(&cache, "a.rs", Some("fn secret() {}"));
+
+    let contents = cache.get_cached_contents(&["a.rs"]).unwrap();
+    assert_eq!(contents["a.rs"], "fn secret() {}");
+    drop(cache);
+
+    let stored: Vec<u8> = rusqlite::Connection::open(&db)
+        .unwrap()
+        .query_row("SELECT content FROM file_cache", [], |row| row.get(0))
+        .unwrap();
+    assert!(stored.starts_with(b"C2PE"));
+
+    let other = CacheKey::from_hex(&"02".repeat(32)).unwrap();
+    for cache in [
+        ScanCache::open_at(&db).unwrap(),
+        ScanCache::open_at(&db).unwrap().with_key(Some(other)),
+    ] {
+        assert!(cached(&cache, "a.rs"));
+        assert!(cache.get_cached_contents(&["a.rs"]).unwrap().is_empty());
+    }
+}
+
+#[test]
+fn test_plaintext_contents_are_dropped_once_a_key_is_set() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("cache.sqlite");
+    let plain = ScanCache::open_at(&db).unwrap();
+    insert(&plain, "a.rs", Some("fn a() {}"));
+    drop(plain);
+
+    let key = CacheKey::from_hex(&"01".repeat(32)).unwrap();
+    let cache = ScanCache::open_at(&db).unwrap().with_key(Some(key));
+    insert(&cache, "b.rs", Some("fn b() {}"));
+    assert_eq!(cache.drop_plaintext_contents().unwrap(), 1);
+
+    assert!(cached(&cache, "a.rs"));
+    let contents = cache.get_cached_contents(&["a.rs", "b.rs"]).unwrap();
+    assert_eq!(contents.len(), 1);
+    assert_eq!(contents["b.rs"], "fn b() {}");
+}
+
+#[test]
+fn test_writer_applies_the_writes_of_every_thread() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("cache.sqlite");
+    let writer = CacheWriter::spawn(ScanCache::open_at(&db).unwrap());
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+    std::thread::scope(|s| {
+        for t in 0..4 {
+            let writes = writer.sender();
+            let db = &db;
+            s.spawn(move || {
+                let cache = ScanCache::open_at(db).unwrap();
+                for i in 0..250 {
+                    let path = format!("{t}/{i}.rs");
+                    let row = cache
+                        .row(
+                            &path,
+                            mtime,
+                            10,
+                            [0; 32],
+                            TokenizerChoice::Cl100k,
+                            i,
+                            Some("fn f() {}"),
+                        )
+                        .unwrap();
+                    writes.send(CacheWrite::Insert(row)).unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(writer.finish().unwrap(), 0);
+
+    let cache = ScanCache::open_at(&db).unwrap();
+    for t in 0..4 {
+        for i in [0, 249] {
+            let hit = cache
+                .lookup(&format!("{t}/{i}.rs"), mtime, 10, TokenizerChoice::Cl100k)
+                .unwrap()
+                .unwrap();
+            assert_eq!(hit.token_count, i);
+            assert!(!hit.stale);
+        }
+    }
+}
+
+#[test]
+fn test_touching_a_stale_row_refreshes_it() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("cache.sqlite");
+    let cache = ScanCache::open_at(&db).unwrap();
+    insert(&cache, "a.rs", None);
+    rusqlite::Connection::open(&db)
+        .unwrap()
+        .execute("UPDATE file_cache SET last_accessed = 0", [])
+        .unwrap();
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    let lookup = || {
+        cache
+            .lookup("a.rs", mtime, 10, TokenizerChoice::Cl100k)
+            .unwrap()
+            .unwrap()
+    };
+    assert!(lookup().stale);
+
+    cache
+        .apply(&[CacheWrite::Touch {
+            rel_path: "a.rs".to_string(),
+            tokenizer: TokenizerChoice::Cl100k,
+        }])
+        .unwrap();
+    assert!(!lookup().stale);
+}
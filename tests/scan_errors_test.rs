@@ -0,0 +1,43 @@
+#![cfg(unix)]
+
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder, model::ScanErrorKind, session::Code2PromptSession,
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_broken_symlinks_are_reported() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("missing.rs"), dir.path().join("link.rs")).unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .follow_symlinks(true)
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+
+    assert_eq!(session.processed_entries.len(), 1);
+    assert_eq!(session.scan_errors.len(), 1, "{:?}", session.scan_errors);
+    let error = &session.scan_errors[0];
+    assert_eq!(error.path.as_deref(), Some("link.rs"));
+    assert_eq!(error.kind, ScanErrorKind::BrokenSymlink);
+}
+
+#[test]
+fn test_a_clean_scan_has_no_errors() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    assert!(session.scan_errors.is_empty());
+}
@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::PathBuf;
+
+use code2prompt_tui::app_controller::{filter_session_entries, run_summary};
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use glob::Pattern;
+use tempfile::tempdir;
+
+#[test]
+fn test_summary_counts_scanned_included_and_skipped_files() {
+    let dir = tempdir().unwrap();
+    for (path, text) in [
+        ("src/main.rs", "fn main() {}\n".to_string()),
+        ("src/lib.rs", "pub fn lib() {}\n".to_string()),
+        ("notes.txt", "notes\n".to_string()),
+        ("empty.txt", String::new()),
+        ("big.txt", "x".repeat(2 << 20)),
+    ] {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .exclude_patterns(vec![Pattern::new("notes.txt").unwrap()])
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    filter_session_entries(&mut session, &[], &[PathBuf::from("src/main.rs")], &[]);
+
+    let summary = run_summary(&session, 42, None);
+    assert_eq!(summary.files_scanned, 5);
+    assert_eq!(summary.files_included, 1);
+    assert_eq!(summary.skipped.filtered, 1);
+    assert_eq!(summary.skipped.empty, 1);
+    assert_eq!(summary.skipped.too_large, 1);
+    assert_eq!(summary.skipped.not_selected, 1);
+    assert_eq!(summary.skipped.total(), 4);
+    assert_eq!(
+        summary.bytes_read,
+        ("fn main() {}\n".len() + "pub fn lib() {}\n".len()) as u64
+    );
+    assert_eq!(summary.cache_hit_rate(), None);
+    assert_eq!(summary.tokens, 42);
+
+    let json = serde_json::to_value(&summary).unwrap();
+    assert_eq!(json["skipped"]["too_large"], 1);
+}
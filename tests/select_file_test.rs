@@ -0,0 +1,62 @@
+use std::fs;
+use std::path::PathBuf;
+
+use code2prompt_tui::app_controller::filter_session_entries;
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use code2prompt_tui::ui::cache::LastSelection;
+use tempfile::tempdir;
+
+#[test]
+fn test_selection_file_filters_like_the_tui() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    for path in ["src/main.rs", "src/lib.rs", "docs/guide.md", "README.md"] {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "content\n").unwrap();
+    }
+    let file = root.join("selection.json");
+    fs::write(
+        &file,
+        r#"{"extensions": ["rs"], "directories": ["src"], "pinned": ["README.md"]}"#,
+    )
+    .unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(root.to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+
+    let selection = LastSelection::from_file(&file).unwrap();
+    let to_paths = |v: &[String]| v.iter().map(PathBuf::from).collect::<Vec<_>>();
+    filter_session_entries(
+        &mut session,
+        &selection.extensions,
+        &to_paths(&selection.directories),
+        &to_paths(&selection.pinned),
+    );
+
+    let mut files: Vec<_> = session
+        .processed_entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    files.sort();
+    assert_eq!(files, ["README.md", "src/lib.rs", "src/main.rs"]);
+}
+
+#[test]
+fn test_pinned_is_optional_in_selection_files() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("selection.json");
+    fs::write(&file, r#"{"extensions": ["py"], "directories": []}"#).unwrap();
+    let selection = LastSelection::from_file(&file).unwrap();
+    assert_eq!(selection.extensions, ["py"]);
+    assert!(selection.pinned.is_empty());
+
+    fs::write(&file, "not json").unwrap();
+    let err = LastSelection::from_file(&file).unwrap_err();
+    assert!(err.to_string().starts_with("Invalid selection file"));
+}
@@ -0,0 +1,159 @@
+#![cfg(feature = "serve")]
+
+use std::fs;
+
+use code2prompt_tui::{
+    engine::config_file::ConfigFile,
+    ui::serve::{RenderRequest, RenderService, is_local_host},
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_render_request_defaults() {
+    let req: RenderRequest =
+        serde_json::from_str(r#"{ "path": "/repo", "include": ["*.rs"] }"#).unwrap();
+    assert_eq!(req.include, vec!["*.rs".to_string()]);
+    assert!(req.exclude.is_empty());
+    assert!(req.template.is_none());
+}
+
+#[test]
+fn test_render_and_rescan() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "ignored\n").unwrap();
+    let tpl = dir.path().join("tpl.hbs");
+    fs::write(
+        &tpl,
+        "{{greeting}}\n{{#each files}}{{path}}\n{{code}}{{/each}}",
+    )
+    .unwrap();
+
+    let mut req: RenderRequest = serde_json::from_value(serde_json::json!({
+        "path": dir.path(),
+        "include": ["*.rs"],
+        "template": tpl,
+    }))
+    .unwrap();
+    req.vars.insert("greeting".into(), "hello".into());
+
+    let mut service = RenderService::new(ConfigFile::default());
+    let resp = service.render(&req).unwrap();
+    assert_eq!(resp.files, vec!["a.rs".to_string()]);
+    assert!(resp.prompt.starts_with("hello\n"));
+    assert!(resp.prompt.contains("fn a() {}"));
+    assert!(resp.token_count > 0);
+
+    // The reused session picks up new files.
+    fs::write(dir.path().join("c.rs"), "fn c() {}\n").unwrap();
+    let resp = service.render(&req).unwrap();
+    assert_eq!(resp.files.len(), 2);
+    assert!(resp.prompt.contains("fn c() {}"));
+}
+
+#[test]
+fn test_render_reports_bad_globs() {
+    let req = RenderRequest {
+        path: ".".into(),
+        include: vec!["[".into()],
+        ..Default::default()
+    };
+    let err = RenderService::new(ConfigFile::default())
+        .render(&req)
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("Invalid glob pattern"));
+}
+
+#[test]
+fn test_only_local_host_names_are_served() {
+    for host in [
+        "localhost",
+        "localhost:8080",
+        "127.0.0.1:8080",
+        "[::1]:8080",
+        "LOCALHOST",
+    ] {
+        assert!(is_local_host(host), "{host}");
+    }
+    for host in [
+        "evil.example",
+        "evil.example:8080",
+        "127.0.0.1.evil.example",
+        "[::2]",
+        "",
+    ] {
+        assert!(!is_local_host(host), "{host}");
+    }
+}
+
+#[test]
+fn test_render_refuses_paths_outside_the_roots() {
+    let root = tempdir().unwrap();
+    let repo = root.path().join("repo");
+    fs::create_dir(&repo).unwrap();
+    fs::write(repo.join("a.rs"), "fn a() {}\n").unwrap();
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("tpl.hbs"), "secret").unwrap();
+
+    let mut service = RenderService::new(ConfigFile::default())
+        .with_roots(&[root.path().to_path_buf()])
+        .unwrap();
+    let mut req = RenderRequest {
+        path: repo.join("..").join("repo"),
+        ..Default::default()
+    };
+    assert_eq!(
+        service.render(&req).unwrap().files,
+        vec!["a.rs".to_string()]
+    );
+
+    req.template = Some(outside.path().join("tpl.hbs"));
+    let err = service.render(&req).unwrap_err();
+    assert!(err.to_string().contains("outside the served directories"));
+
+    req.template = None;
+    req.path = repo.join("../..");
+    let err = service.render(&req).unwrap_err();
+    assert!(err.to_string().contains("outside the served directories"));
+}
+
+#[test]
+fn test_least_recently_used_sessions_are_closed() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+    let mut service = RenderService::new(ConfigFile::default()).with_max_sessions(2);
+    let req = |include: &str| RenderRequest {
+        path: dir.path().to_path_buf(),
+        include: vec![include.to_string()],
+        ..Default::default()
+    };
+
+    service.render(&req("*.rs")).unwrap();
+    service.render(&req("*.txt")).unwrap();
+    service.render(&req("*.rs")).unwrap();
+    service.render(&req("*.md")).unwrap();
+    assert_eq!(service.session_count(), 2);
+}
+
+#[test]
+fn test_render_applies_the_project_config() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(dir.path().join("b.rs"), "fn b() {}\n").unwrap();
+    fs::create_dir(dir.path().join(".code2prompt")).unwrap();
+    fs::write(
+        dir.path().join(".code2prompt/config.toml"),
+        "exclude = [\"b.rs\"]\n",
+    )
+    .unwrap();
+
+    let req = RenderRequest {
+        path: dir.path().to_path_buf(),
+        include: vec!["*.rs".into()],
+        ..Default::default()
+    };
+    let resp = RenderService::new(ConfigFile::default())
+        .render(&req)
+        .unwrap();
+    assert_eq!(resp.files, vec!["a.rs".to_string()]);
+}
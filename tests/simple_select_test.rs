@@ -0,0 +1,21 @@
+#![cfg(feature = "tui")]
+
+use code2prompt_tui::ui::simple_select::parse_numbers;
+
+#[test]
+fn test_parse_numbers_accepts_lists_and_ranges() {
+    assert_eq!(parse_numbers("", 5).unwrap(), Vec::<usize>::new());
+    assert_eq!(parse_numbers("3", 5).unwrap(), [2]);
+    assert_eq!(parse_numbers("1, 3-5", 5).unwrap(), [0, 2, 3, 4]);
+    assert_eq!(parse_numbers("4 2 2", 5).unwrap(), [1, 3]);
+    assert_eq!(parse_numbers("5-3,1", 5).unwrap(), [0, 2, 3, 4]);
+}
+
+#[test]
+fn test_parse_numbers_rejects_bad_input() {
+    assert!(parse_numbers("0", 5).is_err());
+    assert!(parse_numbers("6", 5).is_err());
+    assert!(parse_numbers("2-9", 5).is_err());
+    assert!(parse_numbers("rs", 5).is_err());
+    assert!(parse_numbers("1-", 5).is_err());
+}
@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::PathBuf;
+
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use tempfile::tempdir;
+
+#[test]
+fn test_include_files_adds_hidden_files() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.path().join(".env"), "KEY=1\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    assert_eq!(session.processed_entries.len(), 1);
+
+    session.include_files(&[PathBuf::from(".env")]).unwrap();
+    let files: Vec<_> = session
+        .processed_entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(files, ["main.rs", ".env"]);
+    assert!(session.processed_entries[1].code.is_some());
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn test_skipped_files_follow_the_settings() {
+    use code2prompt_tui::common::hash::HashMap;
+    use code2prompt_tui::engine::model::ProcessedEntry;
+    use code2prompt_tui::ui::{
+        tree_arena::{DirFlags, build_dir_arena, mark_skipped},
+        tree_pane::TreePane,
+    };
+
+    let entry = |path: &str| ProcessedEntry {
+        path: PathBuf::from(path),
+        relative_path: PathBuf::from(path),
+        is_file: true,
+        ..Default::default()
+    };
+    let entries = [
+        entry("src/main.rs"),
+        entry(".github/ci.yml"),
+        entry("dist/app.js"),
+    ];
+    let mut arena = build_dir_arena(&entries, &HashMap::default());
+    let skipped: HashMap<String, DirFlags> = [
+        (".github/ci.yml".to_string(), DirFlags::HIDDEN),
+        ("dist/app.js".to_string(), DirFlags::IGNORED),
+    ]
+    .into_iter()
+    .collect();
+    mark_skipped(&mut arena, &skipped);
+    let dist = arena.iter().position(|n| n.name == "dist").unwrap() as u32;
+    assert!(arena[dist as usize].flags.contains(DirFlags::IGNORED));
+
+    let mut pane = TreePane::new(arena, None);
+    pane.apply_exclusions(false, false);
+    assert!(pane.is_excluded(dist));
+    assert!(pane.get_skipped_paths().is_empty());
+
+    pane.apply_exclusions(true, false);
+    assert_eq!(pane.get_skipped_paths(), [PathBuf::from(".github/ci.yml")]);
+    assert!(pane.is_excluded(dist));
+}
@@ -0,0 +1,59 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    encoding::{ENDS_THRESHOLD, read_source, read_source_ends},
+    token::{TokenizerChoice, estimated_end_bytes, truncate_ends, truncate_middle},
+};
+use tempfile::tempdir;
+
+/// Lines of multi-byte text, so that chunk edges fall inside characters.
+fn large_text() -> String {
+    let mut text = String::new();
+    let mut i = 0;
+    while (text.len() as u64) < 2 * ENDS_THRESHOLD {
+        text.push_str(&format!("ligne {i:05}: données ✓ 🚀\n"));
+        i += 1;
+    }
+    text
+}
+
+#[test]
+fn test_large_read_matches_the_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("big.txt");
+    let text = large_text();
+    fs::write(&path, &text).unwrap();
+
+    let src = read_source(&path).unwrap();
+    assert_eq!(src.text, text);
+    assert!(src.transcoded_from.is_none());
+}
+
+#[test]
+fn test_ends_of_small_files_are_not_read() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("small.txt");
+    fs::write(&path, "fn main() {}\n").unwrap();
+    assert!(read_source_ends(&path, 4, 4).unwrap().is_none());
+}
+
+#[test]
+fn test_truncating_the_ends_matches_truncating_the_whole_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("big.txt");
+    let text = large_text();
+    fs::write(&path, &text).unwrap();
+
+    for tokenizer in [TokenizerChoice::Cl100k, TokenizerChoice::R50kBase] {
+        for max in [1, 99, 1000] {
+            let (head, tail) = estimated_end_bytes(max, tokenizer);
+            let ends = read_source_ends(&path, head, tail).unwrap().unwrap();
+            assert_eq!(ends.total_bytes, text.len());
+            assert_eq!(
+                truncate_ends(&ends.head, &ends.tail, ends.total_bytes, max, tokenizer),
+                truncate_middle(&text, max, tokenizer, true),
+                "max {max}"
+            );
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use code2prompt_tui::ui::output::{split_output_path, split_output_pattern};
+
+#[test]
+fn test_split_pattern_defaults_to_prompt_md() {
+    let pattern = split_output_pattern(None);
+    assert_eq!(pattern, "prompt-{dir}.md");
+    assert_eq!(split_output_path(&pattern, "src"), "prompt-src.md");
+    assert_eq!(split_output_path(&pattern, "."), "prompt-root.md");
+}
+
+#[test]
+fn test_split_pattern_follows_the_output_file() {
+    assert_eq!(
+        split_output_pattern(Some("out/review.md")),
+        "out/review-{dir}.md"
+    );
+    assert_eq!(
+        split_output_pattern(Some("prompts/{repo}-{date}.txt")),
+        "prompts/{repo}-{date}-{dir}.txt"
+    );
+    assert_eq!(split_output_pattern(Some("PROMPT")), "PROMPT-{dir}");
+}
+
+#[test]
+fn test_split_path_keeps_directory_names_file_safe() {
+    assert_eq!(
+        split_output_path("p-{dir}.md", "my dir:v2"),
+        "p-my_dir_v2.md"
+    );
+    assert_eq!(split_output_path("p-{dir}.md", ".github"), "p-.github.md");
+}
@@ -0,0 +1,118 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    symbols::{SymbolSource, build_symbol_index, extract_symbols, parse_ctags_json},
+};
+use tempfile::tempdir;
+
+fn names(source: &str, ext: &str) -> Vec<(String, String, usize)> {
+    extract_symbols(source, ext)
+        .into_iter()
+        .map(|s| (s.kind, s.name, s.line))
+        .collect()
+}
+
+fn sym(kind: &str, name: &str, line: usize) -> (String, String, usize) {
+    (kind.to_string(), name.to_string(), line)
+}
+
+#[test]
+fn test_extract_rust_symbols() {
+    let src = "pub struct Config;\nfn private() {}\npub(crate) fn internal() {}\n\
+               impl Config {\n    pub async fn load() {}\n}\npub const fn answer() -> u8 { 42 }\n\
+               macro_rules! shout { () => {} }\npub const LIMIT: usize = 3;\n";
+    assert_eq!(
+        names(src, "rs"),
+        vec![
+            sym("struct", "Config", 1),
+            sym("fn", "load", 5),
+            sym("fn", "answer", 7),
+            sym("macro", "shout", 8),
+            sym("const", "LIMIT", 9),
+        ]
+    );
+}
+
+#[test]
+fn test_extract_other_languages() {
+    assert_eq!(
+        names(
+            "class A:\n    def m(self): pass\n\nasync def run():\n    pass\n",
+            "py"
+        ),
+        vec![sym("class", "A", 1), sym("def", "run", 4)]
+    );
+    assert_eq!(
+        names(
+            "export default async function main() {}\nconst x = 1;\nexport interface Props {}\n",
+            "ts"
+        ),
+        vec![sym("function", "main", 1), sym("interface", "Props", 3)]
+    );
+    assert_eq!(
+        names(
+            "func (s *Server) Start() {}\nfunc helper() {}\ntype Server struct{}\n",
+            "go"
+        ),
+        vec![sym("func", "Start", 1), sym("type", "Server", 3)]
+    );
+    assert!(extract_symbols("whatever", "txt").is_empty());
+}
+
+#[test]
+fn test_parse_ctags_json() {
+    let out = r#"{"_type": "tag", "name": "main", "path": "src/main.c", "line": 3, "kind": "function"}
+{"_type": "tag", "name": "tmp", "path": "src/main.c", "line": 4, "kind": "local"}
+{"_type": "tag", "name": "Point", "path": "src/main.c", "line": 1, "kind": "struct"}
+{"_type": "tag", "name": "secret", "path": "A.java", "line": 9, "kind": "method", "access": "private"}
+{"_type": "ptag", "name": "JSON_OUTPUT_VERSION", "path": "0.0", "kind": ""}"#;
+    let parsed = parse_ctags_json(out);
+    assert_eq!(parsed.len(), 1);
+    let main: Vec<_> = parsed["src/main.c"]
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(main, vec!["Point", "main"]);
+}
+
+#[test]
+fn test_build_symbol_index_ignores_includes() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn api() {}\n").unwrap();
+    fs::write(dir.path().join("src/skip.rs"), "pub fn hidden() {}\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "pub fn not_code() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .include_patterns(vec![glob::Pattern::new("notes.txt").unwrap()])
+        .exclude_patterns(vec![glob::Pattern::new("**/skip.rs").unwrap()])
+        .build()
+        .unwrap();
+    let index = build_symbol_index(&config, SymbolSource::Builtin).unwrap();
+    assert_eq!(index.len(), 1);
+    assert_eq!(index[0].path, "src/lib.rs");
+    assert_eq!(index[0].symbols[0].name, "api");
+}
+
+#[test]
+fn test_build_symbol_index_skips_excluded_directories() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+    fs::write(dir.path().join("lib.rs"), "pub fn api() {}\n").unwrap();
+    fs::write(
+        dir.path().join("target/debug/gen.rs"),
+        "pub fn built() {}\n",
+    )
+    .unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .exclude_patterns(vec![glob::Pattern::new("target/").unwrap()])
+        .build()
+        .unwrap();
+    let index = build_symbol_index(&config, SymbolSource::Builtin).unwrap();
+    let paths: Vec<_> = index.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["lib.rs"]);
+}
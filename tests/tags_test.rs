@@ -0,0 +1,77 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    session::Code2PromptSession,
+    tags::{TagFile, has_any_tag, tags_of},
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_tags_file_round_trips_added_paths() {
+    let dir = tempdir().unwrap();
+    let mut tags = TagFile::load(dir.path()).unwrap();
+    assert!(tags.0.is_empty());
+
+    assert!(tags.add("core", "src/engine", true));
+    assert!(tags.add("legacy", "src/old[1].rs", false));
+    assert!(!tags.add("core", "src/engine", true));
+    tags.save(dir.path()).unwrap();
+
+    let loaded = TagFile::load(dir.path()).unwrap();
+    assert_eq!(loaded, tags);
+    assert_eq!(loaded.0["core"], ["src/engine/**"]);
+
+    let rules = loaded.rules().unwrap();
+    assert_eq!(tags_of(&rules, "src/engine/session.rs"), ["core"]);
+    assert_eq!(tags_of(&rules, "src/old[1].rs"), ["legacy"]);
+    assert!(tags_of(&rules, "src/old1.rs").is_empty());
+}
+
+#[test]
+fn test_a_path_takes_every_matching_tag_once() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join(".code2prompt")).unwrap();
+    fs::write(
+        TagFile::path(dir.path()),
+        "core = [\"src/**\", \"src/main.rs\"]\ngenerated = [\"**/*.pb.rs\"]\n",
+    )
+    .unwrap();
+    let rules = TagFile::load(dir.path()).unwrap().rules().unwrap();
+
+    assert_eq!(tags_of(&rules, "src/main.rs"), ["core"]);
+    assert_eq!(tags_of(&rules, "src/api.pb.rs"), ["core", "generated"]);
+    assert!(has_any_tag(&rules, "gen/api.pb.rs", &["generated".into()]));
+    assert!(!has_any_tag(&rules, "gen/api.pb.rs", &["core".into()]));
+}
+
+#[test]
+fn test_invalid_tag_glob_is_an_error() {
+    let mut tags = TagFile::default();
+    tags.0.insert("core".into(), vec!["src/[".into()]);
+    assert!(tags.rules().is_err());
+}
+
+#[test]
+fn test_session_keeps_tagged_files_and_exposes_their_tags() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("core")).unwrap();
+    fs::write(dir.path().join("core/lib.rs"), "pub fn lib() {}\n").unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    let mut tags = TagFile::default();
+    tags.add("core", "core", true);
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .tags(tags.rules().unwrap())
+        .tag_filter(vec!["core".into()])
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let context = session.build_template_data(None, None, None).unwrap();
+
+    assert_eq!(context.files.len(), 1);
+    assert_eq!(context.files[0].path, "core/lib.rs");
+    assert_eq!(context.files[0].tags, ["core"]);
+}
@@ -0,0 +1,32 @@
+use code2prompt_tui::ui::template::check_template;
+
+#[test]
+fn test_check_classifies_variables() {
+    let tpl = "{{absolute_code_path}}\n{{#each files}}{{path}}{{@root.ticket}}{{/each}}\n{{task}}";
+    let check = check_template(tpl).unwrap();
+    assert_eq!(check.builtins, vec!["absolute_code_path", "files"]);
+    assert_eq!(check.variables, vec!["task", "ticket"]);
+    assert!(check.unknown_helpers.is_empty());
+    assert!(check.is_ok());
+}
+
+#[test]
+fn test_check_flags_unknown_helpers() {
+    let tpl = "{{#each files}}{{upper path}}{{/each}}{{#if (shout task)}}x{{/if}}";
+    let check = check_template(tpl).unwrap();
+    assert_eq!(check.unknown_helpers, vec!["shout", "upper"]);
+    assert!(check.render_error.is_some());
+    assert!(!check.is_ok());
+}
+
+#[test]
+fn test_check_rejects_invalid_syntax() {
+    assert!(check_template("{{#each files}}{{path}}").is_err());
+}
+
+#[test]
+fn test_check_accepts_default_template() {
+    let check = check_template(include_str!("../default_template.hbs")).unwrap();
+    assert!(check.is_ok(), "{check:?}");
+    assert!(check.variables.is_empty());
+}
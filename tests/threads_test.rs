@@ -0,0 +1,30 @@
+use std::fs;
+
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use tempfile::tempdir;
+
+#[test]
+fn test_single_thread_scan_finds_every_file() {
+    let dir = tempdir().unwrap();
+    for i in 0..40 {
+        fs::write(dir.path().join(format!("file{i}.rs")), "fn f() {}\n").unwrap();
+    }
+
+    let scan = |threads: Option<usize>| {
+        let config = Code2PromptConfigBuilder::default()
+            .path(dir.path().to_path_buf())
+            .threads(threads)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config).unwrap();
+        session.process_codebase().unwrap();
+        let context = session.build_template_data(None, None, None).unwrap();
+        let mut paths: Vec<String> = context.files.into_iter().map(|f| f.path).collect();
+        paths.sort();
+        paths
+    };
+
+    let single = scan(Some(1));
+    assert_eq!(single.len(), 40);
+    assert_eq!(single, scan(None));
+}
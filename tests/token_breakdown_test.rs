@@ -0,0 +1,63 @@
+#![cfg(feature = "token_map")]
+
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    session::Code2PromptSession,
+    token::{TokenizerChoice, count_tokens},
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_breakdown_attributes_each_part() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("main.rs"),
+        "fn main() { println!(\"hi\"); }\n",
+    )
+    .unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let mut context = session.build_template_data(None, None, None).unwrap();
+    context.source_tree = "project\n└── main.rs".to_string();
+
+    let code = &context.files[0].code;
+    let rendered = format!(
+        "Project Path: project\n\nSource Tree:\n\n```\n{}\n```\n\n`main.rs`:\n\n{code}",
+        context.source_tree
+    );
+    let count = |s: &str| count_tokens(s, TokenizerChoice::default()).unwrap();
+    let breakdown = session.token_breakdown(&context, count(&rendered)).unwrap();
+
+    let file_tokens = context.files[0].token_count.unwrap_or_else(|| count(code));
+    assert_eq!(breakdown.files, file_tokens);
+    assert_eq!(breakdown.source_tree, count(&context.source_tree));
+    assert_eq!((breakdown.git_diff, breakdown.git_log), (0, 0));
+    assert!(breakdown.template > 0);
+    assert_eq!(breakdown.total(), count(&rendered));
+}
+
+#[test]
+fn test_breakdown_reuses_the_counts_of_the_scan() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    let mut context = session.build_template_data(None, None, None).unwrap();
+    context.files[0].token_count = Some(1000);
+
+    let breakdown = session.token_breakdown(&context, 1200).unwrap();
+    assert_eq!(breakdown.files, 1000);
+    assert_eq!(breakdown.total(), 1200);
+}
@@ -0,0 +1,29 @@
+use code2prompt_tui::engine::token::{TokenizerChoice, estimate_tokens};
+
+#[test]
+fn test_estimate_is_zero_for_empty_text() {
+    assert_eq!(estimate_tokens("", TokenizerChoice::Cl100k), 0);
+}
+
+#[test]
+fn test_estimate_scales_with_tokenizer_ratio() {
+    let code = "fn main() { println!(\"hello world\"); }\n".repeat(100);
+    let o200k = estimate_tokens(&code, TokenizerChoice::O200kBase);
+    let r50k = estimate_tokens(&code, TokenizerChoice::R50kBase);
+    assert!(o200k < r50k);
+    assert_eq!(o200k, (code.len() as f64 / 4.6).ceil() as usize);
+}
+
+#[cfg(feature = "token_map")]
+#[test]
+fn test_estimate_is_close_to_exact_count_for_source() {
+    use code2prompt_tui::engine::token::count_tokens;
+
+    let source = include_str!("fixtures/estimate_sample.rs");
+    let exact = count_tokens(source, TokenizerChoice::Cl100k).unwrap() as f64;
+    let estimate = estimate_tokens(source, TokenizerChoice::Cl100k) as f64;
+    assert!(
+        (estimate - exact).abs() / exact < 0.25,
+        "{estimate} vs {exact}"
+    );
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use code2prompt_tui::engine::{
+    model::ProcessedEntry,
+    token_map::{
+        TokenMapGroupBy, TokenMapSort, generate_grouped_token_map, group_by_extension,
+        sort_token_map,
+    },
+};
+
+fn file(path: &str, ext: Option<&str>, tokens: usize) -> ProcessedEntry {
+    ProcessedEntry {
+        path: PathBuf::from(path),
+        relative_path: PathBuf::from(path),
+        is_file: true,
+        extension: ext.map(str::to_owned),
+        token_count: Some(tokens),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_group_by_extension_sums_tokens() {
+    let entries = vec![
+        file("src/main.rs", Some("rs"), 10),
+        file("src/lib.rs", Some("rs"), 30),
+        file("README.md", Some("md"), 20),
+        file("Makefile", None, 5),
+    ];
+    let mut groups = group_by_extension(&entries);
+    groups.sort();
+    assert_eq!(
+        groups,
+        vec![
+            ("(none)".to_string(), 5),
+            (".md".to_string(), 20),
+            (".rs".to_string(), 40),
+        ]
+    );
+}
+
+#[test]
+fn test_grouped_map_is_sorted_and_folds_the_tail() {
+    let groups = vec![
+        (".md".to_string(), 20),
+        (".rs".to_string(), 70),
+        (".toml".to_string(), 10),
+    ];
+    let map = generate_grouped_token_map(groups, TokenMapGroupBy::Ext, Some(3), None);
+    let names: Vec<_> = map.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec![".rs", ".md", "(other extensions)"]);
+    assert_eq!(map[2].tokens, 10);
+    assert!(map[2].is_last);
+    assert!((map[0].percentage - 70.0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_grouped_tail_is_named_after_the_grouping_and_sorts_last() {
+    let groups = vec![
+        ("zoe".to_string(), 50),
+        ("max".to_string(), 40),
+        ("ann".to_string(), 10),
+    ];
+    let map = generate_grouped_token_map(groups, TokenMapGroupBy::Author, Some(3), None);
+    let sorted = sort_token_map(map, TokenMapSort::Name);
+    let names: Vec<_> = sorted.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["max", "zoe", "(other authors)"]);
+    assert!(sorted[2].is_last);
+}
+
+#[test]
+fn test_grouped_map_marks_last_group_without_tail() {
+    let map = generate_grouped_token_map(
+        vec![("a".into(), 1), ("b".into(), 2)],
+        TokenMapGroupBy::Ext,
+        None,
+        None,
+    );
+    assert_eq!(map.len(), 2);
+    assert!(!map[0].is_last);
+    assert!(map[1].is_last);
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn test_group_by_author_splits_untracked_files() {
+    use code2prompt_tui::engine::token_map::group_by_author;
+
+    let dir = tempfile::tempdir().unwrap();
+    git2::Repository::init(dir.path()).unwrap();
+    let path = dir.path().join("new.rs");
+    std::fs::write(&path, "fn main() {}\n").unwrap();
+
+    let entries = vec![file(path.to_str().unwrap(), Some("rs"), 12)];
+    let groups = group_by_author(dir.path(), &entries).unwrap();
+    assert_eq!(groups, vec![("(untracked)".to_string(), 12)]);
+}
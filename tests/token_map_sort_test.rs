@@ -0,0 +1,66 @@
+use code2prompt_tui::engine::{
+    model::{EntryMetadata, TokenMapEntry},
+    token_map::{TokenMapSort, sort_token_map},
+};
+
+fn entry(name: &str, depth: usize, tokens: usize) -> TokenMapEntry {
+    TokenMapEntry {
+        path: name.into(),
+        name: name.into(),
+        tokens,
+        percentage: tokens as f64,
+        depth,
+        is_last: false,
+        metadata: EntryMetadata {
+            is_dir: false,
+            is_symlink: false,
+        },
+    }
+}
+
+fn names(entries: &[TokenMapEntry]) -> Vec<&str> {
+    entries.iter().map(|e| e.name.as_str()).collect()
+}
+
+fn sample() -> Vec<TokenMapEntry> {
+    vec![
+        entry("src", 1, 60),
+        entry("main.rs", 2, 40),
+        entry("app.rs", 2, 20),
+        entry("docs", 1, 30),
+        entry("README.md", 1, 5),
+        entry("(other files)", 0, 5),
+    ]
+}
+
+#[test]
+fn test_sort_by_name_keeps_subtrees_together() {
+    let sorted = sort_token_map(sample(), TokenMapSort::Name);
+    assert_eq!(
+        names(&sorted),
+        vec![
+            "README.md",
+            "docs",
+            "src",
+            "app.rs",
+            "main.rs",
+            "(other files)"
+        ]
+    );
+    assert!(sorted[2].is_last, "src is the last top-level sibling");
+    assert!(!sorted[3].is_last);
+    assert!(sorted[4].is_last);
+}
+
+#[test]
+fn test_sort_by_percent_is_largest_first() {
+    let input = vec![
+        entry("docs", 1, 30),
+        entry("src", 1, 60),
+        entry("app.rs", 2, 20),
+        entry("main.rs", 2, 40),
+    ];
+    let sorted = sort_token_map(input, TokenMapSort::Percent);
+    assert_eq!(names(&sorted), vec!["src", "main.rs", "app.rs", "docs"]);
+    assert!(sorted[3].is_last);
+}
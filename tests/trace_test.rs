@@ -0,0 +1,45 @@
+#![cfg(feature = "tracing")]
+
+use std::fs;
+
+use code2prompt_tui::engine::{config::Code2PromptConfigBuilder, session::Code2PromptSession};
+use tempfile::tempdir;
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn test_trace_records_a_span_per_scanned_file() {
+    let dir = tempdir().unwrap();
+    for path in ["src/main.rs", "src/lib.rs", "README.md"] {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "text\n").unwrap();
+    }
+    let out = tempdir().unwrap();
+    let trace = out.path().join("trace.json");
+
+    // Global, so that spans from the walker's threads are recorded too.
+    let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+        .file(&trace)
+        .include_args(true)
+        .build();
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer)).unwrap();
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+    let mut session = Code2PromptSession::new(config).unwrap();
+    session.process_codebase().unwrap();
+    drop(guard);
+
+    let events: Vec<serde_json::Value> =
+        serde_json::from_str(&fs::read_to_string(&trace).unwrap()).unwrap();
+    let mut files: Vec<&str> = events
+        .iter()
+        .filter(|e| e["name"] == "process_file" && e["ph"] == "B")
+        .map(|e| e["args"]["path"].as_str().unwrap())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["README.md", "src/lib.rs", "src/main.rs"]);
+    assert!(events.iter().any(|e| e["name"] == "process_codebase"));
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "tui")]
+
+use code2prompt_tui::ui::triage::{Triage, Verdict};
+
+fn triage() -> Triage {
+    Triage::largest(
+        vec![
+            ("src/lib.rs".to_string(), 300),
+            ("Cargo.lock".to_string(), 9000),
+            ("README.md".to_string(), 300),
+            ("src/main.rs".to_string(), 20),
+        ],
+        3,
+    )
+}
+
+#[test]
+fn test_largest_files_come_first_and_are_kept() {
+    let t = triage();
+    let paths: Vec<_> = t.files.iter().map(|(p, _, _)| p.as_str()).collect();
+    assert_eq!(paths, ["Cargo.lock", "README.md", "src/lib.rs"]);
+    assert!(t.files.iter().all(|(_, _, v)| *v == Verdict::Keep));
+    assert!(t.dropped().is_empty());
+}
+
+#[test]
+fn test_dropping_and_excluding_toggle_back_to_keep() {
+    let mut t = triage();
+    t.toggle_exclude();
+    t.next();
+    t.toggle_drop();
+    assert_eq!(t.dropped(), ["Cargo.lock", "README.md"]);
+    assert_eq!(t.excluded(), ["Cargo.lock"]);
+    assert_eq!(t.dropped_tokens(), 9300);
+
+    t.toggle_drop();
+    t.previous();
+    t.toggle_drop();
+    assert_eq!(t.dropped(), ["Cargo.lock"]);
+    assert!(t.excluded().is_empty());
+    t.toggle_drop();
+    assert!(t.dropped().is_empty());
+}
+
+#[test]
+fn test_cursor_stays_in_the_list() {
+    let mut t = triage();
+    t.previous();
+    assert_eq!(t.cursor, 0);
+    for _ in 0..5 {
+        t.next();
+    }
+    assert_eq!(t.cursor, 2);
+
+    let mut empty = Triage::largest(Vec::new(), 5);
+    empty.next();
+    empty.toggle_drop();
+    assert_eq!(empty.cursor, 0);
+    assert!(empty.dropped().is_empty());
+}
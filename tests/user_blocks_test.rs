@@ -0,0 +1,36 @@
+use code2prompt_tui::ui::template::{load_text_arg, wrap_with_user_blocks};
+
+#[test]
+fn test_blocks_wrap_body_when_template_does_not_use_them() {
+    let out = wrap_with_user_blocks(
+        "BODY".into(),
+        "{{#each files}}{{/each}}",
+        Some("Be concise.\n"),
+        Some("Now fix the bug."),
+    );
+    assert_eq!(out, "Be concise.\n\nBODY\n\nNow fix the bug.");
+}
+
+#[test]
+fn test_blocks_left_to_template_when_referenced() {
+    let out = wrap_with_user_blocks(
+        "BODY".into(),
+        "{{user_prelude}} ...",
+        Some("Be concise."),
+        None,
+    );
+    assert_eq!(out, "BODY");
+}
+
+#[test]
+fn test_load_text_arg_reads_files_with_at_prefix() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("task.md");
+    std::fs::write(&path, "Do the thing").unwrap();
+    assert_eq!(
+        load_text_arg(&format!("@{}", path.display())).unwrap(),
+        "Do the thing"
+    );
+    assert_eq!(load_text_arg("literal").unwrap(), "literal");
+    assert!(load_text_arg("@/does/not/exist").is_err());
+}
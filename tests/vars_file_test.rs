@@ -0,0 +1,25 @@
+use code2prompt_tui::engine::cache::{expand_env_vars, load_vars_from_file};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_expand_env_vars() {
+    unsafe { std::env::set_var("C2P_TEST_HOME", "/home/ada") };
+    assert_eq!(expand_env_vars("${C2P_TEST_HOME}/notes"), "/home/ada/notes");
+    assert_eq!(expand_env_vars("${C2P_TEST_UNSET:-fallback}"), "fallback");
+    assert_eq!(expand_env_vars("${C2P_TEST_UNSET}"), "${C2P_TEST_UNSET}");
+    assert_eq!(expand_env_vars("no vars, $HOME"), "no vars, $HOME");
+}
+
+#[test]
+#[serial]
+fn test_vars_file_values_are_expanded() {
+    unsafe { std::env::set_var("C2P_TEST_TOKEN", "s3cret") };
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vars.toml");
+    std::fs::write(&path, "token = \"${C2P_TEST_TOKEN}\"\nplain = \"x\"\n").unwrap();
+
+    let vars = load_vars_from_file(&path).unwrap();
+    assert_eq!(vars.get("token").map(String::as_str), Some("s3cret"));
+    assert_eq!(vars.get("plain").map(String::as_str), Some("x"));
+}
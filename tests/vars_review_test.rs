@@ -0,0 +1,38 @@
+#![cfg(feature = "tui")]
+
+use code2prompt_tui::ui::vars_review::VarReview;
+
+fn review() -> VarReview {
+    VarReview::new(vec![
+        ("issue".to_string(), "1234".to_string()),
+        ("goal".to_string(), "fix the parser".to_string()),
+    ])
+}
+
+#[test]
+fn test_finishing_an_edit_replaces_the_value_under_the_cursor() {
+    let mut r = review();
+    r.next();
+    r.start_edit();
+    assert_eq!(r.editing.as_deref(), Some("fix the parser"));
+    r.editing = Some("speed up the scan".to_string());
+    r.finish_edit();
+    assert_eq!(r.editing, None);
+    assert_eq!(r.vars[0].1, "1234");
+    assert_eq!(r.vars[1].1, "speed up the scan");
+}
+
+#[test]
+fn test_cancelling_an_edit_keeps_the_value() {
+    let mut r = review();
+    r.start_edit();
+    r.editing.as_mut().unwrap().push('5');
+    r.cancel_edit();
+    assert_eq!(r.vars[0].1, "1234");
+
+    // The cursor stays on the list.
+    r.previous();
+    r.next();
+    r.next();
+    assert_eq!(r.cursor, 1);
+}
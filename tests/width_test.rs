@@ -0,0 +1,34 @@
+#![cfg(feature = "token_map")]
+
+use code2prompt_tui::common::format::{pad_to_width, truncate_to_width};
+use unicode_width::UnicodeWidthStr;
+
+#[test]
+fn test_truncate_to_width_counts_columns() {
+    assert_eq!(truncate_to_width("main.rs", 10), "main.rs");
+    assert_eq!(truncate_to_width("readme.md", 6), "readm…");
+    // Each CJK character takes two columns; a lone column stays empty.
+    assert_eq!(truncate_to_width("設定ファイル.rs", 6), "設定…");
+    assert_eq!(truncate_to_width("設定ファイル.rs", 7), "設定フ…");
+    assert_eq!(truncate_to_width("abc", 0), "");
+}
+
+#[test]
+fn test_truncate_to_width_keeps_grapheme_clusters() {
+    // A thumbs-up with a skin tone, then a family joined with ZWJs.
+    for name in ["👍🏽👍🏽👍🏽.txt", "👨‍👩‍👧👨‍👩‍👧.txt"] {
+        let cut = truncate_to_width(name, 5);
+        let kept = cut.strip_suffix('…').unwrap();
+        assert!(name.starts_with(kept), "{name}");
+        assert!(cut.width() <= 5, "{name}");
+        assert!(!kept.ends_with('\u{200d}'), "{name}");
+    }
+}
+
+#[test]
+fn test_pad_to_width_aligns_wide_names() {
+    let names = ["src", "ソース", "🦀"];
+    let padded: Vec<String> = names.iter().map(|n| pad_to_width(n, 8)).collect();
+    assert!(padded.iter().all(|p| p.width() == 8), "{padded:?}");
+    assert_eq!(pad_to_width("longer-than-width", 4), "longer-than-width");
+}
@@ -0,0 +1,102 @@
+use std::fs;
+
+use code2prompt_tui::engine::{
+    config::Code2PromptConfigBuilder,
+    test_files::tests_for,
+    traverse::{ProcessingMode, process_codebase},
+};
+use glob::Pattern;
+use tempfile::tempdir;
+
+fn files(paths: &[&str]) -> Vec<String> {
+    paths.iter().map(|p| p.to_string()).collect()
+}
+
+#[test]
+fn test_tests_are_matched_by_ecosystem_conventions() {
+    let all = files(&[
+        "pkg/parser.go",
+        "pkg/parser_test.go",
+        "other/parser_test.go",
+        "app/models.py",
+        "tests/test_models.py",
+        "tests/models_test.py",
+        "web/button.tsx",
+        "web/button.spec.tsx",
+        "web/__tests__/button.js",
+        "src/lexer.rs",
+        "tests/lexer.rs",
+        "src/Cache.java",
+        "src/test/java/CacheTest.java",
+        "lib/cart.rb",
+        "spec/cart_spec.rb",
+    ]);
+    let found = tests_for(
+        &[
+            "pkg/parser.go",
+            "app/models.py",
+            "web/button.tsx",
+            "src/lexer.rs",
+            "src/Cache.java",
+            "lib/cart.rb",
+        ],
+        &all,
+    );
+    let found: Vec<&str> = found.iter().map(String::as_str).collect();
+    assert_eq!(
+        found,
+        [
+            "pkg/parser_test.go",
+            "spec/cart_spec.rb",
+            "src/test/java/CacheTest.java",
+            "tests/lexer.rs",
+            "tests/models_test.py",
+            "tests/test_models.py",
+            "web/__tests__/button.js",
+            "web/button.spec.tsx",
+        ]
+    );
+}
+
+#[test]
+fn test_languages_and_common_names_do_not_mix() {
+    let all = files(&[
+        "src/util.py",
+        "src/util_test.go",
+        "src/mod.rs",
+        "tests/mod.rs",
+        "src/index.ts",
+        "src/index.test.ts",
+    ]);
+    assert!(tests_for(&["src/util.py", "src/mod.rs", "src/index.ts"], &all).is_empty());
+}
+
+#[test]
+fn test_scan_adds_the_tests_of_included_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    for (path, content) in [
+        ("pkg/parser.go", "package pkg"),
+        ("pkg/parser_test.go", "package pkg"),
+        ("pkg/lexer.go", "package pkg"),
+        ("pkg/lexer_test.go", "package pkg"),
+    ] {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    let config = Code2PromptConfigBuilder::default()
+        .path(root.to_path_buf())
+        .include_patterns(vec![Pattern::new("pkg/parser.go").unwrap()])
+        .with_tests(true)
+        .build()
+        .unwrap();
+    let (entries, ..) = process_codebase(&config, ProcessingMode::FullProcess).unwrap();
+    let mut files: Vec<_> = entries
+        .iter()
+        .map(|e| e.relative_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    files.sort();
+    assert_eq!(files, ["pkg/parser.go", "pkg/parser_test.go"]);
+}